@@ -0,0 +1,464 @@
+//! Derive macros for eyeutil's `Parse`, `Writable`, and `DataSize` traits. Use them through
+//! eyeutil's `derive` feature, which re-exports them next to the traits.
+//!
+//! The generated impls are the same as `impl_parse!`, `impl_writable!(unchecked ...)`, and
+//! `impl_struct_data_size!` would give: `Parse<F, ()>` with `F: Read + Seek`, `Writable<()>`,
+//! and `DataSize<()>`, with the fields handled in order. So derived and hand-written types can
+//! be nested in each other. Like `impl_parse!`, each field's name (or index) is the context of
+//! any error from parsing it.
+//!
+//! Field attributes, in `#[eyeutil(...)]`:
+//! - `endian = "little"` or `"big"`: the field's data is that `Endian`. Otherwise it is `()`.
+//! - `data = "expr"`: the field's data is the given expression, such as
+//!   `"(Endian::Little, AddrWidth::W32)"`. This is also given to its `DataSize`, while the other
+//!   fields use `()` for that.
+//! - `skip`: the field is `Default::default()` when parsing, and isn't written.
+//! - `len_prefix = "u32"` (or `u8`, `u16`, `u64`): for `Vec` and `String` fields, which are
+//!   preceded by their length. The length uses the field's endianness, or `len_endian` if given,
+//!   and is little endian otherwise. A `String` is read as UTF-8.
+//!
+//! Generic types get bounds on the fields that use their type parameters, such as
+//! `T: Parse<F, Endian>` for an `endian` field of type `T`. Fields with `data` don't get any,
+//! as the derive can't name the type of the expression, so their types should be concrete.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2, TokenTree};
+use quote::{format_ident, quote};
+use syn::{
+    parse_macro_input, parse_quote, spanned::Spanned, Data, DeriveInput, Expr, Fields,
+    GenericArgument, GenericParam, Generics, Ident, LitStr, PathArguments, Type, WherePredicate,
+};
+
+#[proc_macro_derive(Parse, attributes(eyeutil))]
+pub fn derive_parse(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, parse_impl).into()
+}
+
+#[proc_macro_derive(Writable, attributes(eyeutil))]
+pub fn derive_writable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, writable_impl).into()
+}
+
+#[proc_macro_derive(DataSize, attributes(eyeutil))]
+pub fn derive_data_size(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input, data_size_impl).into()
+}
+
+fn expand(
+    input: &DeriveInput,
+    imp: fn(&DeriveInput, &Shape, &[Field]) -> TokenStream2,
+) -> TokenStream2 {
+    match struct_fields(input) {
+        Ok((shape, fields)) => imp(input, &shape, &fields),
+        Err(e) => e.to_compile_error(),
+    }
+}
+
+/// How the struct's fields are named
+enum Shape {
+    Named,
+    Tuple,
+    Unit,
+}
+
+/// The length prefix of a `Vec` or `String` field
+struct LenPrefix {
+    typ: Ident,
+    /// Either `()` for a u8, or an `Endian`
+    data: TokenStream2,
+}
+
+struct Field {
+    /// How the field is accessed on `self`: its name, or its index
+    member: syn::Member,
+    /// The local that the field is parsed into
+    local: Ident,
+    typ: Type,
+    data: TokenStream2,
+    /// The type of [data], unless it was given with `data = "..."`
+    data_type: Option<TokenStream2>,
+    skip: bool,
+    len_prefix: Option<LenPrefix>,
+    is_string: bool,
+}
+
+fn struct_fields(input: &DeriveInput) -> syn::Result<(Shape, Vec<Field>)> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "eyeutil's derives only support structs",
+            ))
+        }
+    };
+    let shape = match fields {
+        Fields::Named(_) => Shape::Named,
+        Fields::Unnamed(_) => Shape::Tuple,
+        Fields::Unit => Shape::Unit,
+    };
+
+    let fields = fields
+        .iter()
+        .enumerate()
+        .map(|(index, field)| {
+            let (member, local) = match &field.ident {
+                Some(name) => (syn::Member::Named(name.clone()), name.clone()),
+                None => (
+                    syn::Member::Unnamed(syn::Index::from(index)),
+                    format_ident!("__field{}", index),
+                ),
+            };
+            let mut parsed = Field {
+                member,
+                local,
+                typ: field.ty.clone(),
+                data: quote!(()),
+                data_type: Some(quote!(())),
+                skip: false,
+                len_prefix: None,
+                is_string: is_string(&field.ty),
+            };
+            parse_attributes(field, &mut parsed)?;
+            Ok(parsed)
+        })
+        .collect::<syn::Result<Vec<Field>>>()?;
+    Ok((shape, fields))
+}
+
+/// Whether [typ] is spelled `String`, which is all a derive can tell
+fn is_string(typ: &Type) -> bool {
+    match typ {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "String"),
+        _ => false,
+    }
+}
+
+/// The element type of a type spelled like `Vec<T>`
+fn element_type(typ: &Type) -> Option<&Type> {
+    let segment = match typ {
+        Type::Path(path) => path.path.segments.last()?,
+        _ => return None,
+    };
+    match &segment.arguments {
+        PathArguments::AngleBracketed(args) => match args.args.first()? {
+            GenericArgument::Type(typ) => Some(typ),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Whether [tokens] use any of [params]
+fn mentions(tokens: TokenStream2, params: &[Ident]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => params.contains(&ident),
+        TokenTree::Group(group) => mentions(group.stream(), params),
+        _ => false,
+    })
+}
+
+/// Adds [bound] to the where clause for each field whose type uses a type parameter. Concrete
+/// types are left out, as they may be private.
+fn bounded_generics(
+    generics: &Generics,
+    fields: &[Field],
+    bound: impl Fn(&Field) -> Option<WherePredicate>,
+) -> Generics {
+    let params: Vec<Ident> = generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(param.ident.clone()),
+            _ => None,
+        })
+        .collect();
+    let predicates: Vec<WherePredicate> = fields
+        .iter()
+        .filter(|field| {
+            let typ = &field.typ;
+            mentions(quote!(#typ), &params)
+        })
+        .filter_map(bound)
+        .collect();
+    let mut generics = generics.clone();
+    generics.make_where_clause().predicates.extend(predicates);
+    generics
+}
+
+fn endian(value: &LitStr) -> syn::Result<TokenStream2> {
+    match value.value().as_str() {
+        "little" => Ok(quote!(::eyeutil::Endian::Little)),
+        "big" => Ok(quote!(::eyeutil::Endian::Big)),
+        _ => Err(syn::Error::new_spanned(
+            value,
+            "expected \"little\" or \"big\"",
+        )),
+    }
+}
+
+fn parse_attributes(field: &syn::Field, parsed: &mut Field) -> syn::Result<()> {
+    let mut endian_data = None;
+    let mut len_prefix = None;
+    let mut len_endian = None;
+    for attr in field.attrs.iter() {
+        if !attr.path().is_ident("eyeutil") {
+            continue;
+        }
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                parsed.skip = true;
+            } else if meta.path.is_ident("endian") {
+                endian_data = Some(endian(&meta.value()?.parse()?)?);
+            } else if meta.path.is_ident("data") {
+                let value: LitStr = meta.value()?.parse()?;
+                let expr: Expr = value.parse()?;
+                parsed.data = quote!(#expr);
+                parsed.data_type = None;
+            } else if meta.path.is_ident("len_prefix") {
+                let value: LitStr = meta.value()?.parse()?;
+                match value.value().as_str() {
+                    "u8" | "u16" | "u32" | "u64" => {
+                        len_prefix = Some(Ident::new(&value.value(), value.span()))
+                    }
+                    _ => {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "expected \"u8\", \"u16\", \"u32\", or \"u64\"",
+                        ))
+                    }
+                }
+            } else if meta.path.is_ident("len_endian") {
+                len_endian = Some(endian(&meta.value()?.parse()?)?);
+            } else {
+                return Err(meta.error("unknown eyeutil attribute"));
+            }
+            Ok(())
+        })?;
+    }
+
+    if let Some(endian) = &endian_data {
+        if parsed.data_type.is_none() {
+            return Err(syn::Error::new(
+                field.span(),
+                "`endian` and `data` can't both be given",
+            ));
+        }
+        parsed.data = endian.clone();
+        parsed.data_type = Some(quote!(::eyeutil::Endian));
+    }
+    if parsed.is_string {
+        // Strings are bytes, so their content has no data
+        parsed.data = quote!(());
+        parsed.data_type = Some(quote!(()));
+    }
+    match len_prefix {
+        Some(typ) => {
+            let data = if typ == "u8" {
+                quote!(())
+            } else {
+                len_endian
+                    .or(endian_data)
+                    .unwrap_or_else(|| quote!(::eyeutil::Endian::Little))
+            };
+            parsed.len_prefix = Some(LenPrefix { typ, data });
+        }
+        None if len_endian.is_some() => {
+            return Err(syn::Error::new(
+                field.span(),
+                "`len_endian` needs `len_prefix`",
+            ))
+        }
+        None => {}
+    }
+    Ok(())
+}
+
+fn construct(shape: &Shape, fields: &[Field]) -> TokenStream2 {
+    let locals = fields.iter().map(|field| &field.local);
+    match shape {
+        Shape::Named => quote!(Self { #(#locals),* }),
+        Shape::Tuple => quote!(Self(#(#locals),*)),
+        Shape::Unit => quote!(Self),
+    }
+}
+
+fn parse_impl(input: &DeriveInput, shape: &Shape, fields: &[Field]) -> TokenStream2 {
+    let reader = Ident::new("__reader", Span::call_site());
+    let parse_fields = fields.iter().map(|field| {
+        let Field {
+            member,
+            local,
+            typ,
+            data,
+            ..
+        } = field;
+        if field.skip {
+            return quote! {
+                let #local: #typ = ::core::default::Default::default();
+            };
+        }
+        let name = match member {
+            syn::Member::Named(name) => name.to_string(),
+            syn::Member::Unnamed(index) => index.index.to_string(),
+        };
+        let parse = match &field.len_prefix {
+            Some(LenPrefix {
+                typ: prefix,
+                data: prefix_data,
+            }) => {
+                let content = if field.is_string {
+                    quote!(::eyeutil::parse::utf8_str(#reader, __len))
+                } else {
+                    quote!(::eyeutil::parse::many_count(#reader, #data, __len))
+                };
+                quote! {
+                    (|| -> ::eyeutil::parse::ParseResult<#typ> {
+                        let __len = <#prefix as ::eyeutil::parse::Parse<_, _>>::parse(
+                            #reader,
+                            #prefix_data,
+                        )?;
+                        let __len = <usize as ::std::convert::TryFrom<#prefix>>::try_from(__len)
+                            .map_err(|_| ::eyeutil::parse::ParseError::IntegerOverflow)?;
+                        #content
+                    })()
+                }
+            }
+            None => quote!(<#typ as ::eyeutil::parse::Parse<_, _>>::parse(#reader, #data)),
+        };
+        quote! {
+            let #local: #typ = ::eyeutil::parse::ParseResultExt::context(#parse, #name)?;
+        }
+    });
+    let construct = construct(shape, fields);
+
+    let name = &input.ident;
+    let (_, ty_generics, _) = input.generics.split_for_impl();
+    let mut generics = bounded_generics(&input.generics, fields, |field| {
+        let typ = &field.typ;
+        if field.skip {
+            return Some(parse_quote!(#typ: ::core::default::Default));
+        }
+        let data = field.data_type.as_ref()?;
+        match &field.len_prefix {
+            Some(_) if field.is_string => None,
+            // Parsed with `many_count`, rather than as the `Vec` itself
+            Some(_) => {
+                let element = element_type(typ)?;
+                Some(parse_quote!(#element: ::eyeutil::parse::Parse<__F, #data>))
+            }
+            None => Some(parse_quote!(#typ: ::eyeutil::parse::Parse<__F, #data>)),
+        }
+    });
+    generics.params.push(parse_quote!(__F));
+    generics
+        .make_where_clause()
+        .predicates
+        .push(parse_quote!(__F: ::std::io::Read + ::std::io::Seek));
+    let (impl_generics, _, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::eyeutil::parse::Parse<__F, ()> for #name #ty_generics
+        #where_clause
+        {
+            fn parse(#reader: &mut __F, _d: ()) -> ::eyeutil::parse::ParseResult<Self> {
+                #(#parse_fields)*
+                Ok(#construct)
+            }
+        }
+    }
+}
+
+fn writable_impl(input: &DeriveInput, _shape: &Shape, fields: &[Field]) -> TokenStream2 {
+    let write_fields = fields.iter().filter(|field| !field.skip).map(|field| {
+        let Field { member, data, .. } = field;
+        let prefix = field.len_prefix.as_ref().map(
+            |LenPrefix {
+                 typ: prefix,
+                 data: prefix_data,
+             }| {
+                quote! {
+                    let __len = <#prefix as ::std::convert::TryFrom<usize>>::try_from(
+                        self.#member.len(),
+                    )
+                    .map_err(|_| ::eyeutil::writable::WriteError::TooManyBits)?;
+                    ::eyeutil::writable::Writable::write_to(&__len, w, #prefix_data)?;
+                }
+            },
+        );
+        quote! {
+            #prefix
+            ::eyeutil::writable::Writable::write_to(&self.#member, w, #data)?;
+        }
+    });
+
+    let name = &input.ident;
+    let generics = bounded_generics(&input.generics, fields, |field| {
+        let typ = &field.typ;
+        let data = field.data_type.as_ref()?;
+        if field.skip {
+            return None;
+        }
+        Some(parse_quote!(#typ: ::eyeutil::writable::Writable<#data>))
+    });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::eyeutil::writable::Writable<()> for #name #ty_generics
+        #where_clause
+        {
+            fn write_to<__W>(&self, w: &mut __W, _d: ()) -> ::eyeutil::writable::WriteResult
+            where
+                __W: ::std::io::Write,
+            {
+                #(#write_fields)*
+                Ok(())
+            }
+        }
+    }
+}
+
+fn data_size_impl(input: &DeriveInput, _shape: &Shape, fields: &[Field]) -> TokenStream2 {
+    let sizes = fields.iter().filter(|field| !field.skip).map(|field| {
+        let member = &field.member;
+        let size = if field.data_type.is_none() {
+            let data = &field.data;
+            quote!(::eyeutil::data_size::DataSize::data_size(&self.#member, #data))
+        } else {
+            quote!(::eyeutil::data_size::DataSize::<()>::data_size(&self.#member, ()))
+        };
+        match &field.len_prefix {
+            Some(LenPrefix { typ: prefix, .. }) => {
+                quote!(+ ::core::mem::size_of::<#prefix>() as u64 + #size)
+            }
+            None => quote!(+ #size),
+        }
+    });
+
+    let name = &input.ident;
+    let generics = bounded_generics(&input.generics, fields, |field| {
+        let typ = &field.typ;
+        if field.skip || field.data_type.is_none() {
+            return None;
+        }
+        Some(parse_quote!(#typ: ::eyeutil::data_size::DataSize<()>))
+    });
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics ::eyeutil::data_size::DataSize<()> for #name #ty_generics
+        #where_clause
+        {
+            fn data_size(&self, _d: ()) -> u64 {
+                0 #(#sizes)*
+            }
+        }
+    }
+}
@@ -0,0 +1,183 @@
+use eyeutil::{
+    addr::{AddrWidth, Uaddr},
+    prelude::*,
+    roundtrip::assert_roundtrip_bytes,
+};
+use std::io::Cursor;
+
+#[derive(Debug, PartialEq, Parse, Writable, DataSize)]
+struct Header {
+    magic: FourCC,
+    #[eyeutil(endian = "big")]
+    version: u16,
+    #[eyeutil(endian = "little")]
+    length: u32,
+    flags: u8,
+    /// Worked out after parsing, so isn't part of the data
+    #[eyeutil(skip)]
+    cached: Option<u32>,
+}
+
+#[derive(Debug, PartialEq, Parse, Writable, DataSize)]
+struct Entry(#[eyeutil(endian = "big")] u16, ZString);
+
+#[derive(Debug, PartialEq, Parse, Writable, DataSize)]
+struct Archive {
+    header: Header,
+    #[eyeutil(len_prefix = "u16", len_endian = "big")]
+    entries: Vec<Entry>,
+    #[eyeutil(len_prefix = "u8")]
+    name: String,
+    #[eyeutil(len_prefix = "u32", endian = "little")]
+    offsets: Vec<u32>,
+    #[eyeutil(data = "(Endian::Little, AddrWidth::W32)")]
+    base: Uaddr,
+}
+
+/// Hand-written, holding a derived type
+struct Wrapper {
+    header: Header,
+}
+impl<F: std::io::Read + std::io::Seek> Parse<F> for Wrapper {
+    fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+        Ok(Wrapper {
+            header: Header::parse(f, ())?,
+        })
+    }
+}
+
+#[derive(Debug, PartialEq, Parse, Writable, DataSize)]
+struct Empty;
+
+#[derive(Debug, PartialEq, Parse, Writable, DataSize)]
+struct Pair<T> {
+    #[eyeutil(endian = "big")]
+    a: T,
+    #[eyeutil(endian = "big")]
+    b: T,
+}
+
+#[derive(Debug, PartialEq, Parse, Writable, DataSize)]
+struct List<T> {
+    #[eyeutil(len_prefix = "u8")]
+    items: Vec<T>,
+    #[eyeutil(skip)]
+    cached: Option<T>,
+}
+
+const HEADER: [u8; 11] = [
+    b'R', b'I', b'F', b'F', // magic
+    0x01, 0x02, // version
+    0x10, 0x00, 0x00, 0x00, // length
+    0x07, // flags
+];
+
+#[test]
+fn test_derive_mixed_endian() {
+    let header = Header::parse(&mut Cursor::new(&HEADER), ()).unwrap();
+    assert_eq!(
+        header,
+        Header {
+            magic: FourCC(*b"RIFF"),
+            version: 0x0102,
+            length: 0x10,
+            flags: 0x7,
+            cached: None,
+        }
+    );
+    assert_eq!(header.data_size(()), 11);
+    assert_roundtrip_bytes::<Header, _>(&HEADER, ());
+
+    // Skipped fields aren't written
+    let mut output = Vec::new();
+    Header {
+        cached: Some(5),
+        ..header
+    }
+    .write_to(&mut output, ())
+    .unwrap();
+    assert_eq!(output, HEADER);
+
+    let wrapper = Wrapper::parse(&mut Cursor::new(&HEADER), ()).unwrap();
+    assert_eq!(wrapper.header.version, 0x0102);
+}
+
+#[test]
+fn test_derive_nested() {
+    let mut data = HEADER.to_vec();
+    data.extend_from_slice(&[
+        0x00, 0x02, // entry count
+        0x00, 0x01, b'a', 0x00, // entry
+        0x00, 0x02, b'b', b'c', 0x00, // entry
+        0x03, b'h', 0xC3, 0xA9, // name
+        0x02, 0x00, 0x00, 0x00, // offset count
+        0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, // offsets
+        0x00, 0x10, 0x00, 0x00, // base
+    ]);
+    let archive = Archive::parse(&mut Cursor::new(&data), ()).unwrap();
+    assert_eq!(
+        archive.entries,
+        [Entry(1, ZString::from("a")), Entry(2, ZString::from("bc"))]
+    );
+    assert_eq!(archive.name, "h\u{E9}");
+    assert_eq!(archive.offsets, [1, 2]);
+    assert_eq!(archive.base, Uaddr(0x1000));
+    assert_eq!(archive.data_size(()), data.len() as u64);
+    assert_roundtrip_bytes::<Archive, _>(&data, ());
+
+    let empty = Empty::parse(&mut Cursor::new(&[]), ()).unwrap();
+    assert_eq!(empty.data_size(()), 0);
+}
+
+#[test]
+fn test_derive_len_prefix_errors() {
+    let archive = |name: String| Archive {
+        header: Header::parse(&mut Cursor::new(&HEADER), ()).unwrap(),
+        entries: Vec::new(),
+        name,
+        offsets: Vec::new(),
+        base: Uaddr(0),
+    };
+
+    let mut output = Vec::new();
+    match archive("x".repeat(256)).write_to(&mut output, ()) {
+        Err(WriteError::TooManyBits) => {}
+        x => panic!("expected too many bits, got {:?}", x),
+    }
+    archive("x".repeat(255)).write_to(&mut output, ()).unwrap();
+
+    let mut data = HEADER.to_vec();
+    data.extend_from_slice(&[0x00, 0x00, 0x02, 0xC3, 0x28]);
+    let e = Archive::parse(&mut Cursor::new(&data), ()).unwrap_err();
+    assert!(matches!(e.root(), ParseError::InvalidByte));
+    assert_eq!(e.to_string(), "name > invalid byte");
+}
+
+#[test]
+fn test_derive_generic() {
+    let data = [0x3C, 0x00, 0xC1, 0x00];
+    let pair: Pair<u16> = Pair::parse(&mut Cursor::new(&data), ()).unwrap();
+    assert_eq!(
+        pair,
+        Pair {
+            a: 0x3C00,
+            b: 0xC100
+        }
+    );
+    assert_roundtrip_bytes::<Pair<u16>, _>(&data, ());
+
+    let pair: Pair<F16> = Pair::parse(&mut Cursor::new(&data), ()).unwrap();
+    assert_eq!((pair.a.to_f32(), pair.b.to_f32()), (1.0, -2.5));
+    assert_eq!(pair.data_size(()), 4);
+    assert_roundtrip_bytes::<Pair<F16>, _>(&data, ());
+
+    let data = [0x02, 0x00, 0x01, b'a', 0x00, 0x00, 0x02, 0x00];
+    let list: List<Entry> = List::parse(&mut Cursor::new(&data), ()).unwrap();
+    assert_eq!(
+        list.items,
+        [Entry(1, ZString::from("a")), Entry(2, ZString::from(""))]
+    );
+    assert_eq!(list.cached, None);
+    assert_eq!(list.data_size(()), data.len() as u64);
+    assert_roundtrip_bytes::<List<Entry>, _>(&data, ());
+}
@@ -0,0 +1,196 @@
+//! Drives the public parsing API with arbitrary bytes. Every parse is allowed to fail, but none
+//! of them may panic (or hang).
+//! The first bytes of the input pick the operation and its parameters, and the rest is the data
+//! that is parsed.
+//!
+//! Run with `cargo +nightly fuzz run parse_api`. Inputs which used to panic are kept in
+//! `fuzz/regressions/parse_api`, and can be checked with
+//! `cargo +nightly fuzz run parse_api fuzz/regressions/parse_api/*`.
+#![no_main]
+
+use eyeutil::{
+    addr::{AddrWidth, Iaddr, Uaddr},
+    framing::{read_stuffed_frame, StuffingConfig},
+    parse::{
+        many, many_count, many_parse, parse_peek, seek_within, take, take_n, take_until, Parse,
+        ParseResult,
+    },
+    sections::SectionMap,
+    slice::{InputLimit, InputSlice, MultiSlice},
+    zstring::{ZString, ZStringArray},
+    Endian,
+};
+use libfuzzer_sys::fuzz_target;
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+/// Takes parameters off the front of the input, giving zeroes once it runs out.
+struct Params<'a> {
+    data: &'a [u8],
+}
+impl<'a> Params<'a> {
+    fn byte(&mut self) -> u8 {
+        match self.data.split_first() {
+            Some((&x, rest)) => {
+                self.data = rest;
+                x
+            }
+            None => 0,
+        }
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_le_bytes([self.byte(), self.byte()])
+    }
+
+    fn u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        for x in bytes.iter_mut() {
+            *x = self.byte();
+        }
+        u64::from_le_bytes(bytes)
+    }
+
+    /// A length that is usually small, but sometimes huge
+    fn len(&mut self) -> u64 {
+        if self.byte() & 0x80 == 0 {
+            u64::from(self.u16())
+        } else {
+            self.u64()
+        }
+    }
+
+    fn seek_from(&mut self) -> SeekFrom {
+        let offset = self.len() as i64;
+        match self.byte() % 5 {
+            0 => SeekFrom::Start(offset as u64),
+            1 => SeekFrom::Current(offset),
+            2 => SeekFrom::Current(offset.wrapping_neg()),
+            3 => SeekFrom::End(offset),
+            _ => SeekFrom::End(offset.wrapping_neg()),
+        }
+    }
+}
+
+fn primitives<F: Read>(f: &mut F, endian: Endian) -> ParseResult<()> {
+    u8::parse(f, ())?;
+    i8::parse(f, ())?;
+    u16::parse(f, endian)?;
+    i16::parse(f, endian)?;
+    u32::parse(f, endian)?;
+    i32::parse(f, endian)?;
+    u64::parse(f, endian)?;
+    i64::parse(f, endian)?;
+    f32::parse(f, endian)?;
+    f64::parse(f, endian)?;
+    for &width in [AddrWidth::W32, AddrWidth::W64].iter() {
+        Uaddr::parse(f, (endian, width))?;
+        Iaddr::parse(f, (endian, width))?;
+    }
+    take_n::<_, 5>(f)?;
+    Ok(())
+}
+
+fn strings<F: Read>(f: &mut F) -> ParseResult<()> {
+    ZString::parse(f, ())?;
+    ZStringArray::<3, 16>::parse(f, ())?;
+    ZStringArray::<0, 4>::parse(f, ())?;
+    Ok(())
+}
+
+fn run(params: &mut Params<'_>, data: &[u8]) -> ParseResult<()> {
+    let op = params.byte();
+    let endian = if params.byte() & 1 == 0 {
+        Endian::Little
+    } else {
+        Endian::Big
+    };
+    let mut f = Cursor::new(data);
+
+    match op % 10 {
+        0 => primitives(&mut f, endian)?,
+        1 => strings(&mut f)?,
+        2 => {
+            take(&mut f, params.len() as usize)?;
+            take_until(&mut f, params.byte(), params.byte() & 1 == 0)?;
+        }
+        3 => {
+            many(&mut f, endian, |f, endian| u16::parse(f, endian))?;
+            f.set_position(0);
+            many_parse::<_, ZString, _>(&mut f, ())?;
+            f.set_position(0);
+            many_parse::<_, (), _>(&mut f, ())?;
+            many_count::<_, u64, _>(&mut f, endian, params.len() as usize)?;
+        }
+        4 => {
+            let start = params.len();
+            let end = params.len();
+            f.seek(SeekFrom::Start(start))?;
+            let mut slice = InputSlice::new(&mut f, start..end)?.strict(params.byte() & 1 == 0);
+            let _ = slice.seek(params.seek_from());
+            let _ = primitives(&mut slice, endian);
+            let _ = slice.stream_position();
+            let _ = slice.remaining();
+            let (sub_start, sub_len) = (params.len(), params.len());
+            let mut sub = slice.slice(sub_start..sub_start.saturating_add(sub_len))?;
+            let _ = sub.seek(params.seek_from());
+            strings(&mut sub)?;
+        }
+        5 => {
+            let extents = (0..params.byte() % 5)
+                .map(|_| {
+                    let start = params.len();
+                    start..start.saturating_add(params.len())
+                })
+                .collect();
+            let mut multi = MultiSlice::new(&mut f, extents);
+            let _ = multi.seek(params.seek_from());
+            let _ = parse_peek::<u32, _, _>(&mut multi, endian);
+            let mut output = Vec::new();
+            multi.read_to_end(&mut output)?;
+        }
+        6 => {
+            let mut slice = InputSlice::new(&mut f, ..)?;
+            let window = usize::from(params.byte() % 16) + 1;
+            let step = usize::from(params.byte() % 16) + 1;
+            for window in slice.windows_iter(window, step).take(64) {
+                window?;
+            }
+        }
+        7 => {
+            let mut limit = InputLimit::new(&mut f, params.len());
+            let _ = limit.seek(params.seek_from());
+            strings(&mut limit)?;
+        }
+        8 => {
+            let cfg = StuffingConfig {
+                start: params.byte(),
+                end: params.byte(),
+                escape: params.byte(),
+                mask: params.byte(),
+            };
+            read_stuffed_frame(&mut f, StuffingConfig::HDLC)?;
+            read_stuffed_frame(&mut f, cfg)?;
+        }
+        _ => {
+            let mut map = SectionMap::with_file_len(data.len() as u64);
+            map.insert("a", params.len(), params.len())?;
+            map.insert_overlapping("b", params.len(), params.len())?;
+            let _ = map.gaps();
+            seek_within(&mut f, params.len(), None)?;
+            let mut slice = map.slice_of(&mut f, "b")?;
+            primitives(&mut slice, endian)?;
+        }
+    }
+
+    Ok(())
+}
+
+fuzz_target!(|input: &[u8]| {
+    // The parameters are pulled from the front, and the data is whatever is after the first
+    // 64 bytes, so that changing a parameter doesn't shift the data
+    let split = input.len().min(64);
+    let mut params = Params {
+        data: &input[..split],
+    };
+    let _ = run(&mut params, &input[split..]);
+});
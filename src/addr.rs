@@ -0,0 +1,353 @@
+//! Offsets and addresses whose width depends on the file, such as ELF's 32 and 64 bit classes.
+//! The width is given alongside the endianness as the data, `(Endian, AddrWidth)`, so one
+//! struct definition can handle both.
+//! `usize` and `isize` take the same data, for when the value is used as a native size.
+
+use crate::{
+    data_size::DataSize,
+    parse::{Parse, ParseError, ParseResult},
+    writable::{Writable, WriteError, WriteResult},
+    Endian,
+};
+use std::{
+    convert::TryFrom,
+    io::{Read, Write},
+};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum AddrWidth {
+    W32,
+    W64,
+}
+impl AddrWidth {
+    /// Returns the size in bytes
+    #[inline]
+    pub fn size(self) -> u64 {
+        match self {
+            AddrWidth::W32 => 4,
+            AddrWidth::W64 => 8,
+        }
+    }
+}
+
+/// Unsigned address, stored as 4 or 8 bytes depending on the `AddrWidth`.
+/// 32-bit values are zero-extended.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Uaddr(pub u64);
+impl<F: Read> Parse<F, (Endian, AddrWidth)> for Uaddr {
+    #[inline]
+    fn parse(f: &mut F, (endian, width): (Endian, AddrWidth)) -> ParseResult<Self> {
+        Ok(Uaddr(match width {
+            AddrWidth::W32 => u64::from(u32::parse(f, endian)?),
+            AddrWidth::W64 => u64::parse(f, endian)?,
+        }))
+    }
+}
+impl Writable<(Endian, AddrWidth)> for Uaddr {
+    /// Errors with `WriteError::TooManyBits` if the value doesn't fit in the width.
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, (endian, width): (Endian, AddrWidth)) -> WriteResult
+    where
+        W: Write,
+    {
+        match width {
+            AddrWidth::W32 => u32::try_from(self.0)
+                .map_err(|_| WriteError::TooManyBits)?
+                .write_to(w, endian),
+            AddrWidth::W64 => self.0.write_to(w, endian),
+        }
+    }
+}
+impl DataSize<(Endian, AddrWidth)> for Uaddr {
+    #[inline]
+    fn data_size(&self, (_, width): (Endian, AddrWidth)) -> u64 {
+        width.size()
+    }
+}
+
+/// Signed address, stored as 4 or 8 bytes depending on the `AddrWidth`.
+/// 32-bit values are sign-extended.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct Iaddr(pub i64);
+impl<F: Read> Parse<F, (Endian, AddrWidth)> for Iaddr {
+    #[inline]
+    fn parse(f: &mut F, (endian, width): (Endian, AddrWidth)) -> ParseResult<Self> {
+        Ok(Iaddr(match width {
+            AddrWidth::W32 => i64::from(i32::parse(f, endian)?),
+            AddrWidth::W64 => i64::parse(f, endian)?,
+        }))
+    }
+}
+impl Writable<(Endian, AddrWidth)> for Iaddr {
+    /// Errors with `WriteError::TooManyBits` if the value doesn't fit in the width.
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, (endian, width): (Endian, AddrWidth)) -> WriteResult
+    where
+        W: Write,
+    {
+        match width {
+            AddrWidth::W32 => i32::try_from(self.0)
+                .map_err(|_| WriteError::TooManyBits)?
+                .write_to(w, endian),
+            AddrWidth::W64 => self.0.write_to(w, endian),
+        }
+    }
+}
+impl DataSize<(Endian, AddrWidth)> for Iaddr {
+    #[inline]
+    fn data_size(&self, (_, width): (Endian, AddrWidth)) -> u64 {
+        width.size()
+    }
+}
+
+/// Implements the address impls for a native integer by going through the address type
+macro_rules! impl_addr_native {
+    ($($typ:ty => $addr:ident($inner:ty)),*) => {
+        $(
+            /// Errors with `ParseError::IntegerOverflow` if the value doesn't fit on this target.
+            impl<F: Read> Parse<F, (Endian, AddrWidth)> for $typ {
+                #[inline]
+                fn parse(f: &mut F, d: (Endian, AddrWidth)) -> ParseResult<Self> {
+                    <$typ>::try_from($addr::parse(f, d)?.0).map_err(|_| ParseError::IntegerOverflow)
+                }
+            }
+            impl Writable<(Endian, AddrWidth)> for $typ {
+                /// Errors with `WriteError::TooManyBits` if the value doesn't fit in the width.
+                #[inline]
+                fn write_to<W>(&self, w: &mut W, d: (Endian, AddrWidth)) -> WriteResult
+                where
+                    W: Write,
+                {
+                    let value = <$inner>::try_from(*self).map_err(|_| WriteError::TooManyBits)?;
+                    $addr(value).write_to(w, d)
+                }
+            }
+            impl DataSize<(Endian, AddrWidth)> for $typ {
+                #[inline]
+                fn data_size(&self, (_, width): (Endian, AddrWidth)) -> u64 {
+                    width.size()
+                }
+            }
+        )*
+    };
+}
+impl_addr_native!(usize => Uaddr(u64), isize => Iaddr(i64));
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// One definition for both widths
+    #[derive(Debug, PartialEq)]
+    struct Segment {
+        offset: Uaddr,
+        size: Uaddr,
+        flags: u32,
+        delta: Iaddr,
+    }
+    impl<F: Read> Parse<F, (Endian, AddrWidth)> for Segment {
+        fn parse(f: &mut F, d: (Endian, AddrWidth)) -> ParseResult<Self> {
+            Ok(Segment {
+                offset: Uaddr::parse(f, d)?,
+                size: Uaddr::parse(f, d)?,
+                flags: u32::parse(f, d.0)?,
+                delta: Iaddr::parse(f, d)?,
+            })
+        }
+    }
+    impl Writable<(Endian, AddrWidth)> for Segment {
+        fn write_to<W>(&self, w: &mut W, d: (Endian, AddrWidth)) -> WriteResult
+        where
+            W: Write,
+        {
+            self.offset.write_to(w, d)?;
+            self.size.write_to(w, d)?;
+            self.flags.write_to(w, d.0)?;
+            self.delta.write_to(w, d)
+        }
+    }
+    impl DataSize<(Endian, AddrWidth)> for Segment {
+        fn data_size(&self, d: (Endian, AddrWidth)) -> u64 {
+            self.offset.data_size(d) + self.size.data_size(d) + 4 + self.delta.data_size(d)
+        }
+    }
+
+    #[test]
+    fn test_addr_widths() {
+        let expected = Segment {
+            offset: Uaddr(0x1000),
+            size: Uaddr(0x8000_0020),
+            flags: 0x5,
+            delta: Iaddr(-2),
+        };
+
+        let fixture_32 = [
+            0x00, 0x10, 0x00, 0x00, // offset
+            0x20, 0x00, 0x00, 0x80, // size
+            0x05, 0x00, 0x00, 0x00, // flags
+            0xFE, 0xFF, 0xFF, 0xFF, // delta
+        ];
+        let d_32 = (Endian::Little, AddrWidth::W32);
+        let parsed = Segment::parse(&mut Cursor::new(&fixture_32), d_32).unwrap();
+        assert_eq!(parsed, expected);
+        assert_eq!(parsed.data_size(d_32), 16);
+
+        let fixture_64 = [
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, // offset
+            0x00, 0x00, 0x00, 0x00, 0x80, 0x00, 0x00, 0x20, // size
+            0x00, 0x00, 0x00, 0x05, // flags
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE, // delta
+        ];
+        let d_64 = (Endian::Big, AddrWidth::W64);
+        let parsed = Segment::parse(&mut Cursor::new(&fixture_64), d_64).unwrap();
+        assert_eq!(parsed, expected);
+        assert_eq!(parsed.data_size(d_64), 28);
+
+        let mut output = Vec::new();
+        expected.write_to(&mut output, d_32).unwrap();
+        assert_eq!(output, fixture_32);
+        let mut output = Vec::new();
+        expected.write_to(&mut output, d_64).unwrap();
+        assert_eq!(output, fixture_64);
+    }
+
+    #[test]
+    fn test_addr_out_of_range() {
+        let d_32 = (Endian::Little, AddrWidth::W32);
+        let mut output = Vec::new();
+        match Uaddr(0x1_0000_0000).write_to(&mut output, d_32) {
+            Err(WriteError::TooManyBits) => {}
+            x => panic!("expected too many bits, got {:?}", x),
+        }
+        match Iaddr(i64::from(i32::MIN) - 1).write_to(&mut output, d_32) {
+            Err(WriteError::TooManyBits) => {}
+            x => panic!("expected too many bits, got {:?}", x),
+        }
+        assert!(output.is_empty());
+
+        Uaddr(u64::from(u32::MAX))
+            .write_to(&mut output, d_32)
+            .unwrap();
+        Iaddr(i64::from(i32::MIN))
+            .write_to(&mut output, d_32)
+            .unwrap();
+        Uaddr(0x1_0000_0000)
+            .write_to(&mut output, (Endian::Little, AddrWidth::W64))
+            .unwrap();
+        assert_eq!(output.len(), 16);
+    }
+
+    #[test]
+    fn test_addr_native() {
+        let d_32 = (Endian::Little, AddrWidth::W32);
+        let d_64 = (Endian::Big, AddrWidth::W64);
+        let data = [0x20, 0x00, 0x00, 0x80];
+        assert_eq!(
+            usize::parse(&mut Cursor::new(&data), d_32).unwrap(),
+            0x8000_0020
+        );
+        assert_eq!(
+            isize::parse(&mut Cursor::new(&data), d_32).unwrap(),
+            -0x7FFF_FFE0
+        );
+        let data = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00];
+        assert_eq!(usize::parse(&mut Cursor::new(&data), d_64).unwrap(), 0x1000);
+        assert_eq!(0usize.data_size(d_32), 4);
+        assert_eq!(0isize.data_size(d_64), 8);
+
+        let mut output = Vec::new();
+        0x1000usize.write_to(&mut output, d_64).unwrap();
+        assert_eq!(output, data);
+        let mut output = Vec::new();
+        (-2isize).write_to(&mut output, d_32).unwrap();
+        assert_eq!(output, [0xFE, 0xFF, 0xFF, 0xFF]);
+
+        // Doesn't fit in the width
+        if let Ok(value) = usize::try_from(0x1_0000_0000u64) {
+            let mut output = Vec::new();
+            match value.write_to(&mut output, d_32) {
+                Err(WriteError::TooManyBits) => {}
+                x => panic!("expected too many bits, got {:?}", x),
+            }
+            assert!(output.is_empty());
+        }
+        // Doesn't fit on the target
+        if usize::try_from(u64::MAX).is_err() {
+            match usize::parse(&mut Cursor::new([0xFF; 8]), d_64) {
+                Err(ParseError::IntegerOverflow) => {}
+                x => panic!("expected integer overflow, got {:?}", x),
+            }
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Entry {
+        kind: u8,
+        offset: Uaddr,
+    }
+    crate::impl_parse!(Entry, [
+        kind: u: u8,
+        offset: (Endian::Big, AddrWidth::W32): Uaddr
+    ]);
+    crate::impl_struct_data_size!(Entry, [kind, offset: (Endian::Big, AddrWidth::W32)]);
+    crate::impl_writable!(Entry, [
+        kind: u: u8,
+        offset: (Endian::Big, AddrWidth::W32): Uaddr
+    ]);
+
+    /// Takes the width as its data, so one definition handles both
+    #[derive(Debug, PartialEq)]
+    struct Table {
+        count: u8,
+        base: Uaddr,
+        delta: Iaddr,
+    }
+    crate::impl_parse!(generic data [] Table where [Uaddr: Parse<F, D>, Iaddr: Parse<F, D>], [
+        count: u: u8,
+        base: d: Uaddr,
+        delta: d: Iaddr
+    ]);
+    crate::impl_struct_data_size!(generic data [] Table where [
+        Uaddr: DataSize<D>,
+        Iaddr: DataSize<D>
+    ], [count, base: d, delta: d]);
+
+    #[test]
+    fn test_addr_macro_data() {
+        let entry = Entry {
+            kind: 0x1,
+            offset: Uaddr(0x20),
+        };
+        let mut output = Vec::new();
+        entry.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, [0x1, 0x0, 0x0, 0x0, 0x20]);
+        assert_eq!(entry.data_size(()), 5);
+        assert_eq!(Entry::parse(&mut Cursor::new(&output), ()).unwrap(), entry);
+
+        let expected = Table {
+            count: 2,
+            base: Uaddr(0x1000),
+            delta: Iaddr(-2),
+        };
+        let d_32 = (Endian::Little, AddrWidth::W32);
+        let data_32 = [
+            0x02, // count
+            0x00, 0x10, 0x00, 0x00, // base
+            0xFE, 0xFF, 0xFF, 0xFF, // delta
+        ];
+        let parsed = Table::parse(&mut Cursor::new(&data_32), d_32).unwrap();
+        assert_eq!(parsed, expected);
+        assert_eq!(parsed.data_size(d_32), 9);
+
+        let d_64 = (Endian::Big, AddrWidth::W64);
+        let data_64 = [
+            0x02, // count
+            0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x00, // base
+            0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE, // delta
+        ];
+        let parsed = Table::parse(&mut Cursor::new(&data_64), d_64).unwrap();
+        assert_eq!(parsed, expected);
+        assert_eq!(parsed.data_size(d_64), 17);
+    }
+}
@@ -0,0 +1,304 @@
+//! Parsing from tokio's `AsyncRead`, such as length-prefixed frames off a `TcpStream`. Only
+//! available with the `async-tokio` feature.
+//!
+//! [AsyncParse] mirrors `Parse` for the primitives, and this module has async versions of
+//! `single`, `take`, `take_n`, `take_until` and `tag`. The helpers that need `Seek` are left
+//! out.
+//!
+//! There is no blanket impl of `AsyncParse` for every `Parse` type. A sync parser pulls bytes
+//! as it goes, so there's no way to know how many to read for it ahead of time (and such an
+//! impl would conflict with the primitive ones anyway). Instead, when the size is known, as
+//! with a length-prefixed frame, [parse_frame] reads that many bytes and then runs the sync
+//! parser over them.
+
+use crate::{
+    parse::{
+        tag_mismatch, utf8_char, utf8_len, Parse, ParseError, ParseResult, TagBuffer,
+        MAX_PREALLOCATE,
+    },
+    Endian,
+};
+use std::{future::Future, io::Cursor};
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+/// Like `Parse`, but reading from an `AsyncRead`.
+/// Named `parse_async` so that it doesn't clash with `Parse::parse` when both are imported.
+pub trait AsyncParse<R, D = ()>: Sized + 'static
+where
+    R: AsyncRead + Unpin,
+{
+    fn parse_async(r: &mut R, d: D) -> impl Future<Output = ParseResult<Self>>;
+}
+
+/// Implements `AsyncParse` by reading the bytes of the type, and then using its `Parse` impl
+/// on them.
+macro_rules! impl_async_parse {
+    ($d:ty, [$($typ:ty),*]) => {
+        $(
+            impl<R> AsyncParse<R, $d> for $typ
+            where
+                R: AsyncRead + Unpin,
+            {
+                #[inline]
+                async fn parse_async(r: &mut R, d: $d) -> ParseResult<Self> {
+                    let data = take_n::<R, { std::mem::size_of::<$typ>() }>(r).await?;
+                    <$typ as Parse<&[u8], $d>>::parse(&mut &data[..], d)
+                }
+            }
+        )*
+    };
+}
+impl_async_parse!((), [u8, i8]);
+impl_async_parse!(Endian, [u16, i16, u32, i32, u64, i64, f32, f64]);
+/// Reads a UTF-8 encoded scalar value, see the `Parse` impl.
+impl<R> AsyncParse<R> for char
+where
+    R: AsyncRead + Unpin,
+{
+    async fn parse_async(r: &mut R, _d: ()) -> ParseResult<Self> {
+        let mut data = [0u8; 4];
+        data[0] = single(r).await?;
+        let len = utf8_len(data[0]).ok_or(ParseError::InvalidByte)?;
+        r.read_exact(&mut data[1..len]).await?;
+        utf8_char(&data[..len])
+    }
+}
+
+pub async fn single<R>(r: &mut R) -> ParseResult<u8>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut output = [0u8];
+    r.read_exact(&mut output).await?;
+    Ok(output[0])
+}
+
+/// Errors with `ParseError::ExpectedBytesFound` if [r] ends before [amount] bytes.
+/// Note: like the sync `take`, [amount] is only allocated as the data is read.
+pub async fn take<R>(r: &mut R, amount: usize) -> ParseResult<Vec<u8>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut output = Vec::with_capacity(amount.min(MAX_PREALLOCATE));
+    let found = (&mut *r)
+        .take(amount as u64)
+        .read_to_end(&mut output)
+        .await?;
+    if found != amount {
+        return Err(ParseError::ExpectedBytesFound(amount, found));
+    }
+    Ok(output)
+}
+
+pub async fn take_n<R, const N: usize>(r: &mut R) -> ParseResult<[u8; N]>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut output = [0_u8; N];
+    r.read_exact(&mut output).await?;
+    Ok(output)
+}
+
+/// Takes all bytes until (and including) the terminator, which is only in the output if
+/// [include_terminator] is true.
+pub async fn take_until<R>(
+    r: &mut R,
+    terminator: u8,
+    include_terminator: bool,
+) -> ParseResult<Vec<u8>>
+where
+    R: AsyncRead + Unpin,
+{
+    let mut result = Vec::new();
+    loop {
+        let value = single(r).await?;
+        if value == terminator {
+            if include_terminator {
+                result.push(value);
+            }
+            break;
+        }
+
+        result.push(value);
+    }
+
+    Ok(result)
+}
+
+/// Errors with `ParseError::TagMismatch` if the upcoming bytes aren't [data].
+/// Note: like the sync `tag`, the whole tag is read before comparing.
+pub async fn tag<R, X>(r: &mut R, data: &[X]) -> ParseResult<()>
+where
+    R: AsyncRead + Unpin,
+    X: PartialEq<u8>,
+{
+    let mut buf = TagBuffer::new(data.len());
+    r.read_exact(buf.as_mut()).await?;
+    tag_mismatch(data, buf.as_mut())
+}
+
+/// Reads [amount] bytes, and parses [P] from them with its sync `Parse` impl.
+/// Errors with `ParseError::ExpectedEOF` if [P] doesn't use the whole frame.
+pub async fn parse_frame<R, P, D>(r: &mut R, amount: usize, d: D) -> ParseResult<P>
+where
+    R: AsyncRead + Unpin,
+    P: Parse<Cursor<Vec<u8>>, D>,
+{
+    let data = take(r, amount).await?;
+    let len = data.len() as u64;
+    let mut cursor = Cursor::new(data);
+    let value = P::parse(&mut cursor, d)?;
+    if cursor.position() != len {
+        return Err(ParseError::ExpectedEOF);
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::ErrorKind,
+        pin::Pin,
+        sync::Arc,
+        task::{Context, Poll, Wake, Waker},
+    };
+    use tokio::io::AsyncWriteExt;
+
+    struct NoopWake;
+    impl Wake for NoopWake {
+        fn wake(self: Arc<Self>) {}
+    }
+
+    fn poll_once<T>(future: &mut Pin<Box<impl Future<Output = T>>>) -> Poll<T> {
+        let waker = Waker::from(Arc::new(NoopWake));
+        future.as_mut().poll(&mut Context::from_waker(&waker))
+    }
+
+    /// Runs [future], which must not have to wait, as all of its data is written beforehand.
+    /// This avoids needing tokio's runtime (and so it as a dev-dependency).
+    fn ready<T>(future: impl Future<Output = T>) -> T {
+        match poll_once(&mut Box::pin(future)) {
+            Poll::Ready(value) => value,
+            Poll::Pending => panic!("future was not ready"),
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Message {
+        id: u16,
+        value: u32,
+    }
+    impl<F: std::io::Read> Parse<F> for Message {
+        fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+            Ok(Message {
+                id: u16::parse(f, Endian::Big)?,
+                value: u32::parse(f, Endian::Big)?,
+            })
+        }
+    }
+
+    #[test]
+    fn test_async_primitives() {
+        let data: &[u8] = &[
+            0x80, 0xFF, 0x01, 0x02, 0x01, 0x02, 0x03, 0x04, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF,
+            0xFF, 0xFE, 0x00, 0x00, 0x80, 0x3F, 0xC3, 0xA9,
+        ];
+        let mut r = data;
+        assert_eq!(ready(u8::parse_async(&mut r, ())).unwrap(), 0x80);
+        assert_eq!(ready(i8::parse_async(&mut r, ())).unwrap(), -1);
+        assert_eq!(
+            ready(u16::parse_async(&mut r, Endian::Big)).unwrap(),
+            0x0102
+        );
+        assert_eq!(
+            ready(u32::parse_async(&mut r, Endian::Little)).unwrap(),
+            0x04030201
+        );
+        assert_eq!(ready(i64::parse_async(&mut r, Endian::Big)).unwrap(), -2);
+        assert_eq!(
+            ready(f32::parse_async(&mut r, Endian::Little)).unwrap(),
+            1.0
+        );
+        assert_eq!(ready(char::parse_async(&mut r, ())).unwrap(), '\u{E9}');
+        assert!(r.is_empty());
+
+        match ready(u16::parse_async(&mut r, Endian::Big)) {
+            Err(ParseError::Io(e)) => assert_eq!(e.kind(), ErrorKind::UnexpectedEof),
+            x => panic!("expected eof, got {:?}", x),
+        }
+        let mut r: &[u8] = &[0xC3, 0x28];
+        match ready(char::parse_async(&mut r, ())) {
+            Err(ParseError::InvalidByte) => {}
+            x => panic!("expected invalid byte, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_async_helpers() {
+        let mut r: &[u8] = b"RIFFabc\0defghij";
+        ready(tag(&mut r, b"RIFF")).unwrap();
+        assert_eq!(ready(take_until(&mut r, 0, false)).unwrap(), b"abc");
+        assert_eq!(ready(take_n::<_, 2>(&mut r)).unwrap(), *b"de");
+        assert_eq!(ready(take(&mut r, 3)).unwrap(), b"fgh");
+        assert_eq!(ready(single(&mut r)).unwrap(), b'i');
+        match ready(tag(&mut r, b"k")) {
+            Err(ParseError::TagMismatch(0)) => {}
+            x => panic!("expected a tag mismatch, got {:?}", x),
+        }
+        match ready(take(&mut r, 2)) {
+            Err(ParseError::ExpectedBytesFound(2, 0)) => {}
+            x => panic!("expected a short read, got {:?}", x),
+        }
+        assert!(ready(take_until(&mut &b"abc"[..], 0, true)).is_err());
+    }
+
+    async fn read_frame<R>(r: &mut R) -> ParseResult<Message>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let len = u16::parse_async(r, Endian::Big).await?;
+        parse_frame(r, usize::from(len), ()).await
+    }
+
+    #[test]
+    fn test_async_duplex_frames() {
+        let (mut client, mut server) = tokio::io::duplex(64);
+
+        // Only part of the frame has arrived
+        let frame = [0x00, 0x06, 0x00, 0x07, 0x01, 0x02, 0x03, 0x04];
+        ready(client.write_all(&frame[..5])).unwrap();
+        let mut future = Box::pin(read_frame(&mut server));
+        // So that it can be spawned onto a multithreaded runtime
+        fn assert_send<T: Send>(_: &T) {}
+        assert_send(&future);
+        assert!(poll_once(&mut future).is_pending());
+        ready(client.write_all(&frame[5..])).unwrap();
+        match poll_once(&mut future) {
+            Poll::Ready(message) => assert_eq!(
+                message.unwrap(),
+                Message {
+                    id: 7,
+                    value: 0x01020304
+                }
+            ),
+            Poll::Pending => panic!("expected the frame to be ready"),
+        }
+        drop(future);
+
+        // A frame that is longer than the message
+        ready(client.write_all(&[0x00, 0x07, 0, 0, 0, 0, 0, 0, 0])).unwrap();
+        match ready(read_frame(&mut server)) {
+            Err(ParseError::ExpectedEOF) => {}
+            x => panic!("expected ExpectedEOF, got {:?}", x),
+        }
+
+        // The other end closing in the middle of a frame
+        ready(client.write_all(&[0x00, 0x06, 0x00])).unwrap();
+        drop(client);
+        match ready(read_frame(&mut server)) {
+            Err(ParseError::ExpectedBytesFound(6, 1)) => {}
+            x => panic!("expected a short frame, got {:?}", x),
+        }
+    }
+}
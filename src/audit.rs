@@ -0,0 +1,214 @@
+//! Test utilities for catching endianness mistakes in `Parse` impls.
+//! A parser that uses the wrong endianness for a field still passes its tests if the fixture's
+//! value reads the same in both byte orders (0, 1, palindromic values), so these help make sure
+//! the fixtures can actually tell the difference.
+
+use crate::{parse::Parse, Endian};
+use std::{fmt::Debug, io::Cursor};
+
+/// Parses the fixture for each endianness with the matching data, and again with the data for
+/// the opposite endianness, asserting that the two results differ.
+/// Also asserts that the little and big endian fixtures parse to the same value, since they
+/// should be encodings of the same thing.
+/// Panics with a description of the problem if the fixture can't distinguish endianness, which
+/// usually means the values in it are too symmetric to catch bugs.
+pub fn assert_endian_sensitive<T, D, M, DF>(make_fixture: M, d_for: DF)
+where
+    T: Parse<Cursor<Vec<u8>>, D> + PartialEq + Debug,
+    M: Fn(Endian) -> Vec<u8>,
+    DF: Fn(Endian) -> D,
+{
+    let type_name = std::any::type_name::<T>();
+    let mut values = Vec::with_capacity(2);
+    for &endian in [Endian::Little, Endian::Big].iter() {
+        let opposite = endian.opposite();
+        let fixture = make_fixture(endian);
+
+        let correct = match T::parse(&mut Cursor::new(fixture.clone()), d_for(endian)) {
+            Ok(x) => x,
+            Err(e) => panic!(
+                "{:?} fixture for {} failed to parse with {:?} data: {:?}\nfixture: {:02X?}",
+                endian, type_name, endian, e, fixture
+            ),
+        };
+
+        // Failing to parse at all with the wrong endianness also counts as distinguishing them
+        if let Ok(wrong) = T::parse(&mut Cursor::new(fixture.clone()), d_for(opposite)) {
+            if wrong == correct {
+                panic!(
+                    "{:?} fixture for {} parses the same with {:?} data, so it can't catch \
+                     endianness mistakes. Use less symmetric values (see fixture_distinct_values)\
+                     \nvalue: {:?}\nfixture: {:02X?}",
+                    endian, type_name, opposite, correct, fixture
+                );
+            }
+        }
+
+        values.push(correct);
+    }
+
+    if values[0] != values[1] {
+        panic!(
+            "Little and big endian fixtures for {} parse to different values\nlittle: {:?}\nbig: {:?}",
+            type_name, values[0], values[1]
+        );
+    }
+}
+
+/// Integers that read differently in little and big endian, for use in fixtures.
+/// Every byte within each value is distinct and nonzero.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct DistinctValues {
+    pub u16_value: u16,
+    pub u32_value: u32,
+    pub u64_value: u64,
+}
+impl DistinctValues {
+    #[inline]
+    pub fn i16_value(&self) -> i16 {
+        self.u16_value as i16
+    }
+
+    #[inline]
+    pub fn i32_value(&self) -> i32 {
+        self.u32_value as i32
+    }
+
+    #[inline]
+    pub fn i64_value(&self) -> i64 {
+        self.u64_value as i64
+    }
+}
+
+/// Produces endian-asymmetric integers, deterministic for a given [seed].
+pub fn fixture_distinct_values(seed: u64) -> DistinctValues {
+    // splitmix64
+    let mut state = seed;
+    let mut next = move || {
+        state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    };
+
+    let mut distinct_bytes = |count: usize| -> u64 {
+        let mut bytes: Vec<u8> = Vec::with_capacity(count);
+        while bytes.len() < count {
+            for byte in next().to_le_bytes().iter() {
+                if bytes.len() < count && *byte != 0 && !bytes.contains(byte) {
+                    bytes.push(*byte);
+                }
+            }
+        }
+
+        bytes
+            .iter()
+            .rev()
+            .fold(0u64, |acc, byte| (acc << 8) | u64::from(*byte))
+    };
+
+    DistinctValues {
+        u16_value: distinct_bytes(2) as u16,
+        u32_value: distinct_bytes(4) as u32,
+        u64_value: distinct_bytes(8),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::ParseResult;
+    use std::io::Read;
+
+    #[derive(Debug, PartialEq)]
+    struct Header {
+        length: u32,
+        kind: u16,
+    }
+    impl<F: Read> Parse<F, Endian> for Header {
+        fn parse(f: &mut F, endian: Endian) -> ParseResult<Self> {
+            Ok(Header {
+                length: u32::parse(f, endian)?,
+                kind: u16::parse(f, endian)?,
+            })
+        }
+    }
+
+    /// Accidentally always parses [kind] as little endian
+    #[derive(Debug, PartialEq)]
+    struct BuggyHeader {
+        length: u32,
+        kind: u16,
+    }
+    impl<F: Read> Parse<F, Endian> for BuggyHeader {
+        fn parse(f: &mut F, endian: Endian) -> ParseResult<Self> {
+            Ok(BuggyHeader {
+                length: u32::parse(f, endian)?,
+                kind: u16::parse(f, Endian::Little)?,
+            })
+        }
+    }
+
+    fn header_fixture(length: u32, kind: u16, endian: Endian) -> Vec<u8> {
+        let mut output = Vec::new();
+        match endian {
+            Endian::Little => {
+                output.extend_from_slice(&length.to_le_bytes());
+                output.extend_from_slice(&kind.to_le_bytes());
+            }
+            Endian::Big => {
+                output.extend_from_slice(&length.to_be_bytes());
+                output.extend_from_slice(&kind.to_be_bytes());
+            }
+        }
+        output
+    }
+
+    #[test]
+    fn test_endian_sensitive() {
+        let values = fixture_distinct_values(1);
+        assert_endian_sensitive::<Header, _, _, _>(
+            |endian| header_fixture(values.u32_value, values.u16_value, endian),
+            |endian| endian,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "can't catch endianness mistakes")]
+    fn test_endian_symmetric_fixture() {
+        // Palindromic value, so it parses the same either way
+        assert_endian_sensitive::<u32, _, _, _>(|_| vec![0x12, 0x34, 0x34, 0x12], |endian| endian);
+    }
+
+    #[test]
+    #[should_panic(expected = "parse to different values")]
+    fn test_endian_buggy_parser() {
+        let values = fixture_distinct_values(2);
+        assert_endian_sensitive::<BuggyHeader, _, _, _>(
+            |endian| header_fixture(values.u32_value, values.u16_value, endian),
+            |endian| endian,
+        );
+    }
+
+    #[test]
+    fn test_fixture_distinct_values() {
+        assert_eq!(fixture_distinct_values(5), fixture_distinct_values(5));
+        for seed in 0..256 {
+            let values = fixture_distinct_values(seed);
+            let check = |bytes: &[u8]| {
+                for (i, byte) in bytes.iter().enumerate() {
+                    assert_ne!(*byte, 0);
+                    assert!(!bytes[i + 1..].contains(byte));
+                }
+            };
+            check(&values.u16_value.to_le_bytes());
+            check(&values.u32_value.to_le_bytes());
+            check(&values.u64_value.to_le_bytes());
+            assert_ne!(values.u16_value, values.u16_value.swap_bytes());
+            assert_ne!(values.u32_value, values.u32_value.swap_bytes());
+            assert_ne!(values.u64_value, values.u64_value.swap_bytes());
+            assert_ne!(values.i32_value(), values.i32_value().swap_bytes());
+        }
+    }
+}
@@ -0,0 +1,382 @@
+//! Reading and writing fields that aren't a whole number of bytes.
+
+use crate::{
+    parse::{ParseError, ParseResult},
+    read_if_possible,
+    writable::{write_full, WriteError, WriteResult},
+};
+use std::io::{Read, Write};
+
+/// Which bit of each byte is read first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitOrder {
+    /// The most significant bit is first, and the first bits read are the most significant of
+    /// the value. Used by most headers.
+    MsbFirst,
+    /// The least significant bit is first, and the first bits read are the least significant of
+    /// the value. Used by deflate and similar.
+    LsbFirst,
+}
+
+/// Wraps a reader, reading from it a number of bits at a time.
+/// Bytes are only read from the inner reader once their bits are needed.
+#[derive(Debug)]
+pub struct BitReader<F: Read> {
+    inner: F,
+    order: BitOrder,
+    /// The byte currently being read from
+    current: u8,
+    /// How many bits of [current] haven't been read yet
+    remaining: u8,
+    consumed: u64,
+}
+impl<F> BitReader<F>
+where
+    F: Read,
+{
+    #[inline]
+    pub fn new(inner: F, order: BitOrder) -> Self {
+        BitReader {
+            inner,
+            order,
+            current: 0,
+            remaining: 0,
+            consumed: 0,
+        }
+    }
+
+    #[inline]
+    pub fn order(&self) -> BitOrder {
+        self.order
+    }
+
+    /// Returns the number of bits read so far, including those skipped by
+    /// [BitReader::align_to_byte].
+    #[inline]
+    pub fn bits_consumed(&self) -> u64 {
+        self.consumed
+    }
+
+    /// Reads [n] bits, which must be at most 64, into the low bits of the result.
+    /// Errors with `ParseError::BitCountTooLarge` if [n] is more than 64, and with
+    /// `ParseError::UnexpectedEOF` if the inner reader ends first.
+    /// Note: if the inner reader ends, the bits that were there are still consumed.
+    pub fn read_bits(&mut self, n: u8) -> ParseResult<u64> {
+        if n > 64 {
+            return Err(ParseError::BitCountTooLarge(n));
+        }
+
+        let mut value = 0u64;
+        let mut read = 0u8;
+        while read < n {
+            if self.remaining == 0 {
+                let mut byte = [0u8];
+                if read_if_possible(&mut self.inner, &mut byte)? == 0 {
+                    return Err(ParseError::UnexpectedEOF);
+                }
+                self.current = byte[0];
+                self.remaining = 8;
+            }
+
+            let take = (n - read).min(self.remaining);
+            let mask = ((1u16 << take) - 1) as u8;
+            match self.order {
+                BitOrder::MsbFirst => {
+                    let bits = (self.current >> (self.remaining - take)) & mask;
+                    // Shifting by 64 at once would overflow, so this is in two steps
+                    value = (value << (take - 1) << 1) | u64::from(bits);
+                }
+                BitOrder::LsbFirst => {
+                    let bits = (self.current >> (8 - self.remaining)) & mask;
+                    value |= u64::from(bits) << read;
+                }
+            }
+            self.remaining -= take;
+            self.consumed += u64::from(take);
+            read += take;
+        }
+        Ok(value)
+    }
+
+    #[inline]
+    pub fn read_bit(&mut self) -> ParseResult<bool> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    /// Skips the rest of the current byte, so that the next read starts at a byte boundary.
+    /// Returns the number of bits skipped.
+    #[inline]
+    pub fn align_to_byte(&mut self) -> u8 {
+        let skipped = self.remaining;
+        self.consumed += u64::from(skipped);
+        self.remaining = 0;
+        skipped
+    }
+
+    /// Note: any unread bits of the current byte are lost.
+    #[inline]
+    pub fn into_inner(self) -> F {
+        self.inner
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &F {
+        &self.inner
+    }
+
+    /// Note: reading from the inner reader through this handle skips the bytes for any further
+    /// bit reads.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut F {
+        &mut self.inner
+    }
+}
+
+/// Wraps a writer, writing to it a number of bits at a time. The counterpart of [BitReader].
+/// Bytes are written to the inner writer once all of their bits are filled in, so a partial
+/// byte must be finished with [BitWriter::align_to_byte] before the end.
+#[derive(Debug)]
+pub struct BitWriter<W: Write> {
+    inner: W,
+    order: BitOrder,
+    /// The byte currently being filled in
+    current: u8,
+    /// How many bits of [current] have been filled in
+    filled: u8,
+    written: u64,
+}
+impl<W> BitWriter<W>
+where
+    W: Write,
+{
+    #[inline]
+    pub fn new(inner: W, order: BitOrder) -> Self {
+        BitWriter {
+            inner,
+            order,
+            current: 0,
+            filled: 0,
+            written: 0,
+        }
+    }
+
+    #[inline]
+    pub fn order(&self) -> BitOrder {
+        self.order
+    }
+
+    /// Returns the number of bits written so far, including padding from
+    /// [BitWriter::align_to_byte].
+    #[inline]
+    pub fn bits_written(&self) -> u64 {
+        self.written
+    }
+
+    /// Writes the low [n] bits of [value], with [n] being at most 64.
+    /// Errors with `WriteError::TooManyBits` if [n] is more than 64, or [value] doesn't fit in
+    /// [n] bits.
+    pub fn write_bits(&mut self, value: u64, n: u8) -> WriteResult {
+        if n > 64 || (n < 64 && value >> n != 0) {
+            return Err(WriteError::TooManyBits);
+        }
+
+        let mut done = 0u8;
+        while done < n {
+            let take = (n - done).min(8 - self.filled);
+            let mask = (1u64 << take) - 1;
+            match self.order {
+                BitOrder::MsbFirst => {
+                    let bits = ((value >> (n - done - take)) & mask) as u8;
+                    self.current |= bits << (8 - self.filled - take);
+                }
+                BitOrder::LsbFirst => {
+                    let bits = ((value >> done) & mask) as u8;
+                    self.current |= bits << self.filled;
+                }
+            }
+            self.filled += take;
+            self.written += u64::from(take);
+            done += take;
+
+            if self.filled == 8 {
+                write_full(&mut self.inner, &[self.current])?;
+                self.current = 0;
+                self.filled = 0;
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub fn write_bit(&mut self, value: bool) -> WriteResult {
+        self.write_bits(u64::from(value), 1)
+    }
+
+    /// Fills the rest of the current byte with [pad_bit] and writes it, so that the next write
+    /// starts at a byte boundary. Returns the number of bits of padding.
+    pub fn align_to_byte(&mut self, pad_bit: bool) -> Result<u8, WriteError> {
+        if self.filled == 0 {
+            return Ok(0);
+        }
+
+        let padding = 8 - self.filled;
+        let pad = if pad_bit { (1u64 << padding) - 1 } else { 0 };
+        self.write_bits(pad, padding)?;
+        Ok(padding)
+    }
+
+    /// Flushes the inner writer.
+    /// Errors with `WriteError::PartialByte` if there is a partial byte that hasn't been
+    /// finished with [BitWriter::align_to_byte].
+    pub fn flush(&mut self) -> WriteResult {
+        if self.filled != 0 {
+            return Err(WriteError::PartialByte(self.filled));
+        }
+        self.inner.flush()?;
+        Ok(())
+    }
+
+    /// Note: any partial byte is lost.
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Note: writing to the inner writer through this handle puts the bytes before any partial
+    /// byte.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 3 bit version of 5, a 13 bit length of 0x1234, a 1 bit flag, then 7 bits of 0x55
+    const MSB_HEADER: [u8; 3] = [0xB2, 0x34, 0xD5];
+    const LSB_HEADER: [u8; 3] = [0xA5, 0x91, 0xAB];
+
+    fn check_header(data: &[u8], order: BitOrder) {
+        let mut reader = BitReader::new(data, order);
+        assert_eq!(reader.read_bits(3).unwrap(), 5);
+        assert_eq!(reader.read_bits(13).unwrap(), 0x1234);
+        assert!(reader.read_bit().unwrap());
+        assert_eq!(reader.bits_consumed(), 17);
+        assert_eq!(reader.read_bits(7).unwrap(), 0x55);
+        assert_eq!(reader.read_bits(0).unwrap(), 0);
+        match reader.read_bits(1) {
+            Err(ParseError::UnexpectedEOF) => {}
+            x => panic!("Expected UnexpectedEOF, got: {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_header() {
+        check_header(&MSB_HEADER, BitOrder::MsbFirst);
+        check_header(&LSB_HEADER, BitOrder::LsbFirst);
+    }
+
+    #[test]
+    fn test_wide_reads() {
+        let data = [0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF, 0xFF];
+        let mut reader = BitReader::new(&data[..], BitOrder::MsbFirst);
+        assert_eq!(reader.read_bits(64).unwrap(), 0x0123456789ABCDEF);
+        assert_eq!(reader.read_bits(4).unwrap(), 0xF);
+
+        let mut reader = BitReader::new(&data[..], BitOrder::LsbFirst);
+        assert_eq!(reader.read_bits(64).unwrap(), 0xEFCDAB8967452301);
+        match reader.read_bits(65) {
+            Err(ParseError::BitCountTooLarge(65)) => {}
+            x => panic!("Expected BitCountTooLarge, got: {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_align_to_byte() {
+        let mut reader = BitReader::new(&MSB_HEADER[..], BitOrder::MsbFirst);
+        assert_eq!(reader.align_to_byte(), 0);
+        assert_eq!(reader.read_bits(3).unwrap(), 5);
+        assert_eq!(reader.align_to_byte(), 5);
+        assert_eq!(reader.bits_consumed(), 8);
+        assert_eq!(reader.read_bits(8).unwrap(), 0x34);
+
+        // Running out partway through a read
+        match reader.read_bits(12) {
+            Err(ParseError::UnexpectedEOF) => {}
+            x => panic!("Expected UnexpectedEOF, got: {:?}", x),
+        }
+    }
+
+    fn write_header(order: BitOrder) -> Vec<u8> {
+        let mut writer = BitWriter::new(Vec::new(), order);
+        writer.write_bits(5, 3).unwrap();
+        writer.write_bits(0x1234, 13).unwrap();
+        writer.write_bit(true).unwrap();
+        writer.write_bits(0x55, 7).unwrap();
+        assert_eq!(writer.bits_written(), 24);
+        writer.flush().unwrap();
+        writer.into_inner()
+    }
+
+    #[test]
+    fn test_write_header() {
+        assert_eq!(write_header(BitOrder::MsbFirst), MSB_HEADER);
+        assert_eq!(write_header(BitOrder::LsbFirst), LSB_HEADER);
+    }
+
+    #[test]
+    fn test_bit_round_trip() {
+        let fields = [
+            (0x1, 1),
+            (0x2A, 6),
+            (0xFFFF, 16),
+            (0x0, 5),
+            (u64::MAX, 64),
+            (0x3, 2),
+        ];
+        for &order in [BitOrder::MsbFirst, BitOrder::LsbFirst].iter() {
+            let mut writer = BitWriter::new(Vec::new(), order);
+            for &(value, n) in fields.iter() {
+                writer.write_bits(value, n).unwrap();
+            }
+            assert_eq!(writer.align_to_byte(true).unwrap(), 2);
+            let data = writer.into_inner();
+            assert_eq!(data.len(), 12);
+
+            let mut reader = BitReader::new(&data[..], order);
+            for &(value, n) in fields.iter() {
+                assert_eq!(reader.read_bits(n).unwrap(), value);
+            }
+            assert_eq!(reader.read_bits(2).unwrap(), 0x3);
+        }
+    }
+
+    #[test]
+    fn test_bit_writer_errors() {
+        let mut writer = BitWriter::new(Vec::new(), BitOrder::MsbFirst);
+        for &(value, n) in [(0x8, 3), (0x1, 0), (0x0, 65)].iter() {
+            match writer.write_bits(value, n) {
+                Err(WriteError::TooManyBits) => {}
+                x => panic!("Expected TooManyBits, got: {:?}", x),
+            }
+        }
+        assert_eq!(writer.bits_written(), 0);
+
+        writer.write_bits(0x1, 3).unwrap();
+        match writer.flush() {
+            Err(WriteError::PartialByte(3)) => {}
+            x => panic!("Expected PartialByte, got: {:?}", x),
+        }
+        assert_eq!(writer.align_to_byte(false).unwrap(), 5);
+        assert_eq!(writer.align_to_byte(false).unwrap(), 0);
+        writer.flush().unwrap();
+        assert_eq!(writer.into_inner(), [0x20]);
+    }
+}
@@ -0,0 +1,279 @@
+//! Zero-copy parsing of data that is already in memory, such as a memory-mapped file or an
+//! embedded asset.
+
+use crate::parse::{tag_mismatch, Parse, ParseError, ParseResult};
+use std::io::Cursor;
+
+/// Parses from a `&[u8]`, giving out subslices of it rather than copying into new `Vec`s.
+/// The returned slices borrow from the data, not from the parser, so they can be kept after
+/// further parsing, or after the parser is dropped.
+/// Errors are `ParseError::AtOffset`, with the offset where the failed operation started, and
+/// leave the position unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SliceParser<'a> {
+    data: &'a [u8],
+    position: usize,
+}
+impl<'a> SliceParser<'a> {
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        SliceParser { data, position: 0 }
+    }
+
+    #[inline]
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Note: a [position] past the end is clamped to the end.
+    #[inline]
+    pub fn set_position(&mut self, position: usize) {
+        self.position = position.min(self.data.len());
+    }
+
+    /// Returns the data that hasn't been parsed yet.
+    #[inline]
+    pub fn remaining(&self) -> &'a [u8] {
+        &self.data[self.position..]
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.remaining().is_empty()
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &'a [u8] {
+        self.data
+    }
+
+    fn error(&self, error: ParseError) -> ParseError {
+        ParseError::AtOffset(self.position as u64, Box::new(error))
+    }
+
+    #[inline]
+    pub fn single(&mut self) -> ParseResult<u8> {
+        self.take(1).map(|data| data[0])
+    }
+
+    /// Takes the next [amount] bytes, without copying them.
+    #[inline]
+    pub fn take(&mut self, amount: usize) -> ParseResult<&'a [u8]> {
+        if amount > self.remaining().len() {
+            return Err(self.error(ParseError::UnexpectedEOF));
+        }
+        let data = &self.remaining()[..amount];
+        self.position += amount;
+        Ok(data)
+    }
+
+    #[inline]
+    pub fn take_n<const N: usize>(&mut self) -> ParseResult<[u8; N]> {
+        let mut output = [0u8; N];
+        output.copy_from_slice(self.take(N)?);
+        Ok(output)
+    }
+
+    /// Takes all bytes until the terminator, which is consumed either way.
+    /// If [include_terminator] is true, then the terminator is included in the output.
+    pub fn take_until(
+        &mut self,
+        terminator: u8,
+        include_terminator: bool,
+    ) -> ParseResult<&'a [u8]> {
+        let remaining = self.remaining();
+        let end = remaining
+            .iter()
+            .position(|x| *x == terminator)
+            .ok_or_else(|| self.error(ParseError::UnexpectedEOF))?;
+        self.position += end + 1;
+        Ok(if include_terminator {
+            &remaining[..=end]
+        } else {
+            &remaining[..end]
+        })
+    }
+
+    /// Expect certain bytes. Does not return them.
+    /// The error is `ParseError::TagMismatch` with the index of the first byte that differs.
+    pub fn tag<X>(&mut self, data: &[X]) -> ParseResult<()>
+    where
+        X: PartialEq<u8>,
+    {
+        let remaining = self.remaining();
+        if remaining.len() < data.len() {
+            return Err(self.error(ParseError::UnexpectedEOF));
+        }
+        if let Err(e) = tag_mismatch(data, remaining) {
+            return Err(self.error(e));
+        }
+        self.position += data.len();
+        Ok(())
+    }
+
+    /// Calls [func] until everything has been consumed.
+    /// Errors with `ParseError::NoProgress` if [func] consumes nothing, rather than looping
+    /// forever.
+    /// Note: unlike the other methods, this doesn't roll back elements that parsed before an
+    /// error, and the error is whatever [func] gave.
+    pub fn many<C, R, E, D>(&mut self, d: D, func: C) -> ParseResult<Vec<R>>
+    where
+        C: Fn(&mut Self, D) -> Result<R, E>,
+        E: Into<ParseError>,
+        D: Clone,
+    {
+        let mut result = Vec::new();
+        while !self.is_empty() {
+            let position = self.position;
+            result.push(func(self, d.clone()).map_err(Into::into)?);
+            if self.position == position {
+                return Err(ParseError::NoProgress(position as u64));
+            }
+        }
+        Ok(result)
+    }
+
+    /// Parses [P] through a `Cursor` over the remaining data, for types which only implement
+    /// `Parse`. That copies as the `Read` based parse does, so prefer the borrowing methods
+    /// for strings and blobs.
+    pub fn parse<P, D>(&mut self, d: D) -> ParseResult<P>
+    where
+        P: Parse<Cursor<&'a [u8]>, D>,
+    {
+        let mut cursor = Cursor::new(self.remaining());
+        let value = P::parse(&mut cursor, d).map_err(|e| self.error(e))?;
+        // Can't be past the remaining data's length, which fits in a usize
+        self.position += cursor.position() as usize;
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parse::{single, take, take_until},
+        Endian,
+    };
+
+    const DATA: [u8; 20] = [
+        0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x8, 0x9, 0xa, 0xb, 0xc, 0xd, 0xe, 0xf, 0x10, 0x11,
+        0x12, 0x13, 0x14,
+    ];
+
+    fn assert_at<T: std::fmt::Debug>(result: ParseResult<T>, offset: u64) {
+        match result {
+            Err(ParseError::AtOffset(x, _)) if x == offset => {}
+            x => panic!("Expected an error at {}, got: {:?}", offset, x),
+        }
+    }
+
+    #[test]
+    fn test_single_take() {
+        let mut parser = SliceParser::new(&DATA);
+        let mut cursor = Cursor::new(&DATA);
+        for _ in 0..4 {
+            assert_eq!(parser.single().unwrap(), single(&mut cursor).unwrap());
+        }
+        assert_eq!(
+            parser.take(4).unwrap(),
+            take(&mut cursor, 4).unwrap().as_slice()
+        );
+        assert_eq!(parser.take_n::<2>().unwrap(), [0x9, 0xa]);
+        assert_eq!(parser.position(), 10);
+
+        assert_at(parser.take(11), 10);
+        assert_eq!(parser.position(), 10);
+        assert_eq!(parser.take(10).unwrap(), &DATA[10..]);
+        assert!(parser.is_empty());
+        assert_at(parser.single(), 20);
+    }
+
+    #[test]
+    fn test_take_until() {
+        let mut parser = SliceParser::new(&DATA);
+        let mut cursor = Cursor::new(&DATA);
+        assert_eq!(
+            parser.take_until(0x7, false).unwrap(),
+            take_until(&mut cursor, 0x7, false).unwrap().as_slice()
+        );
+        assert_eq!(parser.position(), 7);
+        assert_eq!(parser.take_until(0xa, true).unwrap(), [0x8, 0x9, 0xa]);
+        assert_eq!(parser.position(), 10);
+
+        assert_at(parser.take_until(0x1, false), 10);
+        assert_eq!(parser.position(), 10);
+    }
+
+    #[test]
+    fn test_tag() {
+        let mut parser = SliceParser::new(&DATA);
+        parser.tag(&[0x1, 0x2, 0x3, 0x4]).unwrap();
+        parser.tag(&[0x5, 0x6, 0x7, 0x8]).unwrap();
+        match parser.tag(&[0x20, 0x52]) {
+            Err(ParseError::AtOffset(8, e)) if matches!(*e, ParseError::TagMismatch(0)) => {}
+            x => panic!("Expected TagMismatch at 8, got: {:?}", x),
+        }
+        assert_eq!(parser.position(), 8);
+
+        let mut parser = SliceParser::new(&DATA[..2]);
+        assert_at(parser.tag(&[0x1, 0x2, 0x3]), 0);
+    }
+
+    #[test]
+    fn test_many() {
+        let mut parser = SliceParser::new(&DATA);
+        let result = parser.many((), |f, _d| f.take_n::<4>()).unwrap();
+        assert_eq!(
+            result,
+            [
+                [0x1, 0x2, 0x3, 0x4],
+                [0x5, 0x6, 0x7, 0x8],
+                [0x9, 0xa, 0xb, 0xc],
+                [0xd, 0xe, 0xf, 0x10],
+                [0x11, 0x12, 0x13, 0x14]
+            ]
+        );
+
+        let mut parser = SliceParser::new(&DATA);
+        parser
+            .many((), |f, _d| f.take(3))
+            .expect_err("Expected failure in dividing DATA into 3-byte chunks");
+
+        let mut parser = SliceParser::new(&DATA);
+        match parser.many((), |_f, _d| -> ParseResult<()> { Ok(()) }) {
+            Err(ParseError::NoProgress(0)) => {}
+            x => panic!("expected no progress, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let mut parser = SliceParser::new(&DATA);
+        assert_eq!(parser.parse::<u16, _>(Endian::Big).unwrap(), 0x0102);
+        assert_eq!(parser.parse::<u32, _>(Endian::Little).unwrap(), 0x06050403);
+        assert_eq!(parser.position(), 6);
+
+        parser.set_position(18);
+        assert_at(parser.parse::<u32, _>(Endian::Little), 18);
+        assert_eq!(parser.position(), 18);
+    }
+
+    #[test]
+    fn test_borrowed_slices() {
+        let data = b"name\0value\0rest".to_vec();
+        let (name, value) = {
+            let mut parser = SliceParser::new(&data);
+            let name = parser.take_until(0, false).unwrap();
+            let value = parser.take_until(0, false).unwrap();
+            // Both are still usable while the parser goes on
+            assert_eq!(parser.remaining(), b"rest");
+            assert_eq!(name, b"name");
+            (name, value)
+        };
+        // And after it's gone, since they borrow from the data
+        assert_eq!(name, b"name");
+        assert_eq!(value, b"value");
+        assert_eq!(name.as_ptr(), data.as_ptr());
+    }
+}
@@ -0,0 +1,239 @@
+use std::io::{self, Read, Write};
+
+/// A running checksum or hash over a stream of bytes.
+pub trait Checksum {
+    /// Feeds [data] into the checksum.
+    fn update(&mut self, data: &[u8]);
+
+    /// Returns the checksum of everything that was fed in, widened to a u64.
+    fn finalize(self) -> u64;
+}
+
+/// CRC-32 (IEEE 802.3), as used by zip, png, gzip, and many others.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crc32 {
+    state: u32,
+}
+impl Crc32 {
+    /// The reversed form of the IEEE polynomial
+    const POLYNOMIAL: u32 = 0xEDB8_8320;
+    const TABLE: [u32; 256] = Self::make_table();
+
+    #[inline]
+    pub fn new() -> Self {
+        Crc32 { state: !0 }
+    }
+
+    /// Computes the CRC-32 of [data] in one go.
+    #[inline]
+    pub fn checksum(data: &[u8]) -> u32 {
+        let mut crc = Self::new();
+        crc.update(data);
+        crc.value()
+    }
+
+    /// Returns the CRC-32 of everything that was fed in so far.
+    #[inline]
+    pub fn value(&self) -> u32 {
+        !self.state
+    }
+
+    const fn make_table() -> [u32; 256] {
+        let mut table = [0u32; 256];
+        let mut i = 0;
+        while i < 256 {
+            let mut value = i as u32;
+            let mut bit = 0;
+            while bit < 8 {
+                value = if value & 1 == 1 {
+                    (value >> 1) ^ Self::POLYNOMIAL
+                } else {
+                    value >> 1
+                };
+                bit += 1;
+            }
+            table[i] = value;
+            i += 1;
+        }
+        table
+    }
+}
+impl Default for Crc32 {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl Checksum for Crc32 {
+    fn update(&mut self, data: &[u8]) {
+        for &byte in data.iter() {
+            let index = (self.state ^ u32::from(byte)) & 0xFF;
+            self.state = (self.state >> 8) ^ Self::TABLE[index as usize];
+        }
+    }
+
+    #[inline]
+    fn finalize(self) -> u64 {
+        u64::from(self.value())
+    }
+}
+
+/// Wraps a reader, feeding every byte read through it into a [Checksum].
+/// This doesn't implement Seek, since seeking would make the checksum meaningless.
+#[derive(Debug)]
+pub struct HashingReader<R: Read, H: Checksum> {
+    inner: R,
+    hasher: H,
+}
+impl<R, H> HashingReader<R, H>
+where
+    R: Read,
+    H: Checksum,
+{
+    #[inline]
+    pub fn new(inner: R, hasher: H) -> Self {
+        HashingReader { inner, hasher }
+    }
+
+    /// Returns the checksum of everything read so far.
+    #[inline]
+    pub fn digest(&self) -> u64
+    where
+        H: Clone,
+    {
+        self.hasher.clone().finalize()
+    }
+
+    #[inline]
+    pub fn into_parts(self) -> (R, H) {
+        (self.inner, self.hasher)
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Note: reading from the inner reader through this handle will not update the checksum.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+impl<R, H> Read for HashingReader<R, H>
+where
+    R: Read,
+    H: Checksum,
+{
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let amount_read = self.inner.read(buf)?;
+        self.hasher.update(&buf[..amount_read]);
+        Ok(amount_read)
+    }
+}
+
+/// Wraps a writer, feeding every byte written through it into a [Checksum].
+/// This doesn't implement Seek, since seeking would make the checksum meaningless.
+#[derive(Debug)]
+pub struct HashingWriter<W: Write, H: Checksum> {
+    inner: W,
+    hasher: H,
+}
+impl<W, H> HashingWriter<W, H>
+where
+    W: Write,
+    H: Checksum,
+{
+    #[inline]
+    pub fn new(inner: W, hasher: H) -> Self {
+        HashingWriter { inner, hasher }
+    }
+
+    /// Returns the checksum of everything written so far.
+    #[inline]
+    pub fn digest(&self) -> u64
+    where
+        H: Clone,
+    {
+        self.hasher.clone().finalize()
+    }
+
+    #[inline]
+    pub fn into_parts(self) -> (W, H) {
+        (self.inner, self.hasher)
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Note: writing to the inner writer through this handle will not update the checksum.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+impl<W, H> Write for HashingWriter<W, H>
+where
+    W: Write,
+    H: Checksum,
+{
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let amount_written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..amount_written]);
+        Ok(amount_written)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::writable::Writable;
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(Crc32::checksum(b""), 0);
+        assert_eq!(Crc32::checksum(b"a"), 0xE8B7_BE43);
+        assert_eq!(Crc32::checksum(b"123456789"), 0xCBF4_3926);
+        assert_eq!(
+            Crc32::checksum(b"The quick brown fox jumps over the lazy dog"),
+            0x414F_A339
+        );
+
+        // Feeding it in pieces gives the same result
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"");
+        crc.update(b"56789");
+        assert_eq!(crc.finalize(), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_hashing_reader() {
+        let mut reader = HashingReader::new(&b"123456789abc"[..], Crc32::new());
+        let mut out = [0u8; 9];
+        reader.read_exact(&mut out).unwrap();
+        assert_eq!(reader.digest(), 0xCBF4_3926);
+
+        let (rest, crc) = reader.into_parts();
+        assert_eq!(rest, b"abc");
+        assert_eq!(crc.value(), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_hashing_writer() {
+        let mut writer = HashingWriter::new(Vec::new(), Crc32::new());
+        b"1234".as_ref().write_to(&mut writer, ()).unwrap();
+        b"56789".as_ref().write_to(&mut writer, ()).unwrap();
+        assert_eq!(writer.digest(), 0xCBF4_3926);
+        assert_eq!(writer.get_ref(), b"123456789");
+    }
+}
@@ -0,0 +1,132 @@
+//! Parse data that carries more than the endianness, such as a format version or flags read
+//! from a header.
+
+use crate::{
+    parse::{Parse, ParseResult},
+    Endian,
+};
+use std::io::Read;
+
+/// Data for parsing with a default endianness, along with [U] for anything else the format
+/// needs to thread through.
+/// The primitives implement `Parse<F, ParseCtx<U>>` by using [ParseCtx::endian], so a whole
+/// parse tree can switch endianness by changing only the ctx.
+/// `impl_parse!(ctx ...)` implements it for structs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseCtx<U = ()> {
+    pub endian: Endian,
+    pub user: U,
+}
+impl ParseCtx<()> {
+    #[inline]
+    pub fn new(endian: Endian) -> Self {
+        ParseCtx { endian, user: () }
+    }
+}
+impl<U> ParseCtx<U> {
+    #[inline]
+    pub fn with_user(endian: Endian, user: U) -> Self {
+        ParseCtx { endian, user }
+    }
+
+    #[inline]
+    pub fn with_endian(self, endian: Endian) -> Self {
+        ParseCtx { endian, ..self }
+    }
+
+    /// Replaces the user data with [func] applied to it.
+    #[inline]
+    pub fn map_user<V, C>(self, func: C) -> ParseCtx<V>
+    where
+        C: FnOnce(U) -> V,
+    {
+        ParseCtx {
+            endian: self.endian,
+            user: func(self.user),
+        }
+    }
+}
+
+macro_rules! impl_parse_ctx {
+    (single, [$($typ:ty),*]) => {
+        $(
+            impl<F: Read, U> Parse<F, ParseCtx<U>> for $typ {
+                #[inline]
+                fn parse(f: &mut F, _ctx: ParseCtx<U>) -> ParseResult<Self> {
+                    <$typ>::parse(f, ())
+                }
+            }
+        )*
+    };
+    ([$($typ:ty),*]) => {
+        $(
+            impl<F: Read, U> Parse<F, ParseCtx<U>> for $typ {
+                #[inline]
+                fn parse(f: &mut F, ctx: ParseCtx<U>) -> ParseResult<Self> {
+                    <$typ>::parse(f, ctx.endian)
+                }
+            }
+        )*
+    };
+}
+impl_parse_ctx!(single, [u8, i8]);
+impl_parse_ctx!([u16, i16, u32, i32, u64, i64, f32, f64]);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[derive(Debug, PartialEq)]
+    struct Inner {
+        value: u16,
+    }
+    crate::impl_parse!(ctx Inner, [value: c: u16]);
+
+    #[derive(Debug, PartialEq)]
+    struct Header {
+        kind: u8,
+        length: u32,
+        version: u16,
+        inner: Inner,
+        fixed: u16,
+    }
+    crate::impl_parse!(ctx Header, [kind: c: u8, length: c: u32, version: e: u16, inner: c: Inner, fixed: b: u16]);
+
+    const DATA: [u8; 11] = [0x1, 0x2, 0x0, 0x0, 0x0, 0x3, 0x0, 0x4, 0x0, 0x0, 0x5];
+
+    #[test]
+    fn test_parse_ctx() {
+        let little = Header::parse(&mut Cursor::new(&DATA), ParseCtx::new(Endian::Little));
+        assert_eq!(
+            little.unwrap(),
+            Header {
+                kind: 0x1,
+                length: 0x2,
+                version: 0x3,
+                inner: Inner { value: 0x4 },
+                fixed: 0x5,
+            }
+        );
+
+        let ctx = ParseCtx::with_user(Endian::Little, 3u8).with_endian(Endian::Big);
+        let big = Header::parse(&mut Cursor::new(&DATA), ctx);
+        assert_eq!(
+            big.unwrap(),
+            Header {
+                kind: 0x1,
+                length: 0x02000000,
+                version: 0x0300,
+                inner: Inner { value: 0x0400 },
+                fixed: 0x5,
+            }
+        );
+    }
+
+    #[test]
+    fn test_map_user() {
+        let ctx = ParseCtx::new(Endian::Big).map_user(|()| "v2");
+        assert_eq!(ctx, ParseCtx::with_user(Endian::Big, "v2"));
+        assert_eq!(ctx.map_user(str::len).user, 2);
+    }
+}
@@ -1,4 +1,8 @@
-use std::fmt::Debug;
+use std::{borrow::Cow, fmt::Debug, rc::Rc, sync::Arc};
+
+/// `#[derive(DataSize)]`, see the eyeutil-derive crate for its attributes.
+#[cfg(feature = "derive")]
+pub use eyeutil_derive::DataSize;
 
 pub trait DataSize<D>
 where
@@ -36,7 +40,69 @@ macro_rules! impl_data_size {
     };
 }
 
-impl_data_size!((), 1);
+/// Implements `DataSize<()>` for a struct as the sum of the sizes of the listed fields:
+/// `impl_struct_data_size!(Header, [magic, length, flags]);`
+/// Like with `impl_writable!`, a field can give its data in parentheses, rather than `()`:
+/// `impl_struct_data_size!(Entry, [kind, offset: (Endian::Little, AddrWidth::W32)]);`
+/// Generic types are given like with `impl_parse!`:
+/// `impl_struct_data_size!(generic [T: DataSize<()>] Pair<T>, [a, b]);`
+/// With `generic data`, the impl is `DataSize<D>`, and fields can be `d` to be given a clone of
+/// it: `impl_struct_data_size!(generic data [] Table where [Uaddr: DataSize<D>], [count, base: d]);`
+#[macro_export]
+macro_rules! impl_struct_data_size {
+    (@field $value:expr, $d:expr) => {
+        $crate::data_size::DataSize::<()>::data_size($value, ())
+    };
+    (@field $value:expr, $d:expr, d) => {
+        $crate::data_size::DataSize::data_size($value, $d.clone())
+    };
+    (@field $value:expr, $d:expr, ($($data:tt)*)) => {
+        $crate::data_size::DataSize::data_size($value, ($($data)*))
+    };
+    (generic data [$($gen:tt)*] $typ:ty $(where [$($bound:tt)*])?, [$($name:ident $(: $e:tt)?),*]) => {
+        impl<D, $($gen)*> $crate::data_size::DataSize<D> for $typ
+        where
+            D: std::fmt::Debug + Clone + PartialEq,
+            $($($bound)*)?
+        {
+            #[inline]
+            #[allow(unused_variables)]
+            fn data_size(&self, d: D) -> u64 {
+                0 $(+ $crate::impl_struct_data_size!(@field &self.$name, d $(, $e)?))*
+            }
+        }
+    };
+    (generic [$($gen:tt)*] $typ:ty $(where [$($bound:tt)*])?, [$($name:ident $(: $e:tt)?),*]) => {
+        impl<$($gen)*> $crate::data_size::DataSize<()> for $typ
+        where
+            $($($bound)*)?
+        {
+            #[inline]
+            fn data_size(&self, _d: ()) -> u64 {
+                0 $(+ $crate::impl_struct_data_size!(@field &self.$name, () $(, $e)?))*
+            }
+        }
+    };
+    ($typ:ty, [$($name:ident $(: $e:tt)?),*]) => {
+        impl $crate::data_size::DataSize<()> for $typ {
+            #[inline]
+            fn data_size(&self, _d: ()) -> u64 {
+                0 $(+ $crate::impl_struct_data_size!(@field &self.$name, () $(, $e)?))*
+            }
+        }
+    };
+}
+
+impl<D> DataSize<D> for ()
+where
+    D: Debug + Clone + PartialEq,
+{
+    /// Unit is written as nothing, for "no payload" in generic framing code.
+    #[inline]
+    fn data_size(&self, _d: D) -> u64 {
+        0
+    }
+}
 impl_data_size!(u8, 1);
 impl_data_size!(i8, 1);
 impl_data_size!(u16, 2);
@@ -49,7 +115,27 @@ impl_data_size!(u128, 16);
 impl_data_size!(i128, 16);
 impl_data_size!(f32, 4);
 impl_data_size!(f64, 8);
-impl<D, T> DataSize<D> for &[T]
+/// The length of the UTF-8 encoding.
+impl DataSize<()> for char {
+    #[inline]
+    fn data_size(&self, _d: ()) -> u64 {
+        self.len_utf8() as u64
+    }
+}
+/// The length of the UTF-8 bytes, without any framing.
+impl DataSize<()> for str {
+    #[inline]
+    fn data_size(&self, _d: ()) -> u64 {
+        self.len() as u64
+    }
+}
+impl DataSize<()> for String {
+    #[inline]
+    fn data_size(&self, _d: ()) -> u64 {
+        self.len() as u64
+    }
+}
+impl<D, T> DataSize<D> for [T]
 where
     D: Debug + Clone + PartialEq,
     T: DataSize<D>,
@@ -60,6 +146,43 @@ where
             .fold(0u64, |acc, x| acc + x.data_size(d.clone()))
     }
 }
+impl<D, T> DataSize<D> for &T
+where
+    D: Debug + Clone + PartialEq,
+    T: DataSize<D> + ?Sized,
+{
+    #[inline]
+    fn data_size(&self, d: D) -> u64 {
+        (**self).data_size(d)
+    }
+}
+macro_rules! impl_data_size_pointer {
+    ($($ptr:ident),*) => {
+        $(
+            impl<D, T> DataSize<D> for $ptr<T>
+            where
+                D: Debug + Clone + PartialEq,
+                T: DataSize<D> + ?Sized,
+            {
+                #[inline]
+                fn data_size(&self, d: D) -> u64 {
+                    (**self).data_size(d)
+                }
+            }
+        )*
+    };
+}
+impl_data_size_pointer!(Box, Rc, Arc);
+impl<D, T> DataSize<D> for Cow<'_, T>
+where
+    D: Debug + Clone + PartialEq,
+    T: DataSize<D> + ToOwned + ?Sized,
+{
+    #[inline]
+    fn data_size(&self, d: D) -> u64 {
+        (**self).data_size(d)
+    }
+}
 impl<D, T> DataSize<D> for Vec<T>
 where
     D: Debug + Clone + PartialEq,
@@ -70,3 +193,44 @@ where
         self.as_slice().data_size(d)
     }
 }
+impl<D, T> DataSize<D> for Option<T>
+where
+    D: Debug + Clone + PartialEq,
+    T: DataSize<D>,
+{
+    /// `None` is written as nothing.
+    #[inline]
+    fn data_size(&self, d: D) -> u64 {
+        self.as_ref().map_or(0, |value| value.data_size(d))
+    }
+}
+impl<D, T, const N: usize> DataSize<D> for [T; N]
+where
+    D: Debug + Clone + PartialEq,
+    T: DataSize<D>,
+{
+    #[inline]
+    fn data_size(&self, d: D) -> u64 {
+        self.as_ref().data_size(d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unit_data_size() {
+        assert_eq!(().data_size(()), 0);
+        assert_eq!(().data_size(crate::Endian::Little), 0);
+        assert_eq!(vec![(); 4].data_size(()), 0);
+    }
+
+    #[test]
+    fn test_array_data_size() {
+        assert_eq!([0u8; 4].data_size(()), 4);
+        assert_eq!([0f32; 3].data_size(()), 12);
+        assert_eq!([[0u16; 2]; 3].data_size(()), 12);
+        assert_eq!(<[u64; 0]>::default().data_size(()), 0);
+    }
+}
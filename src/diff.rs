@@ -0,0 +1,372 @@
+//! Diffing two inputs by their parsed fields, rather than by their bytes.
+//!
+//! Parsers opt in by implementing [ParseRecorded], parsing through a [FieldRecorder] which
+//! notes the byte range and value of each field. Comparing the recorded fields of two inputs
+//! then says which logical fields differ, and where.
+
+use crate::parse::{Parse, ParseResult};
+use std::{
+    convert::TryFrom,
+    fmt::{self, Debug},
+    io::{Cursor, Read, Seek, SeekFrom},
+    ops::Range,
+};
+
+/// A field recorded while parsing.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FieldRecord {
+    /// Names of the enclosing fields and this field, joined with `.`
+    pub path: String,
+    /// Range of bytes the field was parsed from
+    pub range: Range<u64>,
+    /// Debug rendering of the parsed value
+    pub value: String,
+}
+
+/// Reader over an in-memory input which records the fields parsed through it.
+#[derive(Debug)]
+pub struct FieldRecorder<'a> {
+    cursor: Cursor<&'a [u8]>,
+    path: Vec<String>,
+    fields: Vec<FieldRecord>,
+}
+impl<'a> FieldRecorder<'a> {
+    #[inline]
+    pub fn new(data: &'a [u8]) -> Self {
+        FieldRecorder {
+            cursor: Cursor::new(data),
+            path: Vec::new(),
+            fields: Vec::new(),
+        }
+    }
+
+    fn path_to(&self, name: &str) -> String {
+        let mut path = self.path.join(".");
+        if !path.is_empty() {
+            path.push('.');
+        }
+        path.push_str(name);
+        path
+    }
+
+    /// Parses a `T` named [name], recording it.
+    pub fn field<T, D>(&mut self, name: &str, d: D) -> ParseResult<T>
+    where
+        T: Parse<Cursor<&'a [u8]>, D> + Debug,
+    {
+        let start = self.cursor.position();
+        let value = T::parse(&mut self.cursor, d)?;
+        let end = self.cursor.position();
+        self.fields.push(FieldRecord {
+            path: self.path_to(name),
+            range: start..end,
+            value: format!("{:?}", value),
+        });
+        Ok(value)
+    }
+
+    /// Runs [parse] with [name] prepended to the path of every field it records.
+    /// The nested field itself is not recorded, only the fields within it.
+    pub fn nested<T, P>(&mut self, name: &str, parse: P) -> ParseResult<T>
+    where
+        P: FnOnce(&mut Self) -> ParseResult<T>,
+    {
+        self.path.push(name.to_owned());
+        let result = parse(self);
+        self.path.pop();
+        result
+    }
+
+    #[inline]
+    pub fn fields(&self) -> &[FieldRecord] {
+        &self.fields
+    }
+
+    #[inline]
+    pub fn into_fields(self) -> Vec<FieldRecord> {
+        self.fields
+    }
+}
+impl<'a> Read for FieldRecorder<'a> {
+    /// Note: bytes read directly are not recorded as a field.
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.cursor.read(buf)
+    }
+}
+impl<'a> Seek for FieldRecorder<'a> {
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.cursor.seek(pos)
+    }
+}
+
+/// Parsing that records each field through a [FieldRecorder], for use with [diff_parsed].
+pub trait ParseRecorded<D>: Sized {
+    fn parse_recorded(r: &mut FieldRecorder<'_>, d: D) -> ParseResult<Self>;
+}
+
+/// A single difference between two parses.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum FieldDiff {
+    /// The field exists in both, but its bytes differ
+    Changed {
+        path: String,
+        a_value: String,
+        b_value: String,
+        a_range: Range<u64>,
+        b_range: Range<u64>,
+    },
+    /// The field only exists in the second input
+    Added {
+        path: String,
+        value: String,
+        range: Range<u64>,
+    },
+    /// The field only exists in the first input
+    Removed {
+        path: String,
+        value: String,
+        range: Range<u64>,
+    },
+}
+impl FieldDiff {
+    #[inline]
+    pub fn path(&self) -> &str {
+        match self {
+            FieldDiff::Changed { path, .. }
+            | FieldDiff::Added { path, .. }
+            | FieldDiff::Removed { path, .. } => path,
+        }
+    }
+}
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FieldDiff::Changed {
+                path,
+                a_value,
+                b_value,
+                a_range,
+                b_range,
+            } => write!(
+                f,
+                "~ {}: {} @ {:#X}..{:#X} -> {} @ {:#X}..{:#X}",
+                path, a_value, a_range.start, a_range.end, b_value, b_range.start, b_range.end
+            ),
+            FieldDiff::Added { path, value, range } => write!(
+                f,
+                "+ {}: {} @ {:#X}..{:#X}",
+                path, value, range.start, range.end
+            ),
+            FieldDiff::Removed { path, value, range } => write!(
+                f,
+                "- {}: {} @ {:#X}..{:#X}",
+                path, value, range.start, range.end
+            ),
+        }
+    }
+}
+
+/// The fields which differ between two parses. Unchanged fields are left out.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct StructDiff {
+    pub entries: Vec<FieldDiff>,
+}
+impl StructDiff {
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    #[inline]
+    pub fn get(&self, path: &str) -> Option<&FieldDiff> {
+        self.entries.iter().find(|x| x.path() == path)
+    }
+}
+impl fmt::Display for StructDiff {
+    /// One line per differing field
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.entries.is_empty() {
+            return writeln!(f, "(no differences)");
+        }
+
+        for entry in self.entries.iter() {
+            writeln!(f, "{}", entry)?;
+        }
+        Ok(())
+    }
+}
+
+/// Note: a field can be recorded past the end of the data if the parser seeked there, which
+/// gives no bytes.
+fn bytes_of<'a>(data: &'a [u8], range: &Range<u64>) -> &'a [u8] {
+    let start = usize::try_from(range.start).unwrap_or(usize::MAX);
+    let end = usize::try_from(range.end).unwrap_or(usize::MAX);
+    data.get(start..end).unwrap_or(&[])
+}
+
+/// Parses both inputs as `T`, and lists the fields whose bytes differ between them.
+/// Fields are matched up by their path. Fields that were only parsed from one of the inputs
+/// (such as version-gated fields) are reported as added or removed.
+pub fn diff_parsed<T, D>(a_bytes: &[u8], b_bytes: &[u8], d: D) -> ParseResult<StructDiff>
+where
+    T: ParseRecorded<D>,
+    D: Clone,
+{
+    let mut a = FieldRecorder::new(a_bytes);
+    T::parse_recorded(&mut a, d.clone())?;
+    let mut b = FieldRecorder::new(b_bytes);
+    T::parse_recorded(&mut b, d)?;
+    let (a, b) = (a.into_fields(), b.into_fields());
+
+    let mut entries = Vec::new();
+    for a_field in a.iter() {
+        match b.iter().find(|x| x.path == a_field.path) {
+            Some(b_field) => {
+                if bytes_of(a_bytes, &a_field.range) != bytes_of(b_bytes, &b_field.range) {
+                    entries.push(FieldDiff::Changed {
+                        path: a_field.path.clone(),
+                        a_value: a_field.value.clone(),
+                        b_value: b_field.value.clone(),
+                        a_range: a_field.range.clone(),
+                        b_range: b_field.range.clone(),
+                    });
+                }
+            }
+            None => entries.push(FieldDiff::Removed {
+                path: a_field.path.clone(),
+                value: a_field.value.clone(),
+                range: a_field.range.clone(),
+            }),
+        }
+    }
+
+    for b_field in b.iter() {
+        if !a.iter().any(|x| x.path == b_field.path) {
+            entries.push(FieldDiff::Added {
+                path: b_field.path.clone(),
+                value: b_field.value.clone(),
+                range: b_field.range.clone(),
+            });
+        }
+    }
+
+    Ok(StructDiff { entries })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Endian;
+
+    #[derive(Debug)]
+    struct Header {
+        version: u16,
+        flags: u8,
+    }
+
+    /// `extra` only exists in version 2 and above
+    #[derive(Debug)]
+    struct File {
+        magic: u32,
+        header: Header,
+        extra: Option<u32>,
+        tail: u8,
+    }
+    impl ParseRecorded<Endian> for File {
+        fn parse_recorded(r: &mut FieldRecorder<'_>, endian: Endian) -> ParseResult<Self> {
+            let magic = r.field("magic", endian)?;
+            let header = r.nested("header", |r| {
+                Ok(Header {
+                    version: r.field("version", endian)?,
+                    flags: r.field("flags", ())?,
+                })
+            })?;
+            let extra = if header.version >= 2 {
+                Some(r.field("extra", endian)?)
+            } else {
+                None
+            };
+            Ok(File {
+                magic,
+                header,
+                extra,
+                tail: r.field("tail", ())?,
+            })
+        }
+    }
+
+    #[test]
+    fn test_diff_parsed() {
+        let a = [0xAB, 0xCD, 0xEF, 0x01, 0x00, 0x01, 0x03, 0x7F];
+        let b = [
+            0xAB, 0xCD, 0xEF, 0x01, 0x00, 0x02, 0x03, 0x00, 0x00, 0x00, 0x05, 0x7F,
+        ];
+        let diff = diff_parsed::<File, _>(&a, &b, Endian::Big).unwrap();
+        assert_eq!(diff.len(), 2);
+        assert_eq!(
+            diff.entries[0],
+            FieldDiff::Changed {
+                path: "header.version".to_owned(),
+                a_value: "1".to_owned(),
+                b_value: "2".to_owned(),
+                a_range: 4..6,
+                b_range: 4..6,
+            }
+        );
+        assert_eq!(
+            diff.entries[1],
+            FieldDiff::Added {
+                path: "extra".to_owned(),
+                value: "5".to_owned(),
+                range: 7..11,
+            }
+        );
+        // Moved, but unchanged
+        assert!(diff.get("tail").is_none());
+
+        assert_eq!(
+            diff.to_string(),
+            "~ header.version: 1 @ 0x4..0x6 -> 2 @ 0x4..0x6\n+ extra: 5 @ 0x7..0xB\n"
+        );
+
+        let reversed = diff_parsed::<File, _>(&b, &a, Endian::Big).unwrap();
+        assert_eq!(reversed.len(), 2);
+        assert!(matches!(
+            reversed.get("extra"),
+            Some(FieldDiff::Removed { .. })
+        ));
+
+        let mut recorder = FieldRecorder::new(&b);
+        let file = File::parse_recorded(&mut recorder, Endian::Big).unwrap();
+        assert_eq!(file.magic, 0xABCDEF01);
+        assert_eq!((file.header.version, file.header.flags), (2, 3));
+        assert_eq!((file.extra, file.tail), (Some(5), 0x7F));
+        let paths: Vec<&str> = recorder.fields().iter().map(|x| x.path.as_str()).collect();
+        assert_eq!(
+            paths,
+            ["magic", "header.version", "header.flags", "extra", "tail"]
+        );
+
+        // Fields recorded past the end of the data compare as empty
+        struct Seeker;
+        impl ParseRecorded<()> for Seeker {
+            fn parse_recorded(r: &mut FieldRecorder<'_>, _d: ()) -> ParseResult<Self> {
+                r.seek(SeekFrom::Start(100))?;
+                r.field::<(), _>("nothing", ())?;
+                Ok(Seeker)
+            }
+        }
+        assert!(diff_parsed::<Seeker, _>(&a, &b, ()).unwrap().is_empty());
+
+        let same = diff_parsed::<File, _>(&a, &a, Endian::Big).unwrap();
+        assert!(same.is_empty());
+        assert_eq!(same.to_string(), "(no differences)\n");
+    }
+}
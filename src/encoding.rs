@@ -0,0 +1,132 @@
+//! Decoding and encoding the byte strings in legacy encodings, such as Windows-1252, through
+//! `encoding_rs`. Only available with the `encoding` feature.
+//!
+//! ```
+//! use eyeutil::{encoding_rs::WINDOWS_1252, zstring::ZString};
+//!
+//! let name = ZString::new(vec![0x80, 0x31, 0x30]);
+//! assert_eq!(name.decode(WINDOWS_1252), "€10");
+//! assert_eq!(ZString::encode("€10", WINDOWS_1252).unwrap(), name);
+//! ```
+
+use crate::zstring::{BZString, FixedString, LengthPrefix, PrefixString, ZString};
+use encoding_rs::Encoding;
+use std::borrow::Cow;
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum EncodeError {
+    /// The character .0 can't be represented in the encoding named .1
+    Unmappable(char, &'static str),
+}
+
+fn decode<'a>(data: &'a [u8], encoding: &'static Encoding) -> Cow<'a, str> {
+    // The data is a single string field, so any BOM is content rather than a marker
+    encoding.decode_without_bom_handling(data).0
+}
+
+fn encode(value: &str, encoding: &'static Encoding) -> Result<Vec<u8>, EncodeError> {
+    let (data, _, unmappable) = encoding.encode(value);
+    if !unmappable {
+        return Ok(data.into_owned());
+    }
+
+    // encoding_rs doesn't say which character it was, so find it by encoding them one by one
+    let mut buffer = [0u8; 4];
+    let c = value
+        .chars()
+        .find(|c| encoding.encode(c.encode_utf8(&mut buffer)).2)
+        .unwrap_or(char::REPLACEMENT_CHARACTER);
+    Err(EncodeError::Unmappable(c, encoding.name()))
+}
+
+/// Adds `decode` and `encode` to string types with a `new(Vec<u8>)` constructor.
+macro_rules! impl_encoding {
+    ($(impl[$($generics:tt)*] $typ:ty),*) => {
+        $(
+            impl<$($generics)*> $typ {
+                /// Decodes the string from [encoding]. Malformed sequences are replaced with
+                /// U+FFFD.
+                #[inline]
+                pub fn decode(&self, encoding: &'static Encoding) -> Cow<'_, str> {
+                    decode(self.as_slice(), encoding)
+                }
+
+                /// Encodes [value] in [encoding], erroring with `EncodeError::Unmappable` if
+                /// it contains a character the encoding can't represent.
+                /// Note: the output encoding of UTF-16 encodings is UTF-8, see
+                /// `Encoding::output_encoding`.
+                #[inline]
+                pub fn encode(value: &str, encoding: &'static Encoding) -> Result<Self, EncodeError> {
+                    Ok(<$typ>::new(encode(value, encoding)?))
+                }
+            }
+        )*
+    };
+}
+impl_encoding!(
+    impl[] ZString,
+    impl[] BZString,
+    impl[const N: usize] FixedString<N>,
+    impl[L: LengthPrefix] PrefixString<L>
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse::Parse, writable::Writable, zstring::PString, Endian};
+    use encoding_rs::{SHIFT_JIS, WINDOWS_1252};
+
+    /// These are C1 control codes in Latin-1, but printable in Windows-1252
+    const C1_BYTES: &[u8] = b"\x80\x8A\x8C\x99\x9F";
+    const C1_CHARS: &str = "€ŠŒ™Ÿ";
+
+    #[test]
+    fn test_windows_1252() {
+        let zstring = ZString::new(C1_BYTES.to_vec());
+        assert_eq!(zstring.decode(WINDOWS_1252), C1_CHARS);
+        assert_ne!(
+            zstring.decode(WINDOWS_1252),
+            "\u{80}\u{8A}\u{8C}\u{99}\u{9F}"
+        );
+        assert_eq!(ZString::encode(C1_CHARS, WINDOWS_1252).unwrap(), zstring);
+
+        // Through the other string types, and written out
+        let pstring = PString::encode(C1_CHARS, WINDOWS_1252).unwrap();
+        let mut output = Vec::new();
+        pstring.write_to(&mut output, Endian::Little).unwrap();
+        assert_eq!(output[0], 5);
+        assert_eq!(&output[1..], C1_BYTES);
+        let parsed = PString::parse(&mut std::io::Cursor::new(&output), Endian::Little).unwrap();
+        assert_eq!(parsed.decode(WINDOWS_1252), C1_CHARS);
+
+        let fixed = FixedString::<8>::encode("café", WINDOWS_1252).unwrap();
+        assert_eq!(fixed.as_slice(), b"caf\xE9");
+        assert_eq!(fixed.decode(WINDOWS_1252), "café");
+        let bzstring = BZString::encode("Œuvre", WINDOWS_1252).unwrap();
+        assert_eq!(bzstring.as_slice(), b"\x8Cuvre");
+
+        // Borrows when nothing needs converting
+        assert!(matches!(
+            ZString::new(b"plain".to_vec()).decode(WINDOWS_1252),
+            Cow::Borrowed("plain")
+        ));
+        // UTF-8 sequences aren't UTF-8 here
+        assert_eq!(ZString::from("é").decode(WINDOWS_1252), "Ã©");
+    }
+
+    #[test]
+    fn test_encode_errors() {
+        assert_eq!(
+            ZString::encode("a€日b", WINDOWS_1252),
+            Err(EncodeError::Unmappable('日', "windows-1252"))
+        );
+        assert_eq!(
+            ZString::encode("日本", SHIFT_JIS).unwrap().as_slice(),
+            b"\x93\xFA\x96\x7B"
+        );
+        assert_eq!(
+            ZString::encode("€", SHIFT_JIS),
+            Err(EncodeError::Unmappable('€', "Shift_JIS"))
+        );
+    }
+}
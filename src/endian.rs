@@ -0,0 +1,144 @@
+//! Wrappers that fix the endianness in the type, so that it doesn't need to be passed in.
+//! `Le<u32>::parse(f, ())` is the same as `u32::parse(f, Endian::Little)`, which is useful for
+//! formats that only use one endianness, and lets the wrappers be used with the `u` fields of
+//! `impl_parse!`/`impl_writable!`.
+
+use crate::{
+    data_size::DataSize,
+    parse::{Parse, ParseResult},
+    writable::{Writable, WriteResult},
+    Endian,
+};
+use std::{
+    io::{Read, Write},
+    ops::{Deref, DerefMut},
+};
+
+macro_rules! impl_endian_wrapper {
+    ($(#[$outer:meta])* $name:ident, $endian:expr) => {
+        $(#[$outer])*
+        #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+        pub struct $name<T>(pub T);
+        impl<T> $name<T> {
+            /// The endianness that this parses and writes with.
+            pub const ENDIAN: Endian = $endian;
+
+            #[inline]
+            pub fn into_inner(self) -> T {
+                self.0
+            }
+        }
+        impl<T> From<T> for $name<T> {
+            #[inline]
+            fn from(value: T) -> Self {
+                $name(value)
+            }
+        }
+        impl<T> Deref for $name<T> {
+            type Target = T;
+
+            #[inline]
+            fn deref(&self) -> &Self::Target {
+                &self.0
+            }
+        }
+        impl<T> DerefMut for $name<T> {
+            #[inline]
+            fn deref_mut(&mut self) -> &mut Self::Target {
+                &mut self.0
+            }
+        }
+        impl<F, T> Parse<F> for $name<T>
+        where
+            F: Read,
+            T: Parse<F, Endian>,
+        {
+            #[inline]
+            fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+                T::parse(f, $endian).map($name)
+            }
+        }
+        impl<T> Writable<()> for $name<T>
+        where
+            T: Writable<Endian>,
+        {
+            #[inline]
+            fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
+            where
+                W: Write,
+            {
+                self.0.write_to(w, $endian)
+            }
+        }
+        impl<T> DataSize<()> for $name<T>
+        where
+            T: DataSize<()>,
+        {
+            #[inline]
+            fn data_size(&self, d: ()) -> u64 {
+                self.0.data_size(d)
+            }
+        }
+    };
+}
+
+impl_endian_wrapper!(
+    /// A [T] that is always parsed and written as little endian.
+    Le,
+    Endian::Little
+);
+impl_endian_wrapper!(
+    /// A [T] that is always parsed and written as big endian.
+    Be,
+    Endian::Big
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_le_u64() {
+        let data = [0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x8];
+        let value = Le::<u64>::parse(&mut Cursor::new(&data), ()).unwrap();
+        let expected = u64::parse(&mut Cursor::new(&data), Endian::Little).unwrap();
+        assert_eq!(value, Le(expected));
+        assert_eq!(*value, 0x0807060504030201);
+        assert_eq!(value.data_size(()), 8);
+
+        let mut output = Vec::new();
+        value.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, data);
+    }
+
+    #[test]
+    fn test_be_f32() {
+        let value = Be::from(-2.5f32);
+        let mut output = Vec::new();
+        value.write_to(&mut output, ()).unwrap();
+        let mut expected = Vec::new();
+        (-2.5f32).write_to(&mut expected, Endian::Big).unwrap();
+        assert_eq!(output, expected);
+
+        let parsed = Be::<f32>::parse(&mut Cursor::new(&output), ()).unwrap();
+        assert_eq!(parsed.into_inner(), -2.5);
+        assert_eq!(Be::<f32>::ENDIAN, Endian::Big);
+    }
+
+    struct Header {
+        kind: u8,
+        length: Le<u32>,
+        version: Be<u16>,
+    }
+    crate::impl_parse!(Header, [kind: u: u8, length: u: Le<u32>, version: u: Be<u16>]);
+
+    #[test]
+    fn test_impl_parse() {
+        let data = [0x1, 0x2, 0x0, 0x0, 0x0, 0x0, 0x3];
+        let header = Header::parse(&mut Cursor::new(&data), ()).unwrap();
+        assert_eq!(header.kind, 0x1);
+        assert_eq!(*header.length, 0x2);
+        assert_eq!(*header.version, 0x3);
+    }
+}
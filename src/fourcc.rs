@@ -0,0 +1,130 @@
+use crate::{
+    impl_data_size,
+    parse::{take_n, Parse, ParseError, ParseResult},
+    writable::{write_bytes, Writable, WriteResult},
+};
+use std::{
+    fmt,
+    io::{Read, Write},
+};
+
+/// A four byte tag, such as the `RIFF` at the start of wav files, or a chunk's type.
+/// Displays as ASCII, with any other bytes escaped.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FourCC(pub [u8; 4]);
+impl FourCC {
+    #[inline]
+    pub const fn new(tag: [u8; 4]) -> Self {
+        FourCC(tag)
+    }
+
+    /// Reads a FourCC, and checks that it is [expected].
+    /// Errors with `ParseError::UnexpectedFourCC` if it isn't.
+    #[inline]
+    pub fn expect<F, E>(f: &mut F, expected: E) -> ParseResult<()>
+    where
+        F: Read,
+        E: Into<FourCC>,
+    {
+        let expected = expected.into();
+        let found = FourCC::parse(f, ())?;
+        if found == expected {
+            Ok(())
+        } else {
+            Err(ParseError::UnexpectedFourCC(expected, found))
+        }
+    }
+}
+impl From<[u8; 4]> for FourCC {
+    #[inline]
+    fn from(tag: [u8; 4]) -> Self {
+        FourCC(tag)
+    }
+}
+impl PartialEq<[u8; 4]> for FourCC {
+    #[inline]
+    fn eq(&self, other: &[u8; 4]) -> bool {
+        &self.0 == other
+    }
+}
+impl PartialEq<&[u8; 4]> for FourCC {
+    #[inline]
+    fn eq(&self, other: &&[u8; 4]) -> bool {
+        &self.0 == *other
+    }
+}
+impl fmt::Display for FourCC {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for &byte in self.0.iter() {
+            for c in std::ascii::escape_default(byte) {
+                write!(f, "{}", c as char)?;
+            }
+        }
+        Ok(())
+    }
+}
+impl fmt::Debug for FourCC {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FourCC(\"{}\")", self)
+    }
+}
+impl<F: Read> Parse<F> for FourCC {
+    #[inline]
+    fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+        take_n::<_, 4>(f).map(FourCC)
+    }
+}
+impl Writable<()> for FourCC {
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
+    where
+        W: Write,
+    {
+        write_bytes(w, &self.0)
+    }
+}
+impl_data_size!(FourCC, 4);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_size::DataSize;
+    use std::io::Cursor;
+
+    const RIFF: FourCC = FourCC::new(*b"RIFF");
+
+    #[test]
+    fn test_fourcc() {
+        let mut cursor = Cursor::new(b"RIFFWAVE");
+        let tag = FourCC::parse(&mut cursor, ()).unwrap();
+        assert_eq!(tag, RIFF);
+        assert_eq!(tag, *b"RIFF");
+        assert_eq!(tag, b"RIFF");
+        FourCC::expect(&mut cursor, *b"WAVE").unwrap();
+        assert_eq!(tag.data_size(()), 4);
+
+        let mut output = Vec::new();
+        tag.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, b"RIFF");
+    }
+
+    #[test]
+    fn test_fourcc_mismatch() {
+        let mut cursor = Cursor::new(b"RIFX");
+        match FourCC::expect(&mut cursor, RIFF) {
+            Err(ParseError::UnexpectedFourCC(expected, found)) => {
+                assert_eq!(expected, RIFF);
+                assert_eq!(found, b"RIFX");
+            }
+            x => panic!("Expected UnexpectedFourCC, got: {:?}", x),
+        }
+        assert!(FourCC::expect(&mut cursor, RIFF).is_err());
+    }
+
+    #[test]
+    fn test_fourcc_display() {
+        assert_eq!(RIFF.to_string(), "RIFF");
+        assert_eq!(FourCC(*b"ab\0\xff").to_string(), "ab\\x00\\xff");
+        assert_eq!(format!("{:?}", RIFF), "FourCC(\"RIFF\")");
+    }
+}
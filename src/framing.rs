@@ -0,0 +1,196 @@
+//! Sentinel framing with byte stuffing, as used by HDLC, PPP and many serial protocols.
+//! A frame is a start sentinel, the payload, and an end sentinel. Any payload byte that is a
+//! sentinel or the escape byte is written as the escape byte followed by the byte XORed with
+//! a mask.
+
+use crate::{
+    parse::{single, ParseError, ParseResult},
+    writable::{write_bytes, WriteError, WriteResult},
+};
+use std::io::{Read, Write};
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct StuffingConfig {
+    pub start: u8,
+    /// May be the same as [start]
+    pub end: u8,
+    pub escape: u8,
+    /// XORed with escaped bytes
+    pub mask: u8,
+}
+impl StuffingConfig {
+    /// HDLC/PPP style: `0x7E` for both sentinels, `0x7D` to escape, and `0x20` as the mask.
+    pub const HDLC: StuffingConfig = StuffingConfig {
+        start: 0x7E,
+        end: 0x7E,
+        escape: 0x7D,
+        mask: 0x20,
+    };
+
+    /// Returns whether [byte] has to be escaped when in the payload.
+    #[inline]
+    pub fn needs_escape(&self, byte: u8) -> bool {
+        byte == self.start || byte == self.end || byte == self.escape
+    }
+}
+
+/// Reads a frame, returning the unescaped payload. Consumes up to and including the end
+/// sentinel.
+/// Bytes before the start sentinel are skipped. If the sentinels are the same, then empty
+/// frames are skipped as well, since back to back sentinels are commonly used as filler.
+/// Errors with `ParseError::DanglingEscape` if the frame ends directly after an escape byte,
+/// and `ParseError::EscapedSentinel` if an escape byte is followed by the start sentinel.
+/// If the sentinels differ, a start sentinel within the frame is `ParseError::UnexpectedSentinel`.
+pub fn read_stuffed_frame<F>(f: &mut F, cfg: StuffingConfig) -> ParseResult<Vec<u8>>
+where
+    F: Read,
+{
+    while single(f)? != cfg.start {}
+
+    let mut payload = Vec::new();
+    loop {
+        let byte = single(f)?;
+        if byte == cfg.end {
+            if payload.is_empty() && cfg.start == cfg.end {
+                // That was a start sentinel for the next frame
+                continue;
+            }
+            return Ok(payload);
+        } else if byte == cfg.start {
+            return Err(ParseError::UnexpectedSentinel(byte));
+        } else if byte == cfg.escape {
+            let escaped = single(f)?;
+            if escaped == cfg.end {
+                return Err(ParseError::DanglingEscape);
+            } else if escaped == cfg.start {
+                return Err(ParseError::EscapedSentinel(escaped));
+            }
+            payload.push(escaped ^ cfg.mask);
+        } else {
+            payload.push(byte);
+        }
+    }
+}
+
+/// Writes [payload] as a frame, escaping it as needed.
+/// This is written in a single write call, see `writable::write_bytes`.
+/// Errors with `WriteError::EmptyFrame` if [payload] is empty and the sentinels are the same,
+/// as `read_stuffed_frame` would skip it as filler.
+pub fn write_stuffed_frame<W>(w: &mut W, payload: &[u8], cfg: StuffingConfig) -> WriteResult
+where
+    W: Write,
+{
+    if payload.is_empty() && cfg.start == cfg.end {
+        return Err(WriteError::EmptyFrame);
+    }
+
+    let mut output = Vec::with_capacity(payload.len() + 2);
+    output.push(cfg.start);
+    for &byte in payload.iter() {
+        if cfg.needs_escape(byte) {
+            output.push(cfg.escape);
+            output.push(byte ^ cfg.mask);
+        } else {
+            output.push(byte);
+        }
+    }
+    output.push(cfg.end);
+    write_bytes(w, &output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const DISTINCT: StuffingConfig = StuffingConfig {
+        start: 0x02,
+        end: 0x03,
+        escape: 0x10,
+        mask: 0x40,
+    };
+
+    #[test]
+    fn test_stuffed_round_trip() {
+        let payloads: [&[u8]; 5] = [
+            b"",
+            &[0x01, 0x02, 0x03],
+            &[0x7E],
+            &[0x7D, 0x7E, 0x7D, 0x7D, 0x10, 0x02, 0x03],
+            &[0x7E, 0x00, 0x7E, 0x5E, 0x5D],
+        ];
+        for &cfg in [StuffingConfig::HDLC, DISTINCT].iter() {
+            for payload in payloads.iter() {
+                let mut output = Vec::new();
+                let result = write_stuffed_frame(&mut output, payload, cfg);
+                if payload.is_empty() && cfg.start == cfg.end {
+                    // Would be skipped as filler when read, so it isn't written
+                    assert!(matches!(result, Err(WriteError::EmptyFrame)));
+                    assert!(output.is_empty());
+                } else {
+                    result.unwrap();
+                    // Sentinels only appear at the ends
+                    assert!(!output[1..output.len() - 1].contains(&cfg.start));
+                    assert!(!output[1..output.len() - 1].contains(&cfg.end));
+
+                    let mut cursor = Cursor::new(&output);
+                    let parsed = read_stuffed_frame(&mut cursor, cfg).unwrap();
+                    assert_eq!(&parsed, payload);
+                    assert_eq!(cursor.position(), output.len() as u64);
+                }
+            }
+        }
+
+        let mut output = Vec::new();
+        write_stuffed_frame(&mut output, &[0x7E, 0x41, 0x7D], StuffingConfig::HDLC).unwrap();
+        assert_eq!(output, [0x7E, 0x7D, 0x5E, 0x41, 0x7D, 0x5D, 0x7E]);
+    }
+
+    #[test]
+    fn test_stuffed_stream() {
+        // Garbage, filler sentinels, then two frames
+        let data = [
+            0xFF, 0x00, 0x7E, 0x7E, 0x7E, 0x01, 0x7D, 0x5E, 0x7E, 0x7E, 0x02, 0x7E,
+        ];
+        let mut cursor = Cursor::new(&data[..]);
+        let cfg = StuffingConfig::HDLC;
+        assert_eq!(read_stuffed_frame(&mut cursor, cfg).unwrap(), [0x01, 0x7E]);
+        assert_eq!(read_stuffed_frame(&mut cursor, cfg).unwrap(), [0x02]);
+        match read_stuffed_frame(&mut cursor, cfg) {
+            Err(ParseError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof),
+            x => panic!("expected eof, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_stuffed_errors() {
+        let read = |data: &[u8], cfg| read_stuffed_frame(&mut Cursor::new(data), cfg);
+
+        match read(&[0x7E, 0x01, 0x7D, 0x7E], StuffingConfig::HDLC) {
+            Err(ParseError::DanglingEscape) => {}
+            x => panic!("expected dangling escape, got {:?}", x),
+        }
+        match read(&[0x02, 0x01, 0x10, 0x03], DISTINCT) {
+            Err(ParseError::DanglingEscape) => {}
+            x => panic!("expected dangling escape, got {:?}", x),
+        }
+        match read(&[0x02, 0x01, 0x10, 0x02, 0x03], DISTINCT) {
+            Err(ParseError::EscapedSentinel(0x02)) => {}
+            x => panic!("expected escaped sentinel, got {:?}", x),
+        }
+        match read(&[0x02, 0x01, 0x02, 0x03], DISTINCT) {
+            Err(ParseError::UnexpectedSentinel(0x02)) => {}
+            x => panic!("expected unexpected sentinel, got {:?}", x),
+        }
+        // Missing end sentinel
+        match read(&[0x7E, 0x01, 0x02], StuffingConfig::HDLC) {
+            Err(ParseError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof),
+            x => panic!("expected eof, got {:?}", x),
+        }
+        // Escape at the end of the data
+        match read(&[0x7E, 0x01, 0x7D], StuffingConfig::HDLC) {
+            Err(ParseError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof),
+            x => panic!("expected eof, got {:?}", x),
+        }
+    }
+}
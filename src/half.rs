@@ -0,0 +1,215 @@
+//! IEEE 754 binary16 (half precision) floats, as used for vertex data in GPU asset formats.
+//! Stored as their raw bits, with conversions to and from `f32` for doing any math.
+
+use crate::{
+    impl_data_size,
+    parse::{Parse, ParseResult},
+    writable::{Writable, WriteResult},
+    Endian,
+};
+use std::io::{Read, Write};
+
+/// A half precision float, stored as its raw bits.
+/// Note: equality compares the bits, so `0.0` and `-0.0` differ, and a NaN is equal to itself.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct F16(pub u16);
+impl F16 {
+    const SIGN: u16 = 0x8000;
+    const INFINITY: u16 = 0x7C00;
+
+    /// Note: this is exact, as every `F16` can be represented as an `f32`.
+    /// NaNs keep their payload, including the quiet bit.
+    pub fn to_f32(self) -> f32 {
+        let sign = u32::from(self.0 & F16::SIGN) << 16;
+        let exponent = u32::from((self.0 >> 10) & 0x1F);
+        let mantissa = u32::from(self.0 & 0x3FF);
+        let bits = match (exponent, mantissa) {
+            (0, 0) => sign,
+            // Subnormal, which is a normal number as an f32
+            (0, _) => {
+                let mut exponent = 127 - 14;
+                let mut mantissa = mantissa;
+                while mantissa & 0x400 == 0 {
+                    mantissa <<= 1;
+                    exponent -= 1;
+                }
+                sign | (exponent << 23) | ((mantissa & 0x3FF) << 13)
+            }
+            // Infinity or NaN
+            (0x1F, _) => sign | 0x7F80_0000 | (mantissa << 13),
+            _ => sign | ((exponent + 127 - 15) << 23) | (mantissa << 13),
+        };
+        f32::from_bits(bits)
+    }
+
+    /// Rounds to the nearest `F16`, with ties to even. Values too large become infinity, and
+    /// values too small become zero or a subnormal.
+    /// NaNs keep the top of their payload, including the quiet bit. A signaling NaN whose
+    /// payload would be lost stays a signaling NaN, rather than becoming infinity.
+    pub fn from_f32(value: f32) -> F16 {
+        let bits = value.to_bits();
+        let sign = ((bits >> 16) as u16) & F16::SIGN;
+        let exponent = ((bits >> 23) & 0xFF) as i32;
+        let mantissa = bits & 0x7F_FFFF;
+
+        if exponent == 0xFF {
+            if mantissa == 0 {
+                return F16(sign | F16::INFINITY);
+            }
+            let payload = (mantissa >> 13) as u16;
+            return F16(sign | F16::INFINITY | payload.max(1));
+        }
+
+        let exponent = exponent - 127 + 15;
+        if exponent >= 0x1F {
+            return F16(sign | F16::INFINITY);
+        }
+        if exponent <= 0 {
+            // Subnormal, in units of the smallest subnormal (2^-24)
+            let shift = (14 - exponent) as u32;
+            if shift > 24 {
+                return F16(sign);
+            }
+            let mantissa = mantissa | 0x80_0000;
+            let rounded = round_shift(mantissa, shift);
+            // Rounding up to 0x400 is the smallest normal, which is the right bit pattern
+            return F16(sign | rounded as u16);
+        }
+
+        let bits = ((exponent as u32) << 10) | (mantissa >> 13);
+        let rounded = bits + round_up(mantissa, 13) as u32;
+        // Carrying out of the mantissa increments the exponent, up to infinity
+        F16(sign | rounded as u16)
+    }
+}
+/// Returns whether shifting [value] right by [shift] should round up, with ties to even.
+#[inline]
+fn round_up(value: u32, shift: u32) -> bool {
+    let halfway = 1 << (shift - 1);
+    let remainder = value & ((1 << shift) - 1);
+    remainder > halfway || (remainder == halfway && (value >> shift) & 1 == 1)
+}
+#[inline]
+fn round_shift(value: u32, shift: u32) -> u32 {
+    (value >> shift) + round_up(value, shift) as u32
+}
+impl From<F16> for f32 {
+    #[inline]
+    fn from(value: F16) -> f32 {
+        value.to_f32()
+    }
+}
+impl<F: Read> Parse<F, Endian> for F16 {
+    #[inline]
+    fn parse(f: &mut F, endian: Endian) -> ParseResult<Self> {
+        u16::parse(f, endian).map(F16)
+    }
+}
+impl Writable<Endian> for F16 {
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, endian: Endian) -> WriteResult
+    where
+        W: Write,
+    {
+        self.0.write_to(w, endian)
+    }
+}
+impl_data_size!(F16, 2);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_size::DataSize;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_f16_known_values() {
+        let values = [
+            (0x3C00, 1.0),
+            (0xC100, -2.5),
+            (0x0000, 0.0),
+            (0x7BFF, 65504.0),
+            (0x7C00, f32::INFINITY),
+            (0xFC00, f32::NEG_INFINITY),
+            // Smallest subnormal, 2^-24
+            (0x0001, 2f32.powi(-24)),
+            // Largest subnormal
+            (0x03FF, 1023.0 * 2f32.powi(-24)),
+            // Smallest normal, 2^-14
+            (0x0400, 2f32.powi(-14)),
+        ];
+        for &(bits, value) in values.iter() {
+            assert_eq!(F16(bits).to_f32(), value, "{:#06x}", bits);
+            assert_eq!(F16::from_f32(value), F16(bits), "{}", value);
+        }
+        assert_eq!(F16::from_f32(-0.0), F16(0x8000));
+        assert!(F16(0x8000).to_f32().is_sign_negative());
+    }
+
+    #[test]
+    fn test_f16_rounding() {
+        // Halfway between 0x3C00 and 0x3C01, so ties to the even 0x3C00
+        assert_eq!(F16::from_f32(1.0 + 2f32.powi(-11)), F16(0x3C00));
+        // Halfway between 0x3C01 and 0x3C02
+        assert_eq!(F16::from_f32(1.0 + 3.0 * 2f32.powi(-11)), F16(0x3C02));
+        assert_eq!(F16::from_f32(1.0 + 1.1 * 2f32.powi(-11)), F16(0x3C01));
+        // Rounds up past the largest finite value
+        assert_eq!(F16::from_f32(65520.0), F16(0x7C00));
+        assert_eq!(F16::from_f32(65519.0), F16(0x7BFF));
+        assert_eq!(F16::from_f32(1e10), F16(0x7C00));
+        // Subnormals
+        assert_eq!(F16::from_f32(2f32.powi(-25)), F16(0x0000));
+        assert_eq!(F16::from_f32(1.5 * 2f32.powi(-25)), F16(0x0001));
+        assert_eq!(F16::from_f32(3.0 * 2f32.powi(-24)), F16(0x0003));
+        assert_eq!(F16::from_f32(-1e-10), F16(0x8000));
+        // The largest subnormal rounding up into the smallest normal
+        assert_eq!(
+            F16::from_f32(0x3FF as f32 * 2f32.powi(-24) + 2f32.powi(-25)),
+            F16(0x0400)
+        );
+    }
+
+    #[test]
+    fn test_f16_nan() {
+        let quiet = F16(0x7E00).to_f32();
+        assert!(quiet.is_nan());
+        assert_eq!(quiet.to_bits(), 0x7FC0_0000);
+        assert_eq!(F16::from_f32(quiet), F16(0x7E00));
+        assert_eq!(F16::from_f32(f32::NAN).0 & 0x7E00, 0x7E00);
+
+        // Signaling, with a payload
+        let signaling = F16(0xFC01).to_f32();
+        assert!(signaling.is_nan());
+        assert_eq!(F16::from_f32(signaling), F16(0xFC01));
+        // A signaling NaN whose payload doesn't fit stays a NaN
+        assert_eq!(F16::from_f32(f32::from_bits(0x7F80_0001)), F16(0x7C01));
+    }
+
+    #[test]
+    fn test_f16_exhaustive_roundtrip() {
+        for bits in 0..=u16::MAX {
+            let value = F16(bits);
+            assert_eq!(F16::from_f32(value.to_f32()), value, "{:#06x}", bits);
+        }
+    }
+
+    #[test]
+    fn test_f16_parse_write() {
+        let data = [0x00, 0x3C, 0x00, 0xC1];
+        let mut cursor = Cursor::new(&data);
+        let one = F16::parse(&mut cursor, Endian::Little).unwrap();
+        let value = F16::parse(&mut cursor, Endian::Big).unwrap();
+        assert_eq!(one.to_f32(), 1.0);
+        assert_eq!(f32::from(value), 2f32.powi(-24) * 0xC1 as f32);
+        assert_eq!(one.data_size(()), 2);
+
+        let mut output = Vec::new();
+        F16::from_f32(-2.5)
+            .write_to(&mut output, Endian::Big)
+            .unwrap();
+        F16::from_f32(-2.5)
+            .write_to(&mut output, Endian::Little)
+            .unwrap();
+        assert_eq!(output, [0xC1, 0x00, 0x00, 0xC1]);
+    }
+}
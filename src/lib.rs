@@ -1,14 +1,79 @@
+//! # Minimum supported Rust version
+//! The core of the crate (everything but the modules listed below) supports Rust 1.56, as
+//! declared by `rust-version` in Cargo.toml. Raising it is a minor version bump, and is noted
+//! in the changelog.
+//! Anything that needs a newer compiler is behind a cargo feature, none of which are on by
+//! default, so that a default build works on the MSRV:
+//! - `owned` (Rust 1.65): the `owned` module, which uses generic associated types.
+//! - `mmap` (Rust 1.65): the `mmap` module, as that is what `memmap2` needs.
+//! - `async-tokio` (Rust 1.75): the `async_parse` module, which returns `impl Future` from a
+//!   trait method.
+//! - `derive` (Rust 1.71): the derive macros, as that is what `syn` needs.
+//!
+//! CI runs `cargo +1.56 test --no-default-features` alongside the usual stable build, as a
+//! newer compiler won't catch uses of newer std APIs.
+//!
+//! # `no_std`
+//! The crate requires `std`, and there is no `std` feature to turn off. `Parse` and `Writable`
+//! are built on `std::io`'s `Read`, `Write` and `Seek`, and so is every helper and reader type.
+//! `ZString` and the other byte strings wrap bstr 0.2's `BString`, which only exists with
+//! std, and `ParseError::Custom` holds a `Box<dyn std::error::Error + Send + Sync>`, while
+//! `core::error::Error` needs Rust 1.81. Supporting `core + alloc` would mean an io facade
+//! through the whole crate plus a breaking bstr upgrade, so it waits for a major version.
+
 use std::{
-    fmt::Result,
-    io::{ErrorKind, Read, Seek, SeekFrom},
+    convert::TryFrom,
+    io::{ErrorKind, IoSlice, IoSliceMut, Read, Seek, SeekFrom, Write},
 };
 
+pub mod addr;
+#[cfg(feature = "async-tokio")]
+pub mod async_parse;
+pub mod audit;
+pub mod bits;
+pub mod borrowed;
+pub mod checksum;
+pub mod ctx;
 pub mod data_size;
+pub mod diff;
+#[cfg(feature = "encoding")]
+pub mod encoding;
+pub mod endian;
+pub mod fourcc;
+pub mod framing;
+pub mod half;
+pub mod maybe;
+#[cfg(feature = "mmap")]
+pub mod mmap;
+pub mod net;
+pub mod nonzero;
+pub mod offset;
+#[cfg(feature = "owned")]
+pub mod owned;
 pub mod parse;
+pub mod peek;
+pub mod positioned;
+pub mod prelude;
+pub mod roundtrip;
+pub mod sections;
 pub mod slice;
+pub mod tee;
+#[cfg(test)]
+mod test_util;
+pub mod time;
+pub mod transform;
+pub mod version;
 pub mod writable;
 pub mod zstring;
 pub use bstr;
+#[cfg(feature = "encoding")]
+pub use encoding_rs;
+#[cfg(feature = "mmap")]
+pub use memmap2;
+#[doc(hidden)]
+pub use paste;
+#[cfg(feature = "async-tokio")]
+pub use tokio;
 
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub enum EnumConversionError<V> {
@@ -23,18 +88,92 @@ pub enum Endian {
     Little,
     Big,
 }
+impl Endian {
+    /// The endianness of the target being compiled for.
+    #[cfg(target_endian = "little")]
+    pub const NATIVE: Endian = Endian::Little;
+    /// The endianness of the target being compiled for.
+    #[cfg(target_endian = "big")]
+    pub const NATIVE: Endian = Endian::Big;
+
+    #[inline]
+    pub const fn opposite(self) -> Endian {
+        match self {
+            Endian::Little => Endian::Big,
+            Endian::Big => Endian::Little,
+        }
+    }
+
+    /// Returns whether this is the endianness of the target being compiled for.
+    #[inline]
+    pub fn is_native(self) -> bool {
+        self == Endian::NATIVE
+    }
+
+    /// Reads a 2 byte UTF-16 byte order mark, `FE FF` being big endian and `FF FE` being little
+    /// endian.
+    /// Errors with `ParseError::InvalidByte` if it is neither. The bytes are consumed either way.
+    #[inline]
+    pub fn detect_bom<F: Read>(f: &mut F) -> parse::ParseResult<Endian> {
+        match parse::take_n::<_, 2>(f)? {
+            [0xFE, 0xFF] => Ok(Endian::Big),
+            [0xFF, 0xFE] => Ok(Endian::Little),
+            _ => Err(parse::ParseError::InvalidByte),
+        }
+    }
+
+    /// Reads a 4 byte magic, which is [magic] when read as a little endian u32 in little endian
+    /// files, and when read as a big endian u32 in big endian files.
+    /// Errors with `ParseError::InvalidByte` if it matches neither. The bytes are consumed
+    /// either way.
+    #[inline]
+    pub fn detect_magic<F: Read>(f: &mut F, magic: u32) -> parse::ParseResult<Endian> {
+        Self::detect_with(f, |bytes| {
+            if u32::from_le_bytes(bytes) == magic {
+                Some(Endian::Little)
+            } else if u32::from_be_bytes(bytes) == magic {
+                Some(Endian::Big)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Reads 4 bytes and gives them to [detect], for markers that aren't just a byte swapped
+    /// magic, such as TIFF's `II*\0` and `MM\0*`.
+    /// Errors with `ParseError::InvalidByte` if [detect] returns `None`. The bytes are consumed
+    /// either way.
+    #[inline]
+    pub fn detect_with<F, C>(f: &mut F, detect: C) -> parse::ParseResult<Endian>
+    where
+        F: Read,
+        C: FnOnce([u8; 4]) -> Option<Endian>,
+    {
+        let bytes = parse::take_n::<_, 4>(f)?;
+        detect(bytes).ok_or(parse::ParseError::InvalidByte)
+    }
+}
 
 #[inline]
 pub fn stream_position<F>(f: &mut F) -> std::io::Result<u64>
 where
     F: Seek,
 {
-    f.seek(SeekFrom::Current(0))
+    f.stream_position()
 }
 
 // TODO: once `Seek::stream_len` is stabilized, replace this with it.
 #[inline]
 pub fn stream_len<F>(f: &mut F) -> std::io::Result<u64>
+where
+    F: Seek,
+{
+    stream_position_and_len(f).map(|(_, len)| len)
+}
+
+/// Returns the current position and the length, leaving the position as it was.
+/// This is two seeks if already at the end, and three otherwise.
+pub(crate) fn stream_position_and_len<F>(f: &mut F) -> std::io::Result<(u64, u64)>
 where
     F: Seek,
 {
@@ -46,7 +185,100 @@ where
         f.seek(SeekFrom::Start(old_pos))?;
     }
 
-    Ok(len)
+    Ok((old_pos, len))
+}
+
+/// Returns how many bytes are left after the current position, which is zero if it is past
+/// the end. Cheaper than using both [stream_len] and [stream_position].
+#[inline]
+pub fn stream_remaining<F>(f: &mut F) -> std::io::Result<u64>
+where
+    F: Seek,
+{
+    stream_position_and_len(f).map(|(position, len)| len.saturating_sub(position))
+}
+
+/// Returns whether the current position is at (or past) the end.
+#[inline]
+pub fn is_eof<F>(f: &mut F) -> std::io::Result<bool>
+where
+    F: Seek,
+{
+    stream_remaining(f).map(|remaining| remaining == 0)
+}
+
+/// Like [is_eof], for readers which can't Seek. This peeks a byte, which stays buffered in [f]
+/// for the next read.
+#[inline]
+pub fn probe_eof<F>(f: &mut peek::PeekReader<F>) -> std::io::Result<bool>
+where
+    F: Read,
+{
+    f.peek(&mut [0u8]).map(|amount| amount == 0)
+}
+
+/// Parses [P] from the start of [data].
+/// Anything after it is ignored, see [parse_bytes_strict] to require that it all be used.
+#[inline]
+pub fn parse_bytes<P, D>(data: &[u8], d: D) -> parse::ParseResult<P>
+where
+    P: for<'a> parse::Parse<std::io::Cursor<&'a [u8]>, D>,
+{
+    P::parse(&mut std::io::Cursor::new(data), d)
+}
+
+/// Like [parse_bytes], but errors with `ParseError::ExpectedEOF` if [P] didn't consume all of
+/// [data].
+#[inline]
+pub fn parse_bytes_strict<P, D>(data: &[u8], d: D) -> parse::ParseResult<P>
+where
+    P: for<'a> parse::Parse<std::io::Cursor<&'a [u8]>, D>,
+{
+    let mut cursor = std::io::Cursor::new(data);
+    let value = P::parse(&mut cursor, d)?;
+    if cursor.position() != data.len() as u64 {
+        return Err(parse::ParseError::ExpectedEOF);
+    }
+    Ok(value)
+}
+
+/// Opens the file at [path], and parses [P] from its start through a `BufReader`.
+#[inline]
+pub fn parse_file<P, D, Q>(path: Q, d: D) -> parse::ParseResult<P>
+where
+    P: parse::Parse<std::io::BufReader<std::fs::File>, D>,
+    Q: AsRef<std::path::Path>,
+{
+    let file = std::fs::File::open(path)?;
+    P::parse(&mut std::io::BufReader::new(file), d)
+}
+
+/// Writes [value] into a new `Vec`.
+/// See [write_to_vec_sized] to allocate it up front, for types which implement `DataSize`.
+#[inline]
+pub fn write_to_vec<T, D>(value: &T, d: D) -> Result<Vec<u8>, writable::WriteError>
+where
+    T: writable::Writable<D>,
+    D: std::fmt::Debug + Clone + PartialEq,
+{
+    let mut output = Vec::new();
+    value.write_to(&mut output, d)?;
+    Ok(output)
+}
+
+/// Like [write_to_vec], but allocates the `Vec` with [value]'s data size, so that it doesn't
+/// have to grow.
+/// Note: the size is from `DataSize<()>`, as with `impl_writable!`, whatever [D] is.
+#[inline]
+pub fn write_to_vec_sized<T, D>(value: &T, d: D) -> Result<Vec<u8>, writable::WriteError>
+where
+    T: writable::Writable<D> + data_size::DataSize<()>,
+    D: std::fmt::Debug + Clone + PartialEq,
+{
+    let size = usize::try_from(value.data_size(())).unwrap_or(0);
+    let mut output = Vec::with_capacity(size);
+    value.write_to(&mut output, d)?;
+    Ok(output)
 }
 
 // TODO: should this have a sub-module?
@@ -54,52 +286,298 @@ where
 /// `impl_flags!(LinkFlags, flags, [thing1 : 0b1, thing2: 0b10]);`
 /// implements function that shift-lefts (1 << n) then returns if that bit set:
 /// `impl_flags(shl, LinkFlags, flags, [thing1: 0, thing2: 1]);`
+/// Each flag also gets a `set_thing1(&mut self, value: bool)` setter, and the field gets
+/// `flags_flag_names` and `fmt_flags` methods (named after the field, so that a struct can have
+/// more than one flags field).
+/// Since the macro can't see the type of the field, a `THING1_MASK` associated constant is only
+/// generated when the field's type is given:
+/// `impl_flags!(LinkFlags, flags: u32, [thing1 : 0b1, thing2: 0b10]);`
+/// implements functions that get/set multi-bit values packed into inclusive bit ranges:
+/// `impl_flags!(field Header, bits: u16, [level: 0..=3, kind: 4..=6]);`
+/// The getter returns the value shifted down, and the setter returns false (leaving the field
+/// unchanged) if the value doesn't fit. The field's type is required here, and must be unsigned.
 #[macro_export]
 macro_rules! impl_flags {
-    ($strct:ty, $field:ident, [$($(#[$outer:meta])* $name:ident : $bits:expr),*]) => {
-        impl $strct {
-            $(
-                $(#[$outer])*
-                pub fn $name(&self) -> bool {
-                    (self.$field & $bits) != 0
-                }
-            )*
+    (@masks $strct:ty, $typ:ty, [$($name:ident : $bits:expr),*]) => {
+        $crate::paste::paste! {
+            #[allow(dead_code)]
+            impl $strct {
+                $(
+                    #[doc = concat!("Mask of the `", stringify!($name), "` flag.")]
+                    pub const [<$name:upper _MASK>]: $typ = $bits;
+                )*
+            }
         }
     };
 
-    (shl $strct:ty, $field:ident, [$($(#[$outer:meta])* $name:ident : $l:expr),*]) => {
-        impl $strct {
-            $(
-                $(#[$outer])*
-                pub fn $name(&self) -> bool {
-                    (self.$field & (1 << $l)) != 0
+    (@impl $strct:ty, $field:ident, [$($(#[$outer:meta])* $name:ident : $bits:expr),*]) => {
+        $crate::paste::paste! {
+            // Not every flag's setter gets used, which shouldn't warn in the caller's crate
+            #[allow(dead_code)]
+            impl $strct {
+                $(
+                    $(#[$outer])*
+                    pub fn $name(&self) -> bool {
+                        (self.$field & ($bits)) != 0
+                    }
+
+                    #[doc = concat!("Sets or clears the `", stringify!($name), "` flag.")]
+                    pub fn [<set_ $name>](&mut self, value: bool) {
+                        if value {
+                            self.$field |= $bits;
+                        } else {
+                            self.$field &= !($bits);
+                        }
+                    }
+                )*
+
+                #[doc = concat!("Returns the names of the `", stringify!($field),
+                    "` flags which are set, in declaration order.")]
+                pub fn [<$field _flag_names>](&self) -> Vec<&'static str> {
+                    let mut names = Vec::new();
+                    $(
+                        if self.$name() {
+                            names.push(stringify!($name));
+                        }
+                    )*
+                    names
                 }
-            )*
+
+                #[doc = concat!("Writes the set `", stringify!($field),
+                    "` flags like `thing1 | thing2`, for use in Debug impls.")]
+                /// Any set bits that aren't a declared flag are written in hex at the end,
+                /// like `thing1 | 0x800`. If nothing is set, this writes `(empty)`.
+                pub fn [<fmt_ $field>](&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                    let mut remainder = self.$field;
+                    let mut first = true;
+                    $(
+                        if self.$name() {
+                            if !first {
+                                f.write_str(" | ")?;
+                            }
+                            f.write_str(stringify!($name))?;
+                            first = false;
+                        }
+                        remainder &= !($bits);
+                    )*
+
+                    if remainder != 0 {
+                        if !first {
+                            f.write_str(" | ")?;
+                        }
+                        write!(f, "{:#x}", remainder)?;
+                    } else if first {
+                        f.write_str("(empty)")?;
+                    }
+                    Ok(())
+                }
+            }
+        }
+    };
+
+    (field $strct:ty, $field:ident : $typ:ty, [$($(#[$outer:meta])* $name:ident : $lo:literal ..= $hi:literal),*]) => {
+        $crate::paste::paste! {
+            #[allow(dead_code)]
+            impl $strct {
+                $(
+                    #[doc = concat!("Mask of the `", stringify!($name), "` bits, in place.")]
+                    pub const [<$name:upper _MASK>]: $typ =
+                        ((!(0 as $typ)) >> (<$typ>::BITS - ($hi - $lo + 1))) << $lo;
+
+                    $(#[$outer])*
+                    pub fn $name(&self) -> $typ {
+                        (self.$field & Self::[<$name:upper _MASK>]) >> $lo
+                    }
+
+                    #[doc = concat!("Sets the `", stringify!($name), "` bits to [value].")]
+                    /// Returns false, leaving the field unchanged, if the value doesn't fit.
+                    pub fn [<set_ $name>](&mut self, value: $typ) -> bool {
+                        if value > (Self::[<$name:upper _MASK>] >> $lo) {
+                            return false;
+                        }
+
+                        self.$field = (self.$field & !Self::[<$name:upper _MASK>]) | (value << $lo);
+                        true
+                    }
+                )*
+            }
         }
     };
+
+    (shl $strct:ty, $field:ident : $typ:ty, [$($(#[$outer:meta])* $name:ident : $l:expr),*]) => {
+        $crate::impl_flags!(@masks $strct, $typ, [$($name : (1 << $l)),*]);
+        $crate::impl_flags!(@impl $strct, $field, [$($(#[$outer])* $name : (1 << $l)),*]);
+    };
+
+    (shl $strct:ty, $field:ident, [$($(#[$outer:meta])* $name:ident : $l:expr),*]) => {
+        $crate::impl_flags!(@impl $strct, $field, [$($(#[$outer])* $name : (1 << $l)),*]);
+    };
+
+    ($strct:ty, $field:ident : $typ:ty, [$($(#[$outer:meta])* $name:ident : $bits:expr),*]) => {
+        $crate::impl_flags!(@masks $strct, $typ, [$($name : $bits),*]);
+        $crate::impl_flags!(@impl $strct, $field, [$($(#[$outer])* $name : $bits),*]);
+    };
+
+    ($strct:ty, $field:ident, [$($(#[$outer:meta])* $name:ident : $bits:expr),*]) => {
+        $crate::impl_flags!(@impl $strct, $field, [$($(#[$outer])* $name : $bits),*]);
+    };
+}
+
+/// Chunk size used by [skip], which is a stack buffer.
+pub const SKIP_CHUNK: usize = 8 * 1024;
+
+/// Skip `amount` bytes by reading and discarding them, [SKIP_CHUNK] bytes at a time.
+/// This is for when you don't implement seek, see [skip_seek] for when you do, and [Skip] for
+/// picking between them.
+/// Running out of data gives an [ErrorKind::UnexpectedEof] error, see [skip_reporting] for
+/// finding out how much was skipped before that.
+#[inline]
+pub fn skip<F: Read>(f: F, amount: u64) -> std::io::Result<()> {
+    skip_chunked::<F, SKIP_CHUNK>(f, amount)
 }
 
-/// Skip `amount` bytes. This is for when you don't implement seek.
-/// TODO: This is really shoddy, and specialization would make this way better.
+/// Like [skip], but reading [CHUNK] bytes at a time, such as for a smaller stack buffer.
+/// Note: [CHUNK] must be non-zero.
 #[inline]
-pub fn skip<F: Read, const CHUNK: usize>(mut f: F, mut amount: usize) -> std::io::Result<()> {
+pub fn skip_chunked<F: Read, const CHUNK: usize>(f: F, amount: u64) -> std::io::Result<()> {
+    let skipped = skip_reporting::<F, CHUNK>(f, amount)?;
+    if skipped == amount {
+        Ok(())
+    } else {
+        Err(std::io::Error::new(
+            ErrorKind::UnexpectedEof,
+            format!(
+                "skipped {} of {} bytes before reaching the end of the stream",
+                skipped, amount
+            ),
+        ))
+    }
+}
+
+/// Skip up to `amount` bytes by reading and discarding them, [CHUNK] bytes at a time.
+/// Returns the number of bytes skipped, which is only less than `amount` if the end of the
+/// stream was reached. Reaching the end is not an error, so any partial progress is kept.
+/// If an error occurs, then no assurances are made about how much was skipped.
+/// Note: [CHUNK] must be non-zero.
+pub fn skip_reporting<F: Read, const CHUNK: usize>(mut f: F, amount: u64) -> std::io::Result<u64> {
     let mut buf = [0_u8; CHUNK];
+    let mut skipped = 0;
 
-    loop {
-        if amount == 0 {
-            break;
+    while skipped < amount {
+        // Fits in a usize, since it is at most CHUNK
+        let end = (CHUNK as u64).min(amount - skipped) as usize;
+        let buf_slice: &mut [u8] = &mut buf[..end];
+        match f.read(buf_slice) {
+            Ok(0) => break,
+            // A reader claiming to have read more than it was given can't push this past
+            // `amount`
+            Ok(amount_read) => skipped += amount_read.min(end) as u64,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
         }
+    }
 
-        let end = CHUNK.min(amount);
-        let buf_slice: &mut [u8] = &mut buf[..end];
-        f.read_exact(buf_slice)?;
+    Ok(skipped)
+}
 
-        amount = amount.saturating_sub(CHUNK);
+/// Skip `amount` bytes by seeking forward.
+/// Seeking past the end is allowed by most [Seek] implementations, so this checks against the
+/// stream's length. If there aren't `amount` bytes left it is left at the end, and gives an
+/// [ErrorKind::UnexpectedEof] error, the same as [skip] would.
+pub fn skip_seek<F: Seek>(mut f: F, amount: u64) -> std::io::Result<()> {
+    let (position, len) = stream_position_and_len(&mut f)?;
+    match position.checked_add(amount) {
+        Some(target) if target <= len => {
+            f.seek(SeekFrom::Start(target))?;
+            Ok(())
+        }
+        _ => {
+            // Never seek backwards, even if we somehow started past the end
+            f.seek(SeekFrom::Start(len.max(position)))?;
+            Err(std::io::Error::new(
+                ErrorKind::UnexpectedEof,
+                "skipped past the end of the stream",
+            ))
+        }
     }
+}
 
-    Ok(())
+/// Skipping over bytes, by seeking when the type supports it, or by reading otherwise.
+/// Since there's no specialization, this is implemented for specific types. For your own
+/// types, implement it by calling [skip_seek] or [skip].
+pub trait Skip {
+    /// Skip `amount` bytes.
+    /// Running out of data gives an [ErrorKind::UnexpectedEof] error.
+    fn skip(&mut self, amount: u64) -> std::io::Result<()>;
+}
+impl<T> Skip for &mut T
+where
+    T: Skip + ?Sized,
+{
+    #[inline]
+    fn skip(&mut self, amount: u64) -> std::io::Result<()> {
+        (**self).skip(amount)
+    }
+}
+impl<T> Skip for Box<T>
+where
+    T: Skip + ?Sized,
+{
+    #[inline]
+    fn skip(&mut self, amount: u64) -> std::io::Result<()> {
+        (**self).skip(amount)
+    }
 }
 
+/// Implements [Skip] using [skip_seek] (`seek`) or [skip] (`read`).
+macro_rules! impl_skip {
+    (seek, [$($(#[$attr:meta])* [$($gen:tt)*] $typ:ty),* $(,)?]) => {
+        $(
+            $(#[$attr])*
+            impl<$($gen)*> Skip for $typ {
+                #[inline]
+                fn skip(&mut self, amount: u64) -> std::io::Result<()> {
+                    skip_seek(self, amount)
+                }
+            }
+        )*
+    };
+    (read, [$($(#[$attr:meta])* [$($gen:tt)*] $typ:ty),* $(,)?]) => {
+        $(
+            $(#[$attr])*
+            impl<$($gen)*> Skip for $typ {
+                #[inline]
+                fn skip(&mut self, amount: u64) -> std::io::Result<()> {
+                    skip(self, amount)
+                }
+            }
+        )*
+    };
+}
+impl_skip!(seek, [
+    [T: AsRef<[u8]>] std::io::Cursor<T>,
+    [] std::fs::File,
+    [] &std::fs::File,
+    [R: Read + Seek] std::io::BufReader<R>,
+    [R: Read + Seek] CountingReader<R>,
+    [F: Read + Seek] slice::InputSlice<F>,
+    [F: Read + Seek] slice::InputLimit<F>,
+    [F: Read + Seek] slice::MultiSlice<F>,
+    [R: slice::ReadAt] slice::SharedSlice<R>,
+    #[cfg(feature = "mmap")] [] mmap::MmapSlice,
+]);
+impl_skip!(read, [
+    [] &[u8],
+    [] std::io::Stdin,
+    [] std::io::StdinLock<'_>,
+    [] std::net::TcpStream,
+    [] std::process::ChildStdout,
+    [] std::process::ChildStderr,
+    [R: Read] std::io::Take<R>,
+    [R: Read] peek::PeekReader<R>,
+    [R: Read, W: Write] tee::TeeReader<R, W>,
+]);
+
 /// Reads to fill the buffer if it can.
 /// Values up to the returned Ok(usize) are valid
 /// If there was an error then no assurances are made.
@@ -133,6 +611,421 @@ pub fn read_if_possible<F: Read>(mut f: F, mut buf: &mut [u8]) -> std::io::Resul
     Ok(amount_read)
 }
 
+/// Like [read_if_possible], but reads into several buffers, filling each before the next.
+/// Returns the total amount read, which is only less than their combined length if [f] ended.
+pub fn read_if_possible_vectored<F: Read>(
+    mut f: F,
+    bufs: &mut [IoSliceMut<'_>],
+) -> std::io::Result<usize> {
+    let mut amount_read: usize = 0;
+    // The first buffer that isn't full, and how much of it is
+    let (mut index, mut offset) = (0, 0);
+    loop {
+        while index < bufs.len() && offset == bufs[index].len() {
+            index += 1;
+            offset = 0;
+        }
+        let (first, rest) = match bufs[index..].split_first_mut() {
+            Some(x) => x,
+            None => break,
+        };
+
+        let mut slices = Vec::with_capacity(rest.len() + 1);
+        slices.push(IoSliceMut::new(&mut first[offset..]));
+        slices.extend(rest.iter_mut().map(|buf| IoSliceMut::new(buf)));
+        match f.read_vectored(&mut slices) {
+            Ok(0) => break,
+            Ok(c) => {
+                amount_read += c;
+                advance_slices(&mut index, &mut offset, c, |i| bufs[i].len());
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(amount_read)
+}
+
+/// Writes all of [bufs], in order, continuing after short and partial writes and retrying on
+/// `ErrorKind::Interrupted`.
+/// Errors with `ErrorKind::WriteZero` if the writer stops accepting bytes.
+pub fn write_vectored_all<W: Write + ?Sized>(
+    w: &mut W,
+    bufs: &[IoSlice<'_>],
+) -> std::io::Result<()> {
+    let total: usize = bufs.iter().map(|buf| buf.len()).sum();
+    let mut amount_written: usize = 0;
+    let (mut index, mut offset) = (0, 0);
+    loop {
+        while index < bufs.len() && offset == bufs[index].len() {
+            index += 1;
+            offset = 0;
+        }
+        let (first, rest) = match bufs[index..].split_first() {
+            Some(x) => x,
+            None => break,
+        };
+
+        let mut slices = Vec::with_capacity(rest.len() + 1);
+        slices.push(IoSlice::new(&first[offset..]));
+        slices.extend(rest.iter().map(|buf| IoSlice::new(buf)));
+        match w.write_vectored(&slices) {
+            Ok(0) => {
+                return Err(std::io::Error::new(
+                    ErrorKind::WriteZero,
+                    format!(
+                        "writer accepted no more bytes, after {} of {} were written",
+                        amount_written, total
+                    ),
+                ))
+            }
+            Ok(c) => {
+                amount_written += c;
+                advance_slices(&mut index, &mut offset, c, |i| bufs[i].len());
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(())
+}
+
+/// Moves the position ([index] of the buffer and [offset] within it) forward by [amount]
+/// bytes, across however many buffers that covers.
+fn advance_slices<L>(index: &mut usize, offset: &mut usize, mut amount: usize, len: L)
+where
+    L: Fn(usize) -> usize,
+{
+    while amount > 0 {
+        let available = len(*index) - *offset;
+        if amount < available {
+            *offset += amount;
+            return;
+        }
+        amount -= available;
+        *index += 1;
+        *offset = 0;
+    }
+}
+
+/// The write side of [read_if_possible]: writes as much of [buf] as the writer accepts,
+/// continuing after short writes and retrying on `ErrorKind::Interrupted`.
+/// Returns how many bytes were written, which is only less than `buf.len()` if the writer
+/// stopped accepting bytes (a write returned `Ok(0)`), such as when a disk is full.
+#[inline]
+pub fn write_if_possible<W: Write + ?Sized>(w: &mut W, mut buf: &[u8]) -> std::io::Result<usize> {
+    let mut amount_written: usize = 0;
+    while !buf.is_empty() {
+        match w.write(buf) {
+            Ok(0) => break,
+            Ok(c) => {
+                amount_written += c;
+                buf = &buf[c..];
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(amount_written)
+}
+
+/// Formats [data] like `hexdump -C`, 16 bytes per line: the offset (starting from
+/// [base_offset]), the bytes in hex, and then the printable ASCII characters, with `.` for
+/// everything else.
+/// Every line ends with a newline, and empty data gives an empty string.
+pub fn hexdump(data: &[u8], base_offset: u64) -> String {
+    let mut output = String::new();
+    for (i, line) in data.chunks(16).enumerate() {
+        output.push_str(&format!("{:08x} ", base_offset + (i as u64) * 16));
+        for column in 0..16 {
+            if column == 8 {
+                output.push(' ');
+            }
+            match line.get(column) {
+                Some(byte) => output.push_str(&format!(" {:02x}", byte)),
+                None => output.push_str("   "),
+            }
+        }
+        output.push_str("  |");
+        output.extend(line.iter().map(|&byte| {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                byte as char
+            } else {
+                '.'
+            }
+        }));
+        output.push_str("|\n");
+    }
+    output
+}
+
+/// Dumps the bytes within [context] bytes of [center] with [hexdump], such as around where a
+/// parse failed. The window is cut short by the start and end of [f].
+/// The position of [f] is restored afterwards, even if reading failed.
+pub fn hexdump_around<F>(f: &mut F, center: u64, context: u64) -> std::io::Result<String>
+where
+    F: Read + Seek,
+{
+    let initial_position = stream_position(f)?;
+    let start = center.saturating_sub(context);
+    let len = center.saturating_add(context) - start;
+    let mut window = Vec::new();
+    let read = f
+        .seek(SeekFrom::Start(start))
+        .and_then(|_| f.take(len).read_to_end(&mut window));
+    let restored = f.seek(SeekFrom::Start(initial_position));
+    read?;
+    restored?;
+    Ok(hexdump(&window, start))
+}
+
+/// Chunk size used by [copy_n].
+pub const COPY_CHUNK: usize = 4096;
+
+/// Copies exactly [n] bytes from [r] to [w], in chunks of [COPY_CHUNK] bytes.
+/// See [copy_n_chunked] for choosing the chunk size.
+#[inline]
+pub fn copy_n<R, W>(r: &mut R, w: &mut W, n: u64) -> std::io::Result<u64>
+where
+    R: Read + ?Sized,
+    W: Write + ?Sized,
+{
+    copy_n_chunked::<R, W, COPY_CHUNK>(r, w, n)
+}
+
+/// Copies exactly [n] bytes from [r] to [w], through a stack buffer of [CHUNK] bytes, and
+/// returns [n].
+/// If the reader ends early, then this errors with `ErrorKind::UnexpectedEof`, carrying a
+/// [ShortCopy] with how much was copied. Everything read before that has been written.
+/// Reads are retried on `ErrorKind::Interrupted`, like [read_if_possible].
+/// Note: [CHUNK] must be non-zero.
+pub fn copy_n_chunked<R, W, const CHUNK: usize>(
+    r: &mut R,
+    w: &mut W,
+    n: u64,
+) -> std::io::Result<u64>
+where
+    R: Read + ?Sized,
+    W: Write + ?Sized,
+{
+    let mut buf = [0_u8; CHUNK];
+    let mut copied = 0;
+
+    while copied < n {
+        // Fits in a usize, since it is at most CHUNK
+        let end = (CHUNK as u64).min(n - copied) as usize;
+        match r.read(&mut buf[..end]) {
+            Ok(0) => {
+                return Err(std::io::Error::new(
+                    ErrorKind::UnexpectedEof,
+                    ShortCopy {
+                        copied,
+                        expected: n,
+                    },
+                ))
+            }
+            Ok(amount_read) => {
+                writable::write_full(w, &buf[..amount_read])?;
+                copied += amount_read as u64;
+            }
+            Err(e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(copied)
+}
+
+/// Error carried by the `io::Error` from [copy_n] when the reader ended early.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShortCopy {
+    /// Number of bytes that were copied before the reader ended
+    pub copied: u64,
+    /// Number of bytes that were asked for
+    pub expected: u64,
+}
+impl ShortCopy {
+    /// Gets the [ShortCopy] out of an error returned by [copy_n], if that's why it failed.
+    #[inline]
+    pub fn from_io(err: &std::io::Error) -> Option<&ShortCopy> {
+        err.get_ref().and_then(|inner| inner.downcast_ref())
+    }
+}
+impl std::fmt::Display for ShortCopy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "reader ended after {} of {} bytes were copied",
+            self.copied, self.expected
+        )
+    }
+}
+impl std::error::Error for ShortCopy {}
+
+/// Wraps a reader, keeping track of how many bytes have been read through it.
+/// This is primarily for readers that don't implement Seek (sockets, decompressors), where
+/// there is otherwise no way to know how far into the data you are.
+/// If the inner reader implements Seek, then seeking is passed through and the count is
+/// updated to the new position reported by the inner reader.
+#[derive(Debug)]
+pub struct CountingReader<R: Read> {
+    inner: R,
+    position: u64,
+}
+impl<R> CountingReader<R>
+where
+    R: Read,
+{
+    /// Creates a new `CountingReader` with the count starting at 0.
+    #[inline]
+    pub fn new(inner: R) -> Self {
+        Self::with_position(inner, 0)
+    }
+
+    /// Creates a new `CountingReader` with the count starting at [position].
+    /// Useful if the reader has already been partially consumed.
+    #[inline]
+    pub fn with_position(inner: R, position: u64) -> Self {
+        CountingReader { inner, position }
+    }
+
+    /// Returns the number of bytes read so far (plus the starting position).
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Note: this only changes the count, and does not move the inner reader.
+    #[inline]
+    pub fn set_position(&mut self, position: u64) {
+        self.position = position;
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Note: reading from the inner reader through this handle will not update the count.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+impl<R> Read for CountingReader<R>
+where
+    R: Read,
+{
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let amount_read = self.inner.read(buf)?;
+        // TODO: Is there a better way of handling this than a saturating add?
+        self.position = self.position.saturating_add(amount_read as u64);
+        Ok(amount_read)
+    }
+}
+impl<R> Seek for CountingReader<R>
+where
+    R: Read + Seek,
+{
+    /// Seeks the inner reader, and sets the count to the resulting position.
+    /// Note: this discards any offset given by `with_position`/`set_position`, since the
+    /// inner reader's position is the more accurate one.
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = self.inner.seek(pos)?;
+        self.position = position;
+        Ok(position)
+    }
+}
+
+/// Wraps a writer, keeping track of how many bytes have been written through it.
+/// The write-side counterpart to [CountingReader].
+/// If the inner writer implements Seek, then seeking is passed through and the count is
+/// updated to the new position reported by the inner writer.
+#[derive(Debug)]
+pub struct CountingWriter<W: Write> {
+    inner: W,
+    position: u64,
+}
+impl<W> CountingWriter<W>
+where
+    W: Write,
+{
+    /// Creates a new `CountingWriter` with the count starting at 0.
+    #[inline]
+    pub fn new(inner: W) -> Self {
+        Self::with_position(inner, 0)
+    }
+
+    /// Creates a new `CountingWriter` with the count starting at [position].
+    #[inline]
+    pub fn with_position(inner: W, position: u64) -> Self {
+        CountingWriter { inner, position }
+    }
+
+    /// Returns the number of bytes written so far (plus the starting position).
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Note: this only changes the count, and does not move the inner writer.
+    #[inline]
+    pub fn set_position(&mut self, position: u64) {
+        self.position = position;
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Note: writing to the inner writer through this handle will not update the count.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+}
+impl<W> Write for CountingWriter<W>
+where
+    W: Write,
+{
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let amount_written = self.inner.write(buf)?;
+        self.position = self.position.saturating_add(amount_written as u64);
+        Ok(amount_written)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+impl<W> Seek for CountingWriter<W>
+where
+    W: Write + Seek,
+{
+    /// Seeks the inner writer, and sets the count to the resulting position.
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let position = self.inner.seek(pos)?;
+        self.position = position;
+        Ok(position)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -144,6 +1037,63 @@ mod tests {
 
     // TODO: these are very specific tests just for cursor, rather than testing various types
 
+    #[test]
+    pub fn test_endian() {
+        assert_eq!(Endian::Little.opposite(), Endian::Big);
+        assert_eq!(Endian::Big.opposite(), Endian::Little);
+        assert!(Endian::NATIVE.is_native());
+        assert!(!Endian::NATIVE.opposite().is_native());
+        assert_eq!(
+            u16::from_ne_bytes([0x1, 0x2]),
+            match Endian::NATIVE {
+                Endian::Little => u16::from_le_bytes([0x1, 0x2]),
+                Endian::Big => u16::from_be_bytes([0x1, 0x2]),
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_detect_bom() {
+        let mut data: &[u8] = &[0xFE, 0xFF, 0xFF, 0xFE, 0xFE, 0xFE, 0xFF];
+        assert_eq!(Endian::detect_bom(&mut data).unwrap(), Endian::Big);
+        assert_eq!(Endian::detect_bom(&mut data).unwrap(), Endian::Little);
+        match Endian::detect_bom(&mut data) {
+            Err(parse::ParseError::InvalidByte) => {}
+            x => panic!("Expected InvalidByte, got: {:?}", x),
+        }
+        // Too short
+        assert!(Endian::detect_bom(&mut data).is_err());
+    }
+
+    #[test]
+    pub fn test_detect_magic() {
+        const MAGIC: u32 = 0xA1B2C3D4;
+        let mut data: &[u8] = &[
+            0xD4, 0xC3, 0xB2, 0xA1, 0xA1, 0xB2, 0xC3, 0xD4, 0x0, 0x0, 0x0, 0x0,
+        ];
+        assert_eq!(
+            Endian::detect_magic(&mut data, MAGIC).unwrap(),
+            Endian::Little
+        );
+        assert_eq!(Endian::detect_magic(&mut data, MAGIC).unwrap(), Endian::Big);
+        match Endian::detect_magic(&mut data, MAGIC) {
+            Err(parse::ParseError::InvalidByte) => {}
+            x => panic!("Expected InvalidByte, got: {:?}", x),
+        }
+
+        let tiff = |bytes: [u8; 4]| match &bytes {
+            b"II*\0" => Some(Endian::Little),
+            b"MM\0*" => Some(Endian::Big),
+            _ => None,
+        };
+        let mut data: &[u8] = b"MM\0*II*\0";
+        assert_eq!(Endian::detect_with(&mut data, tiff).unwrap(), Endian::Big);
+        assert_eq!(
+            Endian::detect_with(&mut data, tiff).unwrap(),
+            Endian::Little
+        );
+    }
+
     #[test]
     pub fn test_stream_position() {
         let mut cursor = std::io::Cursor::new(&DATA);
@@ -164,15 +1114,216 @@ mod tests {
         assert_eq!(stream_len(&mut cursor).unwrap(), DATA.len() as u64);
     }
 
+    /// Counts the seeks made through it
+    struct SeekCounter<R> {
+        inner: R,
+        seeks: usize,
+    }
+    impl<R: Read> Read for SeekCounter<R> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.inner.read(buf)
+        }
+    }
+    impl<R: Seek> Seek for SeekCounter<R> {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.seeks += 1;
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    pub fn test_stream_remaining() {
+        let mut cursor = std::io::Cursor::new(&DATA);
+        assert_eq!(stream_remaining(&mut cursor).unwrap(), 16);
+        assert!(!is_eof(&mut cursor).unwrap());
+        cursor.set_position(10);
+        assert_eq!(stream_remaining(&mut cursor).unwrap(), 6);
+        assert!(!is_eof(&mut cursor).unwrap());
+        assert_eq!(cursor.position(), 10);
+        cursor.set_position(16);
+        assert_eq!(stream_remaining(&mut cursor).unwrap(), 0);
+        assert!(is_eof(&mut cursor).unwrap());
+        cursor.set_position(20);
+        assert_eq!(stream_remaining(&mut cursor).unwrap(), 0);
+        assert!(is_eof(&mut cursor).unwrap());
+        assert_eq!(cursor.position(), 20);
+
+        let mut counter = SeekCounter {
+            inner: std::io::Cursor::new(&DATA),
+            seeks: 0,
+        };
+        counter.inner.set_position(4);
+        assert_eq!(stream_remaining(&mut counter).unwrap(), 12);
+        assert_eq!(counter.seeks, 3);
+        counter.inner.set_position(16);
+        assert!(is_eof(&mut counter).unwrap());
+        assert_eq!(counter.seeks, 5);
+    }
+
+    #[test]
+    pub fn test_probe_eof() {
+        let mut reader = peek::PeekReader::new(&DATA[..2]);
+        assert!(!probe_eof(&mut reader).unwrap());
+        let mut out = [0u8; 2];
+        reader.read_exact(&mut out).unwrap();
+        assert_eq!(out, [0x1, 0x2]);
+        assert!(probe_eof(&mut reader).unwrap());
+    }
+
+    #[test]
+    pub fn test_many_seek_count() {
+        let mut counter = SeekCounter {
+            inner: std::io::Cursor::new(&DATA),
+            seeks: 0,
+        };
+        let values: Vec<u32> = parse::many(&mut counter, Endian::Big, |f, d| {
+            <u32 as parse::Parse<_, _>>::parse(f, d)
+        })
+        .unwrap();
+        assert_eq!(values.len(), 4);
+        // Three for the position and length, then one after each element
+        assert_eq!(counter.seeks, 3 + 4);
+
+        counter.inner.set_position(0);
+        counter.seeks = 0;
+        let values: Vec<u32> = parse::many_parse(&mut counter, Endian::Big).unwrap();
+        assert_eq!(values.len(), 4);
+        assert_eq!(counter.seeks, 3 + 4);
+    }
+
     #[test]
     pub fn test_skip() {
         let mut cursor = std::io::Cursor::new(&DATA as &[u8]);
-        skip::<_, 16>(&mut cursor, 1).unwrap();
+        skip_chunked::<_, 16>(&mut cursor, 1).unwrap();
         assert_eq!(cursor.position(), 1);
-        skip::<_, 16>(&mut cursor, 1).unwrap();
+        skip_chunked::<_, 16>(&mut cursor, 1).unwrap();
         assert_eq!(cursor.position(), 2);
-        skip::<_, 16>(&mut cursor, 4).unwrap();
+        skip_chunked::<_, 16>(&mut cursor, 4).unwrap();
         assert_eq!(cursor.position(), 6);
+        skip(&mut cursor, 2).unwrap();
+        assert_eq!(cursor.position(), 8);
+
+        // Running out of data leaves it at the end
+        let err = skip_chunked::<_, 4>(&mut cursor, 9).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(cursor.position(), DATA.len() as u64);
+    }
+
+    #[test]
+    pub fn test_skip_large() {
+        // Several chunks, plus a remainder
+        let len = SKIP_CHUNK * 3 + 17;
+        let data: Vec<u8> = (0..len + 8).map(|x| x as u8).collect();
+        let mut cursor = std::io::Cursor::new(&data);
+        skip(&mut cursor, 0).unwrap();
+        assert_eq!(cursor.position(), 0);
+        skip(&mut cursor, 3).unwrap();
+        assert_eq!(cursor.position(), 3);
+        skip(&mut cursor, len as u64).unwrap();
+        assert_eq!(cursor.position(), len as u64 + 3);
+
+        let err = skip(&mut cursor, 6).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(cursor.position(), data.len() as u64);
+        cursor.set_position(0);
+        let err = skip(&mut cursor, u64::MAX).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(cursor.position(), data.len() as u64);
+    }
+
+    #[test]
+    pub fn test_skip_reporting() {
+        let mut cursor = std::io::Cursor::new(&DATA as &[u8]);
+        assert_eq!(skip_reporting::<_, 4>(&mut cursor, 0).unwrap(), 0);
+        assert_eq!(cursor.position(), 0);
+        skip_chunked::<_, 4>(&mut cursor, 0).unwrap();
+
+        // Exactly to the end
+        assert_eq!(skip_reporting::<_, 4>(&mut cursor, 16).unwrap(), 16);
+        assert_eq!(cursor.position(), DATA.len() as u64);
+        cursor.set_position(0);
+        skip_chunked::<_, 4>(&mut cursor, 16).unwrap();
+
+        // One past the end, with the final read being short
+        cursor.set_position(0);
+        assert_eq!(skip_reporting::<_, 4>(&mut cursor, 17).unwrap(), 16);
+        assert_eq!(cursor.position(), DATA.len() as u64);
+        cursor.set_position(3);
+        let err = skip_chunked::<_, 4>(&mut cursor, 14).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(cursor.position(), DATA.len() as u64);
+
+        // Readers giving less than asked for each time still skip everything
+        let mut reader = ChunkedReader {
+            data: &DATA,
+            chunk: 3,
+        };
+        assert_eq!(skip_reporting::<_, 8>(&mut reader, 10).unwrap(), 10);
+        assert_eq!(skip_reporting::<_, 8>(&mut reader, 10).unwrap(), 6);
+
+        /// Claims to have read more than it was given
+        struct Overreporting;
+        impl Read for Overreporting {
+            fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+                Ok(buf.len() + 5)
+            }
+        }
+        assert_eq!(skip_reporting::<_, 8>(Overreporting, 10).unwrap(), 10);
+        assert_eq!(skip_reporting::<_, 8>(Overreporting, 3).unwrap(), 3);
+    }
+
+    #[test]
+    pub fn test_skip_seek() {
+        let mut read_cursor = std::io::Cursor::new(&DATA as &[u8]);
+        let mut seek_cursor = std::io::Cursor::new(&DATA as &[u8]);
+        for &amount in [0, 1, 3, 7].iter() {
+            skip(&mut read_cursor, amount).unwrap();
+            skip_seek(&mut seek_cursor, amount).unwrap();
+            assert_eq!(read_cursor.position(), seek_cursor.position());
+        }
+        assert_eq!(seek_cursor.position(), 11);
+
+        // Skipping exactly to the end is fine
+        skip_seek(&mut seek_cursor, 5).unwrap();
+        assert_eq!(seek_cursor.position(), DATA.len() as u64);
+
+        seek_cursor.set_position(14);
+        let err = skip_seek(&mut seek_cursor, 3).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(seek_cursor.position(), DATA.len() as u64);
+
+        seek_cursor.set_position(2);
+        let err = skip_seek(&mut seek_cursor, u64::MAX).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(seek_cursor.position(), DATA.len() as u64);
+    }
+
+    #[test]
+    pub fn test_skip_trait() {
+        fn skip_then_read<F: Read + Skip>(mut f: F) -> u8 {
+            f.skip(2).unwrap();
+            f.skip(3).unwrap();
+            let mut out = [0u8; 1];
+            f.read_exact(&mut out).unwrap();
+            out[0]
+        }
+
+        assert_eq!(skip_then_read(std::io::Cursor::new(&DATA)), 0x6);
+        assert_eq!(skip_then_read(&DATA as &[u8]), 0x6);
+        assert_eq!(skip_then_read(peek::PeekReader::new(&DATA as &[u8])), 0x6);
+        let mut cursor = std::io::Cursor::new(&DATA);
+        cursor.set_position(4);
+        let slice = slice::InputSlice::new(&mut cursor, 4..).unwrap();
+        assert_eq!(skip_then_read(slice), 0xa);
+
+        // Both paths fail the same way past the end
+        let mut data = &DATA as &[u8];
+        assert_eq!(data.skip(17).unwrap_err().kind(), ErrorKind::UnexpectedEof);
+        let mut cursor = std::io::Cursor::new(&DATA);
+        assert_eq!(
+            cursor.skip(17).unwrap_err().kind(),
+            ErrorKind::UnexpectedEof
+        );
     }
 
     #[test]
@@ -191,4 +1342,571 @@ mod tests {
         assert_eq!(read_if_possible(&mut cursor, &mut buf).unwrap(), 1);
         assert_eq!(buf[0], 0x10);
     }
+
+    #[test]
+    pub fn test_copy_n() {
+        for &n in [0, 3, 4, 11, 16].iter() {
+            let mut reader = ChunkedReader {
+                data: &DATA,
+                chunk: 3,
+            };
+            let mut out = Vec::new();
+            assert_eq!(
+                copy_n_chunked::<_, _, 4>(&mut reader, &mut out, n).unwrap(),
+                n
+            );
+            assert_eq!(out, &DATA[..n as usize]);
+            assert_eq!(reader.data, &DATA[n as usize..]);
+        }
+
+        let mut reader = &DATA as &[u8];
+        let mut out = Vec::new();
+        assert_eq!(copy_n(&mut reader, &mut out, 10).unwrap(), 10);
+        assert_eq!(copy_n(&mut reader, &mut out, 6).unwrap(), 6);
+        assert_eq!(out, DATA);
+    }
+
+    #[test]
+    pub fn test_copy_n_truncated() {
+        let mut reader = ChunkedReader {
+            data: &DATA,
+            chunk: 5,
+        };
+        let mut out = Vec::new();
+        let err = copy_n_chunked::<_, _, 4>(&mut reader, &mut out, 20).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+        assert_eq!(
+            ShortCopy::from_io(&err),
+            Some(&ShortCopy {
+                copied: 16,
+                expected: 20
+            })
+        );
+        assert_eq!(out, DATA);
+
+        let err = std::io::Error::new(ErrorKind::UnexpectedEof, "other");
+        assert_eq!(ShortCopy::from_io(&err), None);
+    }
+
+    #[test]
+    pub fn test_bytes_and_files() {
+        use crate::zstring::ZString;
+
+        let value = ZString::from("name");
+        let output = write_to_vec(&value, ()).unwrap();
+        assert_eq!(output, b"name\0");
+        let sized = write_to_vec_sized(&0x01020304u32, Endian::Big).unwrap();
+        assert_eq!(sized, [0x1, 0x2, 0x3, 0x4]);
+        assert!(sized.capacity() >= 4);
+
+        assert_eq!(parse_bytes::<ZString, _>(&output, ()).unwrap(), value);
+        assert_eq!(
+            parse_bytes_strict::<ZString, _>(&output, ()).unwrap(),
+            value
+        );
+        assert_eq!(parse_bytes::<u16, _>(&sized, Endian::Big).unwrap(), 0x0102);
+        match parse_bytes_strict::<u16, _>(&sized, Endian::Big) {
+            Err(parse::ParseError::ExpectedEOF) => {}
+            x => panic!("Expected ExpectedEOF, got: {:?}", x),
+        }
+
+        let path = std::env::temp_dir().join(format!("eyeutil-test-{}", std::process::id()));
+        std::fs::write(&path, &sized).unwrap();
+        let parsed = parse_file::<u32, _, _>(&path, Endian::Big);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(parsed.unwrap(), 0x01020304);
+        assert!(parse_file::<u32, _, _>(&path, Endian::Big).is_err());
+    }
+
+    #[test]
+    pub fn test_hexdump() {
+        let data: Vec<u8> = b"Hello, world!\0\x01\x7f\xff"
+            .iter()
+            .copied()
+            .chain(0x41..0x45)
+            .collect();
+        assert_eq!(
+            hexdump(&data, 0),
+            "00000000  48 65 6c 6c 6f 2c 20 77  6f 72 6c 64 21 00 01 7f  |Hello, world!...|\n\
+             00000010  ff 41 42 43 44                                    |.ABCD|\n"
+        );
+        assert_eq!(
+            hexdump(&data[..3], 0x1234),
+            "00001234  48 65 6c                                          |Hel|\n"
+        );
+        assert_eq!(hexdump(&[], 0), "");
+
+        let mut cursor = std::io::Cursor::new(&data);
+        cursor.set_position(7);
+        assert_eq!(
+            hexdump_around(&mut cursor, 4, 2).unwrap(),
+            hexdump(&data[2..6], 2)
+        );
+        assert_eq!(
+            hexdump_around(&mut cursor, 19, 4).unwrap(),
+            hexdump(&data[15..], 15)
+        );
+        assert_eq!(
+            hexdump_around(&mut cursor, 1, 4).unwrap(),
+            hexdump(&data[..5], 0)
+        );
+        assert_eq!(cursor.position(), 7);
+    }
+
+    /// Writer that accepts up to `chunk` bytes per call, up to `capacity` in total, and fails
+    /// every other call with `Interrupted` if `interrupt` is set.
+    struct LimitedWriter {
+        output: Vec<u8>,
+        chunk: usize,
+        capacity: usize,
+        interrupt: bool,
+        interrupted: bool,
+    }
+    impl LimitedWriter {
+        fn new(chunk: usize, capacity: usize, interrupt: bool) -> Self {
+            LimitedWriter {
+                output: Vec::new(),
+                chunk,
+                capacity,
+                interrupt,
+                interrupted: false,
+            }
+        }
+    }
+    impl Write for LimitedWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.interrupt {
+                self.interrupted = !self.interrupted;
+                if self.interrupted {
+                    return Err(std::io::Error::new(ErrorKind::Interrupted, "interrupted"));
+                }
+            }
+            let max = buf
+                .len()
+                .min(self.chunk)
+                .min(self.capacity - self.output.len());
+            self.output.extend_from_slice(&buf[..max]);
+            Ok(max)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    pub fn test_write_if_possible() {
+        let mut writer = LimitedWriter::new(3, 100, false);
+        assert_eq!(write_if_possible(&mut writer, &DATA).unwrap(), 16);
+        assert_eq!(writer.output, DATA);
+
+        let mut writer = LimitedWriter::new(3, 100, true);
+        assert_eq!(write_if_possible(&mut writer, &DATA).unwrap(), 16);
+        assert_eq!(writer.output, DATA);
+
+        // Running out of space gives the partial count
+        let mut writer = LimitedWriter::new(3, 10, true);
+        assert_eq!(write_if_possible(&mut writer, &DATA).unwrap(), 10);
+        assert_eq!(writer.output, DATA[..10]);
+        assert_eq!(write_if_possible(&mut writer, &DATA).unwrap(), 0);
+        let err = writable::write_bytes(&mut writer, &DATA).unwrap_err();
+        assert!(matches!(err, writable::WriteError::Io(e) if e.kind() == ErrorKind::WriteZero));
+
+        assert_eq!(write_if_possible(&mut writer, &[]).unwrap(), 0);
+    }
+
+    /// Reader and writer that handles up to `limit` bytes per vectored call, across buffers,
+    /// and is interrupted on its first call.
+    struct VectoredMock {
+        data: Vec<u8>,
+        limit: usize,
+        calls: usize,
+    }
+    impl VectoredMock {
+        fn call(&mut self) -> std::io::Result<usize> {
+            self.calls += 1;
+            if self.calls == 1 {
+                Err(std::io::Error::new(ErrorKind::Interrupted, "interrupted"))
+            } else {
+                Ok(self.limit)
+            }
+        }
+    }
+    impl Read for VectoredMock {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.read_vectored(&mut [IoSliceMut::new(buf)])
+        }
+
+        fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> std::io::Result<usize> {
+            let mut remaining = self.call()?;
+            let mut amount = 0;
+            for buf in bufs.iter_mut() {
+                let count = buf.len().min(remaining).min(self.data.len());
+                buf[..count].copy_from_slice(&self.data[..count]);
+                self.data.drain(..count);
+                remaining -= count;
+                amount += count;
+            }
+            Ok(amount)
+        }
+    }
+    impl Write for VectoredMock {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.write_vectored(&[IoSlice::new(buf)])
+        }
+
+        fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> std::io::Result<usize> {
+            let mut remaining = self.call()?;
+            let mut amount = 0;
+            for buf in bufs.iter() {
+                let count = buf.len().min(remaining);
+                self.data.extend_from_slice(&buf[..count]);
+                remaining -= count;
+                amount += count;
+            }
+            Ok(amount)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    pub fn test_vectored() {
+        // Each call covers a buffer and a half
+        let mut reader = VectoredMock {
+            data: DATA.to_vec(),
+            limit: 6,
+            calls: 0,
+        };
+        let (mut a, mut b, mut c) = ([0u8; 4], [0u8; 4], [0u8; 10]);
+        let mut bufs = [
+            IoSliceMut::new(&mut a),
+            IoSliceMut::new(&mut []),
+            IoSliceMut::new(&mut b),
+            IoSliceMut::new(&mut c),
+        ];
+        assert_eq!(
+            read_if_possible_vectored(&mut reader, &mut bufs).unwrap(),
+            16
+        );
+        assert_eq!(a, DATA[..4]);
+        assert_eq!(b, DATA[4..8]);
+        assert_eq!(c[..8], DATA[8..]);
+        assert_eq!(c[8..], [0, 0]);
+
+        let mut writer = VectoredMock {
+            data: Vec::new(),
+            limit: 6,
+            calls: 0,
+        };
+        let bufs = [
+            IoSlice::new(&DATA[..4]),
+            IoSlice::new(&[]),
+            IoSlice::new(&DATA[4..8]),
+            IoSlice::new(&DATA[8..]),
+        ];
+        write_vectored_all(&mut writer, &bufs).unwrap();
+        assert_eq!(writer.data, DATA);
+        assert_eq!(writer.calls, 4);
+
+        let mut writer = VectoredMock {
+            data: Vec::new(),
+            limit: 0,
+            calls: 0,
+        };
+        let err = write_vectored_all(&mut writer, &bufs).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::WriteZero);
+        write_vectored_all(&mut writer, &[]).unwrap();
+    }
+
+    /// Reader that only returns up to `chunk` bytes per read call.
+    struct ChunkedReader<'a> {
+        data: &'a [u8],
+        chunk: usize,
+    }
+    impl<'a> Read for ChunkedReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let amount = self.chunk.min(buf.len()).min(self.data.len());
+            buf[..amount].copy_from_slice(&self.data[..amount]);
+            self.data = &self.data[amount..];
+            Ok(amount)
+        }
+    }
+
+    #[test]
+    pub fn test_counting_reader() {
+        let mut reader = CountingReader::new(std::io::Cursor::new(&DATA));
+        assert_eq!(reader.position(), 0);
+        let mut out = [0u8; 4];
+        reader.read_exact(&mut out).unwrap();
+        assert_eq!(out, [0x1, 0x2, 0x3, 0x4]);
+        assert_eq!(reader.position(), 4);
+
+        assert_eq!(reader.seek(SeekFrom::Current(2)).unwrap(), 6);
+        assert_eq!(reader.position(), 6);
+        reader.read_exact(&mut out).unwrap();
+        assert_eq!(out, [0x7, 0x8, 0x9, 0xa]);
+        assert_eq!(reader.position(), 10);
+        assert_eq!(stream_position(&mut reader).unwrap(), 10);
+
+        assert_eq!(reader.seek(SeekFrom::End(-1)).unwrap(), 15);
+        assert_eq!(reader.position(), 15);
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, [0x10]);
+        assert_eq!(reader.position(), 16);
+
+        reader.set_position(100);
+        assert_eq!(reader.position(), 100);
+        assert_eq!(reader.into_inner().position(), 16);
+    }
+
+    #[test]
+    pub fn test_counting_reader_short_reads() {
+        let chunked = ChunkedReader {
+            data: &DATA,
+            chunk: 3,
+        };
+        let mut reader = CountingReader::new(chunked);
+        let mut out = [0u8; 5];
+        assert_eq!(reader.read(&mut out).unwrap(), 3);
+        assert_eq!(reader.position(), 3);
+        reader.read_exact(&mut out).unwrap();
+        assert_eq!(out, [0x4, 0x5, 0x6, 0x7, 0x8]);
+        assert_eq!(reader.position(), 8);
+
+        let mut rest = Vec::new();
+        reader.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest.len(), 8);
+        assert_eq!(reader.position(), DATA.len() as u64);
+
+        let chunked = ChunkedReader {
+            data: &DATA,
+            chunk: 1,
+        };
+        let mut reader = CountingReader::with_position(chunked, 32);
+        assert_eq!(read_if_possible(&mut reader, &mut out).unwrap(), 5);
+        assert_eq!(reader.position(), 37);
+    }
+
+    #[test]
+    pub fn test_counting_writer() {
+        let mut writer = CountingWriter::new(std::io::Cursor::new(Vec::new()));
+        writer.write_all(&DATA[..4]).unwrap();
+        assert_eq!(writer.position(), 4);
+        assert_eq!(writer.seek(SeekFrom::Start(2)).unwrap(), 2);
+        assert_eq!(writer.position(), 2);
+        writer.write_all(&DATA[..4]).unwrap();
+        assert_eq!(writer.position(), 6);
+        assert_eq!(
+            writer.into_inner().into_inner(),
+            [0x1, 0x2, 0x1, 0x2, 0x3, 0x4]
+        );
+    }
+
+    struct LinkFlags {
+        flags: u32,
+    }
+    impl_flags!(LinkFlags, flags: u32, [
+        /// Has a target
+        has_target: 0b1,
+        has_name: 0b10,
+        has_args: 0b1000
+    ]);
+
+    struct WideFlags {
+        flags: u64,
+    }
+    impl_flags!(shl WideFlags, flags: u64, [low: 0, high: 63]);
+
+    /// Without the field's type, so no mask constants
+    struct UntypedFlags {
+        flags: u32,
+    }
+    const UNTYPED_LOW: u32 = 0x1;
+    const UNTYPED_HIGH: u32 = 0x8000_0000;
+    impl_flags!(UntypedFlags, flags, [low: UNTYPED_LOW, high: UNTYPED_HIGH, both: UNTYPED_LOW | UNTYPED_HIGH]);
+
+    struct UntypedShl {
+        flags: u8,
+    }
+    impl_flags!(shl UntypedShl, flags, [first: 0, last: 7]);
+
+    /// Two flags fields on one struct
+    struct Attributes {
+        flags: u8,
+        attrs: u16,
+    }
+    impl_flags!(Attributes, flags: u8, [hidden: 0b1, system: 0b10]);
+    impl_flags!(shl Attributes, attrs, [readonly: 0, archive: 8]);
+
+    impl std::fmt::Debug for LinkFlags {
+        fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            self.fmt_flags(f)
+        }
+    }
+
+    struct PackedHeader {
+        bits: u16,
+    }
+    impl_flags!(field PackedHeader, bits: u16, [
+        level: 0..=3,
+        kind: 4..=6,
+        /// Single bit field
+        marker: 7..=7,
+        length: 8..=15
+    ]);
+
+    struct WholeField {
+        bits: u8,
+    }
+    impl_flags!(field WholeField, bits: u8, [all: 0..=7]);
+
+    #[test]
+    // Grouped by field
+    #[allow(clippy::unusual_byte_groupings)]
+    pub fn test_flag_fields() {
+        assert_eq!(PackedHeader::LEVEL_MASK, 0x000F);
+        assert_eq!(PackedHeader::KIND_MASK, 0x0070);
+        assert_eq!(PackedHeader::MARKER_MASK, 0x0080);
+        assert_eq!(PackedHeader::LENGTH_MASK, 0xFF00);
+
+        //                                length    m kind level
+        let mut value = PackedHeader {
+            bits: 0b10100101_1_011_1001,
+        };
+        assert_eq!(value.level(), 0b1001);
+        assert_eq!(value.kind(), 0b011);
+        assert_eq!(value.marker(), 1);
+        assert_eq!(value.length(), 0b10100101);
+
+        assert!(value.set_kind(0b110));
+        assert_eq!(value.bits, 0b10100101_1_110_1001);
+        assert!(value.set_level(0));
+        assert_eq!(value.bits, 0b10100101_1_110_0000);
+        assert!(value.set_marker(0));
+        assert_eq!(value.bits, 0b10100101_0_110_0000);
+        assert!(value.set_length(0xFF));
+        assert_eq!(value.bits, 0b11111111_0_110_0000);
+
+        // Doesn't fit, and so doesn't change anything
+        assert!(!value.set_kind(0b1000));
+        assert!(!value.set_level(16));
+        assert!(!value.set_marker(2));
+        assert_eq!(value.bits, 0b11111111_0_110_0000);
+
+        let mut value = WholeField { bits: 0 };
+        assert_eq!(WholeField::ALL_MASK, 0xFF);
+        assert!(value.set_all(0xAB));
+        assert_eq!(value.all(), 0xAB);
+    }
+
+    #[test]
+    pub fn test_flag_names() {
+        let value = LinkFlags { flags: 0 };
+        assert!(value.flags_flag_names().is_empty());
+        assert_eq!(format!("{:?}", value), "(empty)");
+
+        let value = LinkFlags { flags: 0b1011 };
+        assert_eq!(
+            value.flags_flag_names(),
+            ["has_target", "has_name", "has_args"]
+        );
+        assert_eq!(format!("{:?}", value), "has_target | has_name | has_args");
+
+        let value = LinkFlags { flags: 0x809 };
+        assert_eq!(value.flags_flag_names(), ["has_target", "has_args"]);
+        assert_eq!(format!("{:?}", value), "has_target | has_args | 0x800");
+
+        let value = LinkFlags { flags: 0x804 };
+        assert!(value.flags_flag_names().is_empty());
+        assert_eq!(format!("{:?}", value), "0x804");
+    }
+
+    #[test]
+    pub fn test_flags_two_fields() {
+        let mut value = Attributes {
+            flags: 0b10,
+            attrs: 0x100,
+        };
+        assert!(value.system());
+        assert!(value.archive());
+        assert!(!value.readonly());
+        value.set_hidden(true);
+        value.set_readonly(true);
+        assert_eq!((value.flags, value.attrs), (0b11, 0x101));
+        assert_eq!(Attributes::HIDDEN_MASK, 0b1_u8);
+        assert_eq!(value.flags_flag_names(), ["hidden", "system"]);
+        assert_eq!(value.attrs_flag_names(), ["readonly", "archive"]);
+
+        struct Show<'a>(&'a Attributes);
+        impl std::fmt::Debug for Show<'_> {
+            fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                self.0.fmt_flags(f)?;
+                f.write_str(", ")?;
+                self.0.fmt_attrs(f)
+            }
+        }
+        value.attrs |= 0x2000;
+        assert_eq!(
+            format!("{:?}", Show(&value)),
+            "hidden | system, readonly | archive | 0x2000"
+        );
+    }
+
+    #[test]
+    pub fn test_flags() {
+        assert_eq!(LinkFlags::HAS_TARGET_MASK, 0b1_u32);
+        assert_eq!(LinkFlags::HAS_ARGS_MASK, 0b1000_u32);
+        let mut value = LinkFlags { flags: 0b1010 };
+        assert!(!value.has_target());
+        assert!(value.has_name());
+        assert!(value.has_args());
+
+        value.set_has_target(true);
+        assert_eq!(value.flags, 0b1011);
+        value.set_has_name(false);
+        assert_eq!(value.flags, 0b1001);
+        value.set_has_name(false);
+        assert_eq!(value.flags, 0b1001);
+        value.set_has_args(true);
+        assert_eq!(value.flags, 0b1001);
+        assert_eq!(
+            value.flags & LinkFlags::HAS_ARGS_MASK,
+            LinkFlags::HAS_ARGS_MASK
+        );
+
+        assert_eq!(WideFlags::LOW_MASK, 1_u64);
+        assert_eq!(WideFlags::HIGH_MASK, 1_u64 << 63);
+        let mut value = WideFlags { flags: 0 };
+        value.set_high(true);
+        assert_eq!(value.flags, 0x8000_0000_0000_0000);
+        assert!(value.high());
+        assert!(!value.low());
+        value.set_low(true);
+        assert_eq!(value.flags, 0x8000_0000_0000_0001);
+        value.set_high(false);
+        assert_eq!(value.flags, 0x1);
+        assert!(!value.high());
+
+        let mut value = UntypedFlags {
+            flags: UNTYPED_HIGH,
+        };
+        assert!(!value.low());
+        assert!(value.high());
+        assert!(value.both());
+        value.set_low(true);
+        assert_eq!(value.flags, 0x8000_0001);
+        value.set_both(false);
+        assert_eq!(value.flags, 0);
+        assert_eq!(value.flags_flag_names(), Vec::<&str>::new());
+
+        let mut value = UntypedShl { flags: 0 };
+        value.set_last(true);
+        assert_eq!(value.flags, 0x80);
+        assert!(value.last());
+        assert!(!value.first());
+    }
 }
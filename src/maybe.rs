@@ -0,0 +1,138 @@
+use crate::{
+    data_size::DataSize,
+    parse::{single, Parse, ParseError, ParseResult},
+    writable::{Writable, WriteResult},
+};
+use std::{
+    fmt::Debug,
+    io::{Read, Write},
+    ops::{Deref, DerefMut},
+};
+
+/// An optional value preceded by a u8 presence flag: 0 for `None`, or 1 followed by the value.
+/// A bare `Option<T>` writes nothing for `None`, without a flag, so this is a separate type.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Maybe<T>(pub Option<T>);
+impl<T> Maybe<T> {
+    #[inline]
+    pub fn into_inner(self) -> Option<T> {
+        self.0
+    }
+}
+impl<T> From<Option<T>> for Maybe<T> {
+    #[inline]
+    fn from(value: Option<T>) -> Self {
+        Maybe(value)
+    }
+}
+impl<T> Deref for Maybe<T> {
+    type Target = Option<T>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<T> DerefMut for Maybe<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+/// Errors with `ParseError::InvalidByte` if the flag is neither 0 nor 1.
+impl<F, D, T> Parse<F, D> for Maybe<T>
+where
+    F: Read,
+    T: Parse<F, D>,
+{
+    fn parse(f: &mut F, d: D) -> ParseResult<Self> {
+        match single(f)? {
+            0 => Ok(Maybe(None)),
+            1 => T::parse(f, d).map(|value| Maybe(Some(value))),
+            _ => Err(ParseError::InvalidByte),
+        }
+    }
+}
+impl<D, T> Writable<D> for Maybe<T>
+where
+    D: Debug + Clone + PartialEq,
+    T: Writable<D>,
+{
+    fn write_to<W>(&self, w: &mut W, d: D) -> WriteResult
+    where
+        W: Write,
+    {
+        match &self.0 {
+            Some(value) => {
+                1u8.write_to(w, ())?;
+                value.write_to(w, d)
+            }
+            None => 0u8.write_to(w, ()),
+        }
+    }
+}
+impl<D, T> DataSize<D> for Maybe<T>
+where
+    D: Debug + Clone + PartialEq,
+    T: DataSize<D>,
+{
+    #[inline]
+    fn data_size(&self, d: D) -> u64 {
+        1 + self.0.data_size(d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Endian;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_maybe() {
+        for value in [Maybe(Some(0x0102u16)), Maybe(None)].iter() {
+            let mut output = Vec::new();
+            value.write_to(&mut output, Endian::Big).unwrap();
+            assert_eq!(output.len() as u64, value.data_size(()));
+
+            let parsed = Maybe::<u16>::parse(&mut Cursor::new(&output), Endian::Big).unwrap();
+            assert_eq!(&parsed, value);
+        }
+
+        let mut output = Vec::new();
+        Maybe(Some(0x0102u16))
+            .write_to(&mut output, Endian::Big)
+            .unwrap();
+        assert_eq!(output, [0x1, 0x1, 0x2]);
+        let mut output = Vec::new();
+        Maybe::<u16>(None)
+            .write_to(&mut output, Endian::Big)
+            .unwrap();
+        assert_eq!(output, [0x0]);
+    }
+
+    #[test]
+    fn test_maybe_unit() {
+        // Just the flag byte
+        for (value, expected) in [(Maybe(Some(())), 0x1), (Maybe(None), 0x0)].iter() {
+            let mut output = Vec::new();
+            value.write_to(&mut output, ()).unwrap();
+            assert_eq!(output, [*expected]);
+            assert_eq!(value.data_size(()), 1);
+
+            let mut cursor = Cursor::new(&output);
+            assert_eq!(&Maybe::<()>::parse(&mut cursor, ()).unwrap(), value);
+            assert_eq!(cursor.position(), 1);
+        }
+    }
+
+    #[test]
+    fn test_maybe_invalid_flag() {
+        match Maybe::<u8>::parse(&mut Cursor::new([0x2, 0x1]), ()) {
+            Err(ParseError::InvalidByte) => {}
+            x => panic!("Expected InvalidByte, got: {:?}", x),
+        }
+        // The flag is there, but the value isn't
+        assert!(Maybe::<u8>::parse(&mut Cursor::new([0x1]), ()).is_err());
+    }
+}
@@ -0,0 +1,266 @@
+//! Parsing memory mapped files, through `memmap2`. Only available with the `mmap` feature.
+//! [MmapSlice] is `Read + Seek + BufRead`, so existing `Parse` impls work on it unchanged,
+//! while reads are copies out of the map rather than system calls.
+
+use memmap2::Mmap;
+use std::{
+    convert::TryFrom,
+    fs::File,
+    io::{self, BufRead, Read, Seek, SeekFrom},
+    ops::{Bound, RangeBounds},
+    path::Path,
+    sync::Arc,
+};
+
+/// A range of a memory map, with its own position.
+/// Sub-slices share the map, so they are cheap to create and can be live at the same time.
+#[derive(Debug, Clone)]
+pub struct MmapSlice {
+    map: Arc<Mmap>,
+    start: usize,
+    /// Exclusive, and always `>= start`
+    end: usize,
+    /// Relative to [start]
+    position: usize,
+}
+impl MmapSlice {
+    /// Maps the whole file at [path].
+    ///
+    /// # Safety
+    /// The same as `memmap2::Mmap::map`: the file must not be modified (or truncated) while it
+    /// is mapped, including by other processes, as that changes memory that is assumed to be
+    /// immutable.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Ok(MmapSlice::new(Mmap::map(&file)?))
+    }
+
+    /// Creates a slice of the whole of [map].
+    #[inline]
+    pub fn new(map: Mmap) -> Self {
+        MmapSlice::from_arc(Arc::new(map))
+    }
+
+    /// Creates a slice of the whole of [map], sharing it.
+    #[inline]
+    pub fn from_arc(map: Arc<Mmap>) -> Self {
+        let end = map.len();
+        MmapSlice {
+            map,
+            start: 0,
+            end,
+            position: 0,
+        }
+    }
+
+    /// Creates a slice of [range], relative to the start of this slice, positioned at its
+    /// start. This doesn't copy or remap anything.
+    /// Errors with `ErrorKind::InvalidInput` if [range] extends past the end of this slice,
+    /// though an unbounded end is clamped to it.
+    pub fn slice<R>(&self, range: R) -> io::Result<MmapSlice>
+    where
+        R: RangeBounds<u64>,
+    {
+        let escapes = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "sub-slice range escapes the parent slice",
+            )
+        };
+        let len = self.end - self.start;
+        let bound = |x: u64| usize::try_from(x).map_err(|_| escapes());
+        let start = match range.start_bound() {
+            Bound::Unbounded => 0,
+            Bound::Included(x) => bound(*x)?,
+            Bound::Excluded(x) => bound(*x)?.checked_add(1).ok_or_else(escapes)?,
+        };
+        let end = match range.end_bound() {
+            Bound::Unbounded => len,
+            Bound::Included(x) => bound(*x)?.checked_add(1).ok_or_else(escapes)?,
+            Bound::Excluded(x) => bound(*x)?,
+        };
+        if start > end || end > len {
+            return Err(escapes());
+        }
+
+        Ok(MmapSlice {
+            map: Arc::clone(&self.map),
+            start: self.start + start,
+            end: self.start + end,
+            position: 0,
+        })
+    }
+
+    /// Returns inclusive start, within the map
+    #[inline]
+    pub fn start(&self) -> u64 {
+        self.start as u64
+    }
+
+    /// Returns exclusive end, within the map
+    #[inline]
+    pub fn end(&self) -> u64 {
+        self.end as u64
+    }
+
+    #[inline]
+    pub fn len(&self) -> u64 {
+        (self.end - self.start) as u64
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Note: returns the position within this slice, rather than in the map as a whole
+    #[inline]
+    pub fn stream_position(&self) -> u64 {
+        self.position as u64
+    }
+
+    /// Returns all of the bytes in the slice, regardless of the position.
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        &self.map[self.start..self.end]
+    }
+
+    /// Returns the bytes from the current position to the end of the slice.
+    #[inline]
+    pub fn remaining_slice(&self) -> &[u8] {
+        &self.as_slice()[self.position..]
+    }
+
+    #[inline]
+    pub fn map(&self) -> &Arc<Mmap> {
+        &self.map
+    }
+}
+impl Read for MmapSlice {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let data = self.remaining_slice();
+        let amount = data.len().min(buf.len());
+        buf[..amount].copy_from_slice(&data[..amount]);
+        self.position += amount;
+        Ok(amount)
+    }
+}
+impl BufRead for MmapSlice {
+    /// Returns everything from the current position to the end of the slice.
+    #[inline]
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        Ok(self.remaining_slice())
+    }
+
+    #[inline]
+    fn consume(&mut self, amt: usize) {
+        self.position = self.position.saturating_add(amt).min(self.end - self.start);
+    }
+}
+impl Seek for MmapSlice {
+    /// Like `InputSlice`, seeking beyond the end constrains you to the end of the slice.
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base_pos, offset) = match pos {
+            SeekFrom::Start(pos) => (pos, 0),
+            SeekFrom::Current(off) => (self.position as u64, off),
+            SeekFrom::End(off) => (self.len(), off),
+        };
+
+        let new_pos = if offset >= 0 {
+            base_pos.checked_add(offset as u64)
+        } else {
+            base_pos.checked_sub(offset.wrapping_neg() as u64)
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        // Fits, as it is at most the length
+        self.position = new_pos.min(self.len()) as usize;
+        Ok(self.position as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parse::{many_parse, Parse, ParseResult},
+        Endian,
+    };
+    use std::io::Cursor;
+
+    #[derive(Debug, PartialEq)]
+    struct Entry {
+        kind: u16,
+        offset: u32,
+    }
+    impl<F: Read> Parse<F> for Entry {
+        fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+            Ok(Entry {
+                kind: u16::parse(f, Endian::Big)?,
+                offset: u32::parse(f, Endian::Big)?,
+            })
+        }
+    }
+
+    /// Writes [data] to a temporary file and maps it
+    fn map(name: &str, data: &[u8]) -> MmapSlice {
+        let path =
+            std::env::temp_dir().join(format!("eyeutil-mmap-{}-{}", name, std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        // The file is only removed, never modified, and the map keeps the data alive
+        let slice = unsafe { MmapSlice::open(&path) }.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        slice
+    }
+
+    #[test]
+    fn test_mmap_parse() {
+        let data: Vec<u8> = (0..60).collect();
+        let mut slice = map("parse", &data);
+        assert_eq!(slice.len(), 60);
+
+        let expected: Vec<Entry> = many_parse(&mut Cursor::new(&data), ()).unwrap();
+        let mapped: Vec<Entry> = many_parse(&mut slice, ()).unwrap();
+        assert_eq!(mapped, expected);
+        assert_eq!(mapped.len(), 10);
+        assert_eq!(slice.stream_position(), 60);
+    }
+
+    #[test]
+    fn test_mmap_slice() {
+        let data: Vec<u8> = (0..32).collect();
+        let whole = map("slice", &data);
+        let mut first = whole.slice(4..16).unwrap();
+        let mut second = first.slice(6..).unwrap();
+        assert_eq!(second.start(), 10);
+        assert_eq!(second.end(), 16);
+        assert!(whole.slice(20..40).is_err());
+        assert!(first.slice(..13).is_err());
+
+        // Independent positions over the shared map
+        assert_eq!(
+            Entry::parse(&mut second, ()).unwrap(),
+            Entry::parse(&mut Cursor::new(&data[10..]), ()).unwrap()
+        );
+        assert_eq!(first.stream_position(), 0);
+        assert_eq!(first.fill_buf().unwrap(), &data[4..16]);
+        first.consume(10);
+        assert_eq!(first.fill_buf().unwrap(), [14, 15]);
+        let mut rest = Vec::new();
+        first.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, [14, 15]);
+        assert!(first.fill_buf().unwrap().is_empty());
+
+        assert_eq!(first.seek(SeekFrom::End(-3)).unwrap(), 9);
+        assert_eq!(first.remaining_slice(), [13, 14, 15]);
+        assert_eq!(first.seek(SeekFrom::Start(100)).unwrap(), 12);
+        assert!(first.seek(SeekFrom::Current(-13)).is_err());
+        assert_eq!(second.as_slice(), &data[10..16]);
+    }
+}
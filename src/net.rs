@@ -0,0 +1,114 @@
+//! Network addresses, as they appear in packets: big endian (network order) throughout.
+
+use crate::{
+    impl_data_size,
+    parse::{take_n, Parse, ParseResult},
+    writable::{write_bytes, Writable, WriteResult},
+    Endian,
+};
+use std::{
+    io::{Read, Write},
+    net::{Ipv4Addr, Ipv6Addr, SocketAddrV4},
+};
+
+impl<F: Read> Parse<F> for Ipv4Addr {
+    #[inline]
+    fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+        Ok(Ipv4Addr::from(take_n::<_, 4>(f)?))
+    }
+}
+impl Writable<()> for Ipv4Addr {
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
+    where
+        W: Write,
+    {
+        write_bytes(w, &self.octets())
+    }
+}
+impl_data_size!(Ipv4Addr, 4);
+
+impl<F: Read> Parse<F> for Ipv6Addr {
+    #[inline]
+    fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+        Ok(Ipv6Addr::from(take_n::<_, 16>(f)?))
+    }
+}
+impl Writable<()> for Ipv6Addr {
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
+    where
+        W: Write,
+    {
+        write_bytes(w, &self.octets())
+    }
+}
+impl_data_size!(Ipv6Addr, 16);
+
+/// The address followed by the port. The port is always big endian, as it is in IP and the
+/// protocols built on it, so there is no data to pass.
+impl<F: Read> Parse<F> for SocketAddrV4 {
+    #[inline]
+    fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+        let ip = Ipv4Addr::parse(f, ())?;
+        let port = u16::parse(f, Endian::Big)?;
+        Ok(SocketAddrV4::new(ip, port))
+    }
+}
+impl Writable<()> for SocketAddrV4 {
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
+    where
+        W: Write,
+    {
+        self.ip().write_to(w, ())?;
+        self.port().write_to(w, Endian::Big)
+    }
+}
+impl_data_size!(SocketAddrV4, 6);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_size::DataSize;
+    use std::{fmt::Debug, io::Cursor};
+
+    fn round_trip<T>(value: T, expected: &[u8])
+    where
+        T: Parse<Cursor<Vec<u8>>> + Writable<()> + DataSize<()> + PartialEq + Debug,
+    {
+        let mut output = Vec::new();
+        value.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, expected);
+        assert_eq!(value.data_size(()), expected.len() as u64);
+
+        let mut cursor = Cursor::new(output);
+        assert_eq!(T::parse(&mut cursor, ()).unwrap(), value);
+        assert_eq!(cursor.position(), expected.len() as u64);
+    }
+
+    #[test]
+    fn test_ipv4() {
+        round_trip(Ipv4Addr::LOCALHOST, &[127, 0, 0, 1]);
+        let addr = Ipv4Addr::new(192, 168, 10, 254);
+        round_trip(addr, &addr.octets());
+        assert!(Ipv4Addr::parse(&mut Cursor::new([1, 2, 3]), ()).is_err());
+    }
+
+    #[test]
+    fn test_ipv6() {
+        let mut localhost = [0u8; 16];
+        localhost[15] = 1;
+        round_trip(Ipv6Addr::LOCALHOST, &localhost);
+        let addr = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0x1234, 0x5678);
+        round_trip(addr, &addr.octets());
+    }
+
+    #[test]
+    fn test_socket_addr_v4() {
+        round_trip(
+            SocketAddrV4::new(Ipv4Addr::LOCALHOST, 8080),
+            &[127, 0, 0, 1, 0x1F, 0x90],
+        );
+    }
+}
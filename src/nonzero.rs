@@ -0,0 +1,159 @@
+//! `NonZero` integers, for fields where zero is invalid or means "absent".
+
+use crate::{
+    data_size::DataSize,
+    parse::{Parse, ParseError, ParseResult},
+    writable::{Writable, WriteResult},
+    Endian,
+};
+use std::{
+    io::{Read, Write},
+    num::{
+        NonZeroI16, NonZeroI32, NonZeroI64, NonZeroI8, NonZeroU16, NonZeroU32, NonZeroU64,
+        NonZeroU8,
+    },
+    ops::{Deref, DerefMut},
+};
+
+/// An optional `NonZero` integer, where zero means `None`, such as an id field that is zero when
+/// there is no id.
+/// A bare `Option<T>` writes nothing for `None`, rather than zero, so this is a separate type.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Nullable<T>(pub Option<T>);
+impl<T> Nullable<T> {
+    #[inline]
+    pub fn into_inner(self) -> Option<T> {
+        self.0
+    }
+}
+impl<T> From<Option<T>> for Nullable<T> {
+    #[inline]
+    fn from(value: Option<T>) -> Self {
+        Nullable(value)
+    }
+}
+impl<T> Deref for Nullable<T> {
+    type Target = Option<T>;
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+impl<T> DerefMut for Nullable<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+macro_rules! impl_nonzero {
+    ($($typ:ident($int:ty, $d:ty, $size:expr)),*) => {
+        $(
+            /// Errors with `ParseError::InvalidEnumerationValueNamed` if the value is zero.
+            impl<F: Read> Parse<F, $d> for $typ {
+                #[inline]
+                fn parse(f: &mut F, d: $d) -> ParseResult<Self> {
+                    $typ::new(<$int>::parse(f, d)?)
+                        .ok_or(ParseError::InvalidEnumerationValueNamed(stringify!($typ)))
+                }
+            }
+            impl Writable<$d> for $typ {
+                #[inline]
+                fn write_to<W>(&self, w: &mut W, d: $d) -> WriteResult
+                where
+                    W: Write,
+                {
+                    self.get().write_to(w, d)
+                }
+            }
+            impl DataSize<()> for $typ {
+                #[inline]
+                fn data_size(&self, _d: ()) -> u64 {
+                    $size
+                }
+            }
+
+            impl<F: Read> Parse<F, $d> for Nullable<$typ> {
+                #[inline]
+                fn parse(f: &mut F, d: $d) -> ParseResult<Self> {
+                    Ok(Nullable($typ::new(<$int>::parse(f, d)?)))
+                }
+            }
+            impl Writable<$d> for Nullable<$typ> {
+                #[inline]
+                fn write_to<W>(&self, w: &mut W, d: $d) -> WriteResult
+                where
+                    W: Write,
+                {
+                    self.0.map_or(0, $typ::get).write_to(w, d)
+                }
+            }
+            impl DataSize<()> for Nullable<$typ> {
+                #[inline]
+                fn data_size(&self, _d: ()) -> u64 {
+                    $size
+                }
+            }
+        )*
+    };
+}
+impl_nonzero!(
+    NonZeroU8(u8, (), 1),
+    NonZeroI8(i8, (), 1),
+    NonZeroU16(u16, Endian, 2),
+    NonZeroI16(i16, Endian, 2),
+    NonZeroU32(u32, Endian, 4),
+    NonZeroI32(i32, Endian, 4),
+    NonZeroU64(u64, Endian, 8),
+    NonZeroI64(i64, Endian, 8)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_nonzero() {
+        let mut cursor = Cursor::new([0x0, 0x0, 0x0, 0x5, 0x0, 0x0, 0x0, 0x0]);
+        let value = NonZeroU32::parse(&mut cursor, Endian::Big).unwrap();
+        assert_eq!(value.get(), 5);
+        match NonZeroU32::parse(&mut cursor, Endian::Big) {
+            Err(ParseError::InvalidEnumerationValueNamed("NonZeroU32")) => {}
+            x => panic!("Expected InvalidEnumerationValueNamed, got: {:?}", x),
+        }
+
+        let mut output = Vec::new();
+        value.write_to(&mut output, Endian::Little).unwrap();
+        assert_eq!(output, [0x5, 0x0, 0x0, 0x0]);
+        assert_eq!(value.data_size(()), 4);
+
+        let mut cursor = Cursor::new([0xFF, 0x0]);
+        assert_eq!(NonZeroI8::parse(&mut cursor, ()).unwrap().get(), -1);
+        assert!(NonZeroI8::parse(&mut cursor, ()).is_err());
+    }
+
+    #[test]
+    fn test_nullable() {
+        let data = [0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x0, 0x7];
+        let mut cursor = Cursor::new(data);
+        let none = Nullable::<NonZeroU32>::parse(&mut cursor, Endian::Big).unwrap();
+        let some = Nullable::<NonZeroU32>::parse(&mut cursor, Endian::Big).unwrap();
+        assert_eq!(none.0, None);
+        assert_eq!(some.0, NonZeroU32::new(7));
+
+        // Zero is written back for None
+        let mut output = Vec::new();
+        none.write_to(&mut output, Endian::Big).unwrap();
+        some.write_to(&mut output, Endian::Big).unwrap();
+        assert_eq!(output, data);
+        assert_eq!(none.data_size(()), 4);
+        assert_eq!(some.data_size(()), 4);
+
+        let mut output = Vec::new();
+        Nullable::<NonZeroU8>(None)
+            .write_to(&mut output, ())
+            .unwrap();
+        assert_eq!(output, [0x0]);
+    }
+}
@@ -0,0 +1,214 @@
+use crate::{
+    data_size::DataSize,
+    parse::{parse_at, Parse, ParseResult},
+    writable::{Writable, WriteResult},
+    Endian,
+};
+use std::{
+    fmt::{self, Debug},
+    hash::{Hash, Hasher},
+    io::{self, Read, Seek, Write},
+    marker::PhantomData,
+};
+
+/// An offset of type [O] to a [T] somewhere else in the stream, such as the entries of an
+/// offset table.
+/// Parsing only reads the offset, and [T] is parsed when it is resolved. Writing writes the
+/// stored offset back unchanged.
+pub struct Offset<T, O = u32> {
+    pub offset: O,
+    _target: PhantomData<fn() -> T>,
+}
+impl<T, O> Offset<T, O>
+where
+    O: Copy + Into<u64>,
+{
+    #[inline]
+    pub fn new(offset: O) -> Self {
+        Offset {
+            offset,
+            _target: PhantomData,
+        }
+    }
+
+    /// Returns the offset, widened.
+    #[inline]
+    pub fn get(&self) -> u64 {
+        self.offset.into()
+    }
+
+    /// Returns whether the offset is zero, which is commonly used for 'no value'.
+    #[inline]
+    pub fn is_null(&self) -> bool {
+        self.get() == 0
+    }
+
+    /// Seeks to the offset, from the start of the stream, and parses [T] there.
+    /// The position of [f] is restored afterwards, even if the parse fails, see [parse_at].
+    #[inline]
+    pub fn resolve<F, D>(&self, f: &mut F, d: D) -> ParseResult<T>
+    where
+        F: Read + Seek,
+        T: Parse<F, D>,
+    {
+        self.resolve_relative(f, 0, d)
+    }
+
+    /// Like [Offset::resolve], but the offset is relative to [base].
+    /// Errors with `ErrorKind::InvalidInput` if `base + offset` overflows.
+    pub fn resolve_relative<F, D>(&self, f: &mut F, base: u64, d: D) -> ParseResult<T>
+    where
+        F: Read + Seek,
+        T: Parse<F, D>,
+    {
+        let target = base.checked_add(self.get()).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("offset {} from {} overflows", self.get(), base),
+            )
+        })?;
+
+        parse_at(f, target, d)
+    }
+
+    /// Like [Offset::resolve], but gives `None` if the offset is zero.
+    /// Note: for formats which use another sentinel, check [Offset::offset] before resolving.
+    #[inline]
+    pub fn resolve_opt<F, D>(&self, f: &mut F, d: D) -> ParseResult<Option<T>>
+    where
+        F: Read + Seek,
+        T: Parse<F, D>,
+    {
+        if self.is_null() {
+            Ok(None)
+        } else {
+            self.resolve(f, d).map(Some)
+        }
+    }
+}
+// These are implemented manually, since deriving them would require [T] to implement them too
+impl<T, O: Debug> Debug for Offset<T, O> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Offset").field(&self.offset).finish()
+    }
+}
+impl<T, O: Clone> Clone for Offset<T, O> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Offset {
+            offset: self.offset.clone(),
+            _target: PhantomData,
+        }
+    }
+}
+impl<T, O: Copy> Copy for Offset<T, O> {}
+impl<T, O: PartialEq> PartialEq for Offset<T, O> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.offset == other.offset
+    }
+}
+impl<T, O: Eq> Eq for Offset<T, O> {}
+impl<T, O: Hash> Hash for Offset<T, O> {
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.offset.hash(state)
+    }
+}
+impl<F, T, O> Parse<F, Endian> for Offset<T, O>
+where
+    F: Read,
+    T: 'static,
+    O: Parse<F, Endian> + Copy + Into<u64>,
+{
+    #[inline]
+    fn parse(f: &mut F, endian: Endian) -> ParseResult<Self> {
+        Ok(Offset::new(O::parse(f, endian)?))
+    }
+}
+impl<T, O> Writable<Endian> for Offset<T, O>
+where
+    O: Writable<Endian>,
+{
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, endian: Endian) -> WriteResult
+    where
+        W: Write,
+    {
+        self.offset.write_to(w, endian)
+    }
+}
+impl<D, T, O> DataSize<D> for Offset<T, O>
+where
+    D: Debug + Clone + PartialEq,
+    O: DataSize<D>,
+{
+    #[inline]
+    fn data_size(&self, d: D) -> u64 {
+        self.offset.data_size(d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zstring::ZString;
+    use std::io::Cursor;
+
+    // A string, then an offset table at 4 with a forward offset, a backward offset, and a null
+    // one, then another string
+    const DATA: [u8; 20] = [
+        0xff, b'a', b'b', 0x0, //
+        0x0, 0x0, 0x0, 0x10, //
+        0x0, 0x0, 0x0, 0x1, //
+        0x0, 0x0, 0x0, 0x0, //
+        b'c', b'd', b'e', 0x0,
+    ];
+
+    #[test]
+    fn test_offset_table() {
+        let mut cursor = Cursor::new(&DATA);
+        cursor.set_position(4);
+        let table = (0..3)
+            .map(|_| Offset::<ZString>::parse(&mut cursor, Endian::Big))
+            .collect::<ParseResult<Vec<_>>>()
+            .unwrap();
+        assert_eq!(cursor.position(), 16);
+        assert_eq!(table[0].get(), 0x10);
+
+        assert_eq!(table[0].resolve(&mut cursor, ()).unwrap(), "cde");
+        assert_eq!(cursor.position(), 16);
+        assert_eq!(table[1].resolve(&mut cursor, ()).unwrap(), "ab");
+        assert_eq!(cursor.position(), 16);
+        assert_eq!(
+            table[1].resolve_opt(&mut cursor, ()).unwrap(),
+            Some(ZString::from("ab"))
+        );
+        assert!(table[2].is_null());
+        assert_eq!(table[2].resolve_opt(&mut cursor, ()).unwrap(), None);
+
+        // Relative to the start of the table
+        let relative = Offset::<ZString, u16>::new(12);
+        assert_eq!(
+            relative.resolve_relative(&mut cursor, 4, ()).unwrap(),
+            "cde"
+        );
+        assert!(relative
+            .resolve_relative(&mut cursor, u64::MAX, ())
+            .is_err());
+        assert_eq!(cursor.position(), 16);
+
+        // Failing to parse still restores the position
+        assert!(Offset::<u32>::new(18)
+            .resolve(&mut cursor, Endian::Big)
+            .is_err());
+        assert_eq!(cursor.position(), 16);
+
+        let mut output = Vec::new();
+        for entry in table.iter() {
+            assert_eq!(entry.data_size(()), 4);
+            entry.write_to(&mut output, Endian::Big).unwrap();
+        }
+        assert_eq!(output, &DATA[4..16]);
+    }
+}
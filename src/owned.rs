@@ -0,0 +1,404 @@
+//! Keeping zero-copy parse results together with the buffer that they borrow from.
+//!
+//! Parsing borrowed views (`&[u8]` fields and the like) out of an in-memory buffer avoids
+//! copying, but the results can't be returned alongside the buffer without a self-referential
+//! struct. [OwnedParsed] is that struct, restricted to owned buffers whose contents stay put
+//! when the buffer itself is moved.
+//!
+//! # Soundness
+//! - The buffer is converted into a raw pointer for as long as the view exists, so moving the
+//!   [OwnedParsed] never moves (or asserts unique access to) the bytes the view points into.
+//!   Only `Vec<u8>`, `Box<[u8]>` and `Arc<[u8]>` are supported, all of which heap-allocate.
+//! - The buffer is never mutably accessed or freed while the view exists. The view is dropped
+//!   before the buffer is rebuilt, both in `Drop` and in `into_owner`.
+//! - The view is stored as `Output<'static>`, but that lifetime is never handed out. `get` goes
+//!   through `ParseView::shorten`, which is safe code, and so is checked by the compiler to not
+//!   produce anything that outlives the borrow of the [OwnedParsed].
+//! - `parse` and `map` take closures that are generic over the buffer's lifetime, so they can't
+//!   smuggle borrows of the buffer out anywhere.
+//!
+//! A view can't escape the closure it was parsed in:
+//! ```compile_fail
+//! use eyeutil::owned::{OwnedParsed, ParseView};
+//!
+//! struct BytesView;
+//! impl ParseView for BytesView {
+//!     type Output<'a> = &'a [u8];
+//!
+//!     fn shorten<'a, 'b: 'a>(long: &'a &'b [u8]) -> &'a &'a [u8] {
+//!         long
+//!     }
+//! }
+//!
+//! let mut escaped: &[u8] = &[];
+//! let parsed = OwnedParsed::<_, BytesView>::parse(vec![1, 2, 3], |cursor| {
+//!     escaped = *cursor.get_ref();
+//!     Ok(*cursor.get_ref())
+//! });
+//! ```
+//!
+//! Nor can it outlive the [OwnedParsed]:
+//! ```compile_fail
+//! use eyeutil::owned::{OwnedParsed, ParseView};
+//!
+//! struct BytesView;
+//! impl ParseView for BytesView {
+//!     type Output<'a> = &'a [u8];
+//!
+//!     fn shorten<'a, 'b: 'a>(long: &'a &'b [u8]) -> &'a &'a [u8] {
+//!         long
+//!     }
+//! }
+//!
+//! let view: &[u8] = {
+//!     let parsed = OwnedParsed::<_, BytesView>::parse(vec![1, 2, 3], |cursor| {
+//!         Ok(*cursor.get_ref())
+//!     })
+//!     .unwrap();
+//!     *parsed.get()
+//! };
+//! ```
+
+use crate::parse::ParseResult;
+use std::{fmt, io::Cursor, marker::PhantomData, mem::ManuallyDrop, ptr::NonNull, sync::Arc};
+
+mod private {
+    use std::ptr::NonNull;
+
+    #[derive(Debug, Copy, Clone)]
+    pub struct RawBuffer {
+        pub ptr: NonNull<u8>,
+        pub len: usize,
+        pub capacity: usize,
+    }
+
+    pub trait Sealed: Sized {
+        fn into_raw(self) -> RawBuffer;
+
+        /// # Safety
+        /// [raw] must have come from `into_raw` on the same type, and not been used since.
+        unsafe fn from_raw(raw: RawBuffer) -> Self;
+    }
+}
+use private::RawBuffer;
+
+/// Owned byte buffers whose contents don't move when the buffer is moved.
+/// This is sealed, as the soundness of [OwnedParsed] depends on the implementations.
+pub trait StableBuffer: private::Sealed {}
+
+impl private::Sealed for Vec<u8> {
+    #[inline]
+    fn into_raw(self) -> RawBuffer {
+        let mut buffer = ManuallyDrop::new(self);
+        RawBuffer {
+            ptr: NonNull::new(buffer.as_mut_ptr()).expect("vec pointer is never null"),
+            len: buffer.len(),
+            capacity: buffer.capacity(),
+        }
+    }
+
+    #[inline]
+    unsafe fn from_raw(raw: RawBuffer) -> Self {
+        Vec::from_raw_parts(raw.ptr.as_ptr(), raw.len, raw.capacity)
+    }
+}
+impl StableBuffer for Vec<u8> {}
+
+impl private::Sealed for Box<[u8]> {
+    #[inline]
+    fn into_raw(self) -> RawBuffer {
+        let len = self.len();
+        let ptr = Box::into_raw(self) as *mut u8;
+        RawBuffer {
+            ptr: NonNull::new(ptr).expect("box pointer is never null"),
+            len,
+            capacity: len,
+        }
+    }
+
+    #[inline]
+    unsafe fn from_raw(raw: RawBuffer) -> Self {
+        Box::from_raw(std::ptr::slice_from_raw_parts_mut(
+            raw.ptr.as_ptr(),
+            raw.len,
+        ))
+    }
+}
+impl StableBuffer for Box<[u8]> {}
+
+impl private::Sealed for Arc<[u8]> {
+    #[inline]
+    fn into_raw(self) -> RawBuffer {
+        let len = self.len();
+        let ptr = Arc::into_raw(self) as *const u8 as *mut u8;
+        RawBuffer {
+            ptr: NonNull::new(ptr).expect("arc pointer is never null"),
+            len,
+            capacity: len,
+        }
+    }
+
+    #[inline]
+    unsafe fn from_raw(raw: RawBuffer) -> Self {
+        Arc::from_raw(std::ptr::slice_from_raw_parts(
+            raw.ptr.as_ptr() as *const u8,
+            raw.len,
+        ))
+    }
+}
+impl StableBuffer for Arc<[u8]> {}
+
+/// Names a type that borrows from a buffer, for any lifetime of that buffer.
+/// Usually implemented on an empty marker type:
+/// ```
+/// use eyeutil::owned::ParseView;
+///
+/// struct Header<'a> {
+///     name: &'a [u8],
+/// }
+///
+/// struct HeaderView;
+/// impl ParseView for HeaderView {
+///     type Output<'a> = Header<'a>;
+///
+///     fn shorten<'a, 'b: 'a>(long: &'a Header<'b>) -> &'a Header<'a> {
+///         long
+///     }
+/// }
+/// ```
+pub trait ParseView {
+    type Output<'a>;
+
+    /// Shortens the lifetime of the borrowed buffer. This should just return [long], which
+    /// compiles if `Output` is covariant, as it is for most types.
+    fn shorten<'a, 'b: 'a>(long: &'a Self::Output<'b>) -> &'a Self::Output<'a>;
+}
+
+/// A parsed value of `V::Output` along with the buffer it borrows from.
+/// See the module documentation for why this is sound.
+pub struct OwnedParsed<B, V>
+where
+    B: StableBuffer,
+    V: ParseView,
+{
+    /// Borrows from [buffer], so it must be dropped first
+    value: ManuallyDrop<V::Output<'static>>,
+    buffer: RawBuffer,
+    _marker: PhantomData<B>,
+}
+impl<B, V> OwnedParsed<B, V>
+where
+    B: StableBuffer,
+    V: ParseView,
+{
+    /// Parses [buffer] with [parser], keeping the result together with the buffer.
+    /// The buffer is returned if parsing fails.
+    pub fn parse<P>(buffer: B, parser: P) -> Result<Self, (B, crate::parse::ParseError)>
+    where
+        P: for<'a> FnOnce(&mut Cursor<&'a [u8]>) -> ParseResult<V::Output<'a>>,
+    {
+        let raw = buffer.into_raw();
+        // Safety: the buffer lives until `from_raw` in Drop or `into_owner`, which happens
+        // after [value] is dropped, and nothing mutates it in between.
+        let data: &'static [u8] =
+            unsafe { std::slice::from_raw_parts(raw.ptr.as_ptr() as *const u8, raw.len) };
+
+        match parser(&mut Cursor::new(data)) {
+            Ok(value) => Ok(OwnedParsed {
+                value: ManuallyDrop::new(value),
+                buffer: raw,
+                _marker: PhantomData,
+            }),
+            // Safety: the error can't borrow from the buffer, as ParseError is 'static
+            Err(e) => Err((unsafe { B::from_raw(raw) }, e)),
+        }
+    }
+
+    #[inline]
+    pub fn get(&self) -> &V::Output<'_> {
+        V::shorten(&self.value)
+    }
+
+    /// Returns the bytes of the buffer.
+    #[inline]
+    pub fn buffer(&self) -> &[u8] {
+        // Safety: see `parse`
+        unsafe {
+            std::slice::from_raw_parts(self.buffer.ptr.as_ptr() as *const u8, self.buffer.len)
+        }
+    }
+
+    /// Transforms the value, keeping it attached to the same buffer.
+    pub fn map<V2, M>(self, map: M) -> OwnedParsed<B, V2>
+    where
+        V2: ParseView,
+        M: for<'a> FnOnce(V::Output<'a>, PhantomData<&'a ()>) -> V2::Output<'a>,
+    {
+        let mut this = ManuallyDrop::new(self);
+        // Safety: [this] is never used again (nor dropped), so the value is only moved out once
+        let value = unsafe { ManuallyDrop::take(&mut this.value) };
+        OwnedParsed {
+            value: ManuallyDrop::new(map(value, PhantomData)),
+            buffer: this.buffer,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Drops the value, returning the buffer.
+    pub fn into_owner(self) -> B {
+        let mut this = ManuallyDrop::new(self);
+        // Safety: [this] is never used again (nor dropped). The value is dropped before the
+        // buffer is rebuilt, and nothing else borrows from the buffer.
+        unsafe {
+            ManuallyDrop::drop(&mut this.value);
+            B::from_raw(this.buffer)
+        }
+    }
+}
+impl<B, V> Drop for OwnedParsed<B, V>
+where
+    B: StableBuffer,
+    V: ParseView,
+{
+    fn drop(&mut self) {
+        // Safety: see `into_owner`
+        unsafe {
+            ManuallyDrop::drop(&mut self.value);
+            drop(B::from_raw(self.buffer));
+        }
+    }
+}
+impl<B, V> fmt::Debug for OwnedParsed<B, V>
+where
+    B: StableBuffer,
+    V: ParseView,
+    for<'a> V::Output<'a>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OwnedParsed")
+            .field("value", self.get())
+            .field("buffer_len", &self.buffer.len)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::{ParseError, ReadExt};
+    use std::rc::Rc;
+
+    #[derive(Debug, PartialEq)]
+    struct Entry<'a> {
+        name: &'a [u8],
+        data: &'a [u8],
+    }
+
+    struct EntryView;
+    impl ParseView for EntryView {
+        type Output<'a> = Entry<'a>;
+
+        fn shorten<'a, 'b: 'a>(long: &'a Entry<'b>) -> &'a Entry<'a> {
+            long
+        }
+    }
+
+    struct NameView;
+    impl ParseView for NameView {
+        type Output<'a> = &'a [u8];
+
+        fn shorten<'a, 'b: 'a>(long: &'a &'b [u8]) -> &'a &'a [u8] {
+            long
+        }
+    }
+
+    /// Parses `[name len: u8][name][data len: u8][data]`
+    fn parse_entry<'a>(cursor: &mut Cursor<&'a [u8]>) -> ParseResult<Entry<'a>> {
+        let borrow = |cursor: &mut Cursor<&'a [u8]>| -> ParseResult<&'a [u8]> {
+            let len = usize::from(cursor.read_u8()?);
+            let start = cursor.position() as usize;
+            let data: &'a [u8] = cursor.get_ref();
+            let value = data
+                .get(start..start + len)
+                .ok_or(ParseError::ExpectedBytes(len))?;
+            cursor.set_position((start + len) as u64);
+            Ok(value)
+        };
+        Ok(Entry {
+            name: borrow(cursor)?,
+            data: borrow(cursor)?,
+        })
+    }
+
+    const DATA: &[u8] = b"\x03abc\x02\x01\x02";
+
+    #[test]
+    fn test_owned_parsed() {
+        let parsed = OwnedParsed::<_, EntryView>::parse(DATA.to_vec(), parse_entry).unwrap();
+        // Moving it doesn't move the bytes
+        let moved = Box::new(parsed);
+        assert_eq!(moved.get().name, b"abc");
+        assert_eq!(moved.get().data, [1, 2]);
+        assert_eq!(moved.buffer(), DATA);
+        assert_eq!(moved.into_owner(), DATA);
+
+        let parsed = OwnedParsed::<Box<[u8]>, EntryView>::parse(DATA.into(), parse_entry).unwrap();
+        let name = parsed.map(|entry, _| -> &[u8] { entry.name });
+        let _: &OwnedParsed<_, NameView> = &name;
+        assert_eq!(*name.get(), b"abc");
+        assert_eq!(&*name.into_owner(), DATA);
+
+        let shared: Arc<[u8]> = DATA.into();
+        let parsed = OwnedParsed::<_, EntryView>::parse(shared.clone(), parse_entry).unwrap();
+        assert_eq!(Arc::strong_count(&shared), 2);
+        assert_eq!(parsed.get().data, [1, 2]);
+        drop(parsed);
+        assert_eq!(Arc::strong_count(&shared), 1);
+    }
+
+    #[test]
+    fn test_owned_parsed_error() {
+        let (buffer, e) =
+            OwnedParsed::<_, EntryView>::parse(b"\x09ab".to_vec(), parse_entry).unwrap_err();
+        assert!(matches!(e, ParseError::ExpectedBytes(9)));
+        assert_eq!(buffer, b"\x09ab");
+    }
+
+    #[test]
+    fn test_owned_parsed_drops_value() {
+        struct Tracked<'a> {
+            data: &'a [u8],
+            _drops: Rc<()>,
+        }
+        struct TrackedView;
+        impl ParseView for TrackedView {
+            type Output<'a> = Tracked<'a>;
+
+            fn shorten<'a, 'b: 'a>(long: &'a Tracked<'b>) -> &'a Tracked<'a> {
+                long
+            }
+        }
+
+        let drops = Rc::new(());
+        let make = |drops: &Rc<()>| {
+            let drops = drops.clone();
+            OwnedParsed::<_, TrackedView>::parse(DATA.to_vec(), move |cursor| {
+                Ok(Tracked {
+                    data: cursor.get_ref(),
+                    _drops: drops,
+                })
+            })
+            .unwrap()
+        };
+
+        let parsed = make(&drops);
+        assert_eq!(Rc::strong_count(&drops), 2);
+        assert_eq!(parsed.get().data, DATA);
+        drop(parsed);
+        assert_eq!(Rc::strong_count(&drops), 1);
+
+        let parsed = make(&drops);
+        parsed.into_owner();
+        assert_eq!(Rc::strong_count(&drops), 1);
+    }
+}
@@ -1,10 +1,22 @@
-use crate::{stream_len, stream_position, Endian, EnumConversionError};
+use crate::{
+    checksum::{Checksum, Crc32, HashingReader},
+    fourcc::FourCC,
+    peek::{PeekLimiter, PeekReader},
+    read_if_possible,
+    sections::SectionError,
+    skip, skip_seek, stream_len, stream_position, stream_position_and_len,
+    writable::write_full,
+    zstring::ZString,
+    Endian, EnumConversionError,
+};
 use std::{
-    convert::TryInto,
     error::Error,
-    fmt::Debug,
-    io::{Read, Seek, SeekFrom},
+    fmt::{self, Debug},
+    io::{Read, Seek, SeekFrom, Write},
     marker::PhantomData,
+    mem::MaybeUninit,
+    rc::Rc,
+    sync::Arc,
 };
 
 #[derive(Debug)]
@@ -24,13 +36,170 @@ pub enum ParseError {
     InvalidEnumerationValueNamed(&'static str),
     /// It read a byte that was invalid.
     InvalidByte,
-    Custom(Box<dyn Error>),
+    /// A buffered peek tried to read more than its budget of .0 bytes.
+    PeekLimitExceeded(usize),
+    /// A section was invalid, or did not exist.
+    Section(SectionError),
+    /// Tried to seek to .0, which is past the end of the data at .1
+    SeekOutOfBounds(u64, u64),
+    /// Tried to seek backwards from .0 to .1
+    SeekBackwards(u64, u64),
+    /// Expected .0 null-terminated strings within a fixed region, but only found .1
+    MissingTerminators(usize, usize),
+    /// String .0 of a fixed region was .1 bytes long, which is over its maximum length
+    StringTooLong(usize, usize),
+    /// Found a nonzero byte at offset .0 in padding, such as after the last string of a fixed
+    /// region (from the region's start), or before an alignment boundary (from the padding's
+    /// start)
+    NonZeroPadding(usize),
+    /// The alignment .0 was zero or not a power of two
+    InvalidAlignment(u64),
+    /// Tried to read .0 bits at once, which is more than fits in a u64
+    BitCountTooLarge(u8),
+    /// Expected the tag .0, but found .1
+    UnexpectedFourCC(FourCC, FourCC),
+    /// A byte-stuffed frame ended directly after an escape byte
+    DanglingEscape,
+    /// An escape byte was followed by the sentinel .0, rather than an escaped byte
+    EscapedSentinel(u8),
+    /// Found the sentinel .0 in the middle of a byte-stuffed frame
+    UnexpectedSentinel(u8),
+    /// A repeated parse consumed no bytes at position .0, so it would never finish
+    NoProgress(u64),
+    /// The data's checksum was .1, but .0 was expected
+    ChecksumMismatch(u64, u64),
+    /// A parsed value failed the check named .0
+    ValidationFailed(&'static str),
+    /// A number was too large to fit in the type it was parsed into
+    IntegerOverflow,
+    /// The size .0 was over the limit of .1
+    SizeLimitExceeded(usize, usize),
+    /// The bytes didn't match the expected tag, first differing at index .0 into it
+    TagMismatch(usize),
+    /// The error .1 happened at offset .0
+    AtOffset(u64, Box<ParseError>),
+    /// The error .1 happened while parsing .0, such as a field name. Displayed as a chain, like
+    /// `Header > name > ...`
+    Context(&'static str, Box<ParseError>),
+    /// Any other error, such as from a user's own validation.
+    /// Note: this is `Send + Sync` so that parse errors can be collected from other threads.
+    Custom(Box<dyn Error + Send + Sync>),
+}
+impl ParseError {
+    /// Wraps [error] in `ParseError::Custom`, which also accepts strings:
+    /// `ParseError::custom(format!("bad chunk {}", name))`
+    #[inline]
+    pub fn custom<E>(error: E) -> Self
+    where
+        E: Into<Box<dyn Error + Send + Sync>>,
+    {
+        Self::Custom(error.into())
+    }
+
+    /// Returns the error under any `Context` or `AtOffset`, for matching on what went wrong.
+    pub fn root(&self) -> &ParseError {
+        match self {
+            ParseError::AtOffset(_, e) | ParseError::Context(_, e) => e.root(),
+            e => e,
+        }
+    }
+}
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Io(e) => write!(f, "{}", e),
+            ParseError::ExpectedBytes(amount) => write!(f, "expected {} bytes", amount),
+            ParseError::ExpectedBytesFound(expected, found) => {
+                write!(f, "expected {} bytes, but found {}", expected, found)
+            }
+            ParseError::UnexpectedEOF => write!(f, "unexpected end of data"),
+            ParseError::ExpectedEOF => write!(f, "expected the end of the data"),
+            ParseError::InvalidEnumerationValue => write!(f, "invalid enumeration value"),
+            ParseError::InvalidEnumerationValueNamed(name) => {
+                write!(f, "invalid enumeration value for {}", name)
+            }
+            ParseError::InvalidByte => write!(f, "invalid byte"),
+            ParseError::PeekLimitExceeded(limit) => {
+                write!(f, "peeked past the limit of {} bytes", limit)
+            }
+            ParseError::Section(e) => write!(f, "invalid section: {:?}", e),
+            ParseError::SeekOutOfBounds(position, len) => write!(
+                f,
+                "seek to {:#X} is past the end of the data at {:#X}",
+                position, len
+            ),
+            ParseError::SeekBackwards(from, to) => {
+                write!(f, "tried to seek backwards from {:#X} to {:#X}", from, to)
+            }
+            ParseError::MissingTerminators(expected, found) => write!(
+                f,
+                "expected {} null-terminated strings, but found {}",
+                expected, found
+            ),
+            ParseError::StringTooLong(index, len) => {
+                write!(f, "string {} is too long, at {} bytes", index, len)
+            }
+            ParseError::NonZeroPadding(offset) => {
+                write!(f, "nonzero byte in padding at offset {:#X}", offset)
+            }
+            ParseError::InvalidAlignment(alignment) => write!(f, "invalid alignment {}", alignment),
+            ParseError::BitCountTooLarge(count) => {
+                write!(f, "tried to read {} bits at once", count)
+            }
+            ParseError::UnexpectedFourCC(expected, found) => {
+                write!(f, "expected the tag {}, but found {}", expected, found)
+            }
+            ParseError::DanglingEscape => write!(f, "frame ended directly after an escape byte"),
+            ParseError::EscapedSentinel(sentinel) => {
+                write!(f, "escaped the sentinel {:#04X}", sentinel)
+            }
+            ParseError::UnexpectedSentinel(sentinel) => {
+                write!(f, "unexpected sentinel {:#04X} within a frame", sentinel)
+            }
+            ParseError::NoProgress(position) => {
+                write!(f, "parse consumed no bytes at {:#X}", position)
+            }
+            ParseError::ChecksumMismatch(expected, found) => write!(
+                f,
+                "checksum was {:#X}, but {:#X} was expected",
+                found, expected
+            ),
+            ParseError::ValidationFailed(check) => write!(f, "failed the check {}", check),
+            ParseError::IntegerOverflow => write!(f, "number too large for its type"),
+            ParseError::SizeLimitExceeded(size, limit) => {
+                write!(f, "size {} is over the limit of {}", size, limit)
+            }
+            ParseError::TagMismatch(index) => write!(f, "tag mismatch at index {}", index),
+            ParseError::AtOffset(offset, e) => write!(f, "at offset {:#X}: {}", offset, e),
+            ParseError::Context(name, e) => write!(f, "{} > {}", name, e),
+            ParseError::Custom(e) => write!(f, "{}", e),
+        }
+    }
 }
+/// Note: there is no `source`, as the displayed message already includes the wrapped errors.
+impl Error for ParseError {}
 impl From<std::io::Error> for ParseError {
     fn from(v: std::io::Error) -> Self {
         Self::Io(v)
     }
 }
+impl From<SectionError> for ParseError {
+    fn from(e: SectionError) -> Self {
+        Self::Section(e)
+    }
+}
+impl From<String> for ParseError {
+    #[inline]
+    fn from(message: String) -> Self {
+        Self::custom(message)
+    }
+}
+impl From<&'static str> for ParseError {
+    #[inline]
+    fn from(message: &'static str) -> Self {
+        Self::custom(message)
+    }
+}
 impl<V> From<EnumConversionError<V>> for ParseError {
     fn from(e: EnumConversionError<V>) -> Self {
         match e {
@@ -41,6 +210,26 @@ impl<V> From<EnumConversionError<V>> for ParseError {
 
 pub type ParseResult<R, E = ParseError> = Result<R, E>;
 
+/// Attaching context to errors, see `ParseError::Context`.
+/// Note: this only does anything on the error path, so successful parses don't allocate.
+pub trait ParseResultExt {
+    fn context(self, name: &'static str) -> Self;
+}
+impl<T> ParseResultExt for ParseResult<T> {
+    #[inline]
+    fn context(self, name: &'static str) -> Self {
+        self.map_err(|e| ParseError::Context(name, Box::new(e)))
+    }
+}
+/// The records that parsed, and the offsets of the ones that didn't, with their errors.
+pub type LossyResult<P> = ParseResult<(Vec<P>, Vec<(u64, ParseError)>)>;
+
+/// Upper bound on how many bytes (or elements) are allocated up front for an amount that likely
+/// came from the data, so that a bogus length fails when the data runs out (such as with
+/// `ParseError::ExpectedBytesFound` from [take]) rather than with an allocation failure. Past
+/// this the buffer grows as data is actually read.
+pub(crate) const MAX_PREALLOCATE: usize = 64 * 1024;
+
 pub fn single<F>(f: &mut F) -> ParseResult<u8>
 where
     F: Read,
@@ -53,18 +242,35 @@ where
 // TODO: take_peek
 // TODO: const generics version that takes in the size as a template param
 //  and returns an array of that size
+/// Errors with `ParseError::ExpectedBytesFound` if [f] ends before [amount] bytes, with how
+/// many there were.
+/// Note: [amount] is only allocated as the data is read, so an untrusted length can't cause an
+/// allocation failure by itself. The buffer isn't zeroed before being read into, either.
 pub fn take<F>(f: &mut F, amount: usize) -> ParseResult<Vec<u8>>
 where
     F: Read,
 {
-    let mut output = Vec::new();
-    output.resize(amount, 0);
-
-    f.read_exact(&mut output)?;
-
+    let mut output = Vec::with_capacity(amount.min(MAX_PREALLOCATE));
+    let found = f.by_ref().take(amount as u64).read_to_end(&mut output)?;
+    if found != amount {
+        return Err(ParseError::ExpectedBytesFound(amount, found));
+    }
     Ok(output)
 }
 
+/// Like [take], but errors with `ParseError::SizeLimitExceeded` if [amount] is over [max],
+/// before reading anything. For lengths from headers that shouldn't be trusted.
+#[inline]
+pub fn take_limited<F>(f: &mut F, amount: usize, max: usize) -> ParseResult<Vec<u8>>
+where
+    F: Read,
+{
+    if amount > max {
+        return Err(ParseError::SizeLimitExceeded(amount, max));
+    }
+    take(f, amount)
+}
+
 /// More efficient than parsing [u8; N]
 pub fn take_n<F: Read, const N: usize>(f: &mut F) -> ParseResult<[u8; N]> {
     let mut output = [0_u8; N];
@@ -101,11 +307,80 @@ where
     Ok(result)
 }
 
+/// Seeks to the absolute position [pos], after checking that it is within the data.
+/// This is for following offsets read from the data itself, erroring immediately with
+/// `SeekOutOfBounds` rather than failing with a confusing EOF error on a later read.
+/// If [len_hint] is given it is used as the length, otherwise `stream_len` is used.
+/// Seeking to exactly the end is allowed. Returns the new position.
+pub fn seek_within<F>(f: &mut F, pos: u64, len_hint: Option<u64>) -> ParseResult<u64>
+where
+    F: Seek,
+{
+    let len = match len_hint {
+        Some(len) => len,
+        None => stream_len(f)?,
+    };
+
+    if pos > len {
+        return Err(ParseError::SeekOutOfBounds(pos, len));
+    }
+
+    Ok(f.seek(SeekFrom::Start(pos))?)
+}
+
+/// Seeks to the absolute position [pos], erroring with `SeekBackwards` if that is before the
+/// current position. For keeping to append-only/streaming invariants.
+/// Returns the new position.
+pub fn seek_forward_only<F>(f: &mut F, pos: u64) -> ParseResult<u64>
+where
+    F: Seek,
+{
+    let current = stream_position(f)?;
+    if pos < current {
+        return Err(ParseError::SeekBackwards(current, pos));
+    }
+
+    Ok(f.seek(SeekFrom::Start(pos))?)
+}
+
+/// Size of the stack buffer used by [relocate].
+const RELOCATE_CHUNK: usize = 8 * 1024;
+
+/// Copies exactly [len] bytes from [f] into [w], without holding them all in memory.
+/// Returns the number of bytes moved, which is always [len] on success, for progress reporting.
+/// Lengths are u64 throughout, so this can move data that could never be buffered with [take]
+/// (such as a 5 GiB payload on a 32-bit target).
+/// If [f] ends before [len] bytes were moved, this errors with `UnexpectedEOF`. Whatever was
+/// read before that has already been written to [w].
+pub fn relocate<F, W>(f: &mut F, w: &mut W, len: u64) -> ParseResult<u64>
+where
+    F: Read,
+    W: Write,
+{
+    let mut buffer = [0u8; RELOCATE_CHUNK];
+    let mut moved: u64 = 0;
+    while moved < len {
+        // Only the min is ever truncated to usize, so this is fine on 32-bit targets.
+        let end = (len - moved).min(RELOCATE_CHUNK as u64) as usize;
+        let amount = read_if_possible(&mut *f, &mut buffer[..end])?;
+        if amount == 0 {
+            return Err(ParseError::UnexpectedEOF);
+        }
+
+        write_full(w, &buffer[..amount])?;
+        moved += amount as u64;
+    }
+
+    Ok(moved)
+}
+
 // TODO: many_peek
 /// This loops until it has consumed everything, or reached an error
 /// Note that this does not rollback when it encounters an error
 /// and should be used when you know that what you're reading from is
 /// parseable by repeated calls to [func]
+/// Errors with `ParseError::NoProgress` if [func] consumes nothing, rather than looping forever.
+/// If the last element seeks past the end, then the input is left there.
 pub fn many<F, C, R, E, D>(f: &mut F, d: D, func: C) -> ParseResult<Vec<R>>
 where
     F: Read + Seek,
@@ -114,49 +389,259 @@ where
     D: Clone,
 {
     let mut result: Vec<R> = Vec::new();
-    let stream_len = stream_len(f)?;
-    loop {
-        if stream_position(f)? >= stream_len {
-            break;
+    let (mut position, stream_len) = stream_position_and_len(f)?;
+    // The position after each element is the start of the next, so there is one seek each
+    while position < stream_len {
+        let value: R = func(f, d.clone()).map_err(Into::into)?;
+        result.push(value);
+        let next = stream_position(f)?;
+        if next == position {
+            return Err(ParseError::NoProgress(position));
         }
+        position = next;
+    }
 
-        let value: R = match func(f, d.clone()) {
-            Ok(x) => x,
-            Err(e) => return Err(e.into()),
-        };
+    Ok(result)
+}
+
+/// Parses elements until one of them makes [keep_going] return false, such as a record with
+/// its continuation flag unset. That last element is included.
+pub fn many_while<F, P, D, K>(f: &mut F, d: D, mut keep_going: K) -> ParseResult<Vec<P>>
+where
+    F: Read,
+    P: Parse<F, D>,
+    D: Clone,
+    K: FnMut(&P) -> bool,
+{
+    let mut result = Vec::new();
+    loop {
+        let value = P::parse(f, d.clone())?;
+        let done = !keep_going(&value);
         result.push(value);
+        if done {
+            return Ok(result);
+        }
     }
+}
 
-    debug_assert_eq!(stream_position(f)?, stream_len);
-
-    Ok(result)
+/// Like [many_while], but the element that makes [keep_going] return false is not included,
+/// and the input is seeked back to before it.
+pub fn many_while_exclusive<F, P, D, K>(f: &mut F, d: D, mut keep_going: K) -> ParseResult<Vec<P>>
+where
+    F: Read + Seek,
+    P: Parse<F, D>,
+    D: Clone,
+    K: FnMut(&P) -> bool,
+{
+    let mut result = Vec::new();
+    loop {
+        let position = stream_position(f)?;
+        let value = P::parse(f, d.clone())?;
+        if !keep_going(&value) {
+            f.seek(SeekFrom::Start(position))?;
+            return Ok(result);
+        }
+        result.push(value);
+    }
 }
 
 // TODO: many_parse_peek
+/// Like [many], but parses each element as `P`.
+#[inline]
 pub fn many_parse<F, P, D>(f: &mut F, d: D) -> ParseResult<Vec<P>>
 where
     F: Read + Seek,
     P: Parse<F, D>,
     D: Clone,
 {
-    let mut result = Vec::new();
-    let stream_len = stream_len(f)?;
+    iter_parse(f, d)?.collect()
+}
 
-    loop {
-        if stream_position(f)? >= stream_len {
-            break;
+/// Like [many_parse], but for damaged input: records that fail to parse are collected along
+/// with their offset, rather than aborting, and parsing resumes at the next record [resync]
+/// finds.
+/// After a failure, [f] is moved to the byte after the failed record's start, and [resync] is
+/// called until it returns true, with [f] left at the start of a record, or [f] ends. If a call
+/// returns false without moving [f], then [f] is moved forward a byte, so that it always
+/// finishes.
+/// Io errors other than `UnexpectedEof` abort, as do errors from [resync].
+pub fn many_lossy<F, P, D, R>(f: &mut F, d: D, mut resync: R) -> LossyResult<P>
+where
+    F: Read + Seek,
+    P: Parse<F, D>,
+    D: Clone,
+    R: FnMut(&mut F) -> ParseResult<bool>,
+{
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    let end = stream_len(f)?;
+
+    let mut position = stream_position(f)?;
+    while position < end {
+        let error = match P::parse(f, d.clone()) {
+            Ok(value) => {
+                values.push(value);
+                let next = stream_position(f)?;
+                if next == position {
+                    return Err(ParseError::NoProgress(position));
+                }
+                position = next;
+                continue;
+            }
+            // Looking through any context, as from a field of an `impl_parse!` type
+            Err(e)
+                if matches!(e.root(), ParseError::Io(io)
+                    if io.kind() != std::io::ErrorKind::UnexpectedEof) =>
+            {
+                return Err(e)
+            }
+            Err(e) => e,
+        };
+        errors.push((position, error));
+
+        position = f.seek(SeekFrom::Start(position + 1))?;
+        while position < end {
+            if resync(f)? {
+                position = stream_position(f)?;
+                break;
+            }
+            let next = stream_position(f)?;
+            position = if next == position {
+                f.seek(SeekFrom::Start(position + 1))?
+            } else {
+                next
+            };
         }
+    }
 
-        // TODO: if we're passed a reference, does this clone the reference or the type behind the reference?
-        let value: P = P::parse(f, d.clone())?;
-        result.push(value);
+    Ok((values, errors))
+}
+
+/// Lazily parses elements as `P` until the end of [f], like [many_parse] but without collecting
+/// them, so that large files can be streamed.
+/// The length of [f] is found up front, so this errors if that fails.
+#[inline]
+pub fn iter_parse<F, P, D>(f: &mut F, d: D) -> ParseResult<ParseIter<'_, F, P, D>>
+where
+    F: Read + Seek,
+    P: Parse<F, D>,
+    D: Clone,
+{
+    let (offset, end) = stream_position_and_len(f)?;
+    Ok(ParseIter {
+        f,
+        d,
+        offset,
+        end,
+        done: false,
+        _marker: PhantomData,
+    })
+}
+
+/// Iterator returned by [iter_parse].
+/// Ends once the reader is at (or past) its end, and after the first error.
+/// Errors with `ParseError::NoProgress` if an element consumes nothing, rather than looping
+/// forever.
+/// Note: stopping early leaves the reader after the last element that was parsed.
+#[derive(Debug)]
+pub struct ParseIter<'f, F, P, D>
+where
+    F: Read + Seek,
+    P: Parse<F, D>,
+    D: Clone,
+{
+    f: &'f mut F,
+    d: D,
+    offset: u64,
+    end: u64,
+    done: bool,
+    _marker: PhantomData<fn() -> P>,
+}
+impl<'f, F, P, D> ParseIter<'f, F, P, D>
+where
+    F: Read + Seek,
+    P: Parse<F, D>,
+    D: Clone,
+{
+    /// Returns the offset of the next element, for progress reporting.
+    #[inline]
+    pub fn offset(&self) -> u64 {
+        self.offset
+    }
+
+    fn parse_next(&mut self) -> ParseResult<P> {
+        let value = P::parse(self.f, self.d.clone())?;
+        let position = stream_position(self.f)?;
+        if position == self.offset {
+            return Err(ParseError::NoProgress(position));
+        }
+        self.offset = position;
+        Ok(value)
+    }
+}
+impl<'f, F, P, D> Iterator for ParseIter<'f, F, P, D>
+where
+    F: Read + Seek,
+    P: Parse<F, D>,
+    D: Clone,
+{
+    type Item = ParseResult<P>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done || self.offset >= self.end {
+            return None;
+        }
+
+        let result = self.parse_next();
+        self.done = result.is_err();
+        Some(result)
     }
+}
+impl<'f, F, P, D> std::iter::FusedIterator for ParseIter<'f, F, P, D>
+where
+    F: Read + Seek,
+    P: Parse<F, D>,
+    D: Clone,
+{
+}
 
-    debug_assert_eq!(stream_position(f)?, stream_len);
+/// Like [many], but for readers which can't Seek, such as pipes and decompressors.
+/// Rather than comparing against the stream's length, this peeks a byte before each element,
+/// and stops when there is none. So [f] ending on an element boundary is the natural end,
+/// while [f] ending partway through an element is still an error from [func].
+/// Errors with `ParseError::NoProgress` if [func] consumes nothing, where the position is the
+/// number of bytes consumed from [f].
+pub fn many_to_eof<F, C, R, E, D>(f: &mut PeekReader<F>, d: D, func: C) -> ParseResult<Vec<R>>
+where
+    F: Read,
+    C: Fn(&mut PeekReader<F>, D) -> Result<R, E>,
+    E: Into<ParseError>,
+    D: Clone,
+{
+    let mut result: Vec<R> = Vec::new();
+    while peek_byte(f)?.is_some() {
+        let position = f.consumed();
+        let value: R = func(f, d.clone()).map_err(Into::into)?;
+        result.push(value);
+        if f.consumed() == position {
+            return Err(ParseError::NoProgress(position));
+        }
+    }
 
     Ok(result)
 }
 
+/// Like [many_to_eof], but parses each element as `P`.
+#[inline]
+pub fn many_parse_to_eof<F, P, D>(f: &mut PeekReader<F>, d: D) -> ParseResult<Vec<P>>
+where
+    F: Read,
+    P: Parse<PeekReader<F>, D>,
+    D: Clone,
+{
+    many_to_eof(f, d, P::parse)
+}
+
 struct ManyCountParseIter<'a, T: Parse<F, D>, F: Read, D: Clone> {
     f: &'a mut F,
     d: D,
@@ -189,32 +674,220 @@ pub fn many_count_iter<'a, T: Parse<F, D>, F: Read, D: Clone + 'a>(
     }
 }
 
+/// Note: like [take], an untrusted [amount] doesn't cause a large allocation up front.
 pub fn many_count<F: Read, R: Parse<F, D>, D: Clone>(
     f: &mut F,
     d: D,
     amount: usize,
 ) -> ParseResult<Vec<R>> {
-    let mut result: Vec<R> = Vec::with_capacity(amount);
+    let elem_size = std::mem::size_of::<R>().max(1);
+    let mut result: Vec<R> = Vec::with_capacity(amount.min(MAX_PREALLOCATE / elem_size));
     for _ in 0..amount {
         result.push(R::parse(f, d.clone())?);
     }
     Ok(result)
 }
 
-// TODO: tag_peek
+/// Parses an ASCII decimal number, as found in text fields of otherwise binary formats.
+/// With a [width], exactly that many bytes are read, and the number ends at the first
+/// [terminator] within them, with the rest of the field ignored.
+/// With only a [terminator], bytes are read up to (and including) it.
+/// With neither, digits are read until the first byte which isn't one, which is left unread.
+/// Spaces around the digits are skipped, and leading zeros are ignored.
+/// Errors with `ParseError::InvalidByte` if there are no digits, or a byte which isn't one,
+/// and with `ParseError::IntegerOverflow` if the number doesn't fit.
+#[inline]
+pub fn ascii_u64_dec<F>(f: &mut F, width: Option<usize>, terminator: Option<u8>) -> ParseResult<u64>
+where
+    F: Read + Seek,
+{
+    ascii_u64(f, width, terminator, 10)
+}
+
+/// Like [ascii_u64_dec], but for hexadecimal, in either case.
+#[inline]
+pub fn ascii_u64_hex<F>(f: &mut F, width: Option<usize>, terminator: Option<u8>) -> ParseResult<u64>
+where
+    F: Read + Seek,
+{
+    ascii_u64(f, width, terminator, 16)
+}
+
+/// Like [ascii_u64_dec], but for octal, such as the numeric fields of tar headers.
+#[inline]
+pub fn ascii_u64_octal<F>(
+    f: &mut F,
+    width: Option<usize>,
+    terminator: Option<u8>,
+) -> ParseResult<u64>
+where
+    F: Read + Seek,
+{
+    ascii_u64(f, width, terminator, 8)
+}
+
+fn ascii_u64<F>(
+    f: &mut F,
+    width: Option<usize>,
+    terminator: Option<u8>,
+    radix: u32,
+) -> ParseResult<u64>
+where
+    F: Read + Seek,
+{
+    let field = match (width, terminator) {
+        (Some(width), terminator) => {
+            let mut field = take(f, width)?;
+            if let Some(end) = terminator.and_then(|t| field.iter().position(|x| *x == t)) {
+                field.truncate(end);
+            }
+            field
+        }
+        (None, Some(terminator)) => take_until(f, terminator, false)?,
+        (None, None) => {
+            let mut field = Vec::new();
+            let mut buffer = [0u8];
+            while read_if_possible(&mut *f, &mut buffer)? != 0 {
+                let value = buffer[0];
+                let leading = field.iter().all(|x| *x == b' ');
+                if (value == b' ' && leading) || (value as char).is_digit(radix) {
+                    field.push(value);
+                } else {
+                    f.seek(SeekFrom::Current(-1))?;
+                    break;
+                }
+            }
+            field
+        }
+    };
+
+    let digits = field
+        .iter()
+        .position(|x| *x != b' ')
+        .map_or(&[][..], |start| &field[start..]);
+    let digits = &digits[..digits.iter().rposition(|x| *x != b' ').map_or(0, |x| x + 1)];
+    if digits.is_empty() {
+        return Err(ParseError::InvalidByte);
+    }
+
+    let mut value: u64 = 0;
+    for digit in digits {
+        let digit = (*digit as char)
+            .to_digit(radix)
+            .ok_or(ParseError::InvalidByte)?;
+        value = value
+            .checked_mul(u64::from(radix))
+            .and_then(|x| x.checked_add(u64::from(digit)))
+            .ok_or(ParseError::IntegerOverflow)?;
+    }
+    Ok(value)
+}
+
+/// Tags up to this long are read into a stack buffer, rather than allocating
+const TAG_STACK: usize = 32;
+
 /// Expect certain bytes. Does not return them.
+/// The bytes are read all at once, and the error is `ParseError::TagMismatch` with the index of
+/// the first one that differs.
+/// Note: on a mismatch, [f] is left after the whole tag, not after the first differing byte. If
+/// [f] ends before the tag does, then it is an io error, even if the bytes before differed.
 pub fn tag<F, X>(f: &mut F, data: &[X]) -> ParseResult<()>
 where
     F: Read,
     X: PartialEq<u8>,
 {
-    for x in data.iter() {
-        let value = single(f)?;
-        if x != &value {
-            return Err(ParseError::InvalidByte);
+    let mut buf = TagBuffer::new(data.len());
+    f.read_exact(buf.as_mut())?;
+    tag_mismatch(data, buf.as_mut())
+}
+
+/// Buffer of a tag's length to read it into, which is on the stack unless the tag is longer
+/// than [TAG_STACK].
+pub(crate) enum TagBuffer {
+    Stack([u8; TAG_STACK], usize),
+    Heap(Vec<u8>),
+}
+impl TagBuffer {
+    #[inline]
+    pub(crate) fn new(len: usize) -> Self {
+        if len <= TAG_STACK {
+            TagBuffer::Stack([0; TAG_STACK], len)
+        } else {
+            TagBuffer::Heap(vec![0; len])
+        }
+    }
+
+    #[inline]
+    pub(crate) fn as_mut(&mut self) -> &mut [u8] {
+        match self {
+            TagBuffer::Stack(buf, len) => &mut buf[..*len],
+            TagBuffer::Heap(buf) => buf,
         }
     }
-    Ok(())
+}
+
+/// Errors with `ParseError::TagMismatch` at the first index where [found] isn't [data].
+pub(crate) fn tag_mismatch<X>(data: &[X], found: &[u8]) -> ParseResult<()>
+where
+    X: PartialEq<u8>,
+{
+    match data.iter().zip(found).position(|(x, value)| x != value) {
+        Some(index) => Err(ParseError::TagMismatch(index)),
+        None => Ok(()),
+    }
+}
+
+/// Returns whether the upcoming bytes are [data], without consuming them.
+/// If [f] ends before there are as many bytes as in [data], then they don't match.
+pub fn peek_tag<F, X>(f: &mut PeekReader<F>, data: &[X]) -> ParseResult<bool>
+where
+    F: Read,
+    X: PartialEq<u8>,
+{
+    let mut buf = vec![0u8; data.len()];
+    let amount = f.peek(&mut buf)?;
+    Ok(amount == data.len() && data.iter().zip(buf.iter()).all(|(x, value)| x == value))
+}
+
+/// Returns the next byte without consuming it, or `None` if [f] has ended.
+#[inline]
+pub fn peek_byte<F>(f: &mut PeekReader<F>) -> ParseResult<Option<u8>>
+where
+    F: Read,
+{
+    let mut output = [0u8];
+    Ok(match f.peek(&mut output)? {
+        0 => None,
+        _ => Some(output[0]),
+    })
+}
+
+/// Reads [len] bytes, and checks that their CRC-32 is [expected].
+/// Errors with `ParseError::ChecksumMismatch` if it isn't.
+#[inline]
+pub fn verify_crc32<F>(f: &mut F, expected: u32, len: u64) -> ParseResult<()>
+where
+    F: Read,
+{
+    verify_checksum(f, Crc32::new(), u64::from(expected), len)
+}
+
+/// Reads [len] bytes through [hasher], and checks that the result is [expected].
+/// Errors with `ParseError::ChecksumMismatch` if it isn't.
+pub fn verify_checksum<F, H>(f: &mut F, hasher: H, expected: u64, len: u64) -> ParseResult<()>
+where
+    F: Read,
+    H: Checksum,
+{
+    let mut reader = HashingReader::new(f, hasher);
+    skip(&mut reader, len)?;
+    let (_, hasher) = reader.into_parts();
+    let found = hasher.finalize();
+    if found == expected {
+        Ok(())
+    } else {
+        Err(ParseError::ChecksumMismatch(expected, found))
+    }
 }
 
 // Internal utilities, since const generics don't exist
@@ -262,28 +935,302 @@ where
     data
 }
 
-// TODO: should this take a template for what error it returns.. that would complicate things
-// TODO: It would be nice to allow non-Seek types. At the very least, forward seek can be
-//   ''implemented'' by reading data and throwing it away.
-//     Most parsing probably doesn't need arbitrarty seeking.
-pub trait Parse<F, D = ()>: Sized + 'static
+/// Returns how many bytes of padding are needed after [position] to reach a multiple of
+/// [alignment] bytes from [base].
+/// Errors if [alignment] is zero or not a power of two, or [position] is before [base].
+fn alignment_padding(position: u64, alignment: u64, base: u64) -> ParseResult<u64> {
+    if !alignment.is_power_of_two() {
+        return Err(ParseError::InvalidAlignment(alignment));
+    }
+    let offset = position.checked_sub(base).ok_or_else(|| {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "position {} is before the alignment base {}",
+                position, base
+            ),
+        )
+    })?;
+    Ok(offset.wrapping_neg() & (alignment - 1))
+}
+
+/// Skips forward to the next multiple of [alignment] bytes from [base], returning how many bytes
+/// were skipped. Does nothing if already aligned.
+/// Errors with `ParseError::InvalidAlignment` if [alignment] is zero or not a power of two.
+/// See [align_to_zeroed] for checking that the padding is zero.
+pub fn align_to<F>(f: &mut F, alignment: u64, base: u64) -> ParseResult<u64>
 where
-    F: Read,
+    F: Read + Seek,
 {
-    fn parse<'a>(f: &'a mut F, d: D) -> ParseResult<Self>;
+    let padding = alignment_padding(f.stream_position()?, alignment, base)?;
+    skip_seek(&mut *f, padding)?;
+    Ok(padding)
 }
 
-impl<F: Read> Parse<F> for u8 {
-    fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
-        Ok(u8::from_le_bytes([single(f)?]))
-    }
-}
+/// Like [align_to], but reads the padding, erroring with `ParseError::NonZeroPadding` if any of
+/// it isn't zero.
+pub fn align_to_zeroed<F>(f: &mut F, alignment: u64, base: u64) -> ParseResult<u64>
+where
+    F: Read + Seek,
+{
+    let padding = alignment_padding(f.stream_position()?, alignment, base)?;
+    for index in 0..padding {
+        if single(f)? != 0 {
+            // Only truncates for alignments over 4 GiB on 32-bit targets
+            return Err(ParseError::NonZeroPadding(index as usize));
+        }
+    }
+    Ok(padding)
+}
+
+/// Seeks to [position] (from the start of the stream), parses [T] there, and then seeks back to
+/// where [f] was, even if the parse failed.
+/// If both the parse and the seek back fail, the parse's error is returned, since it is the more
+/// informative of the two. If only the seek back fails, then its error is returned.
+pub fn parse_at<T, F, D>(f: &mut F, position: u64, d: D) -> ParseResult<T>
+where
+    F: Read + Seek,
+    T: Parse<F, D>,
+{
+    let initial_position = f.stream_position()?;
+    let data = match f.seek(SeekFrom::Start(position)) {
+        Ok(_) => T::parse(f, d),
+        Err(err) => Err(err.into()),
+    };
+    let restored = f.seek(SeekFrom::Start(initial_position));
+    let data = data?;
+    restored?;
+    Ok(data)
+}
+
+/// Takes [byte_len] bytes, and checks that they're valid UTF-8.
+/// Errors with `ParseError::InvalidByte` if they aren't.
+#[inline]
+pub fn utf8_str<F>(f: &mut F, byte_len: usize) -> ParseResult<String>
+where
+    F: Read,
+{
+    String::from_utf8(take(f, byte_len)?).map_err(|_| ParseError::InvalidByte)
+}
+
+/// Like [utf8_str], but replaces invalid UTF-8 with `U+FFFD`, rather than erroring.
+#[inline]
+pub fn utf8_str_lossy<F>(f: &mut F, byte_len: usize) -> ParseResult<String>
+where
+    F: Read,
+{
+    Ok(String::from_utf8_lossy(&take(f, byte_len)?).into_owned())
+}
+
+/// Parses [P] if [cond] holds, and otherwise gives `None` without reading anything.
+/// Useful for fields that are only present in some versions of a format.
+#[inline]
+pub fn parse_if<F, P, D>(f: &mut F, d: D, cond: bool) -> ParseResult<Option<P>>
+where
+    F: Read,
+    P: Parse<F, D>,
+{
+    if cond {
+        P::parse(f, d).map(Some)
+    } else {
+        Ok(None)
+    }
+}
+
+/// Parses [P], and checks it with [pred], such as for a count that must be nonzero.
+/// Errors with `ParseError::ValidationFailed(name)` if the check fails.
+#[inline]
+pub fn verify<F, P, D, V>(f: &mut F, d: D, pred: V, name: &'static str) -> ParseResult<P>
+where
+    F: Read,
+    P: Parse<F, D>,
+    V: Fn(&P) -> bool,
+{
+    let value = P::parse(f, d)?;
+    if pred(&value) {
+        Ok(value)
+    } else {
+        Err(ParseError::ValidationFailed(name))
+    }
+}
+
+/// Parses [P], and converts it with [conv], such as an integer into an enum with its `TryFrom`:
+/// `map_parse(f, Endian::Little, <Kind as TryFrom<u16>>::try_from)`.
+#[inline]
+pub fn map_parse<F, P, D, T, E, C>(f: &mut F, d: D, conv: C) -> ParseResult<T>
+where
+    F: Read,
+    P: Parse<F, D>,
+    E: Into<ParseError>,
+    C: Fn(P) -> Result<T, E>,
+{
+    conv(P::parse(f, d)?).map_err(Into::into)
+}
+
+/// Like [parse_peek], but for readers that don't implement Seek.
+/// Parses from [f], and then pushes everything the parse read back onto [f], so that the same
+/// bytes can be read again.
+/// The parse may read at most [max_peek] bytes. If it tries to read past that, then this errors
+/// with `ParseError::PeekLimitExceeded`, and the stream is still left as it was.
+pub fn parse_peek_buffered<T, D, F>(f: &mut PeekReader<F>, d: D, max_peek: usize) -> ParseResult<T>
+where
+    F: Read,
+    T: for<'r> Parse<PeekLimiter<'r, F>, D>,
+{
+    let mut limiter = PeekLimiter::new(f, max_peek);
+    let data = T::parse(&mut limiter, d);
+    let exceeded = limiter.exceeded();
+    limiter.restore();
+
+    if exceeded {
+        Err(ParseError::PeekLimitExceeded(max_peek))
+    } else {
+        data
+    }
+}
+
+// TODO: should this take a template for what error it returns.. that would complicate things
+/// `#[derive(Parse)]`, see the eyeutil-derive crate for its attributes.
+#[cfg(feature = "derive")]
+pub use eyeutil_derive::Parse;
+
+// TODO: It would be nice to allow non-Seek types. At the very least, forward seek can be
+//   ''implemented'' by reading data and throwing it away.
+//     Most parsing probably doesn't need arbitrarty seeking.
+pub trait Parse<F, D = ()>: Sized + 'static
+where
+    F: Read,
+{
+    fn parse(f: &mut F, d: D) -> ParseResult<Self>;
+}
+
+/// Consumes nothing, for "no payload" in generic framing code.
+impl<F: Read, D> Parse<F, D> for () {
+    #[inline]
+    fn parse(_f: &mut F, _d: D) -> ParseResult<Self> {
+        Ok(())
+    }
+}
+
+macro_rules! impl_parse_pointer {
+    ($($ptr:ident),*) => {
+        $(
+            impl<F, D, T> Parse<F, D> for $ptr<T>
+            where
+                F: Read,
+                T: Parse<F, D>,
+            {
+                #[inline]
+                fn parse(f: &mut F, d: D) -> ParseResult<Self> {
+                    T::parse(f, d).map($ptr::new)
+                }
+            }
+        )*
+    };
+}
+impl_parse_pointer!(Box, Rc, Arc);
+
+/// Parses each element in order, building the array in place rather than going through a Vec.
+/// If an element fails to parse, the elements before it are dropped, and the reader is left
+/// after whatever was read.
+impl<F, D, T, const N: usize> Parse<F, D> for [T; N]
+where
+    F: Read,
+    T: Parse<F, D>,
+    D: Clone,
+{
+    fn parse(f: &mut F, d: D) -> ParseResult<Self> {
+        let mut guard = ArrayGuard::<T, N>::new();
+        while guard.initialized < N {
+            let value = T::parse(f, d.clone())?;
+            guard.array[guard.initialized] = MaybeUninit::new(value);
+            guard.initialized += 1;
+        }
+        Ok(guard.into_array())
+    }
+}
+
+/// An array being filled in from the start, which drops the elements that were filled in if
+/// it is dropped before being finished (such as by an error or panic partway through).
+struct ArrayGuard<T, const N: usize> {
+    array: [MaybeUninit<T>; N],
+    /// The number of elements at the start of [array] that are initialized
+    initialized: usize,
+}
+impl<T, const N: usize> ArrayGuard<T, N> {
+    #[inline]
+    fn new() -> Self {
+        ArrayGuard {
+            // Safety: an array of `MaybeUninit` doesn't need to be initialized
+            array: unsafe { MaybeUninit::uninit().assume_init() },
+            initialized: 0,
+        }
+    }
+
+    /// Note: must only be called once every element is initialized.
+    #[inline]
+    fn into_array(mut self) -> [T; N] {
+        debug_assert_eq!(self.initialized, N);
+        // Safety: every element is initialized, and `MaybeUninit<T>` has the same layout as
+        // `T`. The elements are now owned by the returned array, so the guard must not drop
+        // them.
+        let array = unsafe { std::ptr::read(self.array.as_ptr() as *const [T; N]) };
+        self.initialized = 0;
+        array
+    }
+}
+impl<T, const N: usize> Drop for ArrayGuard<T, N> {
+    fn drop(&mut self) {
+        for value in self.array[..self.initialized].iter_mut() {
+            // Safety: the first `initialized` elements are initialized, and are only dropped
+            // here.
+            unsafe { std::ptr::drop_in_place(value.as_mut_ptr()) };
+        }
+    }
+}
+
+impl<F: Read> Parse<F> for u8 {
+    fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+        Ok(u8::from_le_bytes([single(f)?]))
+    }
+}
 
 impl<F: Read> Parse<F> for i8 {
     fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
         Ok(i8::from_le_bytes([single(f)?]))
     }
 }
+/// Reads a UTF-8 encoded scalar value, of 1 to 4 bytes depending on the first.
+/// Errors with `ParseError::InvalidByte` if the encoding is malformed, overlong, or a surrogate.
+impl<F: Read> Parse<F> for char {
+    fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+        let mut data = [0u8; 4];
+        data[0] = single(f)?;
+        let len = utf8_len(data[0]).ok_or(ParseError::InvalidByte)?;
+        f.read_exact(&mut data[1..len])?;
+        utf8_char(&data[..len])
+    }
+}
+/// Returns the length of the UTF-8 encoding starting with [first], or `None` if it can't start
+/// one.
+#[inline]
+pub(crate) fn utf8_len(first: u8) -> Option<usize> {
+    match first {
+        0x00..=0x7F => Some(1),
+        0xC2..=0xDF => Some(2),
+        0xE0..=0xEF => Some(3),
+        0xF0..=0xF4 => Some(4),
+        _ => None,
+    }
+}
+/// Decodes the single scalar value encoded by [data], which is `utf8_len` bytes long.
+#[inline]
+pub(crate) fn utf8_char(data: &[u8]) -> ParseResult<char> {
+    std::str::from_utf8(data)
+        .ok()
+        .and_then(|text| text.chars().next())
+        .ok_or(ParseError::InvalidByte)
+}
 impl<F: Read> Parse<F, Endian> for u16 {
     fn parse(f: &mut F, endian: Endian) -> ParseResult<Self> {
         let data = take_2(f)?;
@@ -357,28 +1304,192 @@ impl<F: Read> Parse<F, Endian> for f64 {
     }
 }
 
-// TODO: add tests for impl_parse_field and impl_parse
+/// Extension trait for reading values with the endianness in the method name, rather than
+/// passed as data: `f.read_u32_le()?` instead of `u32::parse(&mut f, Endian::Little)?`.
+/// These all defer to the `Parse` impls.
+pub trait ReadExt: Read + Sized {
+    #[inline]
+    fn read_u8(&mut self) -> ParseResult<u8> {
+        u8::parse(self, ())
+    }
+
+    #[inline]
+    fn read_i8(&mut self) -> ParseResult<i8> {
+        i8::parse(self, ())
+    }
+
+    #[inline]
+    fn read_u16_le(&mut self) -> ParseResult<u16> {
+        u16::parse(self, Endian::Little)
+    }
+
+    #[inline]
+    fn read_u16_be(&mut self) -> ParseResult<u16> {
+        u16::parse(self, Endian::Big)
+    }
+
+    #[inline]
+    fn read_i16_le(&mut self) -> ParseResult<i16> {
+        i16::parse(self, Endian::Little)
+    }
+
+    #[inline]
+    fn read_i16_be(&mut self) -> ParseResult<i16> {
+        i16::parse(self, Endian::Big)
+    }
+
+    #[inline]
+    fn read_u32_le(&mut self) -> ParseResult<u32> {
+        u32::parse(self, Endian::Little)
+    }
+
+    #[inline]
+    fn read_u32_be(&mut self) -> ParseResult<u32> {
+        u32::parse(self, Endian::Big)
+    }
+
+    #[inline]
+    fn read_i32_le(&mut self) -> ParseResult<i32> {
+        i32::parse(self, Endian::Little)
+    }
+
+    #[inline]
+    fn read_i32_be(&mut self) -> ParseResult<i32> {
+        i32::parse(self, Endian::Big)
+    }
+
+    #[inline]
+    fn read_u64_le(&mut self) -> ParseResult<u64> {
+        u64::parse(self, Endian::Little)
+    }
+
+    #[inline]
+    fn read_u64_be(&mut self) -> ParseResult<u64> {
+        u64::parse(self, Endian::Big)
+    }
+
+    #[inline]
+    fn read_i64_le(&mut self) -> ParseResult<i64> {
+        i64::parse(self, Endian::Little)
+    }
+
+    #[inline]
+    fn read_i64_be(&mut self) -> ParseResult<i64> {
+        i64::parse(self, Endian::Big)
+    }
+
+    #[inline]
+    fn read_f32_le(&mut self) -> ParseResult<f32> {
+        f32::parse(self, Endian::Little)
+    }
+
+    #[inline]
+    fn read_f32_be(&mut self) -> ParseResult<f32> {
+        f32::parse(self, Endian::Big)
+    }
+
+    #[inline]
+    fn read_f64_le(&mut self) -> ParseResult<f64> {
+        f64::parse(self, Endian::Little)
+    }
+
+    #[inline]
+    fn read_f64_be(&mut self) -> ParseResult<f64> {
+        f64::parse(self, Endian::Big)
+    }
+
+    /// Reads a null-terminated string, consuming the terminator.
+    #[inline]
+    fn read_zstring(&mut self) -> ParseResult<ZString> {
+        ZString::parse(self, ())
+    }
+
+    #[inline]
+    fn read_exact_n<const N: usize>(&mut self) -> ParseResult<[u8; N]> {
+        take_n(self)
+    }
+}
+impl<F: Read> ReadExt for F {}
+
+/// Parses one field for `impl_parse!`, with the field's name as the context of any error.
 #[macro_export]
 macro_rules! impl_parse_field {
     ($name:ident : l : $typ:ty; $input:expr) => {
-        let $name = <$typ>::parse($input, Endian::Little)?;
+        let $name = $crate::parse::ParseResultExt::context(
+            <$typ as $crate::parse::Parse<_, _>>::parse($input, $crate::Endian::Little),
+            stringify!($name),
+        )?;
     };
     ($name:ident : b : $typ:ty; $input:expr) => {
-        let $name = <$typ>::parse($input, Endian::Big)?;
+        let $name = $crate::parse::ParseResultExt::context(
+            <$typ as $crate::parse::Parse<_, _>>::parse($input, $crate::Endian::Big),
+            stringify!($name),
+        )?;
     };
     // No data
     ($name:ident : u : $typ:ty; $input:expr) => {
-        let $name = <$typ>::parse($input, ())?;
+        let $name = $crate::parse::ParseResultExt::context(
+            <$typ as $crate::parse::Parse<_, _>>::parse($input, ()),
+            stringify!($name),
+        )?;
+    };
+    // Explicit data, such as `(Endian::Little, AddrWidth::W32)`
+    ($name:ident : ($($data:tt)*) : $typ:ty; $input:expr) => {
+        let $name = $crate::parse::ParseResultExt::context(
+            <$typ as $crate::parse::Parse<_, _>>::parse($input, ($($data)*)),
+            stringify!($name),
+        )?;
+    };
+    // With a `ParseCtx`: the ctx itself
+    ($name:ident : c : $typ:ty; $input:expr, $ctx:expr) => {
+        let $name = $crate::parse::ParseResultExt::context(
+            <$typ as $crate::parse::Parse<_, _>>::parse($input, $ctx.clone()),
+            stringify!($name),
+        )?;
+    };
+    // With a `ParseCtx`: the ctx's endianness
+    ($name:ident : e : $typ:ty; $input:expr, $ctx:expr) => {
+        let $name = $crate::parse::ParseResultExt::context(
+            <$typ as $crate::parse::Parse<_, _>>::parse($input, $ctx.endian),
+            stringify!($name),
+        )?;
+    };
+    // The impl's own data, for `impl_parse!(generic data ...)`
+    ($name:ident : d : $typ:ty; $input:expr, $data:expr) => {
+        let $name = $crate::parse::ParseResultExt::context(
+            <$typ as $crate::parse::Parse<_, _>>::parse($input, $data.clone()),
+            stringify!($name),
+        )?;
+    };
+    ($name:ident : $e:tt : $typ:ty; $input:expr, $ctx:expr) => {
+        $crate::impl_parse_field!($name : $e : $typ; $input);
     };
 }
 
+/// Implements `Parse<F, ()>` by parsing each field in order. Takes the same field list as
+/// `impl_writable!`, so the two can be kept in sync:
+/// `impl_parse!(Header, [kind: u: u8, length: l: u32]);`
+/// For newtypes: `impl_parse!(newtype Id, id: l: u32);`
+/// Implementing `Parse<F, ParseCtx<U>>` instead, fields can also be `c` to be given the ctx,
+/// or `e` to be given the ctx's endianness:
+/// `impl_parse!(ctx Header, [kind: u: u8, length: c: u32, name: e: ZString16]);`
+/// For generic types, the generics (with their bounds) are given in brackets, optionally
+/// followed by a where clause in brackets. The bounds can name the reader as `F`:
+/// `impl_parse!(generic [T: Parse<F, Endian>] Tagged<T>, [kind: u: u8, value: l: T]);`
+/// With `generic data`, the impl is `Parse<F, D>` for any `D: Clone`, and fields can be `d` to
+/// be given a clone of it, so that the data can be passed through to the generic fields:
+/// `impl_parse!(generic data [T] Pair<T> where [T: Parse<F, D>], [a: d: T, b: d: T]);`
+/// An error from parsing a field is wrapped in `ParseError::Context`, with the field's name.
+/// Note: the impl requires `F: Read + Seek`, so that fields which seek can be used.
 #[macro_export]
 macro_rules! impl_parse {
-    ($on:ty, [$($name:ident : $e:ident : $typ:ty),*]) => {
-        impl $crate::parse::Parse< ()> for $on {
-            fn parse(f: &mut F, _d: ()) -> $crate::parse::ParseResult<Self>
-            where
-                F: std::io::Seek + std::io::Read {
+    (generic [$($gen:tt)*] $on:ty $(where [$($bound:tt)*])?, [$($name:ident : $e:tt : $typ:ty),*]) => {
+        impl<F, $($gen)*> $crate::parse::Parse<F, ()> for $on
+        where
+            F: std::io::Read + std::io::Seek,
+            $($($bound)*)?
+        {
+            fn parse(f: &mut F, _d: ()) -> $crate::parse::ParseResult<Self> {
                 $(
                     $crate::impl_parse_field!($name : $e : $typ; f);
                 )*
@@ -388,11 +1499,64 @@ macro_rules! impl_parse {
             }
         }
     };
-    (newtype $on:ty, $name:ident : $e:ident : $typ:ty) => {
-        impl $crate::parse::Parse< ()> for $on {
-            fn parse(f: &mut F, _d: ()) -> $crate::parse::ParseResult<Self>
-            where
-                F: std::io::Seek + std::io::Read {
+    (generic data [$($gen:tt)*] $on:ty $(where [$($bound:tt)*])?, [$($name:ident : $e:tt : $typ:ty),*]) => {
+        impl<F, D, $($gen)*> $crate::parse::Parse<F, D> for $on
+        where
+            F: std::io::Read + std::io::Seek,
+            D: Clone,
+            $($($bound)*)?
+        {
+            #[allow(unused_variables)]
+            fn parse(f: &mut F, d: D) -> $crate::parse::ParseResult<Self> {
+                $(
+                    $crate::impl_parse_field!($name : $e : $typ; f, d);
+                )*
+                Ok(Self {
+                    $($name),*
+                })
+            }
+        }
+    };
+    (ctx $on:ty, [$($name:ident : $e:tt : $typ:ty),*]) => {
+        impl<F, U> $crate::parse::Parse<F, $crate::ctx::ParseCtx<U>> for $on
+        where
+            F: std::io::Read + std::io::Seek,
+            U: Clone,
+        {
+            fn parse(
+                f: &mut F,
+                ctx: $crate::ctx::ParseCtx<U>,
+            ) -> $crate::parse::ParseResult<Self> {
+                $(
+                    $crate::impl_parse_field!($name : $e : $typ; f, ctx);
+                )*
+                Ok(Self {
+                    $($name),*
+                })
+            }
+        }
+    };
+    ($on:ty, [$($name:ident : $e:tt : $typ:ty),*]) => {
+        impl<F> $crate::parse::Parse<F, ()> for $on
+        where
+            F: std::io::Read + std::io::Seek,
+        {
+            fn parse(f: &mut F, _d: ()) -> $crate::parse::ParseResult<Self> {
+                $(
+                    $crate::impl_parse_field!($name : $e : $typ; f);
+                )*
+                Ok(Self {
+                    $($name),*
+                })
+            }
+        }
+    };
+    (newtype $on:ty, $name:ident : $e:tt : $typ:ty) => {
+        impl<F> $crate::parse::Parse<F, ()> for $on
+        where
+            F: std::io::Read + std::io::Seek,
+        {
+            fn parse(f: &mut F, _d: ()) -> $crate::parse::ParseResult<Self> {
                 $crate::impl_parse_field!($name: $e : $typ; f);
                 Ok(Self($name))
             }
@@ -400,6 +1564,50 @@ macro_rules! impl_parse {
     };
 }
 
+/// Like `impl_parse!(ctx ...)`, for formats where later versions add fields. Implements
+/// `Parse<F, ParseCtx<V>>`, with the version [V] taken from the ctx's user data and bound to
+/// the given name.
+/// Fields can be guarded by a condition on the version, in which case the struct's field is an
+/// `Option` of the listed type, which is only parsed if the condition holds:
+/// `impl_parse_versioned!(Header, version: u32, [kind: c: u8, [if version >= 2] extra: c: u32]);`
+/// The version can be any type that the conditions can compare against, such as
+/// `version::Version` with `[if version >= Version::new(1, 2)]`.
+/// Writing mirrors this, as `Option` fields write nothing when they're `None`.
+#[macro_export]
+macro_rules! impl_parse_versioned {
+    ($on:ty, $version:ident : $vtyp:ty, [$($([if $cond:expr])? $name:ident : $e:tt : $typ:ty),*]) => {
+        impl<F> $crate::parse::Parse<F, $crate::ctx::ParseCtx<$vtyp>> for $on
+        where
+            F: std::io::Read + std::io::Seek,
+        {
+            fn parse(
+                f: &mut F,
+                ctx: $crate::ctx::ParseCtx<$vtyp>,
+            ) -> $crate::parse::ParseResult<Self> {
+                #[allow(unused_variables)]
+                let $version: $vtyp = ctx.user.clone();
+                $(
+                    $crate::impl_parse_versioned!(@field [$($cond)?] $name : $e : $typ; f, ctx);
+                )*
+                Ok(Self {
+                    $($name),*
+                })
+            }
+        }
+    };
+    (@field [] $name:ident : $e:tt : $typ:ty; $input:expr, $ctx:expr) => {
+        $crate::impl_parse_field!($name : $e : $typ; $input, $ctx);
+    };
+    (@field [$cond:expr] $name:ident : $e:tt : $typ:ty; $input:expr, $ctx:expr) => {
+        let $name = if $cond {
+            $crate::impl_parse_field!($name : $e : $typ; $input, $ctx);
+            Some($name)
+        } else {
+            None
+        };
+    };
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -410,6 +1618,87 @@ mod tests {
         0x12, 0x13, 0x14,
     ];
 
+    #[test]
+    fn test_parse_error_custom() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ParseError>();
+
+        let e = ParseError::custom(std::fmt::Error);
+        assert!(matches!(e, ParseError::Custom(e) if e.is::<std::fmt::Error>()));
+        let e = ParseError::from(format!("bad chunk {}", 4));
+        assert!(matches!(e, ParseError::Custom(e) if e.to_string() == "bad chunk 4"));
+
+        // Crossing threads, as when collecting errors from workers
+        let e = std::thread::spawn(|| -> ParseResult<()> { Err("worker failed".into()) })
+            .join()
+            .unwrap()
+            .unwrap_err();
+        assert!(matches!(e, ParseError::Custom(e) if e.to_string() == "worker failed"));
+    }
+
+    /// A flag byte that must be 0 or 1
+    #[derive(Debug)]
+    struct Flag(bool);
+    impl<F: Read> Parse<F> for Flag {
+        fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+            match single(f)? {
+                0 => Ok(Flag(false)),
+                1 => Ok(Flag(true)),
+                _ => Err(ParseError::ValidationFailed("flag")),
+            }
+        }
+    }
+    #[derive(Debug)]
+    struct ContextInner {
+        length: u32,
+        visible: Flag,
+    }
+    crate::impl_parse!(ContextInner, [length: l: u32, visible: u: Flag]);
+    #[derive(Debug)]
+    struct ContextOuter {
+        kind: u8,
+        inner: ContextInner,
+    }
+    crate::impl_parse!(ContextOuter, [kind: u: u8, inner: u: ContextInner]);
+
+    #[test]
+    fn test_parse_error_context() {
+        let data = [0x1, 0x4, 0x0, 0x0, 0x0, 0x1];
+        let value = ContextOuter::parse(&mut Cursor::new(&data), ()).unwrap();
+        assert_eq!((value.kind, value.inner.length), (0x1, 0x4));
+        assert!(value.inner.visible.0);
+
+        let data = [0x1, 0x4, 0x0, 0x0, 0x0, 0x2];
+        let e = ContextOuter::parse(&mut Cursor::new(&data), ())
+            .context("Outer")
+            .unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "Outer > inner > visible > failed the check flag"
+        );
+        assert!(matches!(e.root(), ParseError::ValidationFailed("flag")));
+        match &e {
+            ParseError::Context("Outer", e) => {
+                assert!(matches!(**e, ParseError::Context("inner", _)))
+            }
+            x => panic!("expected context, got {:?}", x),
+        }
+
+        let e = ContextOuter::parse(&mut Cursor::new(&data[..3]), ()).unwrap_err();
+        assert!(matches!(e.root(), ParseError::Io(_)));
+        assert!(e.to_string().starts_with("inner > length > "));
+
+        let e = ParseError::AtOffset(0x10, Box::new(ParseError::InvalidByte));
+        assert_eq!(
+            ParseResult::<()>::Err(e)
+                .context("table")
+                .unwrap_err()
+                .to_string(),
+            "table > at offset 0x10: invalid byte"
+        );
+        assert_eq!(ParseResult::Ok(5).context("table").unwrap(), 5);
+    }
+
     #[test]
     fn test_single() {
         let mut cursor = Cursor::new(&DATA);
@@ -430,6 +1719,52 @@ mod tests {
             take(&mut cursor, 4).unwrap().as_slice(),
             &[0x5, 0x6, 0x7, 0x8]
         );
+
+        // Lengths read from the data shouldn't be trusted for allocation
+        let e = take(&mut cursor, usize::MAX).unwrap_err();
+        assert!(matches!(e, ParseError::ExpectedBytesFound(usize::MAX, 12)));
+        let e = many_count::<_, u64, _>(&mut cursor, Endian::Little, usize::MAX).unwrap_err();
+        assert!(matches!(e, ParseError::Io(e) if e.kind() == std::io::ErrorKind::UnexpectedEof));
+
+        // Longer than the up front allocation
+        let data: Vec<u8> = (0..200_000u32).map(|x| x as u8).collect();
+        let mut cursor = Cursor::new(&data);
+        assert_eq!(take(&mut cursor, data.len()).unwrap(), data);
+    }
+
+    #[test]
+    fn test_take_short_and_limited() {
+        // Exactly the rest of the data
+        let mut cursor = Cursor::new(&DATA[..8]);
+        assert_eq!(take(&mut cursor, 0).unwrap(), []);
+        assert_eq!(take(&mut cursor, 8).unwrap(), DATA[..8]);
+        assert_eq!(cursor.position(), 8);
+
+        // Says how much there was
+        let mut cursor = Cursor::new(&DATA[..8]);
+        single(&mut cursor).unwrap();
+        match take(&mut cursor, 8) {
+            Err(ParseError::ExpectedBytesFound(8, 7)) => {}
+            x => panic!("expected a short read, got {:?}", x),
+        }
+        assert_eq!(cursor.position(), 8);
+
+        // Over the limit doesn't read anything
+        let mut cursor = Cursor::new(&DATA);
+        match take_limited(&mut cursor, 9, 8) {
+            Err(ParseError::SizeLimitExceeded(9, 8)) => {}
+            x => panic!("expected the limit to be exceeded, got {:?}", x),
+        }
+        assert_eq!(cursor.position(), 0);
+        assert_eq!(take_limited(&mut cursor, 8, 8).unwrap(), DATA[..8]);
+        assert!(matches!(
+            take_limited(&mut cursor, usize::MAX, 1 << 20),
+            Err(ParseError::SizeLimitExceeded(usize::MAX, 0x100000))
+        ));
+        assert!(matches!(
+            take_limited(&mut cursor, 16, 32),
+            Err(ParseError::ExpectedBytesFound(16, 12))
+        ));
     }
 
     #[test]
@@ -441,6 +1776,42 @@ mod tests {
         tag(&mut cursor, &[0x20, 0x52]).expect_err("Expected error since invalid bytes!");
     }
 
+    #[test]
+    fn test_tag_position() {
+        let mut cursor = Cursor::new(&DATA);
+        tag(&mut cursor, b"").unwrap();
+        assert_eq!(cursor.position(), 0);
+        tag(&mut cursor, &[0x1, 0x2, 0x3]).unwrap();
+        assert_eq!(cursor.position(), 3);
+
+        // The whole tag is consumed, even though the third byte differs
+        match tag(&mut cursor, &[0x4, 0x5, 0x60, 0x7]) {
+            Err(ParseError::TagMismatch(2)) => {}
+            x => panic!("expected a mismatch at 2, got {:?}", x),
+        }
+        assert_eq!(cursor.position(), 7);
+        // Differing at the first byte
+        match tag(&mut cursor, &[0x0, 0x9]) {
+            Err(ParseError::TagMismatch(0)) => {}
+            x => panic!("expected a mismatch at 0, got {:?}", x),
+        }
+        assert_eq!(cursor.position(), 9);
+
+        // Longer than fits on the stack
+        let long: Vec<u8> = (0..100).collect();
+        let mut cursor = Cursor::new(&long);
+        tag(&mut cursor, &long).unwrap();
+        assert_eq!(cursor.position(), 100);
+        let mut wrong = long.clone();
+        wrong[90] = 0;
+        cursor.set_position(0);
+        match tag(&mut cursor, &wrong) {
+            Err(ParseError::TagMismatch(90)) => {}
+            x => panic!("expected a mismatch at 90, got {:?}", x),
+        }
+        assert_eq!(cursor.position(), 100);
+    }
+
     #[test]
     fn test_many() {
         let mut cursor = Cursor::new(&DATA);
@@ -466,6 +1837,212 @@ mod tests {
             Ok([data[0], data[1], data[2]])
         })
         .expect_err("Expected failure in dividing DATA into 3-byte chunks");
+
+        // An element that consumes nothing would otherwise loop forever
+        let mut cursor = Cursor::new(&DATA);
+        cursor.set_position(4);
+        match many(&mut cursor, (), |_f, _d| -> ParseResult<()> { Ok(()) }) {
+            Err(ParseError::NoProgress(4)) => {}
+            x => panic!("expected no progress, got {:?}", x),
+        }
+        match many_parse::<_, (), _>(&mut cursor, ()) {
+            Err(ParseError::NoProgress(4)) => {}
+            x => panic!("expected no progress, got {:?}", x),
+        }
+
+        // An element seeking past the end stops the loop
+        let mut cursor = Cursor::new(&DATA);
+        let result = many(&mut cursor, (), |f, _d| -> ParseResult<u8> {
+            let value = single(f)?;
+            f.seek(SeekFrom::Current(30))?;
+            Ok(value)
+        })
+        .unwrap();
+        assert_eq!(result, [0x1]);
+    }
+
+    #[test]
+    fn test_iter_parse() {
+        let mut cursor = Cursor::new(&DATA);
+        let collected = iter_parse::<_, u32, _>(&mut cursor, Endian::Little)
+            .unwrap()
+            .collect::<ParseResult<Vec<_>>>()
+            .unwrap();
+        cursor.set_position(0);
+        let many: Vec<u32> = many_parse(&mut cursor, Endian::Little).unwrap();
+        assert_eq!(collected, many);
+
+        // Stopping early leaves the reader after the last record
+        let mut cursor = Cursor::new(&DATA);
+        let mut iter = iter_parse::<_, u32, _>(&mut cursor, Endian::Big).unwrap();
+        assert_eq!(iter.offset(), 0);
+        assert_eq!(iter.next().unwrap().unwrap(), 0x01020304);
+        assert_eq!(iter.next().unwrap().unwrap(), 0x05060708);
+        assert_eq!(iter.offset(), 8);
+        assert_eq!(cursor.position(), 8);
+
+        // An error ends the iteration
+        let mut cursor = Cursor::new(&DATA[..18]);
+        let mut iter = iter_parse::<_, u64, _>(&mut cursor, Endian::Big).unwrap();
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_ok());
+        assert!(iter.next().unwrap().is_err());
+        assert!(iter.next().is_none());
+    }
+
+    /// Record of a 0xAA marker, and a value below 0x80
+    #[derive(Debug, PartialEq)]
+    struct Marked(u8);
+    impl<F: Read> Parse<F> for Marked {
+        fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+            tag(f, &[0xAA])?;
+            match single(f)? {
+                x if x < 0x80 => Ok(Marked(x)),
+                _ => Err(ParseError::InvalidByte),
+            }
+        }
+    }
+
+    fn resync_marked(f: &mut Cursor<&[u8]>) -> ParseResult<bool> {
+        if single(f)? == 0xAA {
+            f.seek(SeekFrom::Current(-1))?;
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    #[test]
+    fn test_many_lossy() {
+        let data: &[u8] = &[0xAA, 0x1, 0xAA, 0xFF, 0x0, 0xAA, 0x3];
+        let mut cursor = Cursor::new(data);
+        let (values, errors) =
+            many_lossy::<_, Marked, _, _>(&mut cursor, (), resync_marked).unwrap();
+        assert_eq!(values, [Marked(0x1), Marked(0x3)]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, 2);
+        assert!(matches!(errors[0].1, ParseError::InvalidByte));
+
+        // A truncated record at the end, and a resync that never moves
+        let data: &[u8] = &[0xAA, 0x1, 0xBB, 0xAA];
+        let mut cursor = Cursor::new(data);
+        let (values, errors) =
+            many_lossy::<_, Marked, _, _>(&mut cursor, (), |_f| Ok(false)).unwrap();
+        assert_eq!(values, [Marked(0x1)]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, 2);
+        assert_eq!(cursor.position(), 4);
+
+        // Errors from resyncing abort
+        let mut cursor = Cursor::new(data);
+        many_lossy::<_, Marked, _, _>(&mut cursor, (), |_f| Err(ParseError::InvalidByte))
+            .unwrap_err();
+
+        // As do io errors, even within a field's context
+        struct Failing;
+        impl Read for Failing {
+            fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+                Err(std::io::Error::new(
+                    std::io::ErrorKind::Other,
+                    "disk failure",
+                ))
+            }
+        }
+        /// Always at the start, of 8 bytes
+        impl Seek for Failing {
+            fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+                Ok(match pos {
+                    SeekFrom::End(_) => 8,
+                    _ => 0,
+                })
+            }
+        }
+        #[derive(Debug)]
+        struct Wrapped {
+            _marked: Marked,
+        }
+        crate::impl_parse!(Wrapped, [_marked: u: Marked]);
+        let e = many_lossy::<_, Wrapped, _, _>(&mut Failing, (), |_f| Ok(false)).unwrap_err();
+        assert!(matches!(e, ParseError::Context("_marked", _)));
+    }
+
+    #[test]
+    fn test_many_to_eof() {
+        // A slice can't Seek, so this only ends by running out
+        let mut reader = PeekReader::new(&DATA[..]);
+        let result: Vec<u32> = many_parse_to_eof(&mut reader, Endian::Big).unwrap();
+        assert_eq!(
+            result,
+            [0x01020304, 0x05060708, 0x090a0b0c, 0x0d0e0f10, 0x11121314]
+        );
+        let mut reader = PeekReader::new(&[][..]);
+        let result: Vec<u32> = many_parse_to_eof(&mut reader, Endian::Big).unwrap();
+        assert!(result.is_empty());
+
+        // A trailing partial record is still an error
+        let mut reader = PeekReader::new(&DATA[..18]);
+        match many_parse_to_eof::<_, u32, _>(&mut reader, Endian::Big) {
+            Err(ParseError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {}
+            x => panic!("expected unexpected eof, got {:?}", x),
+        }
+        let mut reader = PeekReader::new(&DATA[..]);
+        many_to_eof(&mut reader, (), |f, _d| -> ParseResult<[u8; 3]> {
+            take_n(f)
+        })
+        .expect_err("Expected failure in dividing DATA into 3-byte chunks");
+
+        let mut reader = PeekReader::new(&DATA[..]);
+        single(&mut reader).unwrap();
+        match many_parse_to_eof::<_, (), _>(&mut reader, ()) {
+            Err(ParseError::NoProgress(1)) => {}
+            x => panic!("expected no progress, got {:?}", x),
+        }
+    }
+
+    /// Record with a continuation flag in its high bit
+    #[derive(Debug, PartialEq)]
+    struct Link(u8);
+    impl<F: Read> Parse<F> for Link {
+        fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+            Ok(Link(single(f)?))
+        }
+    }
+    impl Link {
+        fn has_next(&self) -> bool {
+            self.0 & 0x80 != 0
+        }
+    }
+
+    #[test]
+    fn test_many_while() {
+        let data = [0x81u8, 0x82, 0x03, 0x84, 0x05];
+        let mut cursor = Cursor::new(&data);
+        let links: Vec<Link> = many_while(&mut cursor, (), Link::has_next).unwrap();
+        assert_eq!(links, [Link(0x81), Link(0x82), Link(0x03)]);
+        assert_eq!(cursor.position(), 3);
+        let links: Vec<Link> = many_while(&mut cursor, (), Link::has_next).unwrap();
+        assert_eq!(links, [Link(0x84), Link(0x05)]);
+
+        let mut cursor = Cursor::new(&data);
+        let links: Vec<Link> = many_while_exclusive(&mut cursor, (), Link::has_next).unwrap();
+        assert_eq!(links, [Link(0x81), Link(0x82)]);
+        // The terminal record is left to be read again
+        assert_eq!(cursor.position(), 2);
+        assert_eq!(single(&mut cursor).unwrap(), 0x03);
+
+        // First record is terminal
+        let data = [0x01u8, 0x82];
+        let mut cursor = Cursor::new(&data);
+        let links: Vec<Link> = many_while(&mut cursor, (), Link::has_next).unwrap();
+        assert_eq!(links, [Link(0x01)]);
+        let mut cursor = Cursor::new(&data);
+        let links: Vec<Link> = many_while_exclusive(&mut cursor, (), Link::has_next).unwrap();
+        assert!(links.is_empty());
+        assert_eq!(cursor.position(), 0);
+
+        // The chain runs off the end of the input
+        let mut cursor = Cursor::new(&[0x81u8, 0x82][..]);
+        many_while::<_, Link, _, _>(&mut cursor, (), Link::has_next).unwrap_err();
     }
 
     #[test]
@@ -487,4 +2064,714 @@ mod tests {
         assert_eq!(value, 0x0102);
         assert_eq!(stream_position(&mut cursor).unwrap(), 0);
     }
+
+    #[test]
+    fn test_relocate() {
+        let mut cursor = Cursor::new(&DATA);
+        single(&mut cursor).unwrap();
+        let mut output = Vec::new();
+        assert_eq!(relocate(&mut cursor, &mut output, 16).unwrap(), 16);
+        assert_eq!(output.as_slice(), &DATA[1..17]);
+        assert_eq!(stream_position(&mut cursor).unwrap(), 17);
+
+        let mut output = Vec::new();
+        assert_eq!(relocate(&mut cursor, &mut output, 0).unwrap(), 0);
+        assert!(output.is_empty());
+
+        match relocate(&mut cursor, &mut output, 4) {
+            Err(ParseError::UnexpectedEOF) => {}
+            x => panic!("Expected UnexpectedEOF, got: {:?}", x),
+        }
+        assert_eq!(output.as_slice(), &DATA[17..]);
+    }
+
+    /// Lazily produces [len] bytes of a repeating pattern, without storing them.
+    struct PatternReader {
+        position: u64,
+        len: u64,
+    }
+    impl PatternReader {
+        const PATTERN: [u8; 256] = {
+            let mut pattern = [0u8; 256];
+            let mut i = 0;
+            while i < 256 {
+                pattern[i] = i as u8;
+                i += 1;
+            }
+            pattern
+        };
+    }
+    impl Read for PatternReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let remaining = self.len.saturating_sub(self.position);
+            let amount = (buf.len() as u64).min(remaining) as usize;
+            let mut written = 0;
+            while written < amount {
+                let offset = ((self.position + written as u64) % 256) as usize;
+                let count = (256 - offset).min(amount - written);
+                buf[written..written + count]
+                    .copy_from_slice(&Self::PATTERN[offset..offset + count]);
+                written += count;
+            }
+            self.position += amount as u64;
+            Ok(amount)
+        }
+    }
+    impl Seek for PatternReader {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.position = match pos {
+                SeekFrom::Start(x) => x,
+                SeekFrom::Current(x) => (self.position as i64 + x) as u64,
+                SeekFrom::End(x) => (self.len as i64 + x) as u64,
+            };
+            Ok(self.position)
+        }
+    }
+
+    #[test]
+    fn test_relocate_beyond_u32() {
+        // Just past what a 32-bit length could hold
+        const LEN: u64 = u32::MAX as u64 + 4097;
+        let mut reader = PatternReader {
+            position: 0,
+            len: LEN + 3,
+        };
+        tag(&mut reader, &[0x0, 0x1, 0x2]).unwrap();
+        let mut slice = crate::slice::InputSlice::new(&mut reader, 3..LEN + 3).unwrap();
+        assert_eq!(slice.stream_len().unwrap(), LEN);
+
+        let mut output = crate::CountingWriter::new(std::io::sink());
+        assert_eq!(relocate(&mut slice, &mut output, LEN).unwrap(), LEN);
+        assert_eq!(output.position(), LEN);
+        assert_eq!(slice.stream_position().unwrap(), LEN);
+        assert_eq!(slice.absolute_stream_position().unwrap(), LEN + 3);
+        assert_eq!(single(&mut slice).ok(), None);
+    }
+
+    #[test]
+    fn test_parse_array() {
+        let mut cursor = Cursor::new(&DATA);
+        let value = <[u16; 3]>::parse(&mut cursor, Endian::Big).unwrap();
+        assert_eq!(value, [0x0102, 0x0304, 0x0506]);
+        assert_eq!(<[u8; 2]>::parse(&mut cursor, ()).unwrap(), [0x7, 0x8]);
+        assert_eq!(<[u8; 0]>::parse(&mut cursor, ()).unwrap(), []);
+        assert_eq!(cursor.position(), 8);
+    }
+
+    #[test]
+    fn test_parse_array_drops() {
+        use std::cell::Cell;
+
+        thread_local! {
+            static DROPS: Cell<usize> = Cell::new(0);
+        }
+
+        /// Counts how many times it is dropped, and fails to parse from 0xFF
+        #[derive(Debug)]
+        struct Counted(Box<u8>);
+        impl Drop for Counted {
+            fn drop(&mut self) {
+                DROPS.with(|drops| drops.set(drops.get() + 1));
+            }
+        }
+        impl<F: Read> Parse<F> for Counted {
+            fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+                match single(f)? {
+                    0xFF => Err(ParseError::InvalidByte),
+                    x => Ok(Counted(Box::new(x))),
+                }
+            }
+        }
+
+        let mut cursor = Cursor::new([0x1, 0x2, 0xFF, 0x4]);
+        match <[Counted; 4]>::parse(&mut cursor, ()) {
+            Err(ParseError::InvalidByte) => {}
+            x => panic!("Expected InvalidByte, got: {:?}", x),
+        }
+        // The two that parsed were dropped once each, and the failing element's byte was read
+        assert_eq!(DROPS.with(Cell::get), 2);
+        assert_eq!(cursor.position(), 3);
+
+        let mut cursor = Cursor::new([0x1, 0x2, 0x3]);
+        let value = <[Counted; 3]>::parse(&mut cursor, ()).unwrap();
+        assert_eq!(DROPS.with(Cell::get), 2);
+        assert_eq!(*value[2].0, 0x3);
+        drop(value);
+        assert_eq!(DROPS.with(Cell::get), 5);
+    }
+
+    #[test]
+    fn test_align_to() {
+        let mut cursor = Cursor::new(&DATA);
+        for position in 0..=8 {
+            cursor.set_position(position);
+            let expected = (8 - position) % 4;
+            assert_eq!(align_to(&mut cursor, 4, 0).unwrap(), expected);
+            assert_eq!(cursor.position(), position + expected);
+        }
+
+        // Relative to a base
+        cursor.set_position(5);
+        assert_eq!(align_to(&mut cursor, 4, 3).unwrap(), 2);
+        assert_eq!(cursor.position(), 7);
+        assert_eq!(align_to(&mut cursor, 1, 0).unwrap(), 0);
+
+        for &alignment in [0, 3, 6].iter() {
+            match align_to(&mut cursor, alignment, 0) {
+                Err(ParseError::InvalidAlignment(x)) if x == alignment => {}
+                x => panic!("Expected InvalidAlignment, got: {:?}", x),
+            }
+        }
+        assert!(align_to(&mut cursor, 4, 8).is_err());
+        // Running out of data
+        cursor.set_position(DATA.len() as u64 - 1);
+        assert!(align_to(&mut cursor, 16, 0).is_err());
+    }
+
+    #[test]
+    fn test_align_to_zeroed() {
+        let mut cursor = Cursor::new([0x1, 0x0, 0x0, 0x0, 0x2, 0x0, 0x3, 0x0]);
+        cursor.set_position(1);
+        assert_eq!(align_to_zeroed(&mut cursor, 4, 0).unwrap(), 3);
+        assert_eq!(align_to_zeroed(&mut cursor, 4, 0).unwrap(), 0);
+        cursor.set_position(5);
+        match align_to_zeroed(&mut cursor, 4, 0) {
+            Err(ParseError::NonZeroPadding(1)) => {}
+            x => panic!("Expected NonZeroPadding, got: {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_parse_if() {
+        let mut cursor = Cursor::new(&DATA);
+        assert_eq!(parse_if::<_, u8, _>(&mut cursor, (), false).unwrap(), None);
+        assert_eq!(cursor.position(), 0);
+        let value = parse_if::<_, u16, _>(&mut cursor, Endian::Big, true).unwrap();
+        assert_eq!(value, Some(0x0102));
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Versioned {
+        kind: u8,
+        length: u32,
+        extra: Option<u16>,
+        flags: Option<u8>,
+    }
+    crate::impl_parse_versioned!(Versioned, version: u32, [
+        kind: u: u8,
+        length: c: u32,
+        [if version >= 2] extra: c: u16,
+        [if version >= 3] flags: u: u8
+    ]);
+    crate::impl_struct_data_size!(Versioned, [kind, length, extra, flags]);
+    crate::impl_writable!(Versioned, [kind: u: u8, length: l: u32, extra: l: Option<u16>, flags: u: Option<u8>]);
+
+    #[test]
+    fn test_parse_versioned() {
+        use crate::{ctx::ParseCtx, writable::Writable};
+
+        let v1 = [0x1, 0x2, 0x0, 0x0, 0x0];
+        let v3 = [0x1, 0x2, 0x0, 0x0, 0x0, 0x3, 0x0, 0x4];
+        for &(version, data) in [(1, &v1[..]), (3, &v3[..])].iter() {
+            let ctx = ParseCtx::with_user(Endian::Little, version);
+            let mut cursor = Cursor::new(data);
+            let value = Versioned::parse(&mut cursor, ctx).unwrap();
+            assert_eq!(cursor.position(), data.len() as u64);
+            assert_eq!(value.kind, 0x1);
+            assert_eq!(value.length, 0x2);
+
+            let mut output = Vec::new();
+            value.write_to(&mut output, ()).unwrap();
+            assert_eq!(output, data);
+        }
+
+        let ctx = ParseCtx::with_user(Endian::Little, 3);
+        let value = Versioned::parse(&mut Cursor::new(&v3), ctx).unwrap();
+        assert_eq!(value.extra, Some(0x3));
+        assert_eq!(value.flags, Some(0x4));
+        // Version 2 stops after the extra field
+        let ctx = ParseCtx::with_user(Endian::Little, 2);
+        let value = Versioned::parse(&mut Cursor::new(&v3), ctx).unwrap();
+        assert_eq!(value.extra, Some(0x3));
+        assert_eq!(value.flags, None);
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Kind {
+        Header,
+        Body,
+    }
+    impl std::convert::TryFrom<u16> for Kind {
+        type Error = EnumConversionError<u16>;
+        fn try_from(value: u16) -> Result<Self, Self::Error> {
+            match value {
+                1 => Ok(Kind::Header),
+                2 => Ok(Kind::Body),
+                x => Err(EnumConversionError::InvalidValue(x)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut cursor = Cursor::new(&DATA);
+        let value: u8 = verify(&mut cursor, (), |x: &u8| *x != 0, "nonzero").unwrap();
+        assert_eq!(value, 0x1);
+        match verify::<_, u8, _, _>(&mut cursor, (), |x| *x > 0x10, "above 0x10") {
+            Err(ParseError::ValidationFailed("above 0x10")) => {}
+            x => panic!("expected failed validation, got {:?}", x),
+        }
+        // The value is still consumed
+        assert_eq!(cursor.position(), 2);
+    }
+
+    #[test]
+    fn test_map_parse() {
+        use std::convert::TryFrom;
+
+        let mut cursor = Cursor::new([0x1, 0x0, 0x2, 0x0, 0x3, 0x0]);
+        assert_eq!(
+            map_parse(
+                &mut cursor,
+                Endian::Little,
+                <Kind as TryFrom<u16>>::try_from
+            )
+            .unwrap(),
+            Kind::Header
+        );
+        assert_eq!(
+            map_parse(
+                &mut cursor,
+                Endian::Little,
+                <Kind as TryFrom<u16>>::try_from
+            )
+            .unwrap(),
+            Kind::Body
+        );
+        match map_parse(
+            &mut cursor,
+            Endian::Little,
+            <Kind as TryFrom<u16>>::try_from,
+        ) {
+            Err(ParseError::InvalidEnumerationValue) => {}
+            x => panic!("expected invalid enumeration, got {:?}", x),
+        }
+
+        let mut cursor = Cursor::new([0x5]);
+        let doubled = map_parse(&mut cursor, (), |x: u8| -> ParseResult<u16> {
+            Ok(u16::from(x) * 2)
+        });
+        assert_eq!(doubled.unwrap(), 10);
+    }
+
+    #[test]
+    fn test_ascii_numbers() {
+        // The size fields of tar headers, zero and space padded
+        let mut cursor = Cursor::new(b"00000001750\0      1750 \0");
+        assert_eq!(
+            ascii_u64_octal(&mut cursor, Some(12), Some(0)).unwrap(),
+            1000
+        );
+        assert_eq!(
+            ascii_u64_octal(&mut cursor, Some(12), Some(0)).unwrap(),
+            1000
+        );
+        assert_eq!(cursor.position(), 24);
+
+        let mut cursor = Cursor::new(b"1aF;0042");
+        assert_eq!(ascii_u64_hex(&mut cursor, None, Some(b';')).unwrap(), 0x1AF);
+        assert_eq!(ascii_u64_dec(&mut cursor, Some(4), None).unwrap(), 42);
+
+        // Without a width or terminator, the first non-digit is left unread
+        let mut cursor = Cursor::new(b" 123abc");
+        assert_eq!(ascii_u64_dec(&mut cursor, None, None).unwrap(), 123);
+        assert_eq!(cursor.position(), 4);
+        let mut cursor = Cursor::new(b"123");
+        assert_eq!(ascii_u64_dec(&mut cursor, None, None).unwrap(), 123);
+        // Space-padded, with a space after the digits ending it
+        let mut cursor = Cursor::new(b"   12 34");
+        assert_eq!(ascii_u64_dec(&mut cursor, None, None).unwrap(), 12);
+        assert_eq!(cursor.position(), 5);
+        let mut cursor = Cursor::new(b"    ");
+        assert!(matches!(
+            ascii_u64_dec(&mut cursor, None, None),
+            Err(ParseError::InvalidByte)
+        ));
+
+        let mut cursor = Cursor::new(b"12x4");
+        assert!(matches!(
+            ascii_u64_dec(&mut cursor, Some(4), None),
+            Err(ParseError::InvalidByte)
+        ));
+        let mut cursor = Cursor::new(b"    ");
+        assert!(matches!(
+            ascii_u64_dec(&mut cursor, Some(4), None),
+            Err(ParseError::InvalidByte)
+        ));
+        let mut cursor = Cursor::new(b"8");
+        assert!(matches!(
+            ascii_u64_octal(&mut cursor, Some(1), None),
+            Err(ParseError::InvalidByte)
+        ));
+
+        let mut cursor = Cursor::new(b"18446744073709551615 18446744073709551616 ");
+        assert_eq!(
+            ascii_u64_dec(&mut cursor, None, Some(b' ')).unwrap(),
+            u64::MAX
+        );
+        assert!(matches!(
+            ascii_u64_dec(&mut cursor, None, Some(b' ')),
+            Err(ParseError::IntegerOverflow)
+        ));
+    }
+
+    /// Binary tree, where each child is preceded by a presence flag
+    #[derive(Debug, PartialEq)]
+    struct Node {
+        value: u8,
+        left: crate::maybe::Maybe<Box<Node>>,
+        right: crate::maybe::Maybe<Box<Node>>,
+    }
+    crate::impl_parse!(Node, [value: u: u8, left: u: crate::maybe::Maybe<Box<Node>>, right: u: crate::maybe::Maybe<Box<Node>>]);
+    crate::impl_struct_data_size!(Node, [value, left, right]);
+    crate::impl_writable!(Node, [value: u: u8, left: u: crate::maybe::Maybe<Box<Node>>, right: u: crate::maybe::Maybe<Box<Node>>]);
+
+    #[test]
+    fn test_parse_pointers() {
+        use crate::{data_size::DataSize, writable::Writable};
+        use std::{borrow::Cow, rc::Rc, sync::Arc};
+
+        // 1 with the children 2 (which has the left child 3) and 4
+        let data = [0x1, 0x1, 0x2, 0x1, 0x3, 0x0, 0x0, 0x0, 0x1, 0x4, 0x0, 0x0];
+        let mut cursor = Cursor::new(&data);
+        let tree = Node::parse(&mut cursor, ()).unwrap();
+        assert_eq!(cursor.position(), data.len() as u64);
+        assert_eq!(tree.value, 0x1);
+        let left = tree.left.as_ref().unwrap();
+        assert_eq!(left.value, 0x2);
+        assert_eq!(left.left.as_ref().unwrap().value, 0x3);
+        assert_eq!(tree.right.as_ref().unwrap().value, 0x4);
+
+        let mut output = Vec::new();
+        tree.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, data);
+        assert_eq!(tree.data_size(()), data.len() as u64);
+
+        let mut cursor = Cursor::new(&DATA);
+        let rc: Rc<u16> = Rc::parse(&mut cursor, Endian::Big).unwrap();
+        let arc: Arc<u16> = Arc::parse(&mut cursor, Endian::Big).unwrap();
+        assert_eq!((*rc, *arc), (0x0102, 0x0304));
+        let cow: Cow<'_, u16> = Cow::Owned(0x0506);
+        let mut output = Vec::new();
+        rc.write_to(&mut output, Endian::Big).unwrap();
+        arc.write_to(&mut output, Endian::Big).unwrap();
+        cow.write_to(&mut output, Endian::Big).unwrap();
+        <&u16 as Writable<Endian>>::write_to(&&0x0708, &mut output, Endian::Big).unwrap();
+        assert_eq!(output, DATA[..8]);
+        assert_eq!(
+            rc.data_size(())
+                + cow.data_size(())
+                + <&Arc<u16> as DataSize<()>>::data_size(&&arc, ()),
+            6
+        );
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Pair<T> {
+        a: T,
+        b: T,
+    }
+    crate::impl_parse!(generic data [T] Pair<T> where [T: Parse<F, D>], [a: d: T, b: d: T]);
+    crate::impl_struct_data_size!(generic [T: crate::data_size::DataSize<()>] Pair<T>, [a, b]);
+    crate::impl_writable!(generic data [T: crate::writable::Writable<D>] Pair<T>, [a: d: T, b: d: T]);
+
+    /// A user type, with its own endianness handling
+    #[derive(Debug, PartialEq)]
+    struct Point {
+        x: u8,
+        y: u16,
+    }
+    impl<F: Read> Parse<F, Endian> for Point {
+        fn parse(f: &mut F, endian: Endian) -> ParseResult<Self> {
+            Ok(Point {
+                x: u8::parse(f, ())?,
+                y: u16::parse(f, endian)?,
+            })
+        }
+    }
+    crate::impl_struct_data_size!(Point, [x, y]);
+    impl crate::writable::Writable<Endian> for Point {
+        fn write_to<W: std::io::Write>(
+            &self,
+            w: &mut W,
+            endian: Endian,
+        ) -> crate::writable::WriteResult {
+            self.x.write_to(w, ())?;
+            self.y.write_to(w, endian)
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Tagged<T> {
+        kind: u8,
+        value: T,
+    }
+    crate::impl_parse!(generic [T: Parse<F, Endian>] Tagged<T>, [kind: u: u8, value: b: T]);
+    crate::impl_writable!(unchecked generic [T] Tagged<T> where [T: crate::writable::Writable<Endian>], [kind: u: u8, value: b: T]);
+
+    #[test]
+    fn test_impl_generic() {
+        use crate::{data_size::DataSize, writable::Writable};
+
+        let data = [0x1, 0x2, 0x3, 0x4, 0x5, 0x6];
+        for &endian in [Endian::Little, Endian::Big].iter() {
+            let mut cursor = Cursor::new(&data);
+            let pair: Pair<u16> = Pair::parse(&mut cursor, endian).unwrap();
+            let expected = match endian {
+                Endian::Little => (0x0201, 0x0403),
+                Endian::Big => (0x0102, 0x0304),
+            };
+            assert_eq!((pair.a, pair.b), expected);
+            assert_eq!(pair.data_size(()), 4);
+            let mut output = Vec::new();
+            pair.write_to(&mut output, endian).unwrap();
+            assert_eq!(output, data[..4]);
+
+            let mut cursor = Cursor::new(&data);
+            let pair: Pair<Point> = Pair::parse(&mut cursor, endian).unwrap();
+            assert_eq!(cursor.position(), 6);
+            let expected = match endian {
+                Endian::Little => (0x0302, 0x0605),
+                Endian::Big => (0x0203, 0x0506),
+            };
+            assert_eq!((pair.a.x, pair.b.x), (0x1, 0x4));
+            assert_eq!((pair.a.y, pair.b.y), expected);
+            assert_eq!(pair.data_size(()), 6);
+            let mut output = Vec::new();
+            pair.write_to(&mut output, endian).unwrap();
+            assert_eq!(output, data);
+        }
+
+        let tagged: Tagged<Point> = Tagged::parse(&mut Cursor::new(&data), ()).unwrap();
+        assert_eq!(
+            tagged,
+            Tagged {
+                kind: 0x1,
+                value: Point { x: 0x2, y: 0x0304 }
+            }
+        );
+        let mut output = Vec::new();
+        tagged.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, data[..4]);
+    }
+
+    #[test]
+    fn test_parse_char() {
+        use crate::{data_size::DataSize, writable::Writable};
+
+        let data = "a\u{20AC}\u{1F600}".as_bytes();
+        let mut cursor = Cursor::new(data);
+        assert_eq!(char::parse(&mut cursor, ()).unwrap(), 'a');
+        assert_eq!(char::parse(&mut cursor, ()).unwrap(), '\u{20AC}');
+        assert_eq!(cursor.position(), 4);
+        assert_eq!(char::parse(&mut cursor, ()).unwrap(), '\u{1F600}');
+        assert_eq!(cursor.position(), data.len() as u64);
+
+        let mut output = Vec::new();
+        for (c, size) in [('a', 1), ('\u{20AC}', 3), ('\u{1F600}', 4)].iter() {
+            c.write_to(&mut output, ()).unwrap();
+            assert_eq!(c.data_size(()), *size);
+        }
+        assert_eq!(output, data);
+
+        // Truncated within the sequence
+        let mut cursor = Cursor::new(&data[1..3]);
+        match char::parse(&mut cursor, ()) {
+            Err(ParseError::Io(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => {}
+            x => panic!("expected unexpected eof, got {:?}", x),
+        }
+        // A continuation byte first, an overlong encoding, and a surrogate
+        for data in [&[0x80][..], &[0xE0, 0x80, 0x80], &[0xED, 0xA0, 0x80]].iter() {
+            assert!(matches!(
+                char::parse(&mut Cursor::new(data), ()),
+                Err(ParseError::InvalidByte)
+            ));
+        }
+    }
+
+    #[test]
+    fn test_utf8_str() {
+        let mut cursor = Cursor::new("h\u{E9}llo".as_bytes());
+        assert_eq!(utf8_str(&mut cursor, 3).unwrap(), "h\u{E9}");
+        assert_eq!(utf8_str(&mut cursor, 3).unwrap(), "llo");
+
+        let data = [b'a', 0xFF, b'b'];
+        assert!(matches!(
+            utf8_str(&mut Cursor::new(&data), 3),
+            Err(ParseError::InvalidByte)
+        ));
+        assert_eq!(
+            utf8_str_lossy(&mut Cursor::new(&data), 3).unwrap(),
+            "a\u{FFFD}b"
+        );
+        assert!(utf8_str(&mut Cursor::new(&data), 4).is_err());
+    }
+
+    #[test]
+    fn test_parse_at() {
+        let mut cursor = Cursor::new(&DATA);
+        cursor.set_position(3);
+        assert_eq!(
+            parse_at::<u16, _, _>(&mut cursor, 8, Endian::Big).unwrap(),
+            0x090a
+        );
+        assert_eq!(cursor.position(), 3);
+
+        // The target is too close to the end to parse, but the position is still restored
+        let end = DATA.len() as u64;
+        assert!(parse_at::<u32, _, _>(&mut cursor, end - 2, Endian::Big).is_err());
+        assert_eq!(cursor.position(), 3);
+        assert!(parse_at::<u8, _, _>(&mut cursor, end + 10, ()).is_err());
+        assert_eq!(cursor.position(), 3);
+    }
+
+    #[test]
+    fn test_parse_peek_buffered() {
+        let mut reader = PeekReader::new(&DATA as &[u8]);
+        let value = parse_peek_buffered::<u16, _, _>(&mut reader, Endian::Big, 4).unwrap();
+        assert_eq!(value, 0x0102);
+        let value = parse_peek_buffered::<u32, _, _>(&mut reader, Endian::Big, 4).unwrap();
+        assert_eq!(value, 0x01020304);
+        assert_eq!(u16::parse(&mut reader, Endian::Big).unwrap(), 0x0102);
+
+        match parse_peek_buffered::<u64, _, _>(&mut reader, Endian::Big, 4) {
+            Err(ParseError::PeekLimitExceeded(4)) => {}
+            x => panic!("Expected PeekLimitExceeded, got: {:?}", x),
+        }
+        assert_eq!(u32::parse(&mut reader, Endian::Big).unwrap(), 0x03040506);
+    }
+
+    #[test]
+    fn test_peek_helpers() {
+        let mut reader = PeekReader::new(&DATA[..4]);
+        assert_eq!(peek_byte(&mut reader).unwrap(), Some(0x1));
+        assert!(peek_tag(&mut reader, &[0x1, 0x2]).unwrap());
+        assert!(!peek_tag(&mut reader, &[0x1, 0x3]).unwrap());
+        assert!(peek_tag(&mut reader, &[] as &[u8]).unwrap());
+        // Longer than the data
+        assert!(!peek_tag(&mut reader, &[0x1, 0x2, 0x3, 0x4, 0x5]).unwrap());
+        tag(&mut reader, &[0x1, 0x2, 0x3]).unwrap();
+
+        assert_eq!(peek_byte(&mut reader).unwrap(), Some(0x4));
+        assert_eq!(single(&mut reader).unwrap(), 0x4);
+        assert_eq!(peek_byte(&mut reader).unwrap(), None);
+        assert!(!peek_tag(&mut reader, &[0x4]).unwrap());
+    }
+
+    #[test]
+    fn test_verify_crc32() {
+        let mut cursor = Cursor::new(b"123456789abc");
+        verify_crc32(&mut cursor, 0xCBF4_3926, 9).unwrap();
+        assert_eq!(cursor.position(), 9);
+
+        cursor.set_position(0);
+        match verify_crc32(&mut cursor, 0xCBF4_3927, 9) {
+            Err(ParseError::ChecksumMismatch(0xCBF4_3927, 0xCBF4_3926)) => {}
+            x => panic!("Expected ChecksumMismatch, got: {:?}", x),
+        }
+
+        cursor.set_position(0);
+        match verify_crc32(&mut cursor, 0xCBF4_3926, 13) {
+            Err(ParseError::Io(_)) => {}
+            x => panic!("Expected Io, got: {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_read_ext() {
+        fn check<T, F>(endian: Endian, ext: F)
+        where
+            T: Parse<Cursor<&'static [u8; 20]>, Endian> + PartialEq + Debug,
+            F: Fn(&mut Cursor<&'static [u8; 20]>) -> ParseResult<T>,
+        {
+            let expected = T::parse(&mut Cursor::new(&DATA), endian).unwrap();
+            let mut cursor = Cursor::new(&DATA);
+            assert_eq!(ext(&mut cursor).unwrap(), expected);
+            assert_eq!(cursor.position(), std::mem::size_of::<T>() as u64);
+        }
+
+        let mut cursor = Cursor::new(&DATA);
+        assert_eq!(cursor.read_u8().unwrap(), 0x1);
+        assert_eq!(cursor.read_i8().unwrap(), 0x2);
+        assert_eq!(cursor.read_exact_n::<3>().unwrap(), [0x3, 0x4, 0x5]);
+
+        check::<u16, _>(Endian::Little, |f| f.read_u16_le());
+        check::<u16, _>(Endian::Big, |f| f.read_u16_be());
+        check::<i16, _>(Endian::Little, |f| f.read_i16_le());
+        check::<i16, _>(Endian::Big, |f| f.read_i16_be());
+        check::<u32, _>(Endian::Little, |f| f.read_u32_le());
+        check::<u32, _>(Endian::Big, |f| f.read_u32_be());
+        check::<i32, _>(Endian::Little, |f| f.read_i32_le());
+        check::<i32, _>(Endian::Big, |f| f.read_i32_be());
+        check::<u64, _>(Endian::Little, |f| f.read_u64_le());
+        check::<u64, _>(Endian::Big, |f| f.read_u64_be());
+        check::<i64, _>(Endian::Little, |f| f.read_i64_le());
+        check::<i64, _>(Endian::Big, |f| f.read_i64_be());
+        check::<f32, _>(Endian::Little, |f| f.read_f32_le());
+        check::<f32, _>(Endian::Big, |f| f.read_f32_be());
+        check::<f64, _>(Endian::Little, |f| f.read_f64_le());
+        check::<f64, _>(Endian::Big, |f| f.read_f64_be());
+
+        let mut cursor = Cursor::new(b"abc\0d");
+        assert_eq!(cursor.read_zstring().unwrap().as_slice(), b"abc");
+        assert_eq!(cursor.read_u8().unwrap(), b'd');
+        cursor
+            .read_u8()
+            .expect_err("Expected error at end of input");
+    }
+
+    #[test]
+    fn test_parse_unit() {
+        let mut cursor = Cursor::new(&DATA);
+        <()>::parse(&mut cursor, ()).unwrap();
+        <()>::parse(&mut cursor, Endian::Little).unwrap();
+        assert_eq!(cursor.position(), 0);
+
+        let values: Vec<()> = many_count(&mut cursor, (), 3).unwrap();
+        assert_eq!(values.len(), 3);
+        assert_eq!(cursor.position(), 0);
+    }
+
+    #[test]
+    fn test_seek_within() {
+        let mut cursor = Cursor::new(&DATA);
+        assert_eq!(seek_within(&mut cursor, 4, None).unwrap(), 4);
+        assert_eq!(single(&mut cursor).unwrap(), 0x5);
+        assert_eq!(seek_within(&mut cursor, 20, None).unwrap(), 20);
+        match seek_within(&mut cursor, 21, None) {
+            Err(ParseError::SeekOutOfBounds(21, 20)) => {}
+            x => panic!("Expected SeekOutOfBounds, got: {:?}", x),
+        }
+        assert_eq!(stream_position(&mut cursor).unwrap(), 20);
+
+        assert_eq!(seek_within(&mut cursor, 8, Some(8)).unwrap(), 8);
+        match seek_within(&mut cursor, 9, Some(8)) {
+            Err(ParseError::SeekOutOfBounds(9, 8)) => {}
+            x => panic!("Expected SeekOutOfBounds, got: {:?}", x),
+        }
+        assert_eq!(stream_position(&mut cursor).unwrap(), 8);
+    }
+
+    #[test]
+    fn test_seek_forward_only() {
+        let mut cursor = Cursor::new(&DATA);
+        assert_eq!(seek_forward_only(&mut cursor, 4).unwrap(), 4);
+        assert_eq!(seek_forward_only(&mut cursor, 4).unwrap(), 4);
+        match seek_forward_only(&mut cursor, 3) {
+            Err(ParseError::SeekBackwards(4, 3)) => {}
+            x => panic!("Expected SeekBackwards, got: {:?}", x),
+        }
+        assert_eq!(stream_position(&mut cursor).unwrap(), 4);
+        assert_eq!(seek_forward_only(&mut cursor, 10).unwrap(), 10);
+    }
 }
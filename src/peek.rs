@@ -0,0 +1,280 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Read},
+};
+
+/// Wraps a reader with a pushback buffer, so that bytes can be 'unread' without
+/// requiring Seek. Useful for sockets, decompressors, and other streams.
+/// Reads replay any pushed back bytes before reading from the inner reader.
+#[derive(Debug)]
+pub struct PeekReader<R: Read> {
+    inner: R,
+    buffer: VecDeque<u8>,
+    consumed: u64,
+}
+impl<R> PeekReader<R>
+where
+    R: Read,
+{
+    #[inline]
+    pub fn new(inner: R) -> Self {
+        PeekReader {
+            inner,
+            buffer: VecDeque::new(),
+            consumed: 0,
+        }
+    }
+
+    /// Pushes [data] back onto the front of the stream, so that the next read starts with it.
+    #[inline]
+    pub fn unread(&mut self, data: &[u8]) {
+        for value in data.iter().rev() {
+            self.buffer.push_front(*value);
+        }
+        self.consumed = self.consumed.saturating_sub(data.len() as u64);
+    }
+
+    /// Fills [buf] with the upcoming bytes, without consuming them, so that the next read still
+    /// starts with them. Reads from the inner reader if not enough bytes are buffered.
+    /// Returns how many bytes were peeked, which is only less than `buf.len()` if the inner
+    /// reader ended.
+    /// Note: if this errors, then anything read before the error is still buffered.
+    pub fn peek(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.fill(buf.len())?;
+        let amount = buf.len().min(self.buffer.len());
+        for (dest, value) in buf.iter_mut().zip(self.buffer.iter()) {
+            *dest = *value;
+        }
+        Ok(amount)
+    }
+
+    /// Like [PeekReader::peek], but errors with `ErrorKind::UnexpectedEof` if the inner reader
+    /// ended before [buf] was filled. The bytes that were there are still buffered.
+    pub fn peek_exact(&mut self, buf: &mut [u8]) -> io::Result<()> {
+        if self.peek(buf)? == buf.len() {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "reached the end of the stream while peeking",
+            ))
+        }
+    }
+
+    /// Reads from the inner reader until at least [amount] bytes are buffered, or it ends.
+    fn fill(&mut self, amount: usize) -> io::Result<()> {
+        let mut chunk = [0u8; 256];
+        while self.buffer.len() < amount {
+            let wanted = (amount - self.buffer.len()).min(chunk.len());
+            match self.inner.read(&mut chunk[..wanted]) {
+                Ok(0) => break,
+                Ok(amount_read) => self.buffer.extend(&chunk[..amount_read]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the number of bytes which are buffered, and so can be read without touching the
+    /// inner reader.
+    #[inline]
+    pub fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+
+    /// Returns how many bytes have been read through this, less any that were unread.
+    /// Peeking doesn't count, as it doesn't consume anything.
+    #[inline]
+    pub fn consumed(&self) -> u64 {
+        self.consumed
+    }
+
+    /// Note: any buffered bytes are lost.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Note: reading from the inner reader through this handle skips over buffered bytes.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+impl<R> Read for PeekReader<R>
+where
+    R: Read,
+{
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let amount = if self.buffer.is_empty() {
+            self.inner.read(buf)?
+        } else {
+            let amount = buf.len().min(self.buffer.len());
+            for (dest, value) in buf.iter_mut().zip(self.buffer.drain(..amount)) {
+                *dest = value;
+            }
+            amount
+        };
+        self.consumed += amount as u64;
+        Ok(amount)
+    }
+}
+
+/// Reader given to the parse in `parse::parse_peek_buffered`.
+/// Records everything read through it so that it can be pushed back afterwards, and refuses to
+/// read more than [limit] bytes.
+#[derive(Debug)]
+pub struct PeekLimiter<'a, R: Read> {
+    reader: &'a mut PeekReader<R>,
+    consumed: Vec<u8>,
+    limit: usize,
+    exceeded: bool,
+}
+impl<'a, R> PeekLimiter<'a, R>
+where
+    R: Read,
+{
+    #[inline]
+    pub fn new(reader: &'a mut PeekReader<R>, limit: usize) -> Self {
+        PeekLimiter {
+            reader,
+            consumed: Vec::new(),
+            limit,
+            exceeded: false,
+        }
+    }
+
+    /// Returns whether a read was attempted past the limit.
+    #[inline]
+    pub fn exceeded(&self) -> bool {
+        self.exceeded
+    }
+
+    /// Pushes everything that was read back onto the [PeekReader].
+    #[inline]
+    pub fn restore(self) {
+        self.reader.unread(&self.consumed);
+    }
+}
+impl<'a, R> Read for PeekLimiter<'a, R>
+where
+    R: Read,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+
+        let remaining = self.limit.saturating_sub(self.consumed.len());
+        if remaining == 0 {
+            self.exceeded = true;
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "attempted to read past peek limit",
+            ));
+        }
+
+        let max = buf.len().min(remaining);
+        let amount_read = self.reader.read(&mut buf[..max])?;
+        self.consumed.extend_from_slice(&buf[..amount_read]);
+        Ok(amount_read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: [u8; 8] = [0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x8];
+
+    #[test]
+    fn test_unread() {
+        let mut reader = PeekReader::new(&DATA as &[u8]);
+        let mut out = [0u8; 3];
+        reader.read_exact(&mut out).unwrap();
+        assert_eq!(out, [0x1, 0x2, 0x3]);
+        reader.unread(&[0x2, 0x3]);
+        assert_eq!(reader.buffered(), 2);
+
+        let mut out = [0u8; 4];
+        reader.read_exact(&mut out).unwrap();
+        assert_eq!(out, [0x2, 0x3, 0x4, 0x5]);
+        assert_eq!(reader.buffered(), 0);
+        assert_eq!(reader.consumed(), 5);
+    }
+
+    /// Reader that returns a single byte per read call.
+    struct ByteReader<'a> {
+        data: &'a [u8],
+    }
+    impl<'a> Read for ByteReader<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match (buf.first_mut(), self.data.split_first()) {
+                (Some(dest), Some((value, rest))) => {
+                    *dest = *value;
+                    self.data = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    #[test]
+    fn test_peek() {
+        let mut reader = PeekReader::new(ByteReader { data: &DATA });
+        let mut out = [0u8; 2];
+        reader.peek_exact(&mut out).unwrap();
+        assert_eq!(out, [0x1, 0x2]);
+        assert_eq!(reader.buffered(), 2);
+
+        // Larger than what was buffered
+        let mut out = [0u8; 5];
+        reader.peek_exact(&mut out).unwrap();
+        assert_eq!(out, [0x1, 0x2, 0x3, 0x4, 0x5]);
+        assert_eq!(reader.buffered(), 5);
+
+        // Interleaved with reads
+        let mut out = [0u8; 3];
+        reader.read_exact(&mut out).unwrap();
+        assert_eq!(out, [0x1, 0x2, 0x3]);
+        let mut out = [0u8; 4];
+        reader.peek_exact(&mut out).unwrap();
+        assert_eq!(out, [0x4, 0x5, 0x6, 0x7]);
+        reader.read_exact(&mut out[..1]).unwrap();
+        assert_eq!(out[0], 0x4);
+
+        // Reaching the end, which keeps what was there
+        let mut out = [0u8; 8];
+        assert_eq!(reader.peek(&mut out).unwrap(), 4);
+        assert_eq!(&out[..4], [0x5, 0x6, 0x7, 0x8]);
+        let err = reader.peek_exact(&mut out).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, [0x5, 0x6, 0x7, 0x8]);
+        assert_eq!(reader.peek(&mut [0u8; 1]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_limiter() {
+        let mut reader = PeekReader::new(&DATA as &[u8]);
+        let mut limiter = PeekLimiter::new(&mut reader, 4);
+        let mut out = [0u8; 3];
+        limiter.read_exact(&mut out).unwrap();
+        limiter.read_exact(&mut out).unwrap_err();
+        assert!(limiter.exceeded());
+        limiter.restore();
+
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+        assert_eq!(out, DATA);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{
+    data_size::DataSize,
+    parse::{Parse, ParseResult},
+    stream_position,
+    writable::{Writable, WriteResult},
+};
+use std::{
+    fmt::Debug,
+    io::{Read, Seek, Write},
+    ops::{Deref, DerefMut},
+};
+
+/// A value along with the offset in the stream that it was parsed from.
+/// Parsing records the stream position before parsing [T], while writing and the data size
+/// ignore the offset entirely, so this can be swapped for [T] in a structure without changing
+/// its layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Positioned<T> {
+    /// Position in the stream where the value started
+    pub offset: u64,
+    pub value: T,
+}
+impl<T> Positioned<T> {
+    #[inline]
+    pub fn new(offset: u64, value: T) -> Self {
+        Positioned { offset, value }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+impl<T> Deref for Positioned<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.value
+    }
+}
+impl<T> DerefMut for Positioned<T> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.value
+    }
+}
+impl<F, D, T> Parse<F, D> for Positioned<T>
+where
+    F: Read + Seek,
+    T: Parse<F, D>,
+{
+    #[inline]
+    fn parse(f: &mut F, d: D) -> ParseResult<Self> {
+        let offset = stream_position(f)?;
+        let value = T::parse(f, d)?;
+        Ok(Positioned { offset, value })
+    }
+}
+impl<D, T> Writable<D> for Positioned<T>
+where
+    D: Debug + Clone + PartialEq,
+    T: Writable<D>,
+{
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, d: D) -> WriteResult
+    where
+        W: Write,
+    {
+        self.value.write_to(w, d)
+    }
+}
+impl<D, T> DataSize<D> for Positioned<T>
+where
+    D: Debug + Clone + PartialEq,
+    T: DataSize<D>,
+{
+    #[inline]
+    fn data_size(&self, d: D) -> u64 {
+        self.value.data_size(d)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse::many_parse, Endian};
+    use std::io::Cursor;
+
+    const DATA: [u8; 12] = [0x1, 0x2, 0x3, 0x4, 0x5, 0x6, 0x7, 0x8, 0x9, 0xa, 0xb, 0xc];
+
+    #[test]
+    fn test_positioned() {
+        let mut cursor = Cursor::new(&DATA);
+        let values: Vec<Positioned<u32>> = many_parse(&mut cursor, Endian::Big).unwrap();
+        let offsets: Vec<u64> = values.iter().map(|x| x.offset).collect();
+        assert_eq!(offsets, [0, 4, 8]);
+        assert_eq!(*values[1], 0x05060708);
+        assert_eq!(values[2].into_inner(), 0x090a0b0c);
+
+        let mut output = Vec::new();
+        for value in values.iter() {
+            assert_eq!(value.data_size(()), 4);
+            value.write_to(&mut output, Endian::Big).unwrap();
+        }
+        assert_eq!(output, DATA);
+    }
+}
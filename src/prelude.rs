@@ -0,0 +1,53 @@
+//! Commonly used traits and types, meant to be glob-imported with
+//! `use eyeutil::prelude::*;`.
+//!
+//! Inclusion policy: the core traits (`Parse`, `Writable`, `DataSize`), their result and error
+//! types, `Endian`, the extension traits, the wrapper types, and the macros.
+//! Free functions with short, collision-prone names (`take`, `single`, `tag`, ...) are
+//! deliberately left out, and stay behind `eyeutil::parse::`.
+//!
+//! Stability: changes to the prelude are only ever additive. Items are not removed or renamed
+//! here without a major version bump, so glob-importing it will not break on minor updates
+//! (beyond the usual caveat of a new name shadowing a glob-imported name of your own).
+//!
+//! ```
+//! #![deny(unused_imports)]
+//! use eyeutil::prelude::*;
+//!
+//! let mut cursor = std::io::Cursor::new(vec![0x01, 0x02, 0x03, 0x04]);
+//! let value: u32 = u32::parse(&mut cursor, Endian::Big).unwrap();
+//! assert_eq!(value, 0x01020304);
+//! assert_eq!(value.data_size(()), 4);
+//!
+//! let mut output = Vec::new();
+//! let result: WriteResult = value.write_to(&mut output, Endian::Little);
+//! result.unwrap();
+//! assert_eq!(output, [0x04, 0x03, 0x02, 0x01]);
+//!
+//! let name: ParseResult<ZString> = ZString::parse(&mut std::io::Cursor::new(b"hi\0"), ());
+//! assert_eq!(name.unwrap().as_slice(), b"hi");
+//! ```
+
+pub use crate::{
+    addr::{AddrWidth, Iaddr, Uaddr},
+    borrowed::SliceParser,
+    ctx::ParseCtx,
+    data_size::DataSize,
+    endian::{Be, Le},
+    fourcc::FourCC,
+    half::F16,
+    impl_data_size, impl_flags, impl_parse, impl_parse_versioned, impl_struct_data_size,
+    impl_writable,
+    maybe::Maybe,
+    nonzero::Nullable,
+    offset::Offset,
+    parse::{Parse, ParseError, ParseResult, ParseResultExt, ReadExt},
+    positioned::Positioned,
+    version::Version,
+    writable::{Bytes, OwnedBytes, Writable, WriteError, WriteExt, WriteResult},
+    zstring::{
+        BZString, FixedString, PString, PString16, PString32, PString64, PrefixString, TermString,
+        ZString, ZString16, ZStringArray, ZStringError,
+    },
+    Endian, EnumConversionError,
+};
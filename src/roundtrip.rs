@@ -0,0 +1,203 @@
+//! Test utilities for checking that `Parse` and `Writable` impls agree with each other.
+//! These panic with the first offset at which the bytes differ, and a hex dump of both around
+//! it, so that a mismatch can be tracked down to a field.
+
+use crate::{hexdump, parse::Parse, writable::Writable};
+use std::{fmt::Debug, io::Cursor};
+
+/// How many bytes are shown on either side of the first difference.
+const CONTEXT: usize = 8;
+
+/// Parses [bytes] as `P`, and writes the result back, asserting that it consumed all of
+/// [bytes] and wrote exactly them.
+pub fn assert_roundtrip_bytes<P, D>(bytes: &[u8], d: D)
+where
+    P: Parse<Cursor<Vec<u8>>, D> + Writable<D> + Debug,
+    D: Debug + Clone + PartialEq,
+{
+    let type_name = std::any::type_name::<P>();
+    let mut cursor = Cursor::new(bytes.to_vec());
+    let value = match P::parse(&mut cursor, d.clone()) {
+        Ok(x) => x,
+        Err(e) => panic!(
+            "{} failed to parse: {:?}\nbytes: {:02X?}",
+            type_name, e, bytes
+        ),
+    };
+    if cursor.position() != bytes.len() as u64 {
+        panic!(
+            "{} only consumed {} of {} bytes\nvalue: {:?}\nleft over: {:02X?}",
+            type_name,
+            cursor.position(),
+            bytes.len(),
+            value,
+            &bytes[cursor.position() as usize..]
+        );
+    }
+
+    let written = write(&value, d, type_name);
+    if written != bytes {
+        panic!(
+            "{} wrote different bytes than it was parsed from\nvalue: {:?}\n{}",
+            type_name,
+            value,
+            describe_mismatch(bytes, &written)
+        );
+    }
+}
+
+/// Writes [value], and parses the result back, asserting that it consumed everything and gave
+/// an equal value.
+pub fn assert_roundtrip_value<T, D>(value: &T, d: D)
+where
+    T: Parse<Cursor<Vec<u8>>, D> + Writable<D> + PartialEq + Debug,
+    D: Debug + Clone + PartialEq,
+{
+    let type_name = std::any::type_name::<T>();
+    let written = write(value, d.clone(), type_name);
+    let mut cursor = Cursor::new(written.clone());
+    let parsed = match T::parse(&mut cursor, d) {
+        Ok(x) => x,
+        Err(e) => panic!(
+            "{} failed to parse what it wrote: {:?}\nvalue: {:?}\nwritten: {:02X?}",
+            type_name, e, value, written
+        ),
+    };
+    if cursor.position() != written.len() as u64 {
+        panic!(
+            "{} only consumed {} of the {} bytes it wrote\nvalue: {:?}\nwritten: {:02X?}",
+            type_name,
+            cursor.position(),
+            written.len(),
+            value,
+            written
+        );
+    }
+    if &parsed != value {
+        panic!(
+            "{} parsed to a different value than was written\nwritten: {:?}\nparsed: {:?}\nbytes: {:02X?}",
+            type_name, value, parsed, written
+        );
+    }
+}
+
+fn write<T, D>(value: &T, d: D, type_name: &str) -> Vec<u8>
+where
+    T: Writable<D> + Debug,
+    D: Debug + Clone + PartialEq,
+{
+    let mut output = Vec::new();
+    if let Err(e) = value.write_to(&mut output, d) {
+        panic!("{} failed to write: {:?}\nvalue: {:?}", type_name, e, value);
+    }
+    output
+}
+
+/// Describes where [expected] and [actual] first differ, with the bytes around there.
+fn describe_mismatch(expected: &[u8], actual: &[u8]) -> String {
+    let offset = expected
+        .iter()
+        .zip(actual.iter())
+        .position(|(a, b)| a != b)
+        .unwrap_or_else(|| expected.len().min(actual.len()));
+    let start = offset.saturating_sub(CONTEXT);
+    let around = |data: &[u8]| {
+        let end = data.len().min(offset + CONTEXT);
+        hexdump(data.get(start..end).unwrap_or(&[]), start as u64)
+    };
+    format!(
+        "first difference at offset {} (lengths {} and {})\nexpected:\n{}actual:\n{}",
+        offset,
+        expected.len(),
+        actual.len(),
+        around(expected),
+        around(actual)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse::ParseResult, writable::WriteResult, zstring::ZString, Endian};
+    use std::io::{Read, Write};
+
+    #[derive(Debug, PartialEq)]
+    struct Header {
+        kind: u8,
+        length: u32,
+        name: ZString,
+    }
+    crate::impl_parse!(Header, [kind: u: u8, length: l: u32, name: u: ZString]);
+    crate::impl_struct_data_size!(Header, [kind, length, name]);
+    crate::impl_writable!(Header, [kind: u: u8, length: l: u32, name: u: ZString]);
+
+    #[test]
+    fn test_roundtrip() {
+        assert_roundtrip_bytes::<u32, _>(&[0x1, 0x2, 0x3, 0x4], Endian::Big);
+        assert_roundtrip_value(&0x01020304u32, Endian::Little);
+        assert_roundtrip_bytes::<ZString, _>(b"name\0", ());
+        assert_roundtrip_value(&ZString::from("name"), ());
+
+        assert_roundtrip_bytes::<Header, _>(&[0x1, 0x2, 0x0, 0x0, 0x0, b'a', 0x0], ());
+        assert_roundtrip_value(
+            &Header {
+                kind: 0x3,
+                length: 0x04050607,
+                name: ZString::from("header"),
+            },
+            (),
+        );
+    }
+
+    /// Accidentally writes its value in big endian
+    #[derive(Debug, PartialEq)]
+    struct Buggy(u16);
+    impl<F: Read> Parse<F> for Buggy {
+        fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+            u16::parse(f, Endian::Little).map(Buggy)
+        }
+    }
+    impl Writable<()> for Buggy {
+        fn write_to<W: Write>(&self, w: &mut W, _d: ()) -> WriteResult {
+            self.0.write_to(w, Endian::Big)
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "first difference at offset 0")]
+    fn test_roundtrip_bytes_mismatch() {
+        assert_roundtrip_bytes::<Buggy, _>(&[0x1, 0x2], ());
+    }
+
+    #[test]
+    #[should_panic(expected = "parsed to a different value")]
+    fn test_roundtrip_value_mismatch() {
+        assert_roundtrip_value(&Buggy(0x0102), ());
+    }
+
+    #[test]
+    #[should_panic(expected = "only consumed 2 of 3 bytes")]
+    fn test_roundtrip_left_over() {
+        assert_roundtrip_bytes::<u16, _>(&[0x1, 0x2, 0x3], Endian::Little);
+    }
+
+    #[test]
+    fn test_describe_mismatch() {
+        let expected: Vec<u8> = (0..32).collect();
+        let mut actual = expected.clone();
+        actual[20] = 0xFF;
+        let description = describe_mismatch(&expected, &actual);
+        assert_eq!(
+            description,
+            "first difference at offset 20 (lengths 32 and 32)\n\
+             expected:\n\
+             0000000c  0c 0d 0e 0f 10 11 12 13  14 15 16 17 18 19 1a 1b  |................|\n\
+             actual:\n\
+             0000000c  0c 0d 0e 0f 10 11 12 13  ff 15 16 17 18 19 1a 1b  |................|\n"
+        );
+
+        // One is a prefix of the other
+        let description = describe_mismatch(&expected, &expected[..4]);
+        assert!(description.starts_with("first difference at offset 4 (lengths 32 and 4)"));
+    }
+}
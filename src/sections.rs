@@ -0,0 +1,605 @@
+use crate::{
+    parse::ParseResult,
+    slice::{InputSlice, OutputSlice},
+    writable::{write_full, WriteError, WriteResult},
+};
+use std::{
+    cell::Cell,
+    io::{Read, Seek, SeekFrom, Write},
+    ops::Range,
+};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum SectionError {
+    /// A section with this name was already inserted.
+    Duplicate(&'static str),
+    /// The section .0 overlaps with the already inserted section .1
+    Overlap(&'static str, &'static str),
+    /// The section ends at .1, which is past the file length of .2
+    OutOfBounds(&'static str, u64, u64),
+    /// The section's offset + length overflows a u64.
+    Overflow(&'static str),
+    /// There is no section with this name.
+    Unknown(String),
+    /// The layout was used before `LayoutPlanner::finalize` was called.
+    NotFinalized,
+}
+
+/// A named, contiguous region of a file.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Section {
+    pub name: &'static str,
+    pub offset: u64,
+    pub len: u64,
+}
+impl Section {
+    /// Returns exclusive end
+    #[inline]
+    pub fn end(&self) -> u64 {
+        // SectionMap checks that this doesn't overflow on insertion, but the fields are public
+        self.offset.saturating_add(self.len)
+    }
+
+    #[inline]
+    pub fn range(&self) -> Range<u64> {
+        self.offset..self.end()
+    }
+
+    /// Whether the two sections share any bytes. Empty sections never overlap anything.
+    #[inline]
+    pub fn overlaps(&self, other: &Section) -> bool {
+        self.len != 0 && other.len != 0 && self.offset < other.end() && other.offset < self.end()
+    }
+}
+
+/// Map of the named sections of a file, as declared by its header, so that
+/// `InputSlice`s are created from validated ranges rather than raw numbers.
+#[derive(Debug, Clone, Default)]
+pub struct SectionMap {
+    /// Kept sorted by offset
+    sections: Vec<Section>,
+    file_len: Option<u64>,
+}
+impl SectionMap {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a map where every section must lie within [file_len] bytes.
+    #[inline]
+    pub fn with_file_len(file_len: u64) -> Self {
+        SectionMap {
+            sections: Vec::new(),
+            file_len: Some(file_len),
+        }
+    }
+
+    #[inline]
+    pub fn file_len(&self) -> Option<u64> {
+        self.file_len
+    }
+
+    /// Inserts a section, which must not overlap any existing section.
+    pub fn insert(&mut self, name: &'static str, offset: u64, len: u64) -> ParseResult<()> {
+        self.insert_inner(name, offset, len, false)
+    }
+
+    /// Inserts a section which is allowed to overlap other sections.
+    /// It is still checked against the file length.
+    pub fn insert_overlapping(
+        &mut self,
+        name: &'static str,
+        offset: u64,
+        len: u64,
+    ) -> ParseResult<()> {
+        self.insert_inner(name, offset, len, true)
+    }
+
+    fn insert_inner(
+        &mut self,
+        name: &'static str,
+        offset: u64,
+        len: u64,
+        allow_overlap: bool,
+    ) -> ParseResult<()> {
+        if self.get(name).is_some() {
+            return Err(SectionError::Duplicate(name).into());
+        }
+
+        let end = offset
+            .checked_add(len)
+            .ok_or(SectionError::Overflow(name))?;
+        if let Some(file_len) = self.file_len {
+            if end > file_len {
+                return Err(SectionError::OutOfBounds(name, end, file_len).into());
+            }
+        }
+
+        let section = Section { name, offset, len };
+        if !allow_overlap {
+            if let Some(other) = self.sections.iter().find(|x| x.overlaps(&section)) {
+                return Err(SectionError::Overlap(name, other.name).into());
+            }
+        }
+
+        // Insert after any sections at the same offset, so that ties keep insertion order
+        let index = self.sections.partition_point(|x| x.offset <= offset);
+        self.sections.insert(index, section);
+        Ok(())
+    }
+
+    #[inline]
+    pub fn get(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|x| x.name == name)
+    }
+
+    #[inline]
+    pub fn range_of(&self, name: &str) -> Option<Range<u64>> {
+        self.get(name).map(Section::range)
+    }
+
+    /// Iterates over the sections in order of offset.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &Section> {
+        self.sections.iter()
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.sections.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.sections.is_empty()
+    }
+
+    /// Seeks [f] to the start of the named section, and returns a slice bounded to it.
+    pub fn slice_of<'f, F>(&self, f: &'f mut F, name: &str) -> ParseResult<InputSlice<&'f mut F>>
+    where
+        F: Read + Seek,
+    {
+        let section = self
+            .get(name)
+            .ok_or_else(|| SectionError::Unknown(name.to_owned()))?;
+        f.seek(SeekFrom::Start(section.offset))?;
+        Ok(InputSlice::new_unchecked(f, section.range()))
+    }
+
+    /// Returns the byte ranges not claimed by any section, in order.
+    /// This covers from 0 up to the file length if it was given, and otherwise up to the end of
+    /// the last section.
+    pub fn gaps(&self) -> Vec<Range<u64>> {
+        let mut gaps = Vec::new();
+        let mut covered_end = 0;
+        for section in self.sections.iter().filter(|x| x.len != 0) {
+            if section.offset > covered_end {
+                gaps.push(covered_end..section.offset);
+            }
+            covered_end = covered_end.max(section.end());
+        }
+
+        if let Some(file_len) = self.file_len {
+            if file_len > covered_end {
+                gaps.push(covered_end..file_len);
+            }
+        }
+
+        gaps
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PlannedSection {
+    name: &'static str,
+    size: u64,
+    align: u64,
+}
+
+/// Plans the layout of the sections of a file, so that a header containing their offsets can be
+/// written first, and the section bodies afterwards in any order.
+/// Sections are laid out in the order they were added, each at the next offset that is a
+/// multiple of its alignment.
+#[derive(Debug, Clone, Default)]
+pub struct LayoutPlanner {
+    planned: Vec<PlannedSection>,
+    /// Filled in by `finalize`
+    layout: Option<SectionMap>,
+    /// How many bytes into each planned section have been written, so that `finish` can zero
+    /// the rest
+    filled: Vec<Cell<u64>>,
+}
+impl LayoutPlanner {
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a section of [size] bytes, aligned to [align] bytes.
+    /// [align] must be a power of two. Invalidates any previous `finalize`.
+    pub fn add(&mut self, name: &'static str, size: u64, align: u64) -> WriteResult {
+        if !align.is_power_of_two() {
+            return Err(WriteError::InvalidAlignment(align));
+        }
+
+        if self.planned.iter().any(|x| x.name == name) {
+            return Err(SectionError::Duplicate(name).into());
+        }
+
+        self.planned.push(PlannedSection { name, size, align });
+        self.layout = None;
+        Ok(())
+    }
+
+    /// Assigns offsets to every section, with the first one placed at or after [base_offset].
+    pub fn finalize(&mut self, base_offset: u64) -> WriteResult {
+        let mut layout = SectionMap::new();
+        let mut position = base_offset;
+        for section in self.planned.iter() {
+            let mask = section.align - 1;
+            let offset = position
+                .checked_add(mask)
+                .ok_or(SectionError::Overflow(section.name))?
+                & !mask;
+            layout
+                .insert(section.name, offset, section.size)
+                .map_err(|_| SectionError::Overflow(section.name))?;
+            position = offset + section.size;
+        }
+
+        self.layout = Some(layout);
+        self.filled = self.planned.iter().map(|_| Cell::new(0)).collect();
+        Ok(())
+    }
+
+    /// Returns the finalized layout.
+    #[inline]
+    pub fn layout(&self) -> Result<&SectionMap, WriteError> {
+        self.layout
+            .as_ref()
+            .ok_or(WriteError::Section(SectionError::NotFinalized))
+    }
+
+    #[inline]
+    pub fn offset_of(&self, name: &str) -> Result<u64, WriteError> {
+        self.section(name).map(|x| x.offset)
+    }
+
+    /// Returns the exclusive end of the last section, or None if there are no sections.
+    #[inline]
+    pub fn end(&self) -> Result<Option<u64>, WriteError> {
+        Ok(self.layout()?.iter().last().map(Section::end))
+    }
+
+    fn section(&self, name: &str) -> Result<&Section, WriteError> {
+        self.layout()?
+            .get(name)
+            .ok_or_else(|| SectionError::Unknown(name.to_owned()).into())
+    }
+
+    /// Seeks [w] to the start of the named section, and returns a writer bounded to it.
+    pub fn writer_for<'p, 'w, W>(
+        &'p self,
+        w: &'w mut W,
+        name: &str,
+    ) -> Result<SectionWriter<'p, &'w mut W>, WriteError>
+    where
+        W: Write + Seek,
+    {
+        let section = self.section(name)?;
+        let index = self
+            .planned
+            .iter()
+            .position(|x| x.name == section.name)
+            .expect("finalized section should be planned");
+        w.seek(SeekFrom::Start(section.offset))?;
+        Ok(SectionWriter {
+            slice: OutputSlice::new_unchecked(w, section.range()),
+            position: 0,
+            filled: &self.filled[index],
+        })
+    }
+
+    /// Writes zeroes over everything in the layout that wasn't written through `writer_for`:
+    /// the alignment padding between sections, and the rest of each underfilled (or never
+    /// written) section. Leaves [w] positioned at the end of the layout.
+    pub fn finish<W>(&self, w: &mut W) -> WriteResult
+    where
+        W: Write + Seek,
+    {
+        let mut end = None;
+        for (planned, filled) in self.planned.iter().zip(self.filled.iter()) {
+            let section = self.section(planned.name)?;
+            if let Some(end) = end {
+                write_zeroes(w, end..section.offset)?;
+            }
+            write_zeroes(w, section.offset + filled.get()..section.end())?;
+            end = Some(section.end());
+        }
+
+        if let Some(end) = end {
+            w.seek(SeekFrom::Start(end))?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes zeroes over [range] of [w].
+fn write_zeroes<W>(w: &mut W, range: Range<u64>) -> std::io::Result<()>
+where
+    W: Write + Seek,
+{
+    if range.is_empty() {
+        return Ok(());
+    }
+
+    w.seek(SeekFrom::Start(range.start))?;
+    let mut remaining = range.end - range.start;
+    let zeroes = [0u8; 512];
+    while remaining != 0 {
+        let amount = remaining.min(zeroes.len() as u64) as usize;
+        write_full(w, &zeroes[..amount])?;
+        remaining -= amount as u64;
+    }
+    Ok(())
+}
+
+/// Writer bounded to one section of a [LayoutPlanner], from `LayoutPlanner::writer_for`.
+/// It records how far into the section has been written, which is what `finish` zero-pads
+/// from. Writing after seeking past that point zeroes the bytes that were seeked over first, so
+/// the written part of the section never has holes.
+#[derive(Debug)]
+pub struct SectionWriter<'p, W: Write> {
+    slice: OutputSlice<W>,
+    /// Position within the section
+    position: u64,
+    filled: &'p Cell<u64>,
+}
+impl<'p, W> SectionWriter<'p, W>
+where
+    W: Write,
+{
+    /// Returns the number of bytes that can still be written before the end of the section.
+    #[inline]
+    pub fn remaining(&self) -> u64 {
+        (self.slice.end() - self.slice.start()).saturating_sub(self.position)
+    }
+}
+impl<'p, W> Write for SectionWriter<'p, W>
+where
+    W: Write + Seek,
+{
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let filled = self.filled.get();
+        if self.position > filled {
+            write_zeroes(&mut self.slice, filled..self.position)?;
+            self.filled.set(self.position);
+        }
+
+        let amount = self.slice.write(buf)?;
+        self.position += amount as u64;
+        self.filled.set(self.filled.get().max(self.position));
+        Ok(amount)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.slice.flush()
+    }
+}
+impl<'p, W> Seek for SectionWriter<'p, W>
+where
+    W: Write + Seek,
+{
+    /// Seeks within the section, like `OutputSlice`.
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        self.position = self.slice.seek(pos)?;
+        Ok(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parse::{Parse, ParseError},
+        writable::Bytes,
+        Endian,
+    };
+    use std::io::Cursor;
+
+    #[test]
+    fn test_insert() {
+        let mut map = SectionMap::with_file_len(32);
+        map.insert("header", 0, 8).unwrap();
+        // Touching but not overlapping
+        map.insert("body", 8, 8).unwrap();
+        map.insert("empty", 8, 0).unwrap();
+        // Empty, within another section
+        map.insert("inner", 10, 0).unwrap();
+        let body = map.get("body").unwrap();
+        assert!(!map.get("inner").unwrap().overlaps(body));
+        assert!(!body.overlaps(map.get("inner").unwrap()));
+
+        match map.insert("bad", 15, 2) {
+            Err(ParseError::Section(SectionError::Overlap("bad", "body"))) => {}
+            x => panic!("Expected overlap error, got: {:?}", x),
+        }
+        match map.insert("body", 20, 2) {
+            Err(ParseError::Section(SectionError::Duplicate("body"))) => {}
+            x => panic!("Expected duplicate error, got: {:?}", x),
+        }
+        match map.insert("tail", 30, 3) {
+            Err(ParseError::Section(SectionError::OutOfBounds("tail", 33, 32))) => {}
+            x => panic!("Expected out of bounds error, got: {:?}", x),
+        }
+        match map.insert("huge", u64::MAX, 1) {
+            Err(ParseError::Section(SectionError::Overflow("huge"))) => {}
+            x => panic!("Expected overflow error, got: {:?}", x),
+        }
+        map.insert("tail", 29, 3).unwrap();
+        map.insert_overlapping("index", 4, 8).unwrap();
+
+        assert_eq!(map.len(), 6);
+        assert_eq!(map.range_of("body"), Some(8..16));
+        assert_eq!(map.range_of("missing"), None);
+        let names: Vec<&str> = map.iter().map(|x| x.name).collect();
+        assert_eq!(names, ["header", "index", "body", "empty", "inner", "tail"]);
+    }
+
+    #[test]
+    fn test_gaps() {
+        let mut map = SectionMap::with_file_len(100);
+        map.insert("c", 60, 20).unwrap();
+        map.insert("a", 4, 10).unwrap();
+        map.insert("b", 14, 16).unwrap();
+        assert_eq!(map.gaps(), [0..4, 30..60, 80..100]);
+
+        map.insert_overlapping("d", 20, 45).unwrap();
+        assert_eq!(map.gaps(), [0..4, 80..100]);
+
+        let mut map = SectionMap::new();
+        map.insert("a", 0, 10).unwrap();
+        map.insert("b", 12, 10).unwrap();
+        map.insert("c", 30, 2).unwrap();
+        assert_eq!(map.gaps(), [10..12, 22..30]);
+    }
+
+    #[test]
+    fn test_slice_of() {
+        let data: Vec<u8> = (0..16).collect();
+        let mut cursor = Cursor::new(data);
+        let mut map = SectionMap::with_file_len(16);
+        map.insert("first", 0, 4).unwrap();
+        map.insert("second", 4, 4).unwrap();
+
+        let mut slice = map.slice_of(&mut cursor, "second").unwrap();
+        assert_eq!(u32::parse(&mut slice, Endian::Big).unwrap(), 0x04050607);
+        u8::parse(&mut slice, ()).expect_err("Expected error reading past the section");
+
+        let mut slice = map.slice_of(&mut cursor, "first").unwrap();
+        assert_eq!(u16::parse(&mut slice, Endian::Little).unwrap(), 0x0100);
+
+        match map.slice_of(&mut cursor, "third") {
+            Err(ParseError::Section(SectionError::Unknown(name))) => assert_eq!(name, "third"),
+            x => panic!("Expected unknown section error, got: {:?}", x.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn test_layout_planner() {
+        use crate::writable::Writable;
+
+        let mut planner = LayoutPlanner::new();
+        planner.add("names", 3, 1).unwrap();
+        planner.add("table", 8, 4).unwrap();
+        planner.add("data", 6, 16).unwrap();
+        match planner.add("bad", 4, 3) {
+            Err(WriteError::InvalidAlignment(3)) => {}
+            x => panic!("Expected invalid alignment error, got: {:?}", x),
+        }
+        match planner.offset_of("names") {
+            Err(WriteError::Section(SectionError::NotFinalized)) => {}
+            x => panic!("Expected not finalized error, got: {:?}", x),
+        }
+
+        planner.finalize(9).unwrap();
+        assert_eq!(planner.offset_of("names").unwrap(), 9);
+        assert_eq!(planner.offset_of("table").unwrap(), 12);
+        assert_eq!(planner.offset_of("data").unwrap(), 32);
+        assert_eq!(planner.end().unwrap(), Some(38));
+
+        let mut cursor = Cursor::new(Vec::new());
+        // Header is written first, containing the offsets
+        for name in ["names", "table", "data"].iter() {
+            (planner.offset_of(name).unwrap() as u8)
+                .write_to(&mut cursor, ())
+                .unwrap();
+        }
+
+        // Then the sections, out of order
+        let mut data = planner.writer_for(&mut cursor, "data").unwrap();
+        0xAABBu16.write_to(&mut data, Endian::Big).unwrap();
+        let mut table = planner.writer_for(&mut cursor, "table").unwrap();
+        0x01020304u32.write_to(&mut table, Endian::Little).unwrap();
+        0x05060708u32.write_to(&mut table, Endian::Little).unwrap();
+        0x0u8
+            .write_to(&mut table, ())
+            .expect_err("Expected error writing past the end of the section");
+        let mut names = planner.writer_for(&mut cursor, "names").unwrap();
+        Bytes(b"abc").write_to(&mut names, ()).unwrap();
+        planner.finish(&mut cursor).unwrap();
+        assert_eq!(cursor.position(), 38);
+
+        let output = cursor.into_inner();
+        assert_eq!(output.len(), 38);
+        let mut cursor = Cursor::new(output);
+        let mut map = SectionMap::with_file_len(38);
+        for name in ["names", "table", "data"].iter() {
+            let offset = u8::parse(&mut cursor, ()).unwrap() as u64;
+            let len = planner.layout().unwrap().get(name).unwrap().len;
+            map.insert(name, offset, len).unwrap();
+        }
+        assert_eq!(map.gaps(), [0..9, 20..32]);
+
+        let mut names = map.slice_of(&mut cursor, "names").unwrap();
+        assert_eq!(crate::parse::take(&mut names, 3).unwrap(), b"abc");
+        let mut table = map.slice_of(&mut cursor, "table").unwrap();
+        assert_eq!(u32::parse(&mut table, Endian::Little).unwrap(), 0x01020304);
+        assert_eq!(u32::parse(&mut table, Endian::Little).unwrap(), 0x05060708);
+        let mut data = map.slice_of(&mut cursor, "data").unwrap();
+        assert_eq!(u16::parse(&mut data, Endian::Big).unwrap(), 0xAABB);
+        // Underfilled, so zero-padded
+        assert_eq!(u32::parse(&mut data, Endian::Big).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_layout_planner_overwrite() {
+        use crate::writable::Writable;
+
+        let mut planner = LayoutPlanner::new();
+        planner.add("first", 2, 1).unwrap();
+        planner.add("middle", 6, 4).unwrap();
+        planner.add("last", 2, 8).unwrap();
+        planner.add("unwritten", 3, 1).unwrap();
+        planner.finalize(1).unwrap();
+        assert_eq!(planner.offset_of("middle").unwrap(), 4);
+        assert_eq!(planner.offset_of("last").unwrap(), 16);
+        assert_eq!(planner.end().unwrap(), Some(21));
+
+        // Existing data, which everything in the layout should replace
+        let mut cursor = Cursor::new(vec![0xFF; 24]);
+        let mut last = planner.writer_for(&mut cursor, "last").unwrap();
+        Bytes(b"yz").write_to(&mut last, ()).unwrap();
+        assert_eq!(last.remaining(), 0);
+        let mut middle = planner.writer_for(&mut cursor, "middle").unwrap();
+        Bytes(b"ab").write_to(&mut middle, ()).unwrap();
+        // Seeking back doesn't lose what was written past it
+        middle.seek(SeekFrom::Start(3)).unwrap();
+        Bytes(b"c").write_to(&mut middle, ()).unwrap();
+        middle.seek(SeekFrom::Start(1)).unwrap();
+        assert_eq!(middle.remaining(), 5);
+        let mut first = planner.writer_for(&mut cursor, "first").unwrap();
+        Bytes(b"12").write_to(&mut first, ()).unwrap();
+        planner.finish(&mut cursor).unwrap();
+        assert_eq!(cursor.position(), 21);
+
+        assert_eq!(
+            cursor.into_inner(),
+            [
+                0xFF, // before the layout, so untouched
+                b'1', b'2', // first
+                0,    // alignment
+                b'a', b'b', 0, b'c', // middle, including the byte that was seeked over
+                0, 0, // middle, underfilled
+                0, 0, 0, 0, 0, 0, // alignment
+                b'y', b'z', // last
+                0, 0, 0, // unwritten
+                0xFF, 0xFF, 0xFF, // after the layout, so untouched
+            ]
+        );
+    }
+}
@@ -1,22 +1,84 @@
-use crate::stream_position;
+use crate::{read_if_possible, stream_len, stream_position};
 use std::{
-    io::{self, Read, Seek, SeekFrom},
-    ops::{Bound, RangeBounds, RangeInclusive},
+    convert::TryFrom,
+    fs::File,
+    io::{self, BufRead, IoSliceMut, Read, Seek, SeekFrom, Write},
+    ops::{Bound, Range, RangeBounds},
+    rc::Rc,
+    sync::Arc,
 };
 
+/// Converts [range] into `[start, end)`, with `u64::MAX` as the end of unbounded ranges.
+/// An inverted range is treated as empty, at its start.
+fn exclusive_bounds<R>(range: R) -> (u64, u64)
+where
+    R: RangeBounds<u64>,
+{
+    let start = match range.start_bound() {
+        Bound::Unbounded => 0,
+        Bound::Included(x) => *x,
+        Bound::Excluded(x) => x.saturating_add(1),
+    };
+    let end = match range.end_bound() {
+        Bound::Unbounded => u64::MAX,
+        Bound::Included(x) => x.saturating_add(1),
+        Bound::Excluded(x) => *x,
+    };
+    (start, end.max(start))
+}
+
+/// Checks that [position] is within `[start, end]`, for the checked slice constructors.
+fn check_position(position: u64, start: u64, end: u64) -> io::Result<()> {
+    if start <= position && position <= end {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "position {} is outside of the slice range {}..{}",
+                position, start, end
+            ),
+        ))
+    }
+}
+
+/// Converts the absolute [position] into one relative to [start], erroring with
+/// `ErrorKind::InvalidData` if it is before it.
+/// That can only happen if the inner reader was moved outside of the slice, see
+/// `InputSlice::resync`.
+fn relative_position(position: u64, start: u64) -> io::Result<u64> {
+    position.checked_sub(start).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "position {} is before the start of the slice at {}",
+                position, start
+            ),
+        )
+    })
+}
+
 /// This was created because Take doesn't support Seek
+/// Note: an end of `u64::MAX` is used for unbounded ranges (and `..=u64::MAX`), since no data
+/// can be at that position anyway.
 #[derive(Debug)]
 pub struct InputSlice<F: Read> {
     input: F,
-    range: RangeInclusive<u64>,
+    start: u64,
+    /// Exclusive, and always `>= start`
+    end: u64,
+    /// Absolute position of [input], if known. Kept up to date by reads and seeks so that
+    /// reading doesn't have to seek to find out where it is.
+    position: Option<u64>,
+    strict: bool,
 }
 impl<F> InputSlice<F>
 where
     F: Read,
 {
     /// Creates `InputSlice` instance withprovided [range] and [range]
-    /// Panics if the input is unsound, by getting current position
-    /// and checking if it is within [range].
+    /// Errors with `ErrorKind::InvalidInput` if the input is unsound, by getting current
+    /// position and checking if it is within [range].
     /// Does not modify current position.
     #[inline]
     pub fn new<R>(mut input: F, range: R) -> std::io::Result<Self>
@@ -24,32 +86,47 @@ where
         R: RangeBounds<u64>,
         F: Seek,
     {
-        let position = input.seek(SeekFrom::Current(0))?;
-        assert!(range.contains(&position));
-        Ok(Self::new_unchecked(input, range))
+        let position = input.stream_position()?;
+        let mut slice = Self::new_unchecked(input, range);
+        check_position(position, slice.start, slice.end)?;
+        slice.position = Some(position);
+        Ok(slice)
     }
 
     /// Creates a new `InputSlice` instance with the provided range and file.
     /// Unlike `InputSlice::new` this does not check if the [input] is sound.
     /// # Soundness: Requires
     ///  `range.start() <= input.seek(SeekFrom::Current(0)) <= range.end()`
+    /// An inverted range (such as `5..3`) is treated as empty, at its start.
     #[inline]
     pub fn new_unchecked<R>(input: F, range: R) -> Self
     where
         R: RangeBounds<u64>,
     {
-        let start = match range.start_bound() {
-            Bound::Unbounded => 0,
-            Bound::Included(x) => *x,
-            Bound::Excluded(x) => x + 1,
-        };
-        let end = match range.end_bound() {
-            Bound::Unbounded => u64::MAX,
-            Bound::Included(x) => *x,
-            Bound::Excluded(x) => x - 1,
-        };
-        let range = RangeInclusive::new(start, end);
-        InputSlice { input, range }
+        let (start, end) = exclusive_bounds(range);
+        InputSlice {
+            input,
+            start,
+            end,
+            position: None,
+            strict: false,
+        }
+    }
+
+    /// Creates a slice of [range] over a borrowed [input], and seeks to the start of it.
+    /// The input can be used again once the slice is dropped, so one file can be sliced into
+    /// several sections one after another. For slices that are live at the same time see
+    /// [SharedSlice].
+    #[inline]
+    pub fn by_ref<R>(input: &mut F, range: R) -> std::io::Result<InputSlice<&mut F>>
+    where
+        R: RangeBounds<u64>,
+        F: Seek,
+    {
+        let mut slice = InputSlice::new_unchecked(input, range);
+        slice.input.seek(SeekFrom::Start(slice.start))?;
+        slice.position = Some(slice.start);
+        Ok(slice)
     }
 
     /// Creates an InputSlice at current position, for [amount] bytes
@@ -61,37 +138,81 @@ where
     where
         F: Seek,
     {
-        let start = input.seek(SeekFrom::Current(0))?;
+        let start = input.stream_position()?;
         let end = start.saturating_add(amount);
-        Ok(Self::new_unchecked(input, start..=end))
+        Ok(Self::new_unchecked(input, start..end))
+    }
+
+    /// Sets whether seeks outside of the range error, rather than being clamped to the end.
+    /// Sub-slices inherit this.
+    #[inline]
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    #[inline]
+    pub fn is_strict(&self) -> bool {
+        self.strict
     }
 
     /// Returns inclusive start
     #[inline]
     pub fn start(&self) -> u64 {
-        *self.range.start()
+        self.start
     }
 
-    /// Returns inclusive end
+    /// Returns inclusive end, the position of the last byte within the slice.
+    /// Returns `None` if the slice is empty, as it has no last byte.
     #[inline]
-    pub fn last(&self) -> u64 {
-        *self.range.end()
+    pub fn last(&self) -> Option<u64> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.end - 1)
+        }
     }
 
     /// Returns exclusive end
     #[inline]
     pub fn end(&self) -> u64 {
-        *self.range.end() + 1
+        self.end
+    }
+
+    /// Returns the length of the range, `end - start`.
+    /// Note: for unbounded ranges this is up to `u64::MAX`, rather than the length of the input,
+    /// see `stream_len` for that.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Returns the number of bytes from the current position to the end of the range, or 0 if
+    /// the input is positioned past it.
+    /// Note: this doesn't check whether the input ends before the range, see [stream_len].
+    /// This uses the cached position, so usually doesn't have to seek.
+    #[inline]
+    pub fn remaining(&mut self) -> std::io::Result<u64>
+    where
+        F: Seek,
+    {
+        let abs_position = self.absolute_stream_position()?;
+        Ok(self.get_distance_from_end(abs_position))
     }
 
     #[inline]
     pub fn contains(&self, position: u64) -> bool {
-        self.range.contains(&position)
+        self.start <= position && position < self.end
     }
 
     #[inline]
-    pub fn range(&self) -> &RangeInclusive<u64> {
-        &self.range
+    pub fn range(&self) -> Range<u64> {
+        self.start..self.end
     }
 
     #[inline]
@@ -104,21 +225,42 @@ where
         &self.input
     }
 
-    /// Note: one should be careful with this handle, as that might invalidate
+    /// Note: one should be careful with this handle, as moving the inner reader through it
+    /// invalidates the cached position. So the cache is cleared, and the next read or seek
+    /// resynchronizes by asking the input.
     #[inline]
     pub fn get_mut(&mut self) -> &mut F {
+        self.position = None;
         &mut self.input
     }
 
     // TODO: Once `Seek::stream_position` is stabilized, use that instead.
-    // TODO: don't assume that we can subtract these two values safely
     /// Note: returns the position within this slice, rather than in the containing input as a whole
+    /// Errors with `ErrorKind::InvalidData` if the input is positioned before the slice.
     #[inline]
     pub fn stream_position(&mut self) -> std::io::Result<u64>
     where
         F: Seek,
     {
-        Ok(self.absolute_stream_position()? - self.start())
+        relative_position(self.absolute_stream_position()?, self.start())
+    }
+
+    /// Asks the input where it is, and seeks it back into the range if it is outside of it:
+    /// to the start if it is before it, or the end if it is past it.
+    /// For after moving the input through [get_mut], or creating the slice with
+    /// [new_unchecked] at an unsound position. Returns the position within the slice.
+    pub fn resync(&mut self) -> std::io::Result<u64>
+    where
+        F: Seek,
+    {
+        self.position = None;
+        let position = stream_position(&mut self.input)?;
+        let clamped = position.max(self.start).min(self.end);
+        if clamped != position {
+            self.input.seek(SeekFrom::Start(clamped))?;
+        }
+        self.position = Some(clamped);
+        Ok(clamped - self.start)
     }
 
     #[inline]
@@ -126,42 +268,262 @@ where
     where
         F: Seek,
     {
+        if let Some(position) = self.position {
+            return Ok(position);
+        }
+
         // Have to use it on input otherwise we get infinite-recursion due to `seek` using
         // self.stream_position internally!
-        stream_position(&mut self.input)
+        let position = stream_position(&mut self.input)?;
+        self.position = Some(position);
+        Ok(position)
     }
 
-    // TODO: use `crate::stream_len`
-    // TODO: once `Seek::stream_len` is stabilized, use that instead.
-    // We will still need to wrap around the stabilzied function
+    /// Returns the number of bytes that can actually be read from the slice, which is shorter
+    /// than the range if the input ends before the range does (such as for unbounded ranges).
     #[inline]
     pub fn stream_len(&mut self) -> std::io::Result<u64>
     where
         F: Seek,
     {
-        let old_pos = self.stream_position()?;
-        let len = self.seek(SeekFrom::Start(self.end()))?;
-
-        // Avoid seeking a third time if we're already at the end
-        if old_pos != len {
-            self.seek(SeekFrom::Start(old_pos))?;
-        }
-
-        // TODO: check this calculation
-        let len = len.min(self.end() - self.start());
+        let input_len = stream_len(&mut self.input)?;
+        Ok(input_len.min(self.end).saturating_sub(self.start))
+    }
 
-        Ok(len)
+    /// Returns whether there is nothing left to read, because the current position is at (or
+    /// past) the end of the range or the end of the input, whichever comes first.
+    /// Unlike [is_position_at_end], this accounts for an input shorter than the range.
+    #[inline]
+    pub fn position_at_end(&mut self) -> std::io::Result<bool>
+    where
+        F: Seek,
+    {
+        let position = self.stream_position()?;
+        Ok(position >= self.stream_len()?)
     }
 
     #[inline]
     pub fn get_distance_from_end(&self, position: u64) -> u64 {
-        self.end() - position
+        self.end.saturating_sub(position)
     }
 
     #[inline]
     pub fn is_position_at_end(&self, position: u64) -> bool {
         position == self.end()
     }
+
+    /// Converts [range], relative to the start of this slice, into absolute bounds.
+    /// An unbounded end is clamped to the end of this slice, while explicit bounds past it
+    /// are an error.
+    fn child_bounds<R>(&self, range: R) -> std::io::Result<(u64, u64)>
+    where
+        R: RangeBounds<u64>,
+    {
+        let escapes = || {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "sub-slice range escapes the parent slice",
+            )
+        };
+        let len = self.end - self.start;
+        let start = match range.start_bound() {
+            Bound::Unbounded => 0,
+            Bound::Included(x) => *x,
+            Bound::Excluded(x) => x.checked_add(1).ok_or_else(escapes)?,
+        };
+        let end = match range.end_bound() {
+            Bound::Unbounded => len,
+            Bound::Included(x) => x.checked_add(1).ok_or_else(escapes)?,
+            Bound::Excluded(x) => *x,
+        };
+        if start > end || end > len {
+            return Err(escapes());
+        }
+
+        Ok((self.start + start, self.start + end))
+    }
+
+    /// Creates a slice of [range] within this slice, relative to its start, and seeks to the
+    /// start of it. The child can't read or seek past the end of this slice.
+    /// Errors if [range] extends past the end of this slice, though an unbounded end is
+    /// clamped to it.
+    pub fn slice<R>(&mut self, range: R) -> std::io::Result<InputSlice<&mut F>>
+    where
+        R: RangeBounds<u64>,
+        F: Seek,
+    {
+        let (start, end) = self.child_bounds(range)?;
+        // The child moves the input without us knowing
+        self.position = None;
+        self.input.seek(SeekFrom::Start(start))?;
+        Ok(InputSlice {
+            input: &mut self.input,
+            start,
+            end,
+            position: Some(start),
+            strict: self.strict,
+        })
+    }
+
+    /// Like [slice], but consumes this slice.
+    pub fn into_slice<R>(mut self, range: R) -> std::io::Result<InputSlice<F>>
+    where
+        R: RangeBounds<u64>,
+        F: Seek,
+    {
+        let (start, end) = self.child_bounds(range)?;
+        self.input.seek(SeekFrom::Start(start))?;
+        Ok(InputSlice {
+            input: self.input,
+            start,
+            end,
+            position: Some(start),
+            strict: self.strict,
+        })
+    }
+
+    /// Iterates over windows of [window] bytes, starting at the current position, with the
+    /// start of each window [step] bytes after the previous one. If `step < window` then the
+    /// windows overlap.
+    /// Yields the absolute offset of each window along with its bytes. A final window that is
+    /// shorter than [window] is yielded by default, see `WindowsIter::short_windows`.
+    /// Panics if [window] or [step] is 0.
+    #[inline]
+    pub fn windows_iter(&mut self, window: usize, step: usize) -> WindowsIter<'_, F>
+    where
+        F: Seek,
+    {
+        WindowsIter {
+            slice: self,
+            state: WindowState::new(window, step, ShortWindow::Yield),
+        }
+    }
+
+    /// Iterates over consecutive, non-overlapping chunks of [size] bytes.
+    /// Equivalent to `windows_iter(size, size)`.
+    #[inline]
+    pub fn chunks_iter(&mut self, size: usize) -> WindowsIter<'_, F>
+    where
+        F: Seek,
+    {
+        self.windows_iter(size, size)
+    }
+
+    /// Like [windows_iter], but calls [callback] with each window instead, which lets the
+    /// same buffer be reused for every window.
+    /// Panics if [window] or [step] is 0.
+    pub fn for_each_window<C>(
+        &mut self,
+        window: usize,
+        step: usize,
+        short: ShortWindow,
+        mut callback: C,
+    ) -> std::io::Result<()>
+    where
+        F: Seek,
+        C: FnMut(u64, &[u8]),
+    {
+        let mut state = WindowState::new(window, step, short);
+        let mut buffer = Vec::with_capacity(window);
+        while let Some(offset) = state.advance(self, &mut buffer)? {
+            callback(offset, &buffer);
+        }
+        Ok(())
+    }
+}
+
+/// What to do with a final window that is shorter than the requested window size.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum ShortWindow {
+    Yield,
+    Skip,
+}
+
+#[derive(Debug)]
+struct WindowState {
+    window: usize,
+    step: usize,
+    short: ShortWindow,
+    done: bool,
+}
+impl WindowState {
+    fn new(window: usize, step: usize, short: ShortWindow) -> Self {
+        assert!(window != 0, "window size must be nonzero");
+        assert!(step != 0, "window step must be nonzero");
+        WindowState {
+            window,
+            step,
+            short,
+            done: false,
+        }
+    }
+
+    /// Reads the next window into [buffer], returning its absolute offset.
+    fn advance<F>(
+        &mut self,
+        slice: &mut InputSlice<F>,
+        buffer: &mut Vec<u8>,
+    ) -> std::io::Result<Option<u64>>
+    where
+        F: Read + Seek,
+    {
+        if self.done {
+            return Ok(None);
+        }
+
+        // Mark as done up front, so that we're fused after an error
+        self.done = true;
+        let position = slice.stream_position()?;
+        buffer.resize(self.window, 0);
+        let amount_read = read_if_possible(&mut *slice, buffer)?;
+        buffer.truncate(amount_read);
+
+        if amount_read == 0 || (amount_read < self.window && self.short == ShortWindow::Skip) {
+            return Ok(None);
+        }
+
+        if amount_read == self.window {
+            // Seeks backwards if the windows overlap. This is clamped to the end of the slice
+            // here, rather than by the seek, so that it works for strict slices.
+            let next = position.saturating_add(self.step as u64).min(slice.len());
+            slice.seek(SeekFrom::Start(next))?;
+            self.done = false;
+        }
+
+        Ok(Some(slice.start() + position))
+    }
+}
+
+/// Iterator over windows of an [InputSlice], see `InputSlice::windows_iter`.
+#[derive(Debug)]
+pub struct WindowsIter<'a, F: Read> {
+    slice: &'a mut InputSlice<F>,
+    state: WindowState,
+}
+impl<'a, F> WindowsIter<'a, F>
+where
+    F: Read,
+{
+    /// Sets what to do with a final window that's shorter than the window size.
+    #[inline]
+    pub fn short_windows(mut self, short: ShortWindow) -> Self {
+        self.state.short = short;
+        self
+    }
+}
+impl<'a, F> Iterator for WindowsIter<'a, F>
+where
+    F: Read + Seek,
+{
+    type Item = std::io::Result<(u64, Vec<u8>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut buffer = Vec::new();
+        self.state
+            .advance(self.slice, &mut buffer)
+            .transpose()
+            .map(|x| x.map(|offset| (offset, buffer)))
+    }
 }
 impl<F> Read for InputSlice<F>
 where
@@ -179,25 +541,91 @@ where
         let dist = self.get_distance_from_end(abs_position);
         let max = std::cmp::min(buf.len() as u64, dist) as usize;
         let buf = &mut buf[..max];
-        let amount_read = self.input.read(buf)?;
-        debug_assert!(self.absolute_stream_position().unwrap() <= self.end());
+        let amount_read = match self.input.read(buf) {
+            Ok(amount_read) => amount_read,
+            Err(e) => {
+                // Can't be sure how far the input got
+                self.position = None;
+                return Err(e);
+            }
+        };
+        let position = abs_position + amount_read as u64;
+        debug_assert!(position <= self.end());
+        self.position = Some(position);
+        Ok(amount_read)
+    }
+
+    /// Calls [input]'s `read_vectored`, with [bufs] cut off at the end of the slice.
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> std::io::Result<usize> {
+        let abs_position = self.absolute_stream_position()?;
+        if self.is_position_at_end(abs_position) {
+            return Ok(0);
+        }
+
+        let mut dist = self.get_distance_from_end(abs_position);
+        let mut bounded = Vec::with_capacity(bufs.len());
+        for buf in bufs.iter_mut() {
+            if dist == 0 {
+                break;
+            }
+            let max = std::cmp::min(buf.len() as u64, dist) as usize;
+            dist -= max as u64;
+            bounded.push(IoSliceMut::new(&mut buf[..max]));
+        }
+        let amount_read = match self.input.read_vectored(&mut bounded) {
+            Ok(amount_read) => amount_read,
+            Err(e) => {
+                // Can't be sure how far the input got
+                self.position = None;
+                return Err(e);
+            }
+        };
+        let position = abs_position + amount_read as u64;
+        debug_assert!(position <= self.end());
+        self.position = Some(position);
         Ok(amount_read)
     }
 
     // TODO: it might be more efficient write wrappers around every <F as Read> method?
 }
+impl<F> BufRead for InputSlice<F>
+where
+    F: BufRead + Seek,
+{
+    /// Returns the inner reader's buffer, cut off at the end of the slice.
+    fn fill_buf(&mut self) -> std::io::Result<&[u8]> {
+        let abs_position = self.absolute_stream_position()?;
+        let dist = self.get_distance_from_end(abs_position);
+        if dist == 0 {
+            // Don't expose whatever the inner reader has buffered past the end
+            return Ok(&[]);
+        }
+
+        let buf = self.input.fill_buf()?;
+        let max = usize::try_from(dist).map_or(buf.len(), |dist| buf.len().min(dist));
+        Ok(&buf[..max])
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.input.consume(amt);
+        self.position = self.position.map(|x| x + amt as u64);
+    }
+}
 impl<F> Seek for InputSlice<F>
 where
     F: Read + Seek,
 {
-    /// If you seek beyond the end, behavior is to constrain you to `*self.range.end()`
+    /// If you seek beyond the end, behavior is to constrain you to `self.end()`.
+    /// In strict mode (see `InputSlice::strict`) that is instead an `ErrorKind::InvalidInput`
+    /// error, and the position is left unchanged.
+    /// Note: `SeekFrom::End` is relative to the end of the data, as given by `stream_len`.
     #[inline]
     fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
         // Get position and offset
         let (base_pos, offset) = match pos {
             SeekFrom::Start(pos) => (pos, 0),
             SeekFrom::Current(off) => (self.stream_position()?, off),
-            SeekFrom::End(off) => (self.end(), off),
+            SeekFrom::End(off) => (self.stream_len()?, off),
         };
 
         // Add the position and offset, properly handling negatives
@@ -221,76 +649,699 @@ where
             )
         })?;
 
-        // Clamp to new_pos
-        let new_pos = if self.contains(new_pos) {
-            new_pos
-        } else {
-            self.end()
-        };
+        if self.strict && new_pos > self.end {
+            return Err(std::io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek past the end of the slice",
+            ));
+        }
+
+        // Clamp to the end
+        let new_pos = new_pos.min(self.end);
 
         // TODO: this shouldn't use a different type of SeekFrom as some
         //  Seek impls may not support it.
 
-        self.input
-            .seek(SeekFrom::Start(new_pos))
-            // Subtract the start offset, so that the returned 'new position' is valid for our range
-            // TODO: test this!
-            // TODO: this should probably be a checked subtraction!
-            .map(|x| x - self.start())
+        let result = self.input.seek(SeekFrom::Start(new_pos));
+        self.position = result.as_ref().ok().copied();
+        // Subtract the start offset, so that the returned 'new position' is valid for our range
+        relative_position(result?, self.start())
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::InputSlice;
-    use std::io::{Cursor, Read, Seek, SeekFrom};
+/// Like [InputSlice], but for readers that can't seek (sockets, decompressors, ...).
+/// Tracks the number of bytes consumed itself, and never reads more than [limit] bytes from
+/// the input.
+/// If the input is Seek, then this is too, with positions relative to where it was created.
+#[derive(Debug)]
+pub struct InputLimit<F: Read> {
+    input: F,
+    consumed: u64,
+    limit: u64,
+}
+impl<F> InputLimit<F>
+where
+    F: Read,
+{
+    #[inline]
+    pub fn new(input: F, limit: u64) -> Self {
+        InputLimit {
+            input,
+            consumed: 0,
+            limit,
+        }
+    }
 
-    #[test]
-    fn test_general() {
-        const INPUT: &[u8] = &[
-            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0xa, 0xb, 0xc, 0xd, 0xe, 0xf, 0x10,
-        ];
-        let input_length = INPUT.len() as u64;
-        println!("Data length: {}", input_length);
-        let cursor = Cursor::new(INPUT);
-        // This should be 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15 ,16
-        // so [0, 16], or as written: [0, 17)
-        let mut slice = InputSlice::new(cursor, 0..input_length).unwrap();
-        println!("Slice range: [{}, {}]", slice.start(), slice.end());
-        assert_eq!(slice.stream_position().unwrap(), 0);
-        assert_eq!(slice.stream_len().unwrap(), 17);
+    #[inline]
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
 
-        let mut data = [0u8; 4];
-        slice.read_exact(&mut data).unwrap();
-        assert_eq!(data, [0, 1, 2, 3]);
-        assert_eq!(slice.stream_position().unwrap(), 4);
+    /// Returns the number of bytes read so far (or the position, if it has been seeked).
+    #[inline]
+    pub fn stream_position(&self) -> u64 {
+        self.consumed
+    }
 
-        slice.read_exact(&mut data).unwrap();
-        assert_eq!(data, [4, 5, 6, 7]);
-        assert_eq!(slice.stream_position().unwrap(), 8);
+    /// Returns the limit, as that's the most that can be read.
+    /// Note: the input may end before this.
+    #[inline]
+    pub fn stream_len(&self) -> u64 {
+        self.limit
+    }
 
-        slice.read_exact(&mut data).unwrap();
-        assert_eq!(data, [8, 9, 0xa, 0xb]);
-        assert_eq!(slice.stream_position().unwrap(), 12);
+    /// Returns the number of bytes that can still be read before hitting the limit.
+    #[inline]
+    pub fn remaining(&self) -> u64 {
+        self.limit.saturating_sub(self.consumed)
+    }
 
-        slice.read_exact(&mut data).unwrap();
-        assert_eq!(data, [0xc, 0xd, 0xe, 0xf]);
-        assert_eq!(slice.stream_position().unwrap(), 16);
+    #[inline]
+    pub fn into_inner(self) -> F {
+        self.input
+    }
 
-        slice
-            .read_exact(&mut data)
-            .expect_err("Expected error when reading last byte!");
+    #[inline]
+    pub fn get_ref(&self) -> &F {
+        &self.input
+    }
 
-        assert_eq!(slice.seek(SeekFrom::Start(13)).unwrap(), 13);
-        assert_eq!(slice.stream_position().unwrap(), 13);
-        slice.read_exact(&mut data).unwrap();
-        assert_eq!(data, [0xd, 0xe, 0xf, 0x10]);
-        assert_eq!(slice.stream_position().unwrap(), input_length);
+    /// Note: reading through this handle is not tracked, which will invalidate the position.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut F {
+        &mut self.input
+    }
+}
+impl<F> Read for InputLimit<F>
+where
+    F: Read,
+{
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let max = buf
+            .len()
+            .min(usize::try_from(self.remaining()).unwrap_or(usize::MAX));
+        if max == 0 {
+            return Ok(0);
+        }
 
-        let data = slice.into_inner().into_inner();
-        let mut cursor = Cursor::new(data);
+        let amount_read = self.input.read(&mut buf[..max])?;
+        self.consumed += amount_read as u64;
+        Ok(amount_read)
+    }
+}
+impl<F> Seek for InputLimit<F>
+where
+    F: Read + Seek,
+{
+    /// Seeks relative to the position the input was at when this was created, constrained to
+    /// `[0, limit]`. `SeekFrom::End` is relative to the limit.
+    /// Note: the input is seeked relatively, so it does not need to support `SeekFrom::Start`.
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let (base_pos, offset) = match pos {
+            SeekFrom::Start(pos) => (pos, 0),
+            SeekFrom::Current(off) => (self.consumed, off),
+            SeekFrom::End(off) => (self.limit, off),
+        };
 
-        // TODO: it would good to do this test
+        let new_pos = if offset >= 0 {
+            base_pos.checked_add(offset as u64)
+        } else {
+            base_pos.checked_sub(offset.wrapping_neg() as u64)
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?
+        .min(self.limit);
+
+        let delta = if new_pos >= self.consumed {
+            i64::try_from(new_pos - self.consumed)
+        } else {
+            i64::try_from(self.consumed - new_pos).map(|x| -x)
+        }
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "seek distance is too large"))?;
+
+        self.input.seek(SeekFrom::Current(delta))?;
+        self.consumed = new_pos;
+        Ok(new_pos)
+    }
+}
+
+/// Several ranges of one input, read as if they were a single contiguous stream.
+/// Positions are relative to the start of the first extent, with each extent following
+/// directly after the previous one.
+/// Note: a read doesn't cross from one extent into the next, so it can return fewer bytes
+/// than requested even when more are available (like `std::io::Chain`).
+#[derive(Debug)]
+pub struct MultiSlice<F: Read + Seek> {
+    input: F,
+    extents: Vec<Range<u64>>,
+    /// Cumulative end of each extent, within the stream
+    ends: Vec<u64>,
+    position: u64,
+    /// Absolute position of [input], if known
+    input_position: Option<u64>,
+}
+impl<F> MultiSlice<F>
+where
+    F: Read + Seek,
+{
+    /// Creates a stream over [extents], in the given order.
+    /// Does not modify the current position, the input is only sought when reading.
+    /// An inverted range (such as `5..3`) is treated as empty.
+    pub fn new(input: F, extents: Vec<Range<u64>>) -> Self {
+        let mut total = 0u64;
+        let extents: Vec<Range<u64>> = extents
+            .into_iter()
+            .map(|x| x.start..x.end.max(x.start))
+            .collect();
+        let ends = extents
+            .iter()
+            .map(|x| {
+                total = total.saturating_add(x.end - x.start);
+                total
+            })
+            .collect();
+        MultiSlice {
+            input,
+            extents,
+            ends,
+            position: 0,
+            input_position: None,
+        }
+    }
+
+    #[inline]
+    pub fn extents(&self) -> &[Range<u64>] {
+        &self.extents
+    }
+
+    /// Returns the sum of the extent lengths
+    #[inline]
+    pub fn stream_len(&self) -> u64 {
+        self.ends.last().copied().unwrap_or(0)
+    }
+
+    /// Note: returns the position within the stream, rather than in the input
+    #[inline]
+    pub fn stream_position(&self) -> u64 {
+        self.position
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> F {
+        self.input
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &F {
+        &self.input
+    }
+
+    /// Forgets where the input is, so the next read seeks it to the right position.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut F {
+        self.input_position = None;
+        &mut self.input
+    }
+
+    /// Returns the absolute position of [position] in the input, along with the number of
+    /// bytes left in its extent. `None` if it is at or past the end of the stream.
+    fn locate(&self, position: u64) -> Option<(u64, u64)> {
+        // Skips over empty extents, as their end is the same as the previous one's
+        let index = self.ends.partition_point(|&end| end <= position);
+        let extent = self.extents.get(index)?;
+        let left = self.ends[index] - position;
+        Some((extent.end - left, left))
+    }
+}
+impl<F> Read for MultiSlice<F>
+where
+    F: Read + Seek,
+{
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let (abs_position, left) = match self.locate(self.position) {
+            Some(x) => x,
+            None => return Ok(0),
+        };
+
+        if self.input_position != Some(abs_position) {
+            self.input_position = None;
+            self.input.seek(SeekFrom::Start(abs_position))?;
+        }
+
+        let max = usize::try_from(left).unwrap_or(usize::MAX).min(buf.len());
+        let amount_read = self.input.read(&mut buf[..max])?;
+        self.position += amount_read as u64;
+        self.input_position = Some(abs_position + amount_read as u64);
+        Ok(amount_read)
+    }
+}
+impl<F> Seek for MultiSlice<F>
+where
+    F: Read + Seek,
+{
+    /// Like `InputSlice`, seeking beyond the end constrains you to the end of the stream.
+    /// This only moves the input when next reading.
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let (base_pos, offset) = match pos {
+            SeekFrom::Start(pos) => (pos, 0),
+            SeekFrom::Current(off) => (self.position, off),
+            SeekFrom::End(off) => (Self::stream_len(self), off),
+        };
+
+        let new_pos = if offset >= 0 {
+            base_pos.checked_add(offset as u64)
+        } else {
+            base_pos.checked_sub(offset.wrapping_neg() as u64)
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        self.position = new_pos.min(Self::stream_len(self));
+        Ok(self.position)
+    }
+}
+
+/// Reading at an offset without a shared position, so that several readers (such as
+/// [SharedSlice]s) can use the same input at once.
+pub trait ReadAt {
+    /// Reads into [buf] from [offset], returning the number of bytes read, which is 0 at or
+    /// past the end of the data.
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize>;
+}
+impl ReadAt for [u8] {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        let data = match usize::try_from(offset) {
+            Ok(offset) if offset < self.len() => &self[offset..],
+            _ => return Ok(0),
+        };
+        let amount = data.len().min(buf.len());
+        buf[..amount].copy_from_slice(&data[..amount]);
+        Ok(amount)
+    }
+}
+impl ReadAt for Vec<u8> {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        self.as_slice().read_at(buf, offset)
+    }
+}
+/// Note: on Windows this moves the file's cursor, though the offset is still respected.
+#[cfg(any(unix, windows))]
+impl ReadAt for File {
+    #[inline]
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+        #[cfg(unix)]
+        return std::os::unix::fs::FileExt::read_at(self, buf, offset);
+        #[cfg(windows)]
+        return std::os::windows::fs::FileExt::seek_read(self, buf, offset);
+    }
+}
+macro_rules! impl_read_at_pointer {
+    ($($ptr:ty),*) => {
+        $(
+            impl<T> ReadAt for $ptr
+            where
+                T: ReadAt + ?Sized,
+            {
+                #[inline]
+                fn read_at(&self, buf: &mut [u8], offset: u64) -> std::io::Result<usize> {
+                    (**self).read_at(buf, offset)
+                }
+            }
+        )*
+    };
+}
+impl_read_at_pointer!(&T, Box<T>, Rc<T>, Arc<T>);
+
+/// Like [InputSlice], but over a [ReadAt] input, with its own position. So, unlike
+/// `InputSlice<&mut F>`, any number of these can be live over one input at a time, such as
+/// several sections of an `Arc<File>`.
+/// Note: `SeekFrom::End` is relative to the end of the range, as a `ReadAt` input doesn't
+/// give its length. Reads stop early if the input is shorter than the range.
+#[derive(Debug, Clone)]
+pub struct SharedSlice<R: ReadAt> {
+    input: R,
+    start: u64,
+    /// Exclusive, and always `>= start`
+    end: u64,
+    /// Relative to [start]
+    position: u64,
+}
+impl<R> SharedSlice<R>
+where
+    R: ReadAt,
+{
+    /// Creates a slice of [range] over [input], positioned at its start.
+    /// An inverted range (such as `5..3`) is treated as empty, at its start.
+    #[inline]
+    pub fn new<B>(input: R, range: B) -> Self
+    where
+        B: RangeBounds<u64>,
+    {
+        let (start, end) = exclusive_bounds(range);
+        SharedSlice {
+            input,
+            start,
+            end,
+            position: 0,
+        }
+    }
+
+    /// Returns inclusive start
+    #[inline]
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Returns exclusive end
+    #[inline]
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    /// Returns the length of the range, `end - start`.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Note: returns the position within this slice, rather than in the input as a whole
+    #[inline]
+    pub fn stream_position(&self) -> u64 {
+        self.position
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.input
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.input
+    }
+}
+impl<R> Read for SharedSlice<R>
+where
+    R: ReadAt,
+{
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let dist = self.len().saturating_sub(self.position);
+        let max = usize::try_from(dist).map_or(buf.len(), |dist| buf.len().min(dist));
+        if max == 0 {
+            return Ok(0);
+        }
+
+        let amount_read = self
+            .input
+            .read_at(&mut buf[..max], self.start + self.position)?;
+        self.position += amount_read as u64;
+        Ok(amount_read)
+    }
+}
+impl<R> Seek for SharedSlice<R>
+where
+    R: ReadAt,
+{
+    /// Like `InputSlice`, seeking beyond the end constrains you to the end of the range.
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let (base_pos, offset) = match pos {
+            SeekFrom::Start(pos) => (pos, 0),
+            SeekFrom::Current(off) => (self.position, off),
+            SeekFrom::End(off) => (self.len(), off),
+        };
+
+        let new_pos = if offset >= 0 {
+            base_pos.checked_add(offset as u64)
+        } else {
+            base_pos.checked_sub(offset.wrapping_neg() as u64)
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        self.position = new_pos.min(self.len());
+        Ok(self.position)
+    }
+}
+
+/// The write counterpart to [InputSlice]: confines writes to `[start, end)` of the output.
+/// A write that would cross the end is truncated, returning a short write count (which makes
+/// `write_all` error with `ErrorKind::WriteZero`). In strict mode such a write instead errors
+/// with `ErrorKind::WriteZero` without writing anything.
+#[derive(Debug)]
+pub struct OutputSlice<W: Write> {
+    output: W,
+    start: u64,
+    /// Exclusive, and always `>= start`
+    end: u64,
+    strict: bool,
+}
+impl<W> OutputSlice<W>
+where
+    W: Write,
+{
+    /// Creates `OutputSlice` instance with provided [range].
+    /// Errors with `ErrorKind::InvalidInput` if the output is not positioned within [range].
+    /// Does not modify current position.
+    #[inline]
+    pub fn new<R>(mut output: W, range: R) -> std::io::Result<Self>
+    where
+        R: RangeBounds<u64>,
+        W: Seek,
+    {
+        let position = output.stream_position()?;
+        let slice = Self::new_unchecked(output, range);
+        check_position(position, slice.start, slice.end)?;
+        Ok(slice)
+    }
+
+    /// Creates a new `OutputSlice` over [range].
+    /// Does not check that the output is positioned within the range.
+    /// # Soundness: Requires
+    ///  `range.start() <= output.stream_position() <= range.end()`
+    #[inline]
+    pub fn new_unchecked<R>(output: W, range: R) -> Self
+    where
+        R: RangeBounds<u64>,
+    {
+        let (start, end) = exclusive_bounds(range);
+        OutputSlice {
+            output,
+            start,
+            end,
+            strict: false,
+        }
+    }
+
+    /// Creates an OutputSlice at the current position, for [amount] bytes.
+    /// Note: `[current position] + amount` performs saturating addition
+    #[inline]
+    pub fn at(mut output: W, amount: u64) -> std::io::Result<Self>
+    where
+        W: Seek,
+    {
+        let start = output.stream_position()?;
+        let end = start.saturating_add(amount);
+        Ok(Self::new_unchecked(output, start..end))
+    }
+
+    /// Sets whether writes that would cross the end error, rather than being truncated.
+    #[inline]
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    #[inline]
+    pub fn is_strict(&self) -> bool {
+        self.strict
+    }
+
+    /// Note: returns the position within this slice, rather than in the output as a whole
+    #[inline]
+    pub fn stream_position(&mut self) -> std::io::Result<u64>
+    where
+        W: Seek,
+    {
+        Ok(stream_position(&mut self.output)?.saturating_sub(self.start))
+    }
+
+    /// Returns the number of bytes that can still be written before the end.
+    #[inline]
+    pub fn remaining(&mut self) -> std::io::Result<u64>
+    where
+        W: Seek,
+    {
+        Ok(self.end.saturating_sub(stream_position(&mut self.output)?))
+    }
+
+    /// Returns inclusive start
+    #[inline]
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// Returns exclusive end
+    #[inline]
+    pub fn end(&self) -> u64 {
+        self.end
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &W {
+        &self.output
+    }
+
+    /// Note: one should be careful with this handle, as moving the inner writer outside of the
+    /// range means later writes may not be bounded correctly.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.output
+    }
+}
+impl<W> Write for OutputSlice<W>
+where
+    W: Write + Seek,
+{
+    #[inline]
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let dist = self.remaining()?;
+        if self.strict && buf.len() as u64 > dist {
+            return Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                format!(
+                    "write of {} bytes would cross the end of the slice, with {} remaining",
+                    buf.len(),
+                    dist
+                ),
+            ));
+        }
+
+        let max = std::cmp::min(buf.len() as u64, dist) as usize;
+        if max == 0 {
+            return Ok(0);
+        }
+
+        self.output.write(&buf[..max])
+    }
+
+    #[inline]
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.output.flush()
+    }
+}
+impl<W> Seek for OutputSlice<W>
+where
+    W: Write + Seek,
+{
+    /// If you seek beyond the end, behavior is to constrain you to the end.
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+        let (base_pos, offset) = match pos {
+            SeekFrom::Start(pos) => (pos, 0),
+            SeekFrom::Current(off) => (self.stream_position()?, off),
+            SeekFrom::End(off) => (self.end - self.start, off),
+        };
+
+        let new_pos = if offset >= 0 {
+            base_pos.checked_add(offset as u64)
+        } else {
+            base_pos.checked_sub(offset.wrapping_neg() as u64)
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        let new_pos = self.start.saturating_add(new_pos).min(self.end);
+        self.output.seek(SeekFrom::Start(new_pos))?;
+        Ok(new_pos - self.start)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        InputLimit, InputSlice, MultiSlice, OutputSlice, ReadAt, SharedSlice, ShortWindow,
+    };
+    use std::{
+        io::{Cursor, Read, Seek, SeekFrom},
+        sync::Arc,
+    };
+
+    #[test]
+    fn test_general() {
+        const INPUT: &[u8] = &[
+            0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0xa, 0xb, 0xc, 0xd, 0xe, 0xf, 0x10,
+        ];
+        let input_length = INPUT.len() as u64;
+        println!("Data length: {}", input_length);
+        let cursor = Cursor::new(INPUT);
+        // This should be 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15 ,16
+        // so [0, 16], or as written: [0, 17)
+        let mut slice = InputSlice::new(cursor, 0..input_length).unwrap();
+        println!("Slice range: [{}, {}]", slice.start(), slice.end());
+        assert_eq!(slice.stream_position().unwrap(), 0);
+        assert_eq!(slice.stream_len().unwrap(), 17);
+
+        let mut data = [0u8; 4];
+        slice.read_exact(&mut data).unwrap();
+        assert_eq!(data, [0, 1, 2, 3]);
+        assert_eq!(slice.stream_position().unwrap(), 4);
+
+        slice.read_exact(&mut data).unwrap();
+        assert_eq!(data, [4, 5, 6, 7]);
+        assert_eq!(slice.stream_position().unwrap(), 8);
+
+        slice.read_exact(&mut data).unwrap();
+        assert_eq!(data, [8, 9, 0xa, 0xb]);
+        assert_eq!(slice.stream_position().unwrap(), 12);
+
+        slice.read_exact(&mut data).unwrap();
+        assert_eq!(data, [0xc, 0xd, 0xe, 0xf]);
+        assert_eq!(slice.stream_position().unwrap(), 16);
+
+        slice
+            .read_exact(&mut data)
+            .expect_err("Expected error when reading last byte!");
+
+        assert_eq!(slice.seek(SeekFrom::Start(13)).unwrap(), 13);
+        assert_eq!(slice.stream_position().unwrap(), 13);
+        slice.read_exact(&mut data).unwrap();
+        assert_eq!(data, [0xd, 0xe, 0xf, 0x10]);
+        assert_eq!(slice.stream_position().unwrap(), input_length);
+
+        let data = slice.into_inner().into_inner();
+        let mut cursor = Cursor::new(data);
+
+        // TODO: it would good to do this test
         // InputSlice::new(cursor, 3..length)
         //     .expect_err("Expected error to okay since not within slice range.");
 
@@ -341,8 +1392,775 @@ mod tests {
         let mut data = Vec::new();
         slice.read_to_end(&mut data).unwrap();
         assert_eq!(data, &[3, 4]);
+    }
+
+    #[test]
+    fn test_input_shorter_than_range() {
+        const INPUT: &[u8] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+        // The range extends 10 bytes past the end of the input
+        let mut cursor = Cursor::new(INPUT);
+        cursor.seek(SeekFrom::Start(4)).unwrap();
+        let mut slice = InputSlice::new(cursor, 4..20).unwrap();
+        assert_eq!(slice.len(), 16);
+        assert_eq!(slice.stream_len().unwrap(), 6);
+        assert_eq!(slice.stream_position().unwrap(), 0);
+        assert!(!slice.position_at_end().unwrap());
+
+        // A read straddling the real end only gets the bytes that exist
+        slice.seek(SeekFrom::Start(3)).unwrap();
+        let mut data = [0xFF; 8];
+        assert_eq!(slice.read(&mut data).unwrap(), 3);
+        assert_eq!(data[..3], [7, 8, 9]);
+        assert_eq!(data[3..], [0xFF; 5]);
+        assert_eq!(slice.stream_position().unwrap(), 6);
+        assert!(slice.position_at_end().unwrap());
+        let position = slice.absolute_stream_position().unwrap();
+        assert!(!slice.is_position_at_end(position));
+        assert_eq!(slice.read(&mut data).unwrap(), 0);
+
+        slice.seek(SeekFrom::Start(4)).unwrap();
+        let e = slice.read_exact(&mut [0; 4]).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof);
+        let mut data = Vec::new();
+        slice.seek(SeekFrom::Start(4)).unwrap();
+        slice.read_to_end(&mut data).unwrap();
+        assert_eq!(data, [8, 9]);
+
+        // End is relative to the readable data, not the range
+        assert_eq!(slice.seek(SeekFrom::End(-1)).unwrap(), 5);
+        assert!(!slice.position_at_end().unwrap());
+        assert_eq!(slice.seek(SeekFrom::End(0)).unwrap(), 6);
+        assert!(slice.position_at_end().unwrap());
+        // Seeking past the real end but within the range still reads nothing
+        assert_eq!(slice.seek(SeekFrom::Start(12)).unwrap(), 12);
+        assert!(slice.position_at_end().unwrap());
+        assert_eq!(slice.read(&mut [0; 4]).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_range_bounds() {
+        const INPUT: &[u8] = &[0, 1, 2, 3, 4, 5, 6, 7, 8, 9];
+
+        let mut slice = InputSlice::new(Cursor::new(INPUT), ..).unwrap();
+        assert_eq!(slice.start(), 0);
+        assert_eq!(slice.end(), u64::MAX);
+        assert_eq!(slice.last(), Some(u64::MAX - 1));
+        assert!(slice.contains(0));
+        assert!(slice.contains(1000));
+        assert_eq!(slice.range(), 0..u64::MAX);
+        assert_eq!(slice.stream_len().unwrap(), 10);
+        let mut data = Vec::new();
+        slice.read_to_end(&mut data).unwrap();
+        assert_eq!(data, INPUT);
+        assert_eq!(slice.seek(SeekFrom::End(-2)).unwrap(), 8);
+
+        let mut cursor = Cursor::new(INPUT);
+        cursor.seek(SeekFrom::Start(5)).unwrap();
+        let mut slice = InputSlice::new(cursor, 5..).unwrap();
+        assert_eq!(slice.start(), 5);
+        assert!(!slice.contains(4));
+        assert!(slice.contains(5));
+        assert_eq!(slice.stream_len().unwrap(), 5);
+        let mut data = Vec::new();
+        slice.read_to_end(&mut data).unwrap();
+        assert_eq!(data, [5, 6, 7, 8, 9]);
+        assert_eq!(slice.seek(SeekFrom::End(0)).unwrap(), 5);
+        assert_eq!(slice.seek(SeekFrom::Start(1)).unwrap(), 1);
+        assert_eq!(slice.absolute_stream_position().unwrap(), 6);
+
+        let mut slice = InputSlice::new(Cursor::new(INPUT), 0..0).unwrap();
+        assert!(slice.is_empty());
+        assert_eq!(slice.end(), 0);
+        assert_eq!(slice.last(), None);
+        assert!(!slice.contains(0));
+        assert_eq!(slice.range(), 0..0);
+        assert_eq!(slice.stream_len().unwrap(), 0);
+        let mut data = Vec::new();
+        slice.read_to_end(&mut data).unwrap();
+        assert!(data.is_empty());
+        assert_eq!(slice.seek(SeekFrom::Start(3)).unwrap(), 0);
+
+        let mut slice = InputSlice::new(Cursor::new(INPUT), ..=u64::MAX).unwrap();
+        assert_eq!(slice.range(), 0..u64::MAX);
+        assert_eq!(slice.stream_len().unwrap(), 10);
+        let mut data = Vec::new();
+        slice.read_to_end(&mut data).unwrap();
+        assert_eq!(data, INPUT);
+
+        // Inclusive and inverted ranges
+        let slice = InputSlice::new(Cursor::new(INPUT), 0..=3).unwrap();
+        assert_eq!(slice.range(), 0..4);
+        assert_eq!(slice.last(), Some(3));
+        let (start, end) = (5, 3);
+        let slice = InputSlice::new_unchecked(Cursor::new(INPUT), start..end);
+        assert!(slice.is_empty());
+        assert_eq!(slice.range(), 5..5);
+
+        // `at` covers exactly [amount] bytes
+        let mut cursor = Cursor::new(INPUT);
+        cursor.seek(SeekFrom::Start(2)).unwrap();
+        let mut slice = InputSlice::at(cursor, 3).unwrap();
+        let mut data = Vec::new();
+        slice.read_to_end(&mut data).unwrap();
+        assert_eq!(data, [2, 3, 4]);
+    }
+
+    #[test]
+    fn test_sub_slice() {
+        let data: Vec<u8> = (0..20).collect();
+        let mut cursor = Cursor::new(data);
+        cursor.seek(SeekFrom::Start(4)).unwrap();
+        let mut parent = InputSlice::new(cursor, 4..14).unwrap();
+
+        {
+            let mut child = parent.slice(2..5).unwrap();
+            assert_eq!(child.range(), 6..9);
+            assert_eq!(child.stream_position().unwrap(), 0);
+            let mut out = Vec::new();
+            child.read_to_end(&mut out).unwrap();
+            assert_eq!(out, [6, 7, 8]);
+            assert_eq!(child.stream_position().unwrap(), 3);
+            assert_eq!(child.seek(SeekFrom::Start(100)).unwrap(), 3);
+            assert_eq!(child.seek(SeekFrom::Start(1)).unwrap(), 1);
+
+            // Nested again
+            let mut grandchild = child.slice(1..).unwrap();
+            assert_eq!(grandchild.range(), 7..9);
+            let mut out = Vec::new();
+            grandchild.read_to_end(&mut out).unwrap();
+            assert_eq!(out, [7, 8]);
+        }
+        // The parent notices that the input was moved
+        assert_eq!(parent.stream_position().unwrap(), 5);
+
+        // Unbounded end is clamped to the parent
+        let mut child = parent.slice(7..).unwrap();
+        assert_eq!(child.range(), 11..14);
+        let mut out = Vec::new();
+        child.read_to_end(&mut out).unwrap();
+        assert_eq!(out, [11, 12, 13]);
+
+        // Escaping the parent
+        for result in [parent.slice(5..11).err(), parent.slice(11..).err()].iter() {
+            assert_eq!(
+                result.as_ref().unwrap().kind(),
+                std::io::ErrorKind::InvalidInput
+            );
+        }
+        assert!(parent.slice(..=10).is_err());
+        assert!(parent.slice(..10).is_ok());
+        assert!(parent.slice(10..).unwrap().is_empty());
+
+        let mut child = parent.into_slice(..2).unwrap();
+        assert_eq!(child.stream_position().unwrap(), 0);
+        let mut out = Vec::new();
+        child.read_to_end(&mut out).unwrap();
+        assert_eq!(out, [4, 5]);
+    }
+
+    #[test]
+    fn test_buf_read() {
+        use std::io::{BufRead, BufReader};
+
+        const INPUT: &[u8] = b"abc\ndef\nghi\njkl\n";
+        let mut reader = BufReader::new(Cursor::new(INPUT));
+        reader.seek(SeekFrom::Start(4)).unwrap();
+        // Ends in the middle of "ghi"
+        let mut slice = InputSlice::new(reader, 4..10).unwrap();
+
+        let mut line = String::new();
+        slice.read_line(&mut line).unwrap();
+        assert_eq!(line, "def\n");
+        assert_eq!(slice.stream_position().unwrap(), 4);
+
+        line.clear();
+        slice.read_line(&mut line).unwrap();
+        assert_eq!(line, "gh");
+        assert_eq!(slice.stream_position().unwrap(), 6);
+
+        // Exactly at the end, even though the inner reader has more buffered
+        assert!(slice.fill_buf().unwrap().is_empty());
+        assert!(!slice.get_mut().fill_buf().unwrap().is_empty());
+        line.clear();
+        assert_eq!(slice.read_line(&mut line).unwrap(), 0);
+
+        slice.seek(SeekFrom::Start(1)).unwrap();
+        assert_eq!(slice.fill_buf().unwrap(), b"ef\ngh");
+        slice.consume(3);
+        assert_eq!(slice.stream_position().unwrap(), 4);
+        let lines: Vec<String> = slice.lines().map(Result::unwrap).collect();
+        assert_eq!(lines, ["gh"]);
+    }
+
+    #[test]
+    fn test_read_vectored() {
+        use std::io::IoSliceMut;
+
+        let input: Vec<u8> = (0..16).collect();
+        let mut cursor = Cursor::new(input);
+        cursor.seek(SeekFrom::Start(3)).unwrap();
+        let mut slice = InputSlice::new(cursor, 3..9).unwrap();
+        let (mut a, mut b) = ([0u8; 4], [0u8; 4]);
+        let amount = slice
+            .read_vectored(&mut [IoSliceMut::new(&mut a), IoSliceMut::new(&mut b)])
+            .unwrap();
+        assert_eq!(amount, 6);
+        assert_eq!(a, [3, 4, 5, 6]);
+        assert_eq!(b, [7, 8, 0, 0]);
+        assert_eq!(slice.stream_position().unwrap(), 6);
+        assert_eq!(
+            slice.read_vectored(&mut [IoSliceMut::new(&mut a)]).unwrap(),
+            0
+        );
+
+        slice.seek(SeekFrom::Start(4)).unwrap();
+        let amount =
+            crate::read_if_possible_vectored(&mut slice, &mut [IoSliceMut::new(&mut a)]).unwrap();
+        assert_eq!(amount, 2);
+        assert_eq!(a[..2], [7, 8]);
+    }
+
+    #[test]
+    fn test_output_slice() {
+        use std::io::Write;
+
+        let mut cursor = Cursor::new(vec![0xFFu8; 12]);
+        cursor.seek(SeekFrom::Start(4)).unwrap();
+        let mut slice = OutputSlice::new(&mut cursor, 4..8).unwrap();
+        assert_eq!(slice.stream_position().unwrap(), 0);
+        assert_eq!(slice.remaining().unwrap(), 4);
+
+        // Exactly fills it
+        slice.write_all(&[1, 2, 3, 4]).unwrap();
+        assert_eq!(slice.remaining().unwrap(), 0);
+        assert_eq!(slice.write(&[5]).unwrap(), 0);
+        assert_eq!(
+            cursor.get_ref(),
+            &[0xFF, 0xFF, 0xFF, 0xFF, 1, 2, 3, 4, 0xFF, 0xFF, 0xFF, 0xFF]
+        );
+
+        // Overflowing is truncated
+        let mut slice = OutputSlice::new_unchecked(&mut cursor, 2..=3);
+        slice.seek(SeekFrom::Start(0)).unwrap();
+        assert_eq!(slice.write(&[9, 9, 9]).unwrap(), 2);
+        let e = slice.write_all(&[9]).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::WriteZero);
+        assert_eq!(&cursor.get_ref()[..5], &[0xFF, 0xFF, 9, 9, 1]);
+
+        // Seek near the end, then write
+        cursor.seek(SeekFrom::Start(8)).unwrap();
+        let mut slice = OutputSlice::at(&mut cursor, 3).unwrap();
+        assert_eq!(slice.end() - slice.start(), 3);
+        assert_eq!(slice.seek(SeekFrom::End(-1)).unwrap(), 2);
+        assert_eq!(slice.remaining().unwrap(), 1);
+        assert_eq!(slice.write(&[7, 7]).unwrap(), 1);
+        assert_eq!(slice.seek(SeekFrom::Current(5)).unwrap(), 3);
+        assert_eq!(&cursor.get_ref()[8..], &[0xFF, 0xFF, 7, 0xFF]);
+
+        // Strict mode refuses writes that don't fit, without writing any of it
+        cursor.seek(SeekFrom::Start(0)).unwrap();
+        let mut slice = OutputSlice::at(&mut cursor, 2).unwrap().strict(true);
+        assert!(slice.is_strict());
+        let e = slice.write(&[6, 6, 6]).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::WriteZero);
+        assert_eq!(slice.stream_position().unwrap(), 0);
+        slice.write_all(&[6, 6]).unwrap();
+        assert_eq!(slice.write(&[]).unwrap(), 0);
+        assert_eq!(&cursor.get_ref()[..3], &[6, 6, 9]);
+    }
+
+    #[test]
+    fn test_remaining() {
+        let mut cursor = Cursor::new((0..10).collect::<Vec<u8>>());
+        cursor.seek(SeekFrom::Start(2)).unwrap();
+        let mut slice = InputSlice::new(cursor, 2..8).unwrap();
+        assert_eq!(slice.len(), 6);
+        assert!(!slice.is_empty());
+        assert_eq!(slice.remaining().unwrap(), 6);
+
+        let mut data = [0u8; 4];
+        slice.read_exact(&mut data).unwrap();
+        assert_eq!(slice.remaining().unwrap(), 2);
+
+        let mut data = [0u8; 2];
+        slice.read_exact(&mut data).unwrap();
+        assert_eq!(slice.remaining().unwrap(), 0);
+        assert_eq!(slice.len(), 6);
+
+        // Positioned past the end from outside
+        slice.get_mut().set_position(9);
+        assert_eq!(slice.remaining().unwrap(), 0);
+
+        let mut slice = InputSlice::new(Cursor::new(vec![1u8, 2]), 0..0).unwrap();
+        assert_eq!(slice.len(), 0);
+        assert!(slice.is_empty());
+        assert_eq!(slice.remaining().unwrap(), 0);
+
+        let slice = InputSlice::new_unchecked(Cursor::new(vec![1u8, 2]), 4..);
+        assert_eq!(slice.len(), u64::MAX - 4);
+    }
+
+    #[test]
+    fn test_unsound_position() {
+        let invalid = |e: std::io::Error| assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+
+        let mut cursor = Cursor::new((0..20).collect::<Vec<u8>>());
+        cursor.seek(SeekFrom::Start(1)).unwrap();
+        invalid(InputSlice::new(&mut cursor, 2..8).unwrap_err());
+        invalid(OutputSlice::new(&mut cursor, 2..8).unwrap_err());
+        cursor.seek(SeekFrom::Start(9)).unwrap();
+        invalid(InputSlice::new(&mut cursor, 2..8).unwrap_err());
+
+        // Positioned before the start, which the unchecked constructor doesn't notice
+        let invalid_data =
+            |e: std::io::Error| assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+        cursor.seek(SeekFrom::Start(1)).unwrap();
+        let mut slice = InputSlice::new_unchecked(&mut cursor, 2..8);
+        invalid_data(slice.stream_position().unwrap_err());
+        invalid_data(slice.seek(SeekFrom::Current(1)).unwrap_err());
+        assert_eq!(slice.seek(SeekFrom::Start(1)).unwrap(), 1);
+        assert_eq!(slice.stream_position().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_resync() {
+        let invalid_data =
+            |e: std::io::Error| assert_eq!(e.kind(), std::io::ErrorKind::InvalidData);
+
+        let mut cursor = Cursor::new((0..20).collect::<Vec<u8>>());
+        cursor.seek(SeekFrom::Start(4)).unwrap();
+        let mut slice = InputSlice::new(cursor, 4..12).unwrap();
+
+        // Moved before the start through the inner reader
+        slice.get_mut().seek(SeekFrom::Start(2)).unwrap();
+        invalid_data(slice.stream_position().unwrap_err());
+        invalid_data(slice.seek(SeekFrom::Current(3)).unwrap_err());
+        assert_eq!(slice.resync().unwrap(), 0);
+        assert_eq!(slice.get_ref().position(), 4);
+        assert_eq!(slice.read(&mut [0; 2]).unwrap(), 2);
+        assert_eq!(slice.stream_position().unwrap(), 2);
+
+        // Moved past the end
+        slice.get_mut().seek(SeekFrom::Start(15)).unwrap();
+        assert_eq!(slice.resync().unwrap(), 8);
+        assert_eq!(slice.get_ref().position(), 12);
+        assert_eq!(slice.read(&mut [0; 2]).unwrap(), 0);
 
-        // TODO: this needs more tests. Like I was able to read multiple bytes past the end
-        //  when I only accidently had 1 extra byte..
+        // Within the range is left alone
+        slice.get_mut().seek(SeekFrom::Start(7)).unwrap();
+        assert_eq!(slice.resync().unwrap(), 3);
+        let mut data = [0; 2];
+        slice.read_exact(&mut data).unwrap();
+        assert_eq!(data, [7, 8]);
+    }
+
+    #[test]
+    fn test_strict_seek() {
+        let invalid = |res: std::io::Result<u64>| {
+            assert_eq!(res.unwrap_err().kind(), std::io::ErrorKind::InvalidInput)
+        };
+
+        let mut cursor = Cursor::new((0..20).collect::<Vec<u8>>());
+        cursor.seek(SeekFrom::Start(4)).unwrap();
+        let mut slice = InputSlice::new(cursor, 4..12).unwrap().strict(true);
+        assert!(slice.is_strict());
+
+        // Within the range, including the end itself
+        assert_eq!(slice.seek(SeekFrom::Start(3)).unwrap(), 3);
+        assert_eq!(slice.seek(SeekFrom::Start(8)).unwrap(), 8);
+        assert_eq!(slice.seek(SeekFrom::Current(-8)).unwrap(), 0);
+        assert_eq!(slice.seek(SeekFrom::Current(5)).unwrap(), 5);
+        assert_eq!(slice.seek(SeekFrom::End(0)).unwrap(), 8);
+        assert_eq!(slice.seek(SeekFrom::End(-8)).unwrap(), 0);
+        assert_eq!(slice.seek(SeekFrom::End(-2)).unwrap(), 6);
+
+        // Outside of it, which leaves the position alone
+        invalid(slice.seek(SeekFrom::Start(9)));
+        invalid(slice.seek(SeekFrom::Start(u64::MAX)));
+        invalid(slice.seek(SeekFrom::Current(3)));
+        invalid(slice.seek(SeekFrom::Current(-7)));
+        invalid(slice.seek(SeekFrom::End(1)));
+        invalid(slice.seek(SeekFrom::End(-9)));
+        assert_eq!(slice.stream_position().unwrap(), 6);
+        assert_eq!(slice.get_mut().position(), 10);
+        let mut data = [0u8; 2];
+        slice.read_exact(&mut data).unwrap();
+        assert_eq!(data, [10, 11]);
+
+        // Sub-slices are strict too
+        let mut sub = slice.slice(2..4).unwrap();
+        assert!(sub.is_strict());
+        invalid(sub.seek(SeekFrom::Start(3)));
+        assert_eq!(sub.seek(SeekFrom::End(0)).unwrap(), 2);
+
+        // While the default still clamps
+        let mut slice = slice.strict(false);
+        assert_eq!(slice.seek(SeekFrom::Start(9)).unwrap(), 8);
+        assert_eq!(slice.seek(SeekFrom::End(1)).unwrap(), 8);
+        invalid(slice.seek(SeekFrom::Current(-9)));
+    }
+
+    /// Counts the number of seeks made on [inner]
+    struct SeekCounter {
+        inner: Cursor<Vec<u8>>,
+        seeks: usize,
+    }
+    impl Read for SeekCounter {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            self.inner.read(buf)
+        }
+    }
+    impl Seek for SeekCounter {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.seeks += 1;
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_cached_position() {
+        let mut counter = SeekCounter {
+            inner: Cursor::new((0..20).collect()),
+            seeks: 0,
+        };
+        counter.seek(SeekFrom::Start(2)).unwrap();
+        let mut slice = InputSlice::new(counter, 2..12).unwrap();
+        let seeks = slice.get_ref().seeks;
+
+        let mut data = [0u8; 3];
+        for expected in [[2, 3, 4], [5, 6, 7], [8, 9, 10]].iter() {
+            slice.read_exact(&mut data).unwrap();
+            assert_eq!(&data, expected);
+        }
+        assert_eq!(slice.read(&mut data).unwrap(), 1);
+        assert_eq!(slice.read(&mut data).unwrap(), 0);
+        assert_eq!(slice.stream_position().unwrap(), 10);
+        assert_eq!(slice.get_ref().seeks, seeks);
+
+        // Seeking still works, and reads after it don't seek either
+        assert_eq!(slice.seek(SeekFrom::Current(-4)).unwrap(), 6);
+        let seeks = slice.get_ref().seeks;
+        slice.read_exact(&mut data).unwrap();
+        assert_eq!(data, [8, 9, 10]);
+        assert_eq!(slice.stream_position().unwrap(), 9);
+        assert_eq!(slice.get_ref().seeks, seeks);
+        assert_eq!(slice.stream_len().unwrap(), 10);
+        assert_eq!(slice.stream_position().unwrap(), 9);
+
+        // Moving the input behind the slice's back resynchronizes
+        slice.get_mut().inner.set_position(4);
+        assert_eq!(slice.stream_position().unwrap(), 2);
+        slice.read_exact(&mut data).unwrap();
+        assert_eq!(data, [4, 5, 6]);
+
+        // Without an initial position, it is found on the first read
+        let mut counter = SeekCounter {
+            inner: Cursor::new((0..20).collect()),
+            seeks: 0,
+        };
+        counter.inner.set_position(5);
+        let mut slice = InputSlice::new_unchecked(counter, 5..8);
+        let mut out = Vec::new();
+        slice.read_to_end(&mut out).unwrap();
+        assert_eq!(out, [5, 6, 7]);
+        assert_eq!(slice.get_ref().seeks, 1);
+    }
+
+    /// Returns at most [max] bytes per read, and can't seek
+    struct ShortReader {
+        data: Vec<u8>,
+        position: usize,
+        max: usize,
+    }
+    impl Read for ShortReader {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let amount = buf.len().min(self.max).min(self.data.len() - self.position);
+            buf[..amount].copy_from_slice(&self.data[self.position..self.position + amount]);
+            self.position += amount;
+            Ok(amount)
+        }
+    }
+
+    #[test]
+    fn test_multi_slice() {
+        let data: Vec<u8> = (0..32).collect();
+        let mut cursor = Cursor::new(data);
+        cursor.seek(SeekFrom::Start(30)).unwrap();
+        // An empty extent in the middle is skipped over
+        let mut multi = MultiSlice::new(cursor, vec![4..8, 20..23, 10..10, 12..16]);
+        assert_eq!(multi.stream_len(), 11);
+        assert_eq!(multi.stream_position(), 0);
+        assert_eq!(multi.get_ref().position(), 30);
+
+        let mut data = [0u8; 6];
+        multi.read_exact(&mut data).unwrap();
+        assert_eq!(data, [4, 5, 6, 7, 20, 21]);
+        assert_eq!(multi.stream_position(), 6);
+
+        // Reads stop at the end of an extent
+        assert_eq!(multi.read(&mut data).unwrap(), 1);
+        assert_eq!(data[0], 22);
+        let mut data = [0u8; 5];
+        assert_eq!(multi.read(&mut data).unwrap(), 4);
+        assert_eq!(&data[..4], &[12, 13, 14, 15]);
+        assert_eq!(multi.read(&mut data).unwrap(), 0);
+
+        // Seeks landing exactly on a boundary read from the start of the next extent
+        assert_eq!(multi.seek(SeekFrom::Start(4)).unwrap(), 4);
+        let mut data = [0u8; 1];
+        multi.read_exact(&mut data).unwrap();
+        assert_eq!(data, [20]);
+        assert_eq!(multi.seek(SeekFrom::Current(2)).unwrap(), 7);
+        multi.read_exact(&mut data).unwrap();
+        assert_eq!(data, [12]);
+        assert_eq!(multi.seek(SeekFrom::End(-4)).unwrap(), 7);
+        multi.read_exact(&mut data).unwrap();
+        assert_eq!(data, [12]);
+        assert_eq!(multi.seek(SeekFrom::Current(-6)).unwrap(), 2);
+        let mut data = [0u8; 9];
+        multi.read_exact(&mut data).unwrap();
+        assert_eq!(data, [6, 7, 20, 21, 22, 12, 13, 14, 15]);
+
+        // Past the end is clamped, and before the start is an error
+        assert_eq!(multi.seek(SeekFrom::Start(50)).unwrap(), 11);
+        assert_eq!(multi.read(&mut data).unwrap(), 0);
+        let e = multi.seek(SeekFrom::End(-12)).unwrap_err();
+        assert_eq!(e.kind(), std::io::ErrorKind::InvalidInput);
+
+        // Moving the input behind its back is fine
+        multi.seek(SeekFrom::Start(5)).unwrap();
+        multi.get_mut().set_position(0);
+        let mut data = [0u8; 1];
+        multi.read_exact(&mut data).unwrap();
+        assert_eq!(data, [21]);
+
+        let mut empty = MultiSlice::new(Cursor::new(vec![1u8, 2]), vec![]);
+        assert_eq!(empty.stream_len(), 0);
+        assert_eq!(empty.read(&mut data).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_input_limit() {
+        let data: Vec<u8> = (0..20).collect();
+        let reader = ShortReader {
+            data: data.clone(),
+            position: 0,
+            max: 3,
+        };
+        let mut limit = InputLimit::new(reader, 10);
+        assert_eq!(limit.remaining(), 10);
+
+        let mut out = [0u8; 4];
+        limit.read_exact(&mut out).unwrap();
+        assert_eq!(out, [0, 1, 2, 3]);
+        assert_eq!(limit.stream_position(), 4);
+        assert_eq!(limit.remaining(), 6);
+
+        let mut out = Vec::new();
+        limit.read_to_end(&mut out).unwrap();
+        assert_eq!(out, [4, 5, 6, 7, 8, 9]);
+        assert_eq!(limit.remaining(), 0);
+        assert_eq!(limit.read(&mut [0u8; 4]).unwrap(), 0);
+        // Never read past the limit from the input
+        assert_eq!(limit.get_ref().position, 10);
+
+        // Input that ends before the limit
+        let reader = ShortReader {
+            data: vec![1, 2, 3],
+            position: 0,
+            max: 2,
+        };
+        let mut limit = InputLimit::new(reader, 10);
+        let mut out = Vec::new();
+        limit.read_to_end(&mut out).unwrap();
+        assert_eq!(out, [1, 2, 3]);
+        assert_eq!(limit.stream_position(), 3);
+        assert_eq!(limit.remaining(), 7);
+
+        // Seekable input
+        let mut cursor = Cursor::new(data);
+        cursor.seek(SeekFrom::Start(5)).unwrap();
+        let mut limit = InputLimit::new(cursor, 4);
+        assert_eq!(limit.seek(SeekFrom::End(-1)).unwrap(), 3);
+        let mut out = Vec::new();
+        limit.read_to_end(&mut out).unwrap();
+        assert_eq!(out, [8]);
+        assert_eq!(limit.seek(SeekFrom::Start(1)).unwrap(), 1);
+        assert_eq!(limit.get_mut().stream_position().unwrap(), 6);
+        assert_eq!(limit.seek(SeekFrom::Current(100)).unwrap(), 4);
+        assert_eq!(limit.get_mut().stream_position().unwrap(), 9);
+        limit.seek(SeekFrom::Current(-5)).unwrap_err();
+    }
+
+    #[test]
+    fn test_windows_iter() {
+        let data: Vec<u8> = (0..10).collect();
+        let mut cursor = Cursor::new(data);
+        cursor.seek(SeekFrom::Start(1)).unwrap();
+        let mut slice = InputSlice::new(cursor, 1..10).unwrap();
+
+        let windows: Vec<(u64, Vec<u8>)> = slice
+            .windows_iter(4, 2)
+            .collect::<std::io::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            windows,
+            [
+                (1, vec![1, 2, 3, 4]),
+                (3, vec![3, 4, 5, 6]),
+                (5, vec![5, 6, 7, 8]),
+                (7, vec![7, 8, 9]),
+            ]
+        );
+
+        slice.seek(SeekFrom::Start(0)).unwrap();
+        let windows: Vec<(u64, Vec<u8>)> = slice
+            .windows_iter(4, 2)
+            .short_windows(ShortWindow::Skip)
+            .collect::<std::io::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            windows,
+            [
+                (1, vec![1, 2, 3, 4]),
+                (3, vec![3, 4, 5, 6]),
+                (5, vec![5, 6, 7, 8]),
+            ]
+        );
+
+        slice.seek(SeekFrom::Start(0)).unwrap();
+        let chunks: Vec<(u64, Vec<u8>)> = slice
+            .chunks_iter(3)
+            .collect::<std::io::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            chunks,
+            [(1, vec![1, 2, 3]), (4, vec![4, 5, 6]), (7, vec![7, 8, 9])]
+        );
+
+        // Stepping further than the window skips bytes
+        slice.seek(SeekFrom::Start(0)).unwrap();
+        let windows: Vec<(u64, Vec<u8>)> = slice
+            .windows_iter(2, 4)
+            .collect::<std::io::Result<_>>()
+            .unwrap();
+        assert_eq!(windows, [(1, vec![1, 2]), (5, vec![5, 6]), (9, vec![9])]);
+    }
+
+    #[test]
+    fn test_for_each_window() {
+        let data: Vec<u8> = (0..10).collect();
+        let mut slice = InputSlice::new(Cursor::new(data), 0..10).unwrap();
+
+        let mut windows = Vec::new();
+        let mut buffers = Vec::new();
+        slice
+            .for_each_window(4, 3, ShortWindow::Yield, |offset, window| {
+                windows.push((offset, window.to_vec()));
+                buffers.push(window.as_ptr());
+            })
+            .unwrap();
+        assert_eq!(
+            windows,
+            [
+                (0, vec![0, 1, 2, 3]),
+                (3, vec![3, 4, 5, 6]),
+                (6, vec![6, 7, 8, 9]),
+                (9, vec![9]),
+            ]
+        );
+        assert!(buffers.iter().all(|x| *x == buffers[0]));
+
+        slice.seek(SeekFrom::Start(0)).unwrap();
+        let mut count = 0;
+        slice
+            .for_each_window(4, 3, ShortWindow::Skip, |_, window| {
+                assert_eq!(window.len(), 4);
+                count += 1;
+            })
+            .unwrap();
+        assert_eq!(count, 3);
+    }
+
+    #[test]
+    fn test_by_ref() {
+        let mut cursor = Cursor::new((0..20).collect::<Vec<u8>>());
+        let mut data = [0; 3];
+        InputSlice::by_ref(&mut cursor, 10..13)
+            .unwrap()
+            .read_exact(&mut data)
+            .unwrap();
+        assert_eq!(data, [10, 11, 12]);
+
+        // The cursor is free again, for the next section
+        let mut section = InputSlice::by_ref(&mut cursor, 2..4).unwrap();
+        assert_eq!(section.stream_position().unwrap(), 0);
+        let mut data = Vec::new();
+        section.read_to_end(&mut data).unwrap();
+        assert_eq!(data, [2, 3]);
+        assert_eq!(cursor.position(), 4);
+    }
+
+    #[test]
+    fn test_shared_slice() {
+        let input = Arc::new((0..20).collect::<Vec<u8>>());
+        let mut first = SharedSlice::new(Arc::clone(&input), 2..8);
+        let mut second = SharedSlice::new(Arc::clone(&input), 10..);
+        assert_eq!(first.len(), 6);
+
+        // Interleaved reads each keep their own position
+        let mut data = [0; 2];
+        first.read_exact(&mut data).unwrap();
+        assert_eq!(data, [2, 3]);
+        second.read_exact(&mut data).unwrap();
+        assert_eq!(data, [10, 11]);
+        first.read_exact(&mut data).unwrap();
+        assert_eq!(data, [4, 5]);
+        assert_eq!(second.seek(SeekFrom::Current(5)).unwrap(), 7);
+        assert_eq!(first.stream_position(), 4);
+        second.read_exact(&mut data).unwrap();
+        assert_eq!(data, [17, 18]);
+        first.read_exact(&mut data).unwrap();
+        assert_eq!(data, [6, 7]);
+
+        // Bounded by the range, and by the data
+        assert_eq!(first.read(&mut data).unwrap(), 0);
+        let mut rest = Vec::new();
+        second.read_to_end(&mut rest).unwrap();
+        assert_eq!(rest, [19]);
+        assert_eq!(first.seek(SeekFrom::End(-1)).unwrap(), 5);
+        assert_eq!(first.seek(SeekFrom::Start(100)).unwrap(), 6);
+        assert!(first.seek(SeekFrom::Current(-7)).is_err());
+
+        // Over a plain slice, which can be a clone of one another
+        let data: &[u8] = &[1, 2, 3, 4];
+        let mut slice = SharedSlice::new(data, 1..3);
+        let mut copy = slice.clone();
+        let mut output = Vec::new();
+        slice.read_to_end(&mut output).unwrap();
+        assert_eq!(output, [2, 3]);
+        assert_eq!(copy.stream_position(), 0);
+        let mut byte = [0];
+        copy.read_exact(&mut byte).unwrap();
+        assert_eq!(byte, [2]);
+        assert_eq!(data.read_at(&mut [0; 4], 10).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_shared_slice_file() {
+        let path = std::env::temp_dir().join(format!("eyeutil-shared-{}", std::process::id()));
+        std::fs::write(&path, (0..16).collect::<Vec<u8>>()).unwrap();
+        let file = Arc::new(std::fs::File::open(&path).unwrap());
+        let mut first = SharedSlice::new(Arc::clone(&file), 0..4);
+        let mut second = SharedSlice::new(file, 12..);
+        let mut data = [0; 2];
+        second.read_exact(&mut data).unwrap();
+        first.read_exact(&mut data).unwrap();
+        assert_eq!(data, [0, 1]);
+        second.read_exact(&mut data).unwrap();
+        assert_eq!(data, [14, 15]);
+        drop((first, second));
+        std::fs::remove_file(&path).unwrap();
     }
 }
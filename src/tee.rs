@@ -0,0 +1,112 @@
+use crate::writable::write_full;
+use std::io::{self, Read, Write};
+
+/// Wraps a reader, writing a copy of every byte read through it into [W], such as to keep the
+/// exact bytes of a section that was parsed so it can be written back out unchanged.
+/// Each chunk is written before the read returns. If writing it fails, then the read returns
+/// that error, and the chunk is lost from the reader's side, since it was already consumed.
+/// This doesn't implement Seek, since seeking would leave gaps or repeats in the copy.
+#[derive(Debug)]
+pub struct TeeReader<R: Read, W: Write> {
+    inner: R,
+    output: W,
+}
+impl<R, W> TeeReader<R, W>
+where
+    R: Read,
+    W: Write,
+{
+    #[inline]
+    pub fn new(inner: R, output: W) -> Self {
+        TeeReader { inner, output }
+    }
+
+    #[inline]
+    pub fn into_parts(self) -> (R, W) {
+        (self.inner, self.output)
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Note: reading from the inner reader through this handle will not copy the bytes.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    #[inline]
+    pub fn output(&self) -> &W {
+        &self.output
+    }
+
+    #[inline]
+    pub fn output_mut(&mut self) -> &mut W {
+        &mut self.output
+    }
+}
+impl<R, W> Read for TeeReader<R, W>
+where
+    R: Read,
+    W: Write,
+{
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let amount_read = self.inner.read(buf)?;
+        write_full(&mut self.output, &buf[..amount_read])?;
+        Ok(amount_read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parse::{take, Parse},
+        zstring::ZString,
+        Endian,
+    };
+
+    const DATA: [u8; 10] = [0x1, 0x2, 0x3, 0x4, b'h', b'i', 0x0, 0x8, 0x9, 0xa];
+
+    #[test]
+    fn test_tee_reader() {
+        let mut reader = TeeReader::new(&DATA[..], Vec::new());
+        assert_eq!(u32::parse(&mut reader, Endian::Little).unwrap(), 0x04030201);
+        assert_eq!(reader.output(), &DATA[..4]);
+        ZString::parse(&mut reader, ()).unwrap();
+        take(&mut reader, 2).unwrap();
+        let (rest, output) = reader.into_parts();
+        assert_eq!(output, DATA[..9]);
+        assert_eq!(rest, [0xa]);
+    }
+
+    /// Writer which accepts [capacity] bytes, and then errors.
+    struct FailingWriter {
+        capacity: usize,
+    }
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.capacity == 0 {
+                return Err(io::Error::new(io::ErrorKind::Other, "out of space"));
+            }
+            let amount = buf.len().min(self.capacity);
+            self.capacity -= amount;
+            Ok(amount)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_tee_reader_failing_writer() {
+        let mut reader = TeeReader::new(&DATA[..], FailingWriter { capacity: 6 });
+        assert_eq!(u32::parse(&mut reader, Endian::Little).unwrap(), 0x04030201);
+        let err = u32::parse(&mut reader, Endian::Little).unwrap_err();
+        assert!(matches!(err, crate::parse::ParseError::Io(e) if e.kind() == io::ErrorKind::Other));
+    }
+}
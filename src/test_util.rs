@@ -0,0 +1,21 @@
+//! Mock readers and writers shared between the modules' tests.
+
+use std::io::Write;
+
+/// Writer that records how many times write was called
+#[derive(Debug, Default)]
+pub(crate) struct CallCountingWriter {
+    pub data: Vec<u8>,
+    pub calls: usize,
+}
+impl Write for CallCountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.calls += 1;
+        self.data.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
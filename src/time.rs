@@ -0,0 +1,469 @@
+//! Timestamps as they're commonly stored in files, with conversions to and from
+//! [SystemTime].
+
+use crate::{
+    impl_data_size,
+    parse::{Parse, ParseResult},
+    writable::{Writable, WriteResult},
+    Endian,
+};
+use std::{
+    convert::TryFrom,
+    io::{Read, Write},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeError {
+    /// The time is before the earliest time the type can represent
+    BeforeEpoch,
+    /// The time is after the latest time the type can represent
+    OutOfRange,
+    /// A field of the date or time is out of range, such as a month of 13
+    Invalid,
+}
+
+macro_rules! impl_timestamp {
+    ($name:ident, $inner:ty, $size:expr) => {
+        impl<F: Read> Parse<F, Endian> for $name {
+            #[inline]
+            fn parse(f: &mut F, endian: Endian) -> ParseResult<Self> {
+                <$inner>::parse(f, endian).map($name)
+            }
+        }
+        impl Writable<Endian> for $name {
+            #[inline]
+            fn write_to<W>(&self, w: &mut W, endian: Endian) -> WriteResult
+            where
+                W: Write,
+            {
+                self.0.write_to(w, endian)
+            }
+        }
+        impl_data_size!($name, $size);
+        impl TryFrom<SystemTime> for $name {
+            type Error = TimeError;
+
+            #[inline]
+            fn try_from(time: SystemTime) -> Result<Self, Self::Error> {
+                Self::from_system_time(time)
+            }
+        }
+        impl TryFrom<$name> for SystemTime {
+            type Error = TimeError;
+
+            #[inline]
+            fn try_from(time: $name) -> Result<Self, Self::Error> {
+                time.to_system_time()
+            }
+        }
+    };
+}
+
+/// Seconds since the Unix epoch (1970-01-01 00:00:00 UTC), as a u32.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnixTime32(pub u32);
+impl UnixTime32 {
+    #[inline]
+    pub fn to_system_time(self) -> Result<SystemTime, TimeError> {
+        UNIX_EPOCH
+            .checked_add(Duration::from_secs(u64::from(self.0)))
+            .ok_or(TimeError::OutOfRange)
+    }
+
+    /// Note: anything less than a second is truncated.
+    #[inline]
+    pub fn from_system_time(time: SystemTime) -> Result<Self, TimeError> {
+        let seconds = UnixTime64::from_system_time(time)?.0;
+        u32::try_from(seconds)
+            .map(UnixTime32)
+            .map_err(|_| TimeError::OutOfRange)
+    }
+}
+impl_timestamp!(UnixTime32, u32, 4);
+
+/// Seconds since the Unix epoch (1970-01-01 00:00:00 UTC), as a u64.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UnixTime64(pub u64);
+impl UnixTime64 {
+    #[inline]
+    pub fn to_system_time(self) -> Result<SystemTime, TimeError> {
+        UNIX_EPOCH
+            .checked_add(Duration::from_secs(self.0))
+            .ok_or(TimeError::OutOfRange)
+    }
+
+    /// Note: anything less than a second is truncated.
+    #[inline]
+    pub fn from_system_time(time: SystemTime) -> Result<Self, TimeError> {
+        time.duration_since(UNIX_EPOCH)
+            .map(|since| UnixTime64(since.as_secs()))
+            .map_err(|_| TimeError::BeforeEpoch)
+    }
+}
+impl_timestamp!(UnixTime64, u64, 8);
+
+/// A Windows `FILETIME`: the number of 100 nanosecond ticks since 1601-01-01 00:00:00 UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct FileTime(pub u64);
+impl FileTime {
+    /// The `FILETIME` of the Unix epoch
+    pub const UNIX_EPOCH: FileTime = FileTime(116_444_736_000_000_000);
+    const TICKS_PER_SECOND: u64 = 10_000_000;
+    const NANOS_PER_TICK: u32 = 100;
+
+    /// Errors with `TimeError::OutOfRange` if the platform's [SystemTime] can't represent it,
+    /// which can happen for times before the Unix epoch.
+    pub fn to_system_time(self) -> Result<SystemTime, TimeError> {
+        let (ticks, after_epoch) = match self.0.checked_sub(Self::UNIX_EPOCH.0) {
+            Some(ticks) => (ticks, true),
+            None => (Self::UNIX_EPOCH.0 - self.0, false),
+        };
+        let duration = Duration::new(
+            ticks / Self::TICKS_PER_SECOND,
+            (ticks % Self::TICKS_PER_SECOND) as u32 * Self::NANOS_PER_TICK,
+        );
+        if after_epoch {
+            UNIX_EPOCH.checked_add(duration)
+        } else {
+            UNIX_EPOCH.checked_sub(duration)
+        }
+        .ok_or(TimeError::OutOfRange)
+    }
+
+    /// Note: anything less than a tick is truncated, rounding towards 1601.
+    pub fn from_system_time(time: SystemTime) -> Result<Self, TimeError> {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => since
+                .as_secs()
+                .checked_mul(Self::TICKS_PER_SECOND)
+                .and_then(|ticks| {
+                    ticks.checked_add(u64::from(since.subsec_nanos() / Self::NANOS_PER_TICK))
+                })
+                .and_then(|ticks| ticks.checked_add(Self::UNIX_EPOCH.0))
+                .map(FileTime)
+                .ok_or(TimeError::OutOfRange),
+            Err(err) => {
+                let before = err.duration();
+                // Rounding up, since this is subtracted
+                let partial_tick =
+                    (before.subsec_nanos() + Self::NANOS_PER_TICK - 1) / Self::NANOS_PER_TICK;
+                before
+                    .as_secs()
+                    .checked_mul(Self::TICKS_PER_SECOND)
+                    .and_then(|ticks| ticks.checked_add(u64::from(partial_tick)))
+                    .and_then(|ticks| Self::UNIX_EPOCH.0.checked_sub(ticks))
+                    .map(FileTime)
+                    .ok_or(TimeError::BeforeEpoch)
+            }
+        }
+    }
+}
+impl_timestamp!(FileTime, u64, 8);
+
+/// A packed DOS date and time, as used by ZIP and FAT. Both are u16s, stored time first:
+/// - time: bits 0..=4 are the seconds divided by 2, 5..=10 the minute, 11..=15 the hour
+/// - date: bits 0..=4 are the day, 5..=8 the month, 9..=15 the years since 1980
+///
+/// The raw bits are kept as they are, so invalid dates (such as a month of 0, which some
+/// writers use for 'no date') still parse and write back unchanged. See
+/// [DosDateTime::is_valid].
+/// Note: DOS times don't have a timezone, and are usually local time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct DosDateTime {
+    pub time: u16,
+    pub date: u16,
+}
+impl DosDateTime {
+    pub const MIN_YEAR: u16 = 1980;
+    pub const MAX_YEAR: u16 = 1980 + 0x7F;
+
+    /// Creates a `DosDateTime` from its components.
+    /// Errors with `TimeError::BeforeEpoch` or `TimeError::OutOfRange` if [year] can't be
+    /// represented, or `TimeError::Invalid` if any other component is out of range.
+    /// Note: DOS times are only precise to 2 seconds, so an odd [second] is rounded down.
+    pub fn new(
+        year: u16,
+        month: u8,
+        day: u8,
+        hour: u8,
+        minute: u8,
+        second: u8,
+    ) -> Result<Self, TimeError> {
+        if year < Self::MIN_YEAR {
+            return Err(TimeError::BeforeEpoch);
+        } else if year > Self::MAX_YEAR {
+            return Err(TimeError::OutOfRange);
+        }
+
+        let value = DosDateTime {
+            time: u16::from(hour) << 11 | u16::from(minute) << 5 | u16::from(second / 2),
+            date: (year - Self::MIN_YEAR) << 9 | u16::from(month) << 5 | u16::from(day),
+        };
+        // Checking the components against their bit widths as well, since they could
+        // otherwise overflow into another component and come out valid
+        let fits = month < 16 && day < 32 && hour < 32 && minute < 64 && second < 60;
+        if fits && value.is_valid() {
+            Ok(value)
+        } else {
+            Err(TimeError::Invalid)
+        }
+    }
+
+    #[inline]
+    pub fn year(&self) -> u16 {
+        Self::MIN_YEAR + (self.date >> 9)
+    }
+
+    #[inline]
+    pub fn month(&self) -> u8 {
+        ((self.date >> 5) & 0xF) as u8
+    }
+
+    #[inline]
+    pub fn day(&self) -> u8 {
+        (self.date & 0x1F) as u8
+    }
+
+    #[inline]
+    pub fn hour(&self) -> u8 {
+        (self.time >> 11) as u8
+    }
+
+    #[inline]
+    pub fn minute(&self) -> u8 {
+        ((self.time >> 5) & 0x3F) as u8
+    }
+
+    /// Note: this is always even, as the format stores the seconds divided by 2.
+    #[inline]
+    pub fn second(&self) -> u8 {
+        (self.time & 0x1F) as u8 * 2
+    }
+
+    /// Returns whether every component is in range, including the day for the given month.
+    pub fn is_valid(&self) -> bool {
+        let days_in_month = match self.month() {
+            1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+            4 | 6 | 9 | 11 => 30,
+            2 if is_leap_year(self.year()) => 29,
+            2 => 28,
+            _ => return false,
+        };
+        (1..=days_in_month).contains(&self.day())
+            && self.hour() < 24
+            && self.minute() < 60
+            && self.second() < 60
+    }
+
+    /// Converts to a [SystemTime], treating the time as UTC.
+    /// Errors with `TimeError::Invalid` if the date or time isn't valid.
+    pub fn to_system_time(self) -> Result<SystemTime, TimeError> {
+        if !self.is_valid() {
+            return Err(TimeError::Invalid);
+        }
+
+        let days = days_since_unix_epoch(self.year(), self.month(), self.day());
+        let seconds = days * 86400
+            + u64::from(self.hour()) * 3600
+            + u64::from(self.minute()) * 60
+            + u64::from(self.second());
+        UnixTime64(seconds).to_system_time()
+    }
+}
+impl<F: Read> Parse<F, Endian> for DosDateTime {
+    #[inline]
+    fn parse(f: &mut F, endian: Endian) -> ParseResult<Self> {
+        let time = u16::parse(f, endian)?;
+        let date = u16::parse(f, endian)?;
+        Ok(DosDateTime { time, date })
+    }
+}
+impl Writable<Endian> for DosDateTime {
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, endian: Endian) -> WriteResult
+    where
+        W: Write,
+    {
+        self.time.write_to(w, endian)?;
+        self.date.write_to(w, endian)
+    }
+}
+impl_data_size!(DosDateTime, 4);
+
+#[inline]
+fn is_leap_year(year: u16) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+/// Returns the number of days from 1970-01-01 to the given date, which must be valid and not
+/// before 1970.
+fn days_since_unix_epoch(year: u16, month: u8, day: u8) -> u64 {
+    let leap_days = |year: u64| year / 4 - year / 100 + year / 400;
+    let year = u64::from(year);
+    // Days in the years before this one, since 1970
+    let mut days = (year - 1970) * 365 + leap_days(year - 1) - leap_days(1969);
+    const CUMULATIVE: [u64; 12] = [0, 31, 59, 90, 120, 151, 181, 212, 243, 273, 304, 334];
+    days += CUMULATIVE[usize::from(month) - 1];
+    if month > 2 && is_leap_year(year as u16) {
+        days += 1;
+    }
+    days + u64::from(day) - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_size::DataSize;
+    use std::io::Cursor;
+
+    const BILLION: Duration = Duration::from_secs(1_000_000_000);
+
+    #[test]
+    fn test_unix_time() {
+        let time = UNIX_EPOCH + BILLION;
+        assert_eq!(UnixTime32(1_000_000_000).to_system_time(), Ok(time));
+        assert_eq!(
+            UnixTime32::from_system_time(time),
+            Ok(UnixTime32(1_000_000_000))
+        );
+        assert_eq!(UnixTime64::try_from(time), Ok(UnixTime64(1_000_000_000)));
+        assert_eq!(
+            UnixTime64::from_system_time(time + Duration::from_millis(999)),
+            Ok(UnixTime64(1_000_000_000))
+        );
+        assert_eq!(
+            SystemTime::try_from(UnixTime32(u32::MAX)),
+            Ok(UNIX_EPOCH + Duration::from_secs(u64::from(u32::MAX)))
+        );
+
+        let late = UNIX_EPOCH + Duration::from_secs(u64::from(u32::MAX) + 1);
+        assert_eq!(
+            UnixTime32::from_system_time(late),
+            Err(TimeError::OutOfRange)
+        );
+        assert_eq!(UnixTime64::from_system_time(late), Ok(UnixTime64(1 << 32)));
+        let early = UNIX_EPOCH - Duration::from_secs(1);
+        assert_eq!(
+            UnixTime32::from_system_time(early),
+            Err(TimeError::BeforeEpoch)
+        );
+        assert_eq!(
+            UnixTime64::from_system_time(early),
+            Err(TimeError::BeforeEpoch)
+        );
+    }
+
+    #[test]
+    fn test_file_time() {
+        assert_eq!(
+            FileTime(116_444_736_000_000_000).to_system_time(),
+            Ok(UNIX_EPOCH)
+        );
+        assert_eq!(
+            FileTime::from_system_time(UNIX_EPOCH),
+            Ok(FileTime::UNIX_EPOCH)
+        );
+
+        let time = UNIX_EPOCH + BILLION + Duration::from_nanos(1234);
+        let file_time = FileTime(126_444_736_000_000_012);
+        assert_eq!(FileTime::from_system_time(time), Ok(file_time));
+        assert_eq!(
+            file_time.to_system_time(),
+            Ok(UNIX_EPOCH + BILLION + Duration::from_nanos(1200))
+        );
+
+        // Before the Unix epoch
+        let time = UNIX_EPOCH - Duration::from_secs(1) - Duration::from_nanos(50);
+        let file_time = FileTime(116_444_735_989_999_999);
+        assert_eq!(FileTime::from_system_time(time), Ok(file_time));
+        assert_eq!(
+            file_time.to_system_time(),
+            Ok(UNIX_EPOCH - Duration::from_nanos(1_000_000_100))
+        );
+    }
+
+    #[test]
+    fn test_timestamp_round_trip() {
+        let data = [0x0, 0x80, 0x3E, 0xD5, 0xDE, 0xB1, 0x9D, 0x01];
+        let value = FileTime::parse(&mut Cursor::new(&data), Endian::Little).unwrap();
+        assert_eq!(value, FileTime::UNIX_EPOCH);
+        assert_eq!(value.data_size(()), 8);
+        let mut output = Vec::new();
+        value.write_to(&mut output, Endian::Little).unwrap();
+        assert_eq!(output, data);
+
+        let value = UnixTime32::parse(&mut Cursor::new([0x3B, 0x9A, 0xCA, 0x0]), Endian::Big);
+        assert_eq!(value.unwrap(), UnixTime32(1_000_000_000));
+        assert_eq!(UnixTime32(0).data_size(()), 4);
+    }
+
+    #[test]
+    fn test_dos_date_time() {
+        // 2020-06-15 13:45:30, as it appears in a zip local file header
+        let data = [0xAF, 0x6D, 0xCF, 0x50];
+        let value = DosDateTime::parse(&mut Cursor::new(&data), Endian::Little).unwrap();
+        assert_eq!(value, DosDateTime::new(2020, 6, 15, 13, 45, 30).unwrap());
+        assert_eq!((value.year(), value.month(), value.day()), (2020, 6, 15));
+        assert_eq!((value.hour(), value.minute(), value.second()), (13, 45, 30));
+        assert!(value.is_valid());
+        assert_eq!(
+            value.to_system_time(),
+            Ok(UNIX_EPOCH + Duration::from_secs(1_592_228_730))
+        );
+        assert_eq!(value.data_size(()), 4);
+
+        let mut output = Vec::new();
+        value.write_to(&mut output, Endian::Little).unwrap();
+        assert_eq!(output, data);
+    }
+
+    #[test]
+    fn test_dos_date_time_ranges() {
+        let start = DosDateTime::new(1980, 1, 1, 0, 0, 0).unwrap();
+        assert_eq!((start.time, start.date), (0, 0x21));
+        assert_eq!(
+            start.to_system_time(),
+            Ok(UNIX_EPOCH + Duration::from_secs(315_532_800))
+        );
+        let leap = DosDateTime::new(2024, 2, 29, 23, 59, 59).unwrap();
+        assert_eq!(leap.second(), 58);
+        assert_eq!(
+            leap.to_system_time(),
+            Ok(UNIX_EPOCH + Duration::from_secs(1_709_251_198))
+        );
+
+        assert_eq!(
+            DosDateTime::new(1979, 1, 1, 0, 0, 0),
+            Err(TimeError::BeforeEpoch)
+        );
+        assert_eq!(
+            DosDateTime::new(2108, 1, 1, 0, 0, 0),
+            Err(TimeError::OutOfRange)
+        );
+        assert!(DosDateTime::new(2107, 12, 31, 0, 0, 0).is_ok());
+        for &(month, day, hour, minute, second) in [
+            (0, 1, 0, 0, 0),
+            (13, 1, 0, 0, 0),
+            (2, 29, 0, 0, 0),
+            (4, 31, 0, 0, 0),
+            (1, 0, 0, 0, 0),
+            (1, 1, 24, 0, 0),
+            (1, 1, 0, 60, 0),
+            (1, 1, 0, 0, 60),
+            (1, 33, 0, 0, 0),
+        ]
+        .iter()
+        {
+            assert_eq!(
+                DosDateTime::new(2023, month, day, hour, minute, second),
+                Err(TimeError::Invalid)
+            );
+        }
+
+        // Zero dates are kept, but aren't valid
+        let zero = DosDateTime { time: 0, date: 0 };
+        assert!(!zero.is_valid());
+        assert_eq!(zero.to_system_time(), Err(TimeError::Invalid));
+    }
+}
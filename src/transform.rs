@@ -0,0 +1,239 @@
+use std::io::{self, Read, Seek, SeekFrom};
+
+/// A transformation applied to each byte of a stream, given the byte's index in the stream.
+/// This is implemented for any `FnMut(u64, u8) -> u8`.
+pub trait ByteTransform {
+    fn transform(&mut self, index: u64, byte: u8) -> u8;
+}
+impl<T> ByteTransform for T
+where
+    T: FnMut(u64, u8) -> u8,
+{
+    #[inline]
+    fn transform(&mut self, index: u64, byte: u8) -> u8 {
+        self(index, byte)
+    }
+}
+
+/// Wraps a reader, applying [T] to each byte as it is read. Useful for formats which XOR or
+/// otherwise lightly obfuscate their data, without decoding all of it into memory first.
+/// If the reader implements Seek, then seeking is passed through, and the index given to [T]
+/// follows the inner reader's position.
+#[derive(Debug)]
+pub struct TransformReader<R: Read, T: ByteTransform> {
+    inner: R,
+    transform: T,
+    index: u64,
+}
+impl<R, T> TransformReader<R, T>
+where
+    R: Read,
+    T: FnMut(u64, u8) -> u8,
+{
+    /// Creates a new `TransformReader`, with the first byte read being index 0.
+    #[inline]
+    pub fn new(inner: R, transform: T) -> Self {
+        Self::with_transform(inner, transform, 0)
+    }
+
+    /// Creates a new `TransformReader`, with the first byte read being index [position].
+    /// Useful if the reader has already been partially consumed.
+    #[inline]
+    pub fn with_position(inner: R, transform: T, position: u64) -> Self {
+        Self::with_transform(inner, transform, position)
+    }
+}
+impl<R, T> TransformReader<R, T>
+where
+    R: Read,
+    T: ByteTransform,
+{
+    /// Like [TransformReader::with_position], but for [ByteTransform] implementations which
+    /// aren't closures.
+    /// Note: closures go through `new`/`with_position`, since the types of their parameters
+    /// can't be inferred here.
+    #[inline]
+    pub fn with_transform(inner: R, transform: T, position: u64) -> Self {
+        TransformReader {
+            inner,
+            transform,
+            index: position,
+        }
+    }
+
+    /// Returns the index that will be given for the next byte read.
+    #[inline]
+    pub fn position(&self) -> u64 {
+        self.index
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Note: reading from the inner reader through this handle will not update the index, and
+    /// gives the untransformed bytes.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+}
+impl<R, T> Read for TransformReader<R, T>
+where
+    R: Read,
+    T: ByteTransform,
+{
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let amount_read = self.inner.read(buf)?;
+        for value in buf[..amount_read].iter_mut() {
+            *value = self.transform.transform(self.index, *value);
+            self.index = self.index.wrapping_add(1);
+        }
+        Ok(amount_read)
+    }
+}
+impl<R, T> Seek for TransformReader<R, T>
+where
+    R: Read + Seek,
+    T: ByteTransform,
+{
+    /// Seeks the inner reader, and sets the index to the resulting position.
+    /// Note: this discards any offset given by `with_position`, since the inner reader's
+    /// position is the more accurate one.
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let position = self.inner.seek(pos)?;
+        self.index = position;
+        Ok(position)
+    }
+}
+
+/// XORs each byte with a repeating key.
+#[derive(Debug, Clone)]
+struct XorKey {
+    key: Vec<u8>,
+}
+impl ByteTransform for XorKey {
+    #[inline]
+    fn transform(&mut self, index: u64, byte: u8) -> u8 {
+        if self.key.is_empty() {
+            byte
+        } else {
+            // The remainder is less than the key's length, so it fits in a usize
+            byte ^ self.key[(index % self.key.len() as u64) as usize]
+        }
+    }
+}
+
+/// Wraps a reader, XORing each byte with a repeating key. The key lines up with the start of
+/// the stream, including after seeking.
+/// An empty key leaves the data unchanged.
+#[derive(Debug)]
+pub struct XorReader<R: Read> {
+    inner: TransformReader<R, XorKey>,
+}
+impl<R> XorReader<R>
+where
+    R: Read,
+{
+    #[inline]
+    pub fn new(inner: R, key: &[u8]) -> Self {
+        Self::with_position(inner, key, 0)
+    }
+
+    /// Creates a new `XorReader`, for a reader that is already [position] bytes into the
+    /// stream, so that the key still lines up.
+    #[inline]
+    pub fn with_position(inner: R, key: &[u8], position: u64) -> Self {
+        let key = XorKey { key: key.to_vec() };
+        XorReader {
+            inner: TransformReader::with_transform(inner, key, position),
+        }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.inner.into_inner()
+    }
+
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        self.inner.get_ref()
+    }
+
+    /// Note: reading from the inner reader through this handle gives the bytes without the XOR,
+    /// and will not keep the key lined up.
+    #[inline]
+    pub fn get_mut(&mut self) -> &mut R {
+        self.inner.get_mut()
+    }
+}
+impl<R> Read for XorReader<R>
+where
+    R: Read,
+{
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+impl<R> Seek for XorReader<R>
+where
+    R: Read + Seek,
+{
+    #[inline]
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.inner.seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        parse::{many_parse, Parse},
+        Endian,
+    };
+    use std::io::Cursor;
+
+    const KEY: [u8; 3] = [0xAA, 0x55, 0x0F];
+    /// The big endian u32s 1, 2, 3 XORed with [KEY]
+    const ENCODED: [u8; 12] = [
+        0xAA, 0x55, 0x0F, 0xAB, //
+        0x55, 0x0F, 0xAA, 0x57, //
+        0x0F, 0xAA, 0x55, 0x0C,
+    ];
+
+    #[test]
+    fn test_xor_reader() {
+        let mut reader = XorReader::new(Cursor::new(&ENCODED), &KEY);
+        let values: Vec<u32> = many_parse(&mut reader, Endian::Big).unwrap();
+        assert_eq!(values, [1, 2, 3]);
+
+        // The key stays lined up after seeking into the middle of it
+        reader.seek(SeekFrom::Start(4)).unwrap();
+        assert_eq!(u32::parse(&mut reader, Endian::Big).unwrap(), 2);
+        reader.seek(SeekFrom::Current(-2)).unwrap();
+        assert_eq!(u16::parse(&mut reader, Endian::Big).unwrap(), 2);
+
+        let mut reader = XorReader::new(&ENCODED[..4], &[]);
+        assert_eq!(u32::parse(&mut reader, Endian::Big).unwrap(), 0xAA550FAB);
+    }
+
+    #[test]
+    fn test_transform_reader() {
+        let mut reader = TransformReader::new(&[0x10u8, 0x10, 0x10][..], |index, byte| {
+            byte.wrapping_add(index as u8)
+        });
+        let mut out = [0u8; 3];
+        reader.read_exact(&mut out).unwrap();
+        assert_eq!(out, [0x10, 0x11, 0x12]);
+        assert_eq!(reader.position(), 3);
+    }
+}
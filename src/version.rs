@@ -0,0 +1,118 @@
+use crate::{
+    impl_data_size,
+    parse::{Parse, ParseResult},
+    writable::{Writable, WriteResult},
+    Endian,
+};
+use std::{
+    fmt,
+    io::{Read, Write},
+};
+
+/// A (major, minor) version pair, as found in file headers.
+/// Orders by major and then minor, so that `1.9 < 2.0`, and displays as `1.4`.
+/// Stored as the major version followed by the minor version.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Version {
+    pub major: u16,
+    pub minor: u16,
+}
+impl Version {
+    #[inline]
+    pub const fn new(major: u16, minor: u16) -> Self {
+        Version { major, minor }
+    }
+}
+impl From<(u16, u16)> for Version {
+    #[inline]
+    fn from((major, minor): (u16, u16)) -> Self {
+        Version { major, minor }
+    }
+}
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.major, self.minor)
+    }
+}
+impl<F: Read> Parse<F, Endian> for Version {
+    #[inline]
+    fn parse(f: &mut F, endian: Endian) -> ParseResult<Self> {
+        let major = u16::parse(f, endian)?;
+        let minor = u16::parse(f, endian)?;
+        Ok(Version { major, minor })
+    }
+}
+impl Writable<Endian> for Version {
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, endian: Endian) -> WriteResult
+    where
+        W: Write,
+    {
+        self.major.write_to(w, endian)?;
+        self.minor.write_to(w, endian)
+    }
+}
+impl_data_size!(Version, 4);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data_size::DataSize;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_version_ordering() {
+        assert!(Version::new(1, 9) < Version::new(2, 0));
+        assert!(Version::new(2, 0) < Version::new(2, 1));
+        assert!(Version::new(1, 10) > Version::new(1, 9));
+        assert_eq!(Version::new(1, 4), Version::from((1, 4)));
+        assert_eq!(Version::new(1, 4).to_string(), "1.4");
+        let mut versions = vec![Version::new(2, 0), Version::new(1, 10), Version::new(1, 9)];
+        versions.sort();
+        assert_eq!(
+            versions,
+            [Version::new(1, 9), Version::new(1, 10), Version::new(2, 0)]
+        );
+    }
+
+    #[test]
+    fn test_version_round_trip() {
+        let version = Version::new(1, 0x0204);
+        for &(endian, data) in [
+            (Endian::Little, [0x1, 0x0, 0x4, 0x2]),
+            (Endian::Big, [0x0, 0x1, 0x2, 0x4]),
+        ]
+        .iter()
+        {
+            let mut output = Vec::new();
+            version.write_to(&mut output, endian).unwrap();
+            assert_eq!(output, data);
+            assert_eq!(version.data_size(()), 4);
+            let mut cursor = Cursor::new(output);
+            assert_eq!(Version::parse(&mut cursor, endian).unwrap(), version);
+        }
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct Header {
+        kind: u8,
+        extra: Option<u16>,
+    }
+    crate::impl_parse_versioned!(Header, version: Version, [
+        kind: u: u8,
+        [if version >= Version::new(1, 2)] extra: c: u16
+    ]);
+
+    #[test]
+    fn test_version_threshold() {
+        use crate::ctx::ParseCtx;
+
+        let data = [0x1, 0x2, 0x0];
+        let ctx = ParseCtx::with_user(Endian::Little, Version::new(1, 1));
+        let header = Header::parse(&mut Cursor::new(&data), ctx).unwrap();
+        assert_eq!(header.extra, None);
+        let ctx = ParseCtx::with_user(Endian::Little, Version::new(2, 0));
+        let header = Header::parse(&mut Cursor::new(&data), ctx).unwrap();
+        assert_eq!(header.extra, Some(0x2));
+    }
+}
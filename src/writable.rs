@@ -1,5 +1,14 @@
-use crate::Endian;
-use std::{fmt::Debug, io::Write};
+use crate::{
+    data_size::DataSize, sections::SectionError, write_if_possible, zstring::ZString, Endian,
+};
+use std::{
+    borrow::Cow,
+    convert::TryFrom,
+    fmt::Debug,
+    io::{self, Seek, SeekFrom, Write},
+    rc::Rc,
+    sync::Arc,
+};
 
 pub type WriteResult = Result<(), WriteError>;
 
@@ -13,12 +22,34 @@ pub enum WriteError {
     /// The amount of data exceeds the amount that can be fit within whatever bitness the integer
     /// that tracks the size can contain.
     TooManyBits,
+    /// The alignment was zero or not a power of two.
+    InvalidAlignment(u64),
+    /// A section was invalid, or did not exist.
+    Section(SectionError),
+    /// The data contained a byte that can't be written out, such as a null within a string
+    /// that is written null-terminated.
+    InvalidByte,
+    /// Tried to finish bit-level writing with .0 bits that don't make up a whole byte.
+    PartialByte(u8),
+    /// Tried to write an empty frame, which readers can't tell apart from filler.
+    EmptyFrame,
+    /// String .0 was .1 bytes long, which is over its maximum length.
+    StringTooLong(usize, usize),
 }
 impl From<std::io::Error> for WriteError {
     fn from(v: std::io::Error) -> Self {
         Self::Io(v)
     }
 }
+impl From<SectionError> for WriteError {
+    fn from(e: SectionError) -> Self {
+        Self::Section(e)
+    }
+}
+
+/// `#[derive(Writable)]`, see the eyeutil-derive crate for its attributes.
+#[cfg(feature = "derive")]
+pub use eyeutil_derive::Writable;
 
 // TODO: it'd be nice to support Little|Big endian as a more general crate for my parsing needs
 /// NOTE: all Writables will write integers and floats in little endian.
@@ -31,13 +62,26 @@ where
         W: Write;
 }
 
+/// Writes nothing, for "no payload" in generic framing code.
+impl<D> Writable<D> for ()
+where
+    D: Debug + Clone + PartialEq,
+{
+    #[inline]
+    fn write_to<W>(&self, _w: &mut W, _d: D) -> WriteResult
+    where
+        W: Write,
+    {
+        Ok(())
+    }
+}
 impl Writable<()> for u8 {
     #[inline]
     fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
     where
         W: Write,
     {
-        w.write_all(&self.to_le_bytes())?;
+        write_full(w, &self.to_le_bytes())?;
         Ok(())
     }
 }
@@ -47,20 +91,57 @@ impl Writable<()> for i8 {
     where
         W: Write,
     {
-        w.write_all(&self.to_le_bytes())?;
+        write_full(w, &self.to_le_bytes())?;
+        Ok(())
+    }
+}
+/// Writes the UTF-8 encoding.
+impl Writable<()> for char {
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
+    where
+        W: Write,
+    {
+        let mut data = [0u8; 4];
+        write_full(w, self.encode_utf8(&mut data).as_bytes())?;
         Ok(())
     }
 }
+/// Writes the raw UTF-8 bytes, with no length or terminator, like `&[T]`.
+/// For framed strings see `ZString` (null-terminated), `PrefixString`/`PString`
+/// (length-prefixed), `FixedString` (fixed region) and `BZString` (both).
+impl Writable<()> for &str {
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
+    where
+        W: Write,
+    {
+        write_bytes(w, self.as_bytes())
+    }
+}
+/// Writes the raw UTF-8 bytes, with no length or terminator. See the `&str` impl.
+impl Writable<()> for String {
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
+    where
+        W: Write,
+    {
+        write_bytes(w, self.as_bytes())
+    }
+}
 impl Writable<Endian> for u16 {
     #[inline]
     fn write_to<W>(&self, w: &mut W, endian: Endian) -> WriteResult
     where
         W: Write,
     {
-        w.write_all(&match endian {
-            Endian::Big => self.to_be_bytes(),
-            Endian::Little => self.to_le_bytes(),
-        })?;
+        write_full(
+            w,
+            &match endian {
+                Endian::Big => self.to_be_bytes(),
+                Endian::Little => self.to_le_bytes(),
+            },
+        )?;
         Ok(())
     }
 }
@@ -70,10 +151,13 @@ impl Writable<Endian> for i16 {
     where
         W: Write,
     {
-        w.write_all(&match endian {
-            Endian::Big => self.to_be_bytes(),
-            Endian::Little => self.to_le_bytes(),
-        })?;
+        write_full(
+            w,
+            &match endian {
+                Endian::Big => self.to_be_bytes(),
+                Endian::Little => self.to_le_bytes(),
+            },
+        )?;
         Ok(())
     }
 }
@@ -83,10 +167,13 @@ impl Writable<Endian> for u32 {
     where
         W: Write,
     {
-        w.write_all(&match endian {
-            Endian::Big => self.to_be_bytes(),
-            Endian::Little => self.to_le_bytes(),
-        })?;
+        write_full(
+            w,
+            &match endian {
+                Endian::Big => self.to_be_bytes(),
+                Endian::Little => self.to_le_bytes(),
+            },
+        )?;
         Ok(())
     }
 }
@@ -96,10 +183,13 @@ impl Writable<Endian> for i32 {
     where
         W: Write,
     {
-        w.write_all(&match endian {
-            Endian::Big => self.to_be_bytes(),
-            Endian::Little => self.to_le_bytes(),
-        })?;
+        write_full(
+            w,
+            &match endian {
+                Endian::Big => self.to_be_bytes(),
+                Endian::Little => self.to_le_bytes(),
+            },
+        )?;
         Ok(())
     }
 }
@@ -109,10 +199,13 @@ impl Writable<Endian> for u64 {
     where
         W: Write,
     {
-        w.write_all(&match endian {
-            Endian::Big => self.to_be_bytes(),
-            Endian::Little => self.to_le_bytes(),
-        })?;
+        write_full(
+            w,
+            &match endian {
+                Endian::Big => self.to_be_bytes(),
+                Endian::Little => self.to_le_bytes(),
+            },
+        )?;
         Ok(())
     }
 }
@@ -122,10 +215,13 @@ impl Writable<Endian> for i64 {
     where
         W: Write,
     {
-        w.write_all(&match endian {
-            Endian::Big => self.to_be_bytes(),
-            Endian::Little => self.to_le_bytes(),
-        })?;
+        write_full(
+            w,
+            &match endian {
+                Endian::Big => self.to_be_bytes(),
+                Endian::Little => self.to_le_bytes(),
+            },
+        )?;
         Ok(())
     }
 }
@@ -135,10 +231,13 @@ impl Writable<Endian> for f32 {
     where
         W: Write,
     {
-        w.write_all(&match endian {
-            Endian::Big => self.to_be_bytes(),
-            Endian::Little => self.to_le_bytes(),
-        })?;
+        write_full(
+            w,
+            &match endian {
+                Endian::Big => self.to_be_bytes(),
+                Endian::Little => self.to_le_bytes(),
+            },
+        )?;
         Ok(())
     }
 }
@@ -148,10 +247,13 @@ impl Writable<Endian> for f64 {
     where
         W: Write,
     {
-        w.write_all(&match endian {
-            Endian::Big => self.to_be_bytes(),
-            Endian::Little => self.to_le_bytes(),
-        })?;
+        write_full(
+            w,
+            &match endian {
+                Endian::Big => self.to_be_bytes(),
+                Endian::Little => self.to_le_bytes(),
+            },
+        )?;
         Ok(())
     }
 }
@@ -172,6 +274,52 @@ where
         Ok(())
     }
 }
+impl<D, T> Writable<D> for &T
+where
+    T: Writable<D>,
+    D: Debug + Clone + PartialEq,
+{
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, d: D) -> WriteResult
+    where
+        W: Write,
+    {
+        (**self).write_to(w, d)
+    }
+}
+macro_rules! impl_writable_pointer {
+    ($($ptr:ident),*) => {
+        $(
+            impl<D, T> Writable<D> for $ptr<T>
+            where
+                T: Writable<D>,
+                D: Debug + Clone + PartialEq,
+            {
+                #[inline]
+                fn write_to<W>(&self, w: &mut W, d: D) -> WriteResult
+                where
+                    W: Write,
+                {
+                    (**self).write_to(w, d)
+                }
+            }
+        )*
+    };
+}
+impl_writable_pointer!(Box, Rc, Arc);
+impl<D, T> Writable<D> for Cow<'_, T>
+where
+    T: Writable<D> + Clone,
+    D: Debug + Clone + PartialEq,
+{
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, d: D) -> WriteResult
+    where
+        W: Write,
+    {
+        (**self).write_to(w, d)
+    }
+}
 impl<D, T> Writable<D> for Vec<T>
 where
     T: Writable<D>,
@@ -185,5 +333,980 @@ where
         self.as_slice().write_to(w, d)
     }
 }
+/// Writes nothing for `None`, for fields that are only present in some versions of a format.
+impl<D, T> Writable<D> for Option<T>
+where
+    T: Writable<D>,
+    D: Debug + Clone + PartialEq,
+{
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, d: D) -> WriteResult
+    where
+        W: Write,
+    {
+        match self {
+            Some(value) => value.write_to(w, d),
+            None => Ok(()),
+        }
+    }
+}
+impl<D, T, const N: usize> Writable<D> for [T; N]
+where
+    T: Writable<D>,
+    D: Debug + Clone + PartialEq,
+{
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, d: D) -> WriteResult
+    where
+        W: Write,
+    {
+        self.as_ref().write_to(w, d)
+    }
+}
+
+/// Extension trait for writing values with the endianness in the method name, rather than
+/// passed as data: `w.write_u32_le(value)?` instead of `value.write_to(&mut w, Endian::Little)?`.
+/// These all defer to the `Writable` impls.
+pub trait WriteExt: Write + Sized {
+    #[inline]
+    fn write_u8(&mut self, value: u8) -> WriteResult {
+        value.write_to(self, ())
+    }
+
+    #[inline]
+    fn write_i8(&mut self, value: i8) -> WriteResult {
+        value.write_to(self, ())
+    }
+
+    #[inline]
+    fn write_u16_le(&mut self, value: u16) -> WriteResult {
+        value.write_to(self, Endian::Little)
+    }
+
+    #[inline]
+    fn write_u16_be(&mut self, value: u16) -> WriteResult {
+        value.write_to(self, Endian::Big)
+    }
+
+    #[inline]
+    fn write_i16_le(&mut self, value: i16) -> WriteResult {
+        value.write_to(self, Endian::Little)
+    }
+
+    #[inline]
+    fn write_i16_be(&mut self, value: i16) -> WriteResult {
+        value.write_to(self, Endian::Big)
+    }
+
+    #[inline]
+    fn write_u32_le(&mut self, value: u32) -> WriteResult {
+        value.write_to(self, Endian::Little)
+    }
+
+    #[inline]
+    fn write_u32_be(&mut self, value: u32) -> WriteResult {
+        value.write_to(self, Endian::Big)
+    }
+
+    #[inline]
+    fn write_i32_le(&mut self, value: i32) -> WriteResult {
+        value.write_to(self, Endian::Little)
+    }
+
+    #[inline]
+    fn write_i32_be(&mut self, value: i32) -> WriteResult {
+        value.write_to(self, Endian::Big)
+    }
+
+    #[inline]
+    fn write_u64_le(&mut self, value: u64) -> WriteResult {
+        value.write_to(self, Endian::Little)
+    }
+
+    #[inline]
+    fn write_u64_be(&mut self, value: u64) -> WriteResult {
+        value.write_to(self, Endian::Big)
+    }
+
+    #[inline]
+    fn write_i64_le(&mut self, value: i64) -> WriteResult {
+        value.write_to(self, Endian::Little)
+    }
+
+    #[inline]
+    fn write_i64_be(&mut self, value: i64) -> WriteResult {
+        value.write_to(self, Endian::Big)
+    }
+
+    #[inline]
+    fn write_f32_le(&mut self, value: f32) -> WriteResult {
+        value.write_to(self, Endian::Little)
+    }
+
+    #[inline]
+    fn write_f32_be(&mut self, value: f32) -> WriteResult {
+        value.write_to(self, Endian::Big)
+    }
+
+    #[inline]
+    fn write_f64_le(&mut self, value: f64) -> WriteResult {
+        value.write_to(self, Endian::Little)
+    }
+
+    #[inline]
+    fn write_f64_be(&mut self, value: f64) -> WriteResult {
+        value.write_to(self, Endian::Big)
+    }
+
+    /// Writes the string, followed by its null-terminator.
+    #[inline]
+    fn write_zstring(&mut self, value: &ZString) -> WriteResult {
+        value.write_to(self, ())
+    }
+}
+impl<W: Write> WriteExt for W {}
+
+/// Writes all of [data] with [write_if_possible].
+/// Errors with `ErrorKind::WriteZero` if the writer stops accepting bytes.
+/// Every write in this crate goes through this, rather than calling `write` directly.
+pub(crate) fn write_full<W>(w: &mut W, data: &[u8]) -> std::io::Result<()>
+where
+    W: Write + ?Sized,
+{
+    let written = write_if_possible(w, data)?;
+    if written < data.len() {
+        return Err(io::Error::new(
+            io::ErrorKind::WriteZero,
+            format!(
+                "writer accepted no more bytes, after {} of {} were written",
+                written,
+                data.len()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+/// Writes all of [data] at once, which is a single write call if the writer accepts it all.
+/// The `Writable` impl for `&[T]` writes element by element, which for bytes means a write
+/// call per byte.
+#[inline]
+pub fn write_bytes<W>(w: &mut W, data: &[u8]) -> WriteResult
+where
+    W: Write,
+{
+    write_full(w, data)?;
+    Ok(())
+}
+
+/// Borrowed bytes which are written with a single write call, see [write_bytes].
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Bytes<'a>(pub &'a [u8]);
+impl<'a> Writable<()> for Bytes<'a> {
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
+    where
+        W: Write,
+    {
+        write_bytes(w, self.0)
+    }
+}
+impl<'a> DataSize<()> for Bytes<'a> {
+    #[inline]
+    fn data_size(&self, _d: ()) -> u64 {
+        self.0.len() as u64
+    }
+}
+
+/// Owned bytes which are written with a single write call, see [write_bytes].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct OwnedBytes(pub Vec<u8>);
+impl Writable<()> for OwnedBytes {
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
+    where
+        W: Write,
+    {
+        write_bytes(w, &self.0)
+    }
+}
+impl DataSize<()> for OwnedBytes {
+    #[inline]
+    fn data_size(&self, _d: ()) -> u64 {
+        self.0.len() as u64
+    }
+}
+
+/// Writes a length-prefixed body where the length isn't known until the body is written.
+/// This writes a zero placeholder of type [T], runs [body], and then seeks back to patch the
+/// placeholder with the number of bytes [body] wrote (measured by stream positions), before
+/// seeking back to the end of the body.
+/// Errors with `WriteError::TooManyBits` if the body's size can't be represented by [T].
+/// Note: the length written does not include the size of the length itself.
+pub fn write_deferred_len<W, T, C>(w: &mut W, endian: Endian, body: C) -> WriteResult
+where
+    W: Write + Seek,
+    T: Writable<Endian> + TryFrom<u64>,
+    C: FnOnce(&mut W) -> WriteResult,
+{
+    let placeholder = T::try_from(0).map_err(|_| WriteError::TooManyBits)?;
+    let len_position = w.stream_position()?;
+    placeholder.write_to(w, endian)?;
+
+    let body_start = w.stream_position()?;
+    body(w)?;
+    let body_end = w.stream_position()?;
+
+    let len = body_end
+        .checked_sub(body_start)
+        .ok_or(WriteError::ExcessiveData)?;
+    let len = T::try_from(len).map_err(|_| WriteError::TooManyBits)?;
+
+    w.seek(SeekFrom::Start(len_position))?;
+    len.write_to(w, endian)?;
+    w.seek(SeekFrom::Start(body_end))?;
+    Ok(())
+}
+
+/// Writes [pad_byte] until the position is a multiple of [alignment] bytes from [base].
+/// Does nothing if already aligned.
+/// Errors with `WriteError::InvalidAlignment` if [alignment] is zero or not a power of two.
+pub fn pad_to<W>(w: &mut W, alignment: u64, base: u64, pad_byte: u8) -> WriteResult
+where
+    W: Write + Seek,
+{
+    if !alignment.is_power_of_two() {
+        return Err(WriteError::InvalidAlignment(alignment));
+    }
+    let position = w.stream_position()?;
+    let offset = position.checked_sub(base).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "position {} is before the alignment base {}",
+                position, base
+            ),
+        )
+    })?;
+
+    let mut padding = offset.wrapping_neg() & (alignment - 1);
+    let chunk = [pad_byte; 64];
+    while padding != 0 {
+        // Fits in a usize, since it is at most the chunk's length
+        let amount = padding.min(chunk.len() as u64) as usize;
+        write_full(w, &chunk[..amount])?;
+        padding -= amount as u64;
+    }
+    Ok(())
+}
+
+/// Seeks to [position] (from the start of the stream), writes [value] there, and then seeks
+/// back to where [w] was, even if the write failed.
+/// If both the write and the seek back fail, the write's error is returned, since it is the more
+/// informative of the two. If only the seek back fails, then its error is returned.
+pub fn write_at<W, T, D>(w: &mut W, position: u64, value: &T, d: D) -> WriteResult
+where
+    W: Write + Seek,
+    T: Writable<D>,
+    D: Debug + Clone + PartialEq,
+{
+    let initial_position = w.stream_position()?;
+    let written = match w.seek(SeekFrom::Start(position)) {
+        Ok(_) => value.write_to(w, d),
+        Err(err) => Err(err.into()),
+    };
+    let restored = w.seek(SeekFrom::Start(initial_position));
+    written?;
+    restored?;
+    Ok(())
+}
+
+/// Writes [value] as ASCII decimal, right-aligned in a field of [width] bytes, with [fill]
+/// before it, such as `b'0'` or `b' '`.
+/// Errors with `WriteError::ExcessiveData` if it takes more than [width] digits.
+#[inline]
+pub fn write_ascii_u64_dec<W>(w: &mut W, value: u64, width: usize, fill: u8) -> WriteResult
+where
+    W: Write,
+{
+    write_ascii_digits(w, &format!("{}", value), width, fill)
+}
+
+/// Like [write_ascii_u64_dec], but for lowercase hexadecimal.
+#[inline]
+pub fn write_ascii_u64_hex<W>(w: &mut W, value: u64, width: usize, fill: u8) -> WriteResult
+where
+    W: Write,
+{
+    write_ascii_digits(w, &format!("{:x}", value), width, fill)
+}
+
+/// Like [write_ascii_u64_dec], but for octal, such as the numeric fields of tar headers.
+/// Note: tar's terminator has to be written separately, and isn't counted in [width].
+#[inline]
+pub fn write_ascii_u64_octal<W>(w: &mut W, value: u64, width: usize, fill: u8) -> WriteResult
+where
+    W: Write,
+{
+    write_ascii_digits(w, &format!("{:o}", value), width, fill)
+}
+
+fn write_ascii_digits<W>(w: &mut W, digits: &str, width: usize, fill: u8) -> WriteResult
+where
+    W: Write,
+{
+    let padding = width
+        .checked_sub(digits.len())
+        .ok_or(WriteError::ExcessiveData)?;
+    write_full(w, &vec![fill; padding])?;
+    write_full(w, digits.as_bytes())?;
+    Ok(())
+}
+
+#[macro_export]
+macro_rules! impl_writable_field {
+    ($value:expr => l; $output:expr) => {
+        $crate::writable::Writable::write_to($value, $output, $crate::Endian::Little)?;
+    };
+    ($value:expr => b; $output:expr) => {
+        $crate::writable::Writable::write_to($value, $output, $crate::Endian::Big)?;
+    };
+    // No data
+    ($value:expr => u; $output:expr) => {
+        $crate::writable::Writable::write_to($value, $output, ())?;
+    };
+    // Explicit data, such as `(Endian::Little, AddrWidth::W32)`
+    ($value:expr => ($($data:tt)*); $output:expr) => {
+        $crate::writable::Writable::write_to($value, $output, ($($data)*))?;
+    };
+    // The impl's own data, for `impl_writable!(generic data ...)`
+    ($value:expr => d; $output:expr, $data:expr) => {
+        $crate::writable::Writable::write_to($value, $output, $data.clone())?;
+    };
+    ($value:expr => $e:tt; $output:expr, $data:expr) => {
+        $crate::impl_writable_field!($value => $e; $output);
+    };
+}
+
+/// Implements `Writable<()>` by writing each field in order. Takes the same field list as
+/// `impl_parse!`, so the two can be kept in sync:
+/// `impl_writable!(Header, [kind: u: u8, length: l: u32]);`
+/// In debug builds the amount written is checked against the type's `DataSize<()>` impl,
+/// panicking if they disagree. For types whose data size intentionally differs from what is
+/// written (or which don't implement DataSize), use the unchecked arm:
+/// `impl_writable!(unchecked Header, [kind: u: u8, length: l: u32]);`
+/// Instead of `l`/`b`/`u`, a field can give its data in parentheses:
+/// `impl_writable!(Entry, [offset: (Endian::Little, AddrWidth::W32): Uaddr]);`
+/// Generic types are written like with `impl_parse!`, either of the forms going after
+/// `unchecked`, and `generic data` requiring `D: Debug + Clone + PartialEq`:
+/// `impl_writable!(generic data [T] Pair<T> where [T: Writable<D>], [a: d: T, b: d: T]);`
+/// Note: the checked generic forms require the type to implement `DataSize<()>`, whatever [D]
+/// is, so that the check is the same in release builds.
+#[macro_export]
+macro_rules! impl_writable {
+    (generic [$($gen:tt)*] $on:ty $(where [$($bound:tt)*])?, [$($fields:tt)*]) => {
+        $crate::impl_writable!(@checked [$($gen)*] $on, (),
+            [$on: $crate::data_size::DataSize<()>, $($($bound)*)?], [$($fields)*]);
+    };
+    (generic data [$($gen:tt)*] $on:ty $(where [$($bound:tt)*])?, [$($fields:tt)*]) => {
+        $crate::impl_writable!(@checked [D, $($gen)*] $on, D,
+            [
+                D: std::fmt::Debug + Clone + PartialEq,
+                $on: $crate::data_size::DataSize<()>,
+                $($($bound)*)?
+            ],
+            [$($fields)*]);
+    };
+    (unchecked generic [$($gen:tt)*] $on:ty $(where [$($bound:tt)*])?, [$($fields:tt)*]) => {
+        $crate::impl_writable!(@unchecked [$($gen)*] $on, (), [$($($bound)*)?], [$($fields)*]);
+    };
+    (unchecked generic data [$($gen:tt)*] $on:ty $(where [$($bound:tt)*])?, [$($fields:tt)*]) => {
+        $crate::impl_writable!(@unchecked [D, $($gen)*] $on, D,
+            [D: std::fmt::Debug + Clone + PartialEq, $($($bound)*)?], [$($fields)*]);
+    };
+    (unchecked $on:ty, [$($fields:tt)*]) => {
+        $crate::impl_writable!(@unchecked [] $on, (), [], [$($fields)*]);
+    };
+    ($on:ty, [$($fields:tt)*]) => {
+        $crate::impl_writable!(@checked [] $on, (), [], [$($fields)*]);
+    };
+    (@checked [$($gen:tt)*] $on:ty, $data:ty, [$($bound:tt)*], [$($name:ident : $e:tt : $typ:ty),*]) => {
+        impl<$($gen)*> $crate::writable::Writable<$data> for $on
+        where
+            $($bound)*
+        {
+            #[allow(unused_variables)]
+            fn write_to<W>(&self, w: &mut W, d: $data) -> $crate::writable::WriteResult
+            where
+                W: std::io::Write,
+            {
+                // Erasing the writer's type keeps recursive types (such as a node with boxed
+                // children) from wrapping the writer again at every level
+                #[cfg(debug_assertions)]
+                let mut counter = $crate::CountingWriter::new(w as &mut dyn std::io::Write);
+                #[cfg(debug_assertions)]
+                let w = &mut counter;
+                $(
+                    $crate::impl_writable_field!(&self.$name => $e; w, d);
+                )*
+                #[cfg(debug_assertions)]
+                {
+                    let expected = $crate::data_size::DataSize::<()>::data_size(self, ());
+                    debug_assert_eq!(
+                        counter.position(),
+                        expected,
+                        "amount written by {} does not match its DataSize",
+                        stringify!($on)
+                    );
+                }
+                Ok(())
+            }
+        }
+    };
+    (@unchecked [$($gen:tt)*] $on:ty, $data:ty, [$($bound:tt)*], [$($name:ident : $e:tt : $typ:ty),*]) => {
+        impl<$($gen)*> $crate::writable::Writable<$data> for $on
+        where
+            $($bound)*
+        {
+            #[allow(unused_variables)]
+            fn write_to<W>(&self, w: &mut W, d: $data) -> $crate::writable::WriteResult
+            where
+                W: std::io::Write,
+            {
+                $(
+                    $crate::impl_writable_field!(&self.$name => $e; w, d);
+                )*
+                Ok(())
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::CallCountingWriter;
+
+    struct Header {
+        kind: u8,
+        length: u32,
+        version: u16,
+    }
+    crate::impl_struct_data_size!(Header, [kind, length, version]);
+    crate::impl_writable!(Header, [kind: u: u8, length: l: u32, version: b: u16]);
+
+    /// Deliberately reports the size aligned up to 8 bytes
+    struct Aligned {
+        kind: u8,
+        length: u32,
+    }
+    impl DataSize<()> for Aligned {
+        fn data_size(&self, _d: ()) -> u64 {
+            8
+        }
+    }
+    crate::impl_writable!(Aligned, [kind: u: u8, length: l: u32]);
+
+    struct AlignedUnchecked {
+        kind: u8,
+        length: u32,
+    }
+    impl DataSize<()> for AlignedUnchecked {
+        fn data_size(&self, _d: ()) -> u64 {
+            8
+        }
+    }
+    crate::impl_writable!(unchecked AlignedUnchecked, [kind: u: u8, length: l: u32]);
+
+    #[test]
+    fn test_impl_writable_consistent() {
+        let header = Header {
+            kind: 0x1,
+            length: 0x02030405,
+            version: 0x0607,
+        };
+        assert_eq!(header.data_size(()), 7);
+        let mut output = Vec::new();
+        header.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, [0x1, 0x5, 0x4, 0x3, 0x2, 0x6, 0x7]);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "does not match its DataSize")]
+    fn test_impl_writable_inconsistent() {
+        let value = Aligned {
+            kind: 0x1,
+            length: 0x2,
+        };
+        let mut output = Vec::new();
+        let _ = value.write_to(&mut output, ());
+    }
+
+    #[cfg(not(debug_assertions))]
+    #[test]
+    fn test_impl_writable_inconsistent() {
+        let value = Aligned {
+            kind: 0x1,
+            length: 0x2,
+        };
+        let mut output = Vec::new();
+        value.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, [0x1, 0x2, 0x0, 0x0, 0x0]);
+    }
 
-// TODO: add tests
+    #[test]
+    fn test_impl_writable_unchecked() {
+        let value = AlignedUnchecked {
+            kind: 0x1,
+            length: 0x2,
+        };
+        assert_eq!(value.data_size(()), 8);
+        let mut output = Vec::new();
+        value.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, [0x1, 0x2, 0x0, 0x0, 0x0]);
+    }
+
+    #[test]
+    fn test_write_deferred_len() {
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        0xAAu8.write_to(&mut cursor, ()).unwrap();
+        write_deferred_len::<_, u32, _>(&mut cursor, Endian::Little, |w| {
+            0x1u8.write_to(w, ())?;
+            write_deferred_len::<_, u16, _>(w, Endian::Big, |w| {
+                [0x2u8, 0x3, 0x4].as_ref().write_to(w, ())
+            })?;
+            0x5u8.write_to(w, ())
+        })
+        .unwrap();
+        assert_eq!(cursor.position(), 12);
+        0xBBu8.write_to(&mut cursor, ()).unwrap();
+        assert_eq!(
+            cursor.into_inner(),
+            [0xAA, 0x7, 0x0, 0x0, 0x0, 0x1, 0x0, 0x3, 0x2, 0x3, 0x4, 0x5, 0xBB]
+        );
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        write_deferred_len::<_, u16, _>(&mut cursor, Endian::Little, |_| Ok(())).unwrap();
+        assert_eq!(cursor.into_inner(), [0x0, 0x0]);
+    }
+
+    #[test]
+    fn test_write_deferred_len_too_large() {
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        let result = write_deferred_len::<_, u16, _>(&mut cursor, Endian::Little, |w| {
+            vec![0u8; 0x10000].write_to(w, ())
+        });
+        match result {
+            Err(WriteError::TooManyBits) => {}
+            x => panic!("Expected TooManyBits, got: {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_pad_to() {
+        for start in 0..=4u8 {
+            let mut cursor = std::io::Cursor::new(vec![0xAA; start as usize]);
+            cursor.set_position(u64::from(start));
+            pad_to(&mut cursor, 4, 0, 0xEE).unwrap();
+            let expected = (4 - start) % 4;
+            assert_eq!(cursor.position(), u64::from(start + expected));
+            assert!(cursor.get_ref()[start as usize..]
+                .iter()
+                .all(|&x| x == 0xEE));
+        }
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        0xAAu8.write_to(&mut cursor, ()).unwrap();
+        pad_to(&mut cursor, 8, 1, 0).unwrap();
+        assert_eq!(cursor.position(), 1);
+        0xBBu8.write_to(&mut cursor, ()).unwrap();
+        pad_to(&mut cursor, 8, 1, 0).unwrap();
+        assert_eq!(cursor.position(), 9);
+
+        for &alignment in [0, 3, 12].iter() {
+            match pad_to(&mut cursor, alignment, 0, 0) {
+                Err(WriteError::InvalidAlignment(x)) if x == alignment => {}
+                x => panic!("Expected InvalidAlignment, got: {:?}", x),
+            }
+        }
+        assert!(pad_to(&mut cursor, 4, 10, 0).is_err());
+    }
+
+    #[test]
+    fn test_write_ascii() {
+        use crate::parse::{ascii_u64_dec, ascii_u64_hex, ascii_u64_octal};
+
+        // A tar header's size field, and its terminator
+        let mut output = Vec::new();
+        write_ascii_u64_octal(&mut output, 1000, 11, b'0').unwrap();
+        0u8.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, b"00000001750\0");
+        let mut cursor = std::io::Cursor::new(&output);
+        assert_eq!(
+            ascii_u64_octal(&mut cursor, Some(12), Some(0)).unwrap(),
+            1000
+        );
+
+        let mut output = Vec::new();
+        write_ascii_u64_dec(&mut output, 42, 6, b' ').unwrap();
+        write_ascii_u64_hex(&mut output, 0xBEEF, 4, b'0').unwrap();
+        assert_eq!(output, b"    42beef");
+        let mut cursor = std::io::Cursor::new(&output);
+        assert_eq!(ascii_u64_dec(&mut cursor, Some(6), None).unwrap(), 42);
+        assert_eq!(ascii_u64_hex(&mut cursor, Some(4), None).unwrap(), 0xBEEF);
+
+        assert!(matches!(
+            write_ascii_u64_dec(&mut Vec::new(), 1000, 3, b'0'),
+            Err(WriteError::ExcessiveData)
+        ));
+    }
+
+    #[test]
+    fn test_write_at() {
+        let mut buffer = [0u8; 6];
+        let mut cursor = std::io::Cursor::new(&mut buffer[..]);
+        0xAAu8.write_to(&mut cursor, ()).unwrap();
+        write_at(&mut cursor, 3, &0x0102u16, Endian::Big).unwrap();
+        assert_eq!(cursor.position(), 1);
+
+        // Doesn't fit at the end, but the position is still restored
+        match write_at(&mut cursor, 5, &0x0304u16, Endian::Big) {
+            Err(WriteError::Io(_)) => {}
+            x => panic!("Expected Io, got: {:?}", x),
+        }
+        assert_eq!(cursor.position(), 1);
+        0xBBu8.write_to(&mut cursor, ()).unwrap();
+        assert_eq!(buffer, [0xAA, 0xBB, 0x0, 0x1, 0x2, 0x3]);
+    }
+
+    #[test]
+    fn test_write_array() {
+        let mut output = Vec::new();
+        [0x1u8, 0x2, 0x3, 0x4].write_to(&mut output, ()).unwrap();
+        assert_eq!(output, [0x1, 0x2, 0x3, 0x4]);
+
+        let values = [1.0f32, -2.0, 0.5];
+        let mut output = Vec::new();
+        values.write_to(&mut output, Endian::Little).unwrap();
+        assert_eq!(
+            output,
+            [0x00, 0x00, 0x80, 0x3F, 0x00, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x3F]
+        );
+
+        let mut output = Vec::new();
+        values.write_to(&mut output, Endian::Big).unwrap();
+        assert_eq!(
+            output,
+            [0x3F, 0x80, 0x00, 0x00, 0xC0, 0x00, 0x00, 0x00, 0x3F, 0x00, 0x00, 0x00]
+        );
+    }
+
+    #[test]
+    fn test_write_bytes() {
+        let payload = vec![0xABu8; 10 * 1024];
+
+        let mut output = CallCountingWriter::default();
+        write_bytes(&mut output, &payload).unwrap();
+        assert_eq!(output.calls, 1);
+        assert_eq!(output.data, payload);
+
+        let mut output = CallCountingWriter::default();
+        Bytes(&payload).write_to(&mut output, ()).unwrap();
+        assert_eq!(output.calls, 1);
+        assert_eq!(output.data, payload);
+
+        let owned = OwnedBytes(payload.clone());
+        assert_eq!(owned.data_size(()), 10 * 1024);
+        let mut output = CallCountingWriter::default();
+        owned.write_to(&mut output, ()).unwrap();
+        assert_eq!(output.calls, 1);
+        assert_eq!(output.data, payload);
+    }
+
+    #[test]
+    fn test_write_ext() {
+        fn check<T, F>(value: T, endian: Endian, ext: F)
+        where
+            T: Writable<Endian>,
+            F: Fn(&mut Vec<u8>) -> WriteResult,
+        {
+            let mut expected = Vec::new();
+            value.write_to(&mut expected, endian).unwrap();
+            let mut output = Vec::new();
+            ext(&mut output).unwrap();
+            assert_eq!(output, expected);
+        }
+
+        let mut output = Vec::new();
+        output.write_u8(0xF1).unwrap();
+        output.write_i8(-2).unwrap();
+        assert_eq!(output, [0xF1, 0xFE]);
+
+        check(0x0102u16, Endian::Little, |w| w.write_u16_le(0x0102));
+        check(0x0102u16, Endian::Big, |w| w.write_u16_be(0x0102));
+        check(-0x0102i16, Endian::Little, |w| w.write_i16_le(-0x0102));
+        check(-0x0102i16, Endian::Big, |w| w.write_i16_be(-0x0102));
+        check(0x01020304u32, Endian::Little, |w| {
+            w.write_u32_le(0x01020304)
+        });
+        check(0x01020304u32, Endian::Big, |w| w.write_u32_be(0x01020304));
+        check(-0x01020304i32, Endian::Little, |w| {
+            w.write_i32_le(-0x01020304)
+        });
+        check(-0x01020304i32, Endian::Big, |w| w.write_i32_be(-0x01020304));
+        let x = 0x0102030405060708u64;
+        check(x, Endian::Little, |w| w.write_u64_le(x));
+        check(x, Endian::Big, |w| w.write_u64_be(x));
+        let x = -0x0102030405060708i64;
+        check(x, Endian::Little, |w| w.write_i64_le(x));
+        check(x, Endian::Big, |w| w.write_i64_be(x));
+        check(1.5f32, Endian::Little, |w| w.write_f32_le(1.5));
+        check(1.5f32, Endian::Big, |w| w.write_f32_be(1.5));
+        check(-1.25f64, Endian::Little, |w| w.write_f64_le(-1.25));
+        check(-1.25f64, Endian::Big, |w| w.write_f64_be(-1.25));
+
+        let mut output = Vec::new();
+        output
+            .write_zstring(&ZString::new(b"abc".to_vec()))
+            .unwrap();
+        assert_eq!(output, b"abc\0");
+    }
+
+    #[test]
+    fn test_write_unit() {
+        let mut output = Vec::new();
+        ().write_to(&mut output, ()).unwrap();
+        ().write_to(&mut output, Endian::Big).unwrap();
+        assert!(output.is_empty());
+
+        // A frame with no payload
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        write_deferred_len::<_, u32, _>(&mut cursor, Endian::Little, |w| {
+            ().write_to(w, Endian::Little)
+        })
+        .unwrap();
+        assert_eq!(cursor.into_inner(), [0x0, 0x0, 0x0, 0x0]);
+    }
+
+    /// Fails every other write with `Interrupted`, and otherwise only accepts up to [max] bytes.
+    struct InterruptingWriter {
+        inner: std::io::Cursor<Vec<u8>>,
+        max: usize,
+        interrupt: bool,
+    }
+    impl InterruptingWriter {
+        fn new(max: usize) -> Self {
+            InterruptingWriter {
+                inner: std::io::Cursor::new(Vec::new()),
+                max,
+                interrupt: true,
+            }
+        }
+    }
+    impl Write for InterruptingWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.interrupt = !self.interrupt;
+            if !self.interrupt {
+                return Err(io::Error::from(io::ErrorKind::Interrupted));
+            }
+            let max = buf.len().min(self.max);
+            self.inner.write(&buf[..max])
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    impl Seek for InterruptingWriter {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    /// Calls every public write helper in the crate
+    fn write_everything<W: Write + Seek>(w: &mut W) -> WriteResult {
+        use crate::{
+            parse::relocate, sections::LayoutPlanner, slice::OutputSlice, zstring::ZStringArray,
+            CountingWriter,
+        };
+
+        w.write_u8(0x12)?;
+        w.write_i8(-3)?;
+        w.write_u16_le(0x1234)?;
+        w.write_u16_be(0x1234)?;
+        w.write_i16_le(-0x1234)?;
+        w.write_i16_be(-0x1234)?;
+        w.write_u32_le(0x1234_5678)?;
+        w.write_u32_be(0x1234_5678)?;
+        w.write_i32_le(-0x1234_5678)?;
+        w.write_i32_be(-0x1234_5678)?;
+        w.write_u64_le(0x1234_5678_9ABC_DEF0)?;
+        w.write_u64_be(0x1234_5678_9ABC_DEF0)?;
+        w.write_i64_le(-0x1234_5678_9ABC_DEF0)?;
+        w.write_i64_be(-0x1234_5678_9ABC_DEF0)?;
+        w.write_f32_le(1.5)?;
+        w.write_f32_be(1.5)?;
+        w.write_f64_le(-1.25)?;
+        w.write_f64_be(-1.25)?;
+        w.write_zstring(&ZString::new(b"name".to_vec()))?;
+
+        write_bytes(w, b"bytes")?;
+        Bytes(b"borrowed").write_to(w, ())?;
+        OwnedBytes(b"owned".to_vec()).write_to(w, ())?;
+        (&[0x1u16, 0x2][..]).write_to(w, Endian::Big)?;
+        vec![0x3u32, 0x4].write_to(w, Endian::Little)?;
+        [0x5u8, 0x6, 0x7].write_to(w, ())?;
+        ().write_to(w, ())?;
+        ZStringArray::<2, 12, 4>::new([ZString::new(b"ab".to_vec()), ZString::new(b"c".to_vec())])
+            .write_to(w, ())?;
+        Header {
+            kind: 0x9,
+            length: 0x0A0B_0C0D,
+            version: 0x0E0F,
+        }
+        .write_to(w, ())?;
+        write_deferred_len::<_, u32, _>(w, Endian::Big, |w| write_bytes(w, b"deferred"))?;
+
+        let mut counter = CountingWriter::new(&mut *w);
+        write_bytes(&mut counter, b"counted")?;
+        assert_eq!(counter.position(), 7);
+
+        let start = w.stream_position()?;
+        let mut slice = OutputSlice::new_unchecked(&mut *w, start..start + 6);
+        write_bytes(&mut slice, b"sliced")?;
+
+        relocate(&mut &b"relocated"[..], w, 9).expect("relocate failed");
+
+        let base = w.stream_position()?;
+        let mut planner = LayoutPlanner::new();
+        planner.add("first", 3, 1)?;
+        planner.add("second", 2, 8)?;
+        planner.finalize(base)?;
+        write_bytes(&mut planner.writer_for(w, "first")?, b"abc")?;
+        planner.finish(w)?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_interrupted() {
+        let mut expected = std::io::Cursor::new(Vec::new());
+        write_everything(&mut expected).unwrap();
+        let expected = expected.into_inner();
+
+        for max in 1..4 {
+            let mut output = InterruptingWriter::new(max);
+            write_everything(&mut output).unwrap();
+            assert_eq!(
+                output.inner.into_inner(),
+                expected,
+                "max of {} bytes per write",
+                max
+            );
+        }
+    }
+
+    #[test]
+    fn test_write_zero() {
+        /// Accepts [left] bytes, and then nothing
+        struct StalledWriter {
+            left: usize,
+        }
+        impl Write for StalledWriter {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                let amount = buf.len().min(self.left);
+                self.left -= amount;
+                Ok(amount)
+            }
+
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        match write_bytes(&mut StalledWriter { left: 3 }, b"abcdef") {
+            Err(WriteError::Io(e)) => {
+                assert_eq!(e.kind(), io::ErrorKind::WriteZero);
+                assert!(e.to_string().contains("after 3 of 6"));
+            }
+            x => panic!("expected write zero error, got {:?}", x),
+        }
+    }
+
+    /// Writes must go through `write_full`, which is built on `write_if_possible`, the only
+    /// place allowed to call `write`.
+    /// Write impls which forward to an inner writer are the exception.
+    #[test]
+    fn test_no_raw_writes() {
+        let sources = [
+            ("addr.rs", include_str!("addr.rs")),
+            ("async_parse.rs", include_str!("async_parse.rs")),
+            ("audit.rs", include_str!("audit.rs")),
+            ("bits.rs", include_str!("bits.rs")),
+            ("borrowed.rs", include_str!("borrowed.rs")),
+            ("checksum.rs", include_str!("checksum.rs")),
+            ("ctx.rs", include_str!("ctx.rs")),
+            ("data_size.rs", include_str!("data_size.rs")),
+            ("diff.rs", include_str!("diff.rs")),
+            ("encoding.rs", include_str!("encoding.rs")),
+            ("endian.rs", include_str!("endian.rs")),
+            ("fourcc.rs", include_str!("fourcc.rs")),
+            ("framing.rs", include_str!("framing.rs")),
+            ("half.rs", include_str!("half.rs")),
+            ("lib.rs", include_str!("lib.rs")),
+            ("maybe.rs", include_str!("maybe.rs")),
+            ("mmap.rs", include_str!("mmap.rs")),
+            ("net.rs", include_str!("net.rs")),
+            ("nonzero.rs", include_str!("nonzero.rs")),
+            ("offset.rs", include_str!("offset.rs")),
+            ("owned.rs", include_str!("owned.rs")),
+            ("parse.rs", include_str!("parse.rs")),
+            ("peek.rs", include_str!("peek.rs")),
+            ("positioned.rs", include_str!("positioned.rs")),
+            ("prelude.rs", include_str!("prelude.rs")),
+            ("roundtrip.rs", include_str!("roundtrip.rs")),
+            ("sections.rs", include_str!("sections.rs")),
+            ("slice.rs", include_str!("slice.rs")),
+            ("tee.rs", include_str!("tee.rs")),
+            ("test_util.rs", include_str!("test_util.rs")),
+            ("time.rs", include_str!("time.rs")),
+            ("transform.rs", include_str!("transform.rs")),
+            ("version.rs", include_str!("version.rs")),
+            ("writable.rs", include_str!("writable.rs")),
+            ("zstring.rs", include_str!("zstring.rs")),
+        ];
+        // So that new modules aren't left out
+        let dir = concat!(env!("CARGO_MANIFEST_DIR"), "/src");
+        for entry in std::fs::read_dir(dir).unwrap() {
+            let name = entry.unwrap().file_name();
+            let name = name.to_str().unwrap();
+            if name.ends_with(".rs") {
+                assert!(
+                    sources.iter().any(|(x, _)| *x == name),
+                    "{} is not checked",
+                    name
+                );
+            }
+        }
+        let allowed = [
+            "match w.write(buf) {",
+            "let amount_written = self.inner.write(buf)?;",
+            "self.output.write(&buf[..max])",
+            "let amount = self.slice.write(buf)?;",
+        ];
+        for (name, source) in sources.iter() {
+            let code = source.split("#[cfg(test)]").next().unwrap();
+            for line in code.lines().map(str::trim) {
+                if line.starts_with("//") || allowed.contains(&line) {
+                    continue;
+                }
+                assert!(
+                    !line.contains(".write(") && !line.contains(".write_all("),
+                    "{} writes without write_full: {}",
+                    name,
+                    line
+                );
+            }
+        }
+    }
+}
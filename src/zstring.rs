@@ -1,9 +1,24 @@
 use crate::{
-    parse::{take_until, Parse, ParseResult},
-    writable::{Writable, WriteResult},
+    data_size::DataSize,
+    parse::{take, take_n, take_until, Parse, ParseError, ParseResult},
+    writable::{write_bytes, Writable, WriteError, WriteResult},
+    Endian,
 };
-use bstr::BString;
-use std::io::{Read, Seek, Write};
+use bstr::{BStr, BString, ByteSlice, Utf8Error};
+use std::{
+    convert::{Infallible, TryFrom},
+    fmt::{self, Debug},
+    io::{Read, Write},
+    marker::PhantomData,
+    ops::{Deref, Index},
+    str::FromStr,
+};
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub enum ZStringError {
+    /// Found a null at index .0, which would end the string early when written
+    InteriorNull(usize),
+}
 
 /// Simple (ascii-ish, but more a byte-string) Null-terminated string.
 /// Is not meant to work on unicode.
@@ -15,12 +30,28 @@ impl ZString {
 
     /// Note: this is without null-terminator!
     /// This also does _not_ check if this contains nulls, which may confuse things if
-    /// you aren't sure!
+    /// you aren't sure! See [new_checked].
     #[inline]
     pub fn new(data: Vec<u8>) -> Self {
         ZString(BString::from(data))
     }
 
+    /// Like [new], but errors if [data] contains a null, as then it would not parse back as
+    /// the same string once written.
+    #[inline]
+    pub fn new_checked(data: Vec<u8>) -> Result<Self, ZStringError> {
+        match data.iter().position(|x| *x == ZString::TERMINATOR) {
+            Some(index) => Err(ZStringError::InteriorNull(index)),
+            None => Ok(ZString::new(data)),
+        }
+    }
+
+    /// Returns whether there is a null within the string, which would end it early when written.
+    #[inline]
+    pub fn contains_null(&self) -> bool {
+        self.0.contains(&ZString::TERMINATOR)
+    }
+
     // TODO: provide various methods that bstring/vec<u8> might provide.
 
     /// Returns the number of elements.
@@ -48,29 +79,725 @@ impl ZString {
     pub fn as_mut_slice(&mut self) -> &mut [u8] {
         self.0.as_mut_slice()
     }
+
+    /// Like [new], but removes any nulls, since they would end the string early when written.
+    fn without_nulls(mut data: Vec<u8>) -> Self {
+        data.retain(|x| *x != ZString::TERMINATOR);
+        ZString::new(data)
+    }
 }
-impl<F: Read> Parse<F> for ZString {
-    fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
-        let data = take_until(f, ZString::TERMINATOR, false)?;
+/// Note: interior nulls are removed.
+impl From<&str> for ZString {
+    #[inline]
+    fn from(value: &str) -> Self {
+        ZString::without_nulls(value.as_bytes().to_vec())
+    }
+}
+/// Note: interior nulls are removed.
+impl From<String> for ZString {
+    #[inline]
+    fn from(value: String) -> Self {
+        ZString::without_nulls(value.into_bytes())
+    }
+}
+/// Note: interior nulls are removed, like `From<&str>`.
+/// Unlike writing a `&str` directly, the resulting `ZString` writes a null-terminator.
+impl FromStr for ZString {
+    type Err = Infallible;
+
+    #[inline]
+    fn from_str(value: &str) -> Result<Self, Infallible> {
+        Ok(ZString::from(value))
+    }
+}
+/// Note: like `ZString::new`, this does not check for nulls.
+impl From<&[u8]> for ZString {
+    #[inline]
+    fn from(value: &[u8]) -> Self {
+        ZString::new(value.to_vec())
+    }
+}
+/// Note: like `ZString::new`, this does not check for nulls.
+impl From<BString> for ZString {
+    #[inline]
+    fn from(value: BString) -> Self {
+        ZString(value)
+    }
+}
+impl Deref for ZString {
+    type Target = BString;
+
+    #[inline]
+    fn deref(&self) -> &BString {
+        &self.0
+    }
+}
+impl fmt::Display for ZString {
+    /// Invalid UTF-8 is displayed lossily, see `BString`'s Display impl.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+/// Implements `PartialEq` between `ZString` and [typ] in both directions, by comparing bytes.
+macro_rules! impl_zstring_eq {
+    ($($typ:ty),*) => {
+        $(
+            impl PartialEq<$typ> for ZString {
+                #[inline]
+                fn eq(&self, other: &$typ) -> bool {
+                    let other: &[u8] = other.as_ref();
+                    self.as_slice() == other
+                }
+            }
+            impl PartialEq<ZString> for $typ {
+                #[inline]
+                fn eq(&self, other: &ZString) -> bool {
+                    other == self
+                }
+            }
+        )*
+    };
+}
+impl_zstring_eq!(str, &str, [u8], &[u8]);
+
+impl ZString {
+    /// Like the `Parse` impl, but for strings ended by [terminator] rather than a null, such
+    /// as `b'\n'` or `0xFF`. The terminator is consumed, but not stored.
+    #[inline]
+    pub fn parse_with_terminator<F: Read>(f: &mut F, terminator: u8) -> ParseResult<Self> {
+        let data = take_until(f, terminator, false)?;
 
         Ok(ZString::new(data))
     }
+
+    /// Like the `Writable` impl, but writes [terminator] after the string rather than a null.
+    /// Note: in debug builds this asserts that the string does not contain [terminator].
+    pub fn write_with_terminator<W: Write>(&self, w: &mut W, terminator: u8) -> WriteResult {
+        debug_assert!(
+            !self.0.contains(&terminator),
+            "ZString contains its terminator {:#04X}, so won't parse back the same",
+            terminator
+        );
+        write_bytes(w, self.as_slice())?;
+        // Write terminator due to it not being included in stored string
+        terminator.write_to(w, ())
+    }
+}
+impl<F: Read> Parse<F> for ZString {
+    #[inline]
+    fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+        ZString::parse_with_terminator(f, ZString::TERMINATOR)
+    }
 }
 impl Writable<()> for ZString {
+    /// Note: in debug builds this asserts that the string does not contain a null.
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
+    where
+        W: Write,
+    {
+        self.write_with_terminator(w, ZString::TERMINATOR)
+    }
+}
+impl DataSize<()> for ZString {
+    /// Includes the null-terminator
+    #[inline]
+    fn data_size(&self, _d: ()) -> u64 {
+        self.len() as u64 + 1
+    }
+}
+
+/// A [ZString] that remembers its terminator, for formats that end strings with something
+/// other than a null. Parsing takes the terminator as the data, and writing uses the stored
+/// one.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TermString {
+    pub string: ZString,
+    pub terminator: u8,
+}
+impl TermString {
+    #[inline]
+    pub fn new(data: Vec<u8>, terminator: u8) -> Self {
+        TermString {
+            string: ZString::new(data),
+            terminator,
+        }
+    }
+
+    /// Note: Does not include the terminator.
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        self.string.as_slice()
+    }
+}
+impl<F: Read> Parse<F, u8> for TermString {
+    #[inline]
+    fn parse(f: &mut F, terminator: u8) -> ParseResult<Self> {
+        Ok(TermString {
+            string: ZString::parse_with_terminator(f, terminator)?,
+            terminator,
+        })
+    }
+}
+impl Writable<()> for TermString {
     #[inline]
     fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
     where
         W: Write,
     {
-        self.0.as_slice().write_to(w, ())?;
-        // Write null-terminator due to it not being included in stored string
-        ZString::TERMINATOR.write_to(w, ())
+        self.string.write_with_terminator(w, self.terminator)
+    }
+}
+impl DataSize<()> for TermString {
+    /// Includes the terminator
+    #[inline]
+    fn data_size(&self, _d: ()) -> u64 {
+        self.string.data_size(())
+    }
+}
+
+/// Null-terminated UTF-16 string, as stored by Windows formats, terminated by a 0x0000 code
+/// unit. The code units are kept as is, so unpaired surrogates round-trip unchanged.
+/// Does not store null-terminator, but does write it out when requested.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ZString16(pub Vec<u16>);
+impl ZString16 {
+    pub const TERMINATOR: u16 = 0x0000;
+
+    /// Note: this is without null-terminator, and does not check for nulls.
+    #[inline]
+    pub fn new(data: Vec<u16>) -> Self {
+        ZString16(data)
+    }
+
+    /// Returns the number of code units.
+    /// Note: Does not include null-terminator.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Note: Does not include null-terminator.
+    #[inline]
+    pub fn as_slice(&self) -> &[u16] {
+        &self.0
+    }
+
+    /// Unpaired surrogates are replaced with U+FFFD.
+    #[inline]
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf16_lossy(&self.0)
+    }
+}
+impl From<&str> for ZString16 {
+    #[inline]
+    fn from(value: &str) -> Self {
+        ZString16(value.encode_utf16().collect())
+    }
+}
+impl FromStr for ZString16 {
+    type Err = Infallible;
+
+    #[inline]
+    fn from_str(value: &str) -> Result<Self, Infallible> {
+        Ok(ZString16::from(value))
+    }
+}
+impl<F: Read> Parse<F, Endian> for ZString16 {
+    /// Errors with `UnexpectedEof` if the data ends before the terminator, including in the
+    /// middle of a code unit.
+    fn parse(f: &mut F, endian: Endian) -> ParseResult<Self> {
+        let mut data = Vec::new();
+        loop {
+            let unit = u16::parse(f, endian)?;
+            if unit == ZString16::TERMINATOR {
+                return Ok(ZString16(data));
+            }
+            data.push(unit);
+        }
+    }
+}
+impl Writable<Endian> for ZString16 {
+    /// Written in a single write call, along with the terminator.
+    fn write_to<W>(&self, w: &mut W, endian: Endian) -> WriteResult
+    where
+        W: Write,
+    {
+        let mut output = Vec::with_capacity(self.0.len() * 2 + 2);
+        for &unit in self.0.iter().chain(std::iter::once(&ZString16::TERMINATOR)) {
+            output.extend_from_slice(&match endian {
+                Endian::Big => unit.to_be_bytes(),
+                Endian::Little => unit.to_le_bytes(),
+            });
+        }
+        write_bytes(w, &output)
+    }
+}
+impl DataSize<Endian> for ZString16 {
+    /// Includes the null-terminator
+    #[inline]
+    fn data_size(&self, _d: Endian) -> u64 {
+        2 * self.0.len() as u64 + 2
+    }
+}
+
+/// String stored in a fixed-width field of [N] bytes, padded at the end.
+/// Parsing and writing with `()` as the data pads with nulls, while passing a `u8` uses that as
+/// the pad byte instead (such as `b' '`).
+/// Note: trailing pad bytes are trimmed when parsing, so content that itself ends with the pad
+/// byte does not round-trip: `b"ab\0"` written with null padding parses back as `b"ab"`.
+/// Note: the content is only checked to fit within [N] bytes when writing.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct FixedString<const N: usize>(pub BString);
+impl<const N: usize> FixedString<N> {
+    #[inline]
+    pub fn new(data: Vec<u8>) -> Self {
+        FixedString(BString::from(data))
+    }
+
+    /// Returns the length of the content, without padding.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Note: Does not include padding.
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+
+    #[inline]
+    pub fn as_bstr(&self) -> &BStr {
+        self.0.as_slice().as_bstr()
+    }
+
+    #[inline]
+    pub fn to_str(&self) -> Result<&str, Utf8Error> {
+        self.0.to_str()
+    }
+}
+impl<F: Read, const N: usize> Parse<F, u8> for FixedString<N> {
+    /// Always reads exactly [N] bytes, trimming trailing [pad] bytes.
+    fn parse(f: &mut F, pad: u8) -> ParseResult<Self> {
+        let data = take_n::<F, N>(f)?;
+        let len = data.iter().rposition(|x| *x != pad).map_or(0, |x| x + 1);
+        Ok(FixedString::new(data[..len].to_vec()))
+    }
+}
+impl<F: Read, const N: usize> Parse<F> for FixedString<N> {
+    #[inline]
+    fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+        FixedString::parse(f, 0u8)
+    }
+}
+impl<const N: usize> Writable<u8> for FixedString<N> {
+    /// Errors with `WriteError::ExcessiveData` if the content is longer than [N] bytes, before
+    /// writing anything.
+    fn write_to<W>(&self, w: &mut W, pad: u8) -> WriteResult
+    where
+        W: Write,
+    {
+        if self.len() > N {
+            return Err(WriteError::ExcessiveData);
+        }
+
+        let mut output = [pad; N];
+        output[..self.len()].copy_from_slice(self.as_slice());
+        write_bytes(w, &output)
+    }
+}
+impl<const N: usize> Writable<()> for FixedString<N> {
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
+    where
+        W: Write,
+    {
+        self.write_to(w, 0u8)
+    }
+}
+impl<const N: usize> DataSize<u8> for FixedString<N> {
+    #[inline]
+    fn data_size(&self, _pad: u8) -> u64 {
+        N as u64
+    }
+}
+impl<const N: usize> DataSize<()> for FixedString<N> {
+    #[inline]
+    fn data_size(&self, _d: ()) -> u64 {
+        N as u64
+    }
+}
+
+/// Unsigned integers which can be used as the length of a [PrefixString].
+pub trait LengthPrefix: Copy + Debug + Eq + 'static {
+    /// Size of the length in bytes
+    const SIZE: u64;
+
+    fn parse_len<F: Read>(f: &mut F, endian: Endian) -> ParseResult<u64>;
+
+    /// Errors with `WriteError::TooManyBits` if [len] doesn't fit.
+    fn write_len<W: Write>(w: &mut W, len: usize, endian: Endian) -> WriteResult;
+}
+impl LengthPrefix for u8 {
+    const SIZE: u64 = 1;
+
+    #[inline]
+    fn parse_len<F: Read>(f: &mut F, _endian: Endian) -> ParseResult<u64> {
+        Ok(u64::from(u8::parse(f, ())?))
+    }
+
+    #[inline]
+    fn write_len<W: Write>(w: &mut W, len: usize, _endian: Endian) -> WriteResult {
+        u8::try_from(len)
+            .map_err(|_| WriteError::TooManyBits)?
+            .write_to(w, ())
+    }
+}
+macro_rules! impl_length_prefix {
+    ($($typ:ty),*) => {
+        $(
+            impl LengthPrefix for $typ {
+                const SIZE: u64 = std::mem::size_of::<$typ>() as u64;
+
+                #[inline]
+                fn parse_len<F: Read>(f: &mut F, endian: Endian) -> ParseResult<u64> {
+                    Ok(u64::from(<$typ>::parse(f, endian)?))
+                }
+
+                #[inline]
+                fn write_len<W: Write>(w: &mut W, len: usize, endian: Endian) -> WriteResult {
+                    <$typ>::try_from(len)
+                        .map_err(|_| WriteError::TooManyBits)?
+                        .write_to(w, endian)
+                }
+            }
+        )*
+    };
+}
+impl_length_prefix!(u16, u32, u64);
+
+/// String stored as its length, as an [L], followed by that many bytes. There is no terminator.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PrefixString<L: LengthPrefix> {
+    data: BString,
+    _length: PhantomData<L>,
+}
+/// Pascal style string, with a u8 length.
+pub type PString = PrefixString<u8>;
+pub type PString16 = PrefixString<u16>;
+pub type PString32 = PrefixString<u32>;
+pub type PString64 = PrefixString<u64>;
+impl<L: LengthPrefix> PrefixString<L> {
+    /// Note: the length is only checked to fit in [L] when writing.
+    #[inline]
+    pub fn new(data: Vec<u8>) -> Self {
+        PrefixString {
+            data: BString::from(data),
+            _length: PhantomData,
+        }
+    }
+
+    /// Note: Does not include the length prefix.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        self.data.as_slice()
+    }
+
+    #[inline]
+    pub fn as_bstr(&self) -> &BStr {
+        self.data.as_slice().as_bstr()
+    }
+
+    #[inline]
+    pub fn to_str(&self) -> Result<&str, Utf8Error> {
+        self.data.to_str()
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> BString {
+        self.data
+    }
+}
+impl<F: Read, L: LengthPrefix> Parse<F, Endian> for PrefixString<L> {
+    fn parse(f: &mut F, endian: Endian) -> ParseResult<Self> {
+        // A length that doesn't fit in a usize could never be read into memory anyway, so
+        // this fails with an EOF error
+        let len = usize::try_from(L::parse_len(f, endian)?).unwrap_or(usize::MAX);
+        Ok(PrefixString::new(take(f, len)?))
+    }
+}
+impl<L: LengthPrefix> Writable<Endian> for PrefixString<L> {
+    /// Errors with `WriteError::TooManyBits` if the length doesn't fit in [L], before writing
+    /// anything.
+    #[inline]
+    fn write_to<W>(&self, w: &mut W, endian: Endian) -> WriteResult
+    where
+        W: Write,
+    {
+        L::write_len(w, self.len(), endian)?;
+        write_bytes(w, self.as_slice())
+    }
+}
+impl<L: LengthPrefix> DataSize<Endian> for PrefixString<L> {
+    /// Includes the length prefix
+    #[inline]
+    fn data_size(&self, _endian: Endian) -> u64 {
+        L::SIZE + self.len() as u64
+    }
+}
+
+/// String that is both length-prefixed and null-terminated, as used by Bethesda archives.
+/// Stored as a u8 length which includes the terminator, the bytes, and then a null.
+/// The terminator is not stored.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct BZString(pub BString);
+impl BZString {
+    /// Most bytes the content can have, since the length (with the terminator) is a u8
+    pub const MAX_LEN: usize = 254;
+
+    /// Note: the length is only checked when writing.
+    #[inline]
+    pub fn new(data: Vec<u8>) -> Self {
+        BZString(BString::from(data))
+    }
+
+    /// Note: Does not include the length or null-terminator.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    #[inline]
+    pub fn as_slice(&self) -> &[u8] {
+        self.0.as_slice()
+    }
+
+    #[inline]
+    pub fn as_bstr(&self) -> &BStr {
+        self.0.as_slice().as_bstr()
+    }
+
+    #[inline]
+    pub fn to_str(&self) -> Result<&str, Utf8Error> {
+        self.0.to_str()
+    }
+}
+impl From<&str> for BZString {
+    #[inline]
+    fn from(value: &str) -> Self {
+        BZString(BString::from(value))
+    }
+}
+impl From<ZString> for BZString {
+    #[inline]
+    fn from(value: ZString) -> Self {
+        BZString(value.0)
+    }
+}
+impl From<BZString> for ZString {
+    /// Note: the content of a `BZString` may contain nulls, see `ZString::contains_null`.
+    #[inline]
+    fn from(value: BZString) -> Self {
+        ZString(value.0)
+    }
+}
+impl<F: Read> Parse<F> for BZString {
+    /// Errors with `ParseError::InvalidByte` if the last byte isn't a null, or if the length is
+    /// 0 and so has no room for one.
+    fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+        let len = u8::parse(f, ())?;
+        let mut data = take(f, usize::from(len))?;
+        if data.pop() != Some(ZString::TERMINATOR) {
+            return Err(ParseError::InvalidByte);
+        }
+        Ok(BZString::new(data))
+    }
+}
+impl Writable<()> for BZString {
+    /// Errors with `WriteError::TooManyBits` if the content is longer than [MAX_LEN], before
+    /// writing anything.
+    fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
+    where
+        W: Write,
+    {
+        if self.len() > BZString::MAX_LEN {
+            return Err(WriteError::TooManyBits);
+        }
+
+        let mut output = Vec::with_capacity(self.len() + 2);
+        output.push(self.len() as u8 + 1);
+        output.extend_from_slice(self.as_slice());
+        output.push(ZString::TERMINATOR);
+        write_bytes(w, &output)
+    }
+}
+impl DataSize<()> for BZString {
+    /// Includes the length and null-terminator
+    #[inline]
+    fn data_size(&self, _d: ()) -> u64 {
+        self.len() as u64 + 2
+    }
+}
+
+/// [COUNT] null-terminated strings stored back to back in a fixed region of [REGION] bytes,
+/// with the rest of the region zero padded. Each string is at most [MAX] bytes, not counting
+/// its null-terminator; use [REGION] (or more) for no limit beyond the region's.
+/// Note: `new` doesn't check that the strings fit, that is only done when writing.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ZStringArray<const COUNT: usize, const REGION: usize, const MAX: usize> {
+    strings: [ZString; COUNT],
+}
+impl<const COUNT: usize, const REGION: usize, const MAX: usize> ZStringArray<COUNT, REGION, MAX> {
+    #[inline]
+    pub fn new(strings: [ZString; COUNT]) -> Self {
+        ZStringArray { strings }
+    }
+
+    #[inline]
+    pub fn get(&self, index: usize) -> Option<&ZString> {
+        self.strings.get(index)
+    }
+
+    #[inline]
+    pub fn iter(&self) -> std::slice::Iter<'_, ZString> {
+        self.strings.iter()
+    }
+
+    #[inline]
+    pub fn strings(&self) -> &[ZString; COUNT] {
+        &self.strings
+    }
+
+    /// Note: modifying these may make them no longer fit within the region.
+    #[inline]
+    pub fn strings_mut(&mut self) -> &mut [ZString; COUNT] {
+        &mut self.strings
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> [ZString; COUNT] {
+        self.strings
+    }
+
+    /// Returns the number of bytes the strings take up, including their null-terminators.
+    /// Must be at most [REGION] to be written.
+    #[inline]
+    pub fn content_size(&self) -> usize {
+        self.strings.iter().map(|x| x.len() + 1).sum()
+    }
+}
+impl<const COUNT: usize, const REGION: usize, const MAX: usize> Index<usize>
+    for ZStringArray<COUNT, REGION, MAX>
+{
+    type Output = ZString;
+
+    #[inline]
+    fn index(&self, index: usize) -> &ZString {
+        &self.strings[index]
+    }
+}
+impl<F: Read, const COUNT: usize, const REGION: usize, const MAX: usize> Parse<F>
+    for ZStringArray<COUNT, REGION, MAX>
+{
+    /// Always reads exactly [REGION] bytes.
+    /// Errors with `ParseError::StringTooLong` if a string is longer than [MAX].
+    fn parse(f: &mut F, _d: ()) -> ParseResult<Self> {
+        let region = take(f, REGION)?;
+
+        let mut strings = Vec::with_capacity(COUNT);
+        let mut rest = region.as_slice();
+        while strings.len() < COUNT {
+            let terminator = rest
+                .iter()
+                .position(|x| *x == ZString::TERMINATOR)
+                .ok_or(ParseError::MissingTerminators(COUNT, strings.len()))?;
+            if terminator > MAX {
+                return Err(ParseError::StringTooLong(strings.len(), terminator));
+            }
+            strings.push(ZString::new(rest[..terminator].to_vec()));
+            rest = &rest[terminator + 1..];
+        }
+
+        if let Some(index) = rest.iter().position(|x| *x != 0) {
+            return Err(ParseError::NonZeroPadding(REGION - rest.len() + index));
+        }
+
+        // Can't panic: the loop above only exits once there are COUNT strings, or by returning
+        let strings = <[ZString; COUNT]>::try_from(strings)
+            .unwrap_or_else(|_| unreachable!("exactly COUNT strings were parsed"));
+        Ok(ZStringArray { strings })
+    }
+}
+impl<const COUNT: usize, const REGION: usize, const MAX: usize> Writable<()>
+    for ZStringArray<COUNT, REGION, MAX>
+{
+    /// Errors with `WriteError::StringTooLong` if a string is longer than [MAX],
+    /// `WriteError::ExcessiveData` if the strings don't fit within [REGION] bytes, and
+    /// `WriteError::InvalidByte` if any of them contain a null, before writing anything.
+    fn write_to<W>(&self, w: &mut W, _d: ()) -> WriteResult
+    where
+        W: Write,
+    {
+        if let Some((index, string)) = self.iter().enumerate().find(|(_, x)| x.len() > MAX) {
+            return Err(WriteError::StringTooLong(index, string.len()));
+        }
+
+        let content_size = self.content_size();
+        if content_size > REGION {
+            return Err(WriteError::ExcessiveData);
+        }
+
+        if self.strings.iter().any(ZString::contains_null) {
+            return Err(WriteError::InvalidByte);
+        }
+
+        for string in self.strings.iter() {
+            string.write_to(w, ())?;
+        }
+        write_bytes(w, &vec![0; REGION - content_size])
+    }
+}
+impl<const COUNT: usize, const REGION: usize, const MAX: usize> DataSize<()>
+    for ZStringArray<COUNT, REGION, MAX>
+{
+    #[inline]
+    fn data_size(&self, _d: ()) -> u64 {
+        REGION as u64
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::CallCountingWriter;
 
     const NSTR: &[u8] = b"HELLO\x00";
 
@@ -80,7 +807,7 @@ mod tests {
         let zstring = ZString::parse(&mut cursor, ()).unwrap();
         assert_eq!(zstring.as_slice(), b"HELLO");
         assert_eq!(zstring.len(), 5);
-        assert_eq!(zstring.is_empty(), false);
+        assert!(!zstring.is_empty());
 
         // Test writing back
         let mut output = [0u8; 6];
@@ -88,4 +815,460 @@ mod tests {
         zstring.write_to(&mut output_cursor, ()).unwrap();
         assert_eq!(&output, NSTR);
     }
+
+    #[test]
+    fn test_write_single_call() {
+        let zstring = ZString::new(vec![b'a'; 10 * 1024]);
+        let mut output = CallCountingWriter::default();
+        zstring.write_to(&mut output, ()).unwrap();
+        // Once for the content, once for the terminator
+        assert_eq!(output.calls, 2);
+        assert_eq!(output.data.len(), 10 * 1024 + 1);
+        assert_eq!(output.data.last(), Some(&ZString::TERMINATOR));
+    }
+
+    #[test]
+    fn test_conversions() {
+        let name = ZString::from("héllo");
+        assert_eq!(name.as_slice(), "héllo".as_bytes());
+        assert_eq!(name.len(), 6);
+        assert_eq!(name, "héllo");
+        assert_eq!("héllo", name);
+        assert_eq!(name, *"héllo");
+        assert_eq!(*"héllo", name);
+        assert_eq!(name, "héllo".as_bytes());
+        assert_eq!("héllo".as_bytes(), name);
+        assert_eq!(name, *"héllo".as_bytes());
+        assert_eq!(*"héllo".as_bytes(), name);
+        assert_ne!(name, "hello");
+        assert_ne!("hello", name);
+        assert_eq!(name.to_string(), "héllo");
+
+        // Nulls would end the string early when written
+        assert_eq!(ZString::from("a\0b"), "ab");
+        assert_eq!(ZString::from(String::from("\0x\0")), "x");
+
+        let raw = ZString::from(&b"\xFFa"[..]);
+        assert_eq!(raw.len(), 2);
+        assert_eq!(raw.to_string(), "\u{FFFD}a");
+        assert_eq!(ZString::from(BString::from("abc")), "abc");
+
+        // BString's methods through Deref
+        assert!(name.starts_with(b"h\xC3\xA9"));
+        assert_eq!(name.find("llo"), Some(3));
+        assert_eq!(name.to_str().unwrap(), "héllo");
+    }
+
+    #[test]
+    fn test_new_checked() {
+        assert_eq!(
+            ZString::new_checked(b"a\0b".to_vec()),
+            Err(ZStringError::InteriorNull(1))
+        );
+        assert!(ZString::new(b"a\0b".to_vec()).contains_null());
+        assert!(!ZString::new(b"ab".to_vec()).contains_null());
+
+        // Every string of up to 4 bytes from an alphabet including null
+        let alphabet = [0x00, 0x01, b'a', 0xFF];
+        let mut checked = 0;
+        for len in 0..=4 {
+            for n in 0..alphabet.len().pow(len) {
+                let data: Vec<u8> = (0..len)
+                    .map(|i| alphabet[n / alphabet.len().pow(i) % alphabet.len()])
+                    .collect();
+                let zstring = match ZString::new_checked(data.clone()) {
+                    Ok(x) => x,
+                    Err(ZStringError::InteriorNull(index)) => {
+                        assert_eq!(data[index], 0);
+                        assert!(!data[..index].contains(&0));
+                        continue;
+                    }
+                };
+                checked += 1;
+
+                let mut output = Vec::new();
+                zstring.write_to(&mut output, ()).unwrap();
+                let parsed = ZString::parse(&mut std::io::Cursor::new(&output), ()).unwrap();
+                assert_eq!(parsed, zstring);
+            }
+        }
+        // 3^0 + 3^1 + ... + 3^4 strings without a null
+        assert_eq!(checked, 121);
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    #[should_panic(expected = "won't parse back the same")]
+    fn test_write_interior_null() {
+        let _ = ZString::new(b"a\0b".to_vec()).write_to(&mut Vec::new(), ());
+    }
+
+    #[test]
+    fn test_zstring16() {
+        // ASCII
+        let data = [b'h', 0, b'i', 0, 0, 0, 0xFF];
+        let mut cursor = std::io::Cursor::new(&data);
+        let parsed = ZString16::parse(&mut cursor, Endian::Little).unwrap();
+        assert_eq!(parsed.as_slice(), &[0x68, 0x69]);
+        assert_eq!(parsed.to_string_lossy(), "hi");
+        assert_eq!(parsed.data_size(Endian::Little), 6);
+        assert_eq!(cursor.position(), 6);
+
+        // Non-ASCII within the BMP, in both byte orders
+        let name = ZString16::from_str("é€").unwrap();
+        assert_eq!(name.as_slice(), &[0x00E9, 0x20AC]);
+        let mut output = Vec::new();
+        name.write_to(&mut output, Endian::Big).unwrap();
+        assert_eq!(output, [0x00, 0xE9, 0x20, 0xAC, 0x00, 0x00]);
+        let mut output = Vec::new();
+        name.write_to(&mut output, Endian::Little).unwrap();
+        assert_eq!(output, [0xE9, 0x00, 0xAC, 0x20, 0x00, 0x00]);
+        assert_eq!(output.len() as u64, name.data_size(Endian::Little));
+        let parsed = ZString16::parse(&mut std::io::Cursor::new(&output), Endian::Little).unwrap();
+        assert_eq!(parsed, name);
+        assert_eq!(parsed.to_string_lossy(), "é€");
+
+        // A lone surrogate round-trips untouched, and only the lossy conversion replaces it
+        let lone = ZString16::new(vec![0x61, 0xD800, 0x62]);
+        let mut output = Vec::new();
+        lone.write_to(&mut output, Endian::Little).unwrap();
+        let parsed = ZString16::parse(&mut std::io::Cursor::new(&output), Endian::Little).unwrap();
+        assert_eq!(parsed, lone);
+        assert_eq!(parsed.to_string_lossy(), "a\u{FFFD}b");
+
+        let empty = ZString16::from("");
+        assert!(empty.is_empty());
+        assert_eq!(empty.data_size(Endian::Big), 2);
+
+        // Ends in the middle of a code unit
+        for data in [&[b'h', 0, b'i'][..], &[b'h', 0], &[]].iter() {
+            match ZString16::parse(&mut std::io::Cursor::new(data), Endian::Little) {
+                Err(ParseError::Io(e)) => {
+                    assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof)
+                }
+                x => panic!("expected eof, got {:?}", x),
+            }
+        }
+    }
+
+    #[test]
+    fn test_fixed_string() {
+        type Name = FixedString<8>;
+        let parse = |data: &[u8], pad: u8| Name::parse(&mut std::io::Cursor::new(data), pad);
+
+        // Exactly N bytes of content
+        let full = parse(b"abcdefgh", 0).unwrap();
+        assert_eq!(full.as_slice(), b"abcdefgh");
+        let mut output = Vec::new();
+        full.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, b"abcdefgh");
+
+        // Shorter, with null and space padding
+        let mut cursor = std::io::Cursor::new(b"tar\0\0\0\0\0rest");
+        let name = Name::parse(&mut cursor, ()).unwrap();
+        assert_eq!(cursor.position(), 8);
+        assert_eq!(name.as_slice(), b"tar");
+        assert_eq!(name.to_str().unwrap(), "tar");
+        assert_eq!(name.as_bstr(), "tar");
+        assert_eq!(name.data_size(()), 8);
+        let name = parse(b"ISO     ", b' ').unwrap();
+        assert_eq!(name.as_slice(), b"ISO");
+        let mut output = Vec::new();
+        name.write_to(&mut output, b' ').unwrap();
+        assert_eq!(output, b"ISO     ");
+        let mut output = Vec::new();
+        name.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, b"ISO\0\0\0\0\0");
+        assert!(parse(b"\0\0\0\0\0\0\0\0", 0).unwrap().is_empty());
+
+        // Only trailing padding is trimmed
+        assert_eq!(parse(b"a b     ", b' ').unwrap().as_slice(), b"a b");
+        // Content ending with the pad byte can't be told apart from padding
+        let mut output = Vec::new();
+        Name::new(b"ab\0".to_vec())
+            .write_to(&mut output, ())
+            .unwrap();
+        assert_eq!(parse(&output, 0).unwrap().as_slice(), b"ab");
+
+        let mut output = Vec::new();
+        match Name::new(b"too long!".to_vec()).write_to(&mut output, ()) {
+            Err(WriteError::ExcessiveData) => {}
+            x => panic!("expected excessive data, got {:?}", x),
+        }
+        assert!(output.is_empty());
+
+        match parse(b"short", 0) {
+            Err(ParseError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof),
+            x => panic!("expected eof, got {:?}", x),
+        }
+    }
+
+    fn prefix_round_trip<L: LengthPrefix>(content: &[u8], endian: Endian, expected: &[u8]) {
+        let string = PrefixString::<L>::new(content.to_vec());
+        let mut output = Vec::new();
+        string.write_to(&mut output, endian).unwrap();
+        assert_eq!(output, expected);
+        assert_eq!(string.data_size(endian), expected.len() as u64);
+
+        let mut cursor = std::io::Cursor::new(&output);
+        let parsed = PrefixString::<L>::parse(&mut cursor, endian).unwrap();
+        assert_eq!(parsed, string);
+        assert_eq!(cursor.position(), expected.len() as u64);
+    }
+
+    #[test]
+    fn test_prefix_string() {
+        prefix_round_trip::<u8>(b"abc", Endian::Big, b"\x03abc");
+        prefix_round_trip::<u8>(b"", Endian::Little, b"\x00");
+        prefix_round_trip::<u16>(b"abc", Endian::Little, b"\x03\x00abc");
+        prefix_round_trip::<u16>(b"abc", Endian::Big, b"\x00\x03abc");
+        prefix_round_trip::<u32>(b"ab", Endian::Big, b"\x00\x00\x00\x02ab");
+        prefix_round_trip::<u64>(b"ab", Endian::Little, b"\x02\0\0\0\0\0\0\0ab");
+
+        // The longest a u8 length can describe
+        let longest = vec![b'x'; 255];
+        let mut expected = vec![0xFF];
+        expected.extend_from_slice(&longest);
+        prefix_round_trip::<u8>(&longest, Endian::Little, &expected);
+
+        let parsed = PString::parse(&mut std::io::Cursor::new(b"\x02hi!"), Endian::Little).unwrap();
+        assert_eq!(parsed.to_str().unwrap(), "hi");
+        assert_eq!(parsed.as_bstr(), "hi");
+
+        let mut output = Vec::new();
+        match PString::new(vec![b'x'; 256]).write_to(&mut output, Endian::Little) {
+            Err(WriteError::TooManyBits) => {}
+            x => panic!("expected too many bits, got {:?}", x),
+        }
+        assert!(output.is_empty());
+        PString16::new(vec![b'x'; 256])
+            .write_to(&mut output, Endian::Little)
+            .unwrap();
+        assert_eq!(&output[..2], &[0x00, 0x01]);
+
+        // The data ends before the length does
+        match PString32::parse(&mut std::io::Cursor::new(b"\x05\0\0\0abc"), Endian::Little) {
+            Err(ParseError::ExpectedBytesFound(5, 3)) => {}
+            x => panic!("expected a short read, got {:?}", x),
+        }
+        let huge = b"\xFF\xFF\xFF\xFF\xFF\xFF\xFF\xFFabc";
+        assert!(PString64::parse(&mut std::io::Cursor::new(huge), Endian::Little).is_err());
+    }
+
+    #[test]
+    fn test_bzstring() {
+        let data = b"\x06Armor\0rest";
+        let mut cursor = std::io::Cursor::new(&data);
+        let parsed = BZString::parse(&mut cursor, ()).unwrap();
+        assert_eq!(parsed.as_slice(), b"Armor");
+        assert_eq!(parsed.to_str().unwrap(), "Armor");
+        assert_eq!(parsed.data_size(()), 7);
+        assert_eq!(cursor.position(), 7);
+        let mut output = Vec::new();
+        parsed.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, &data[..7]);
+
+        let empty = BZString::from("");
+        let mut output = Vec::new();
+        empty.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, b"\x01\0");
+        assert_eq!(
+            BZString::parse(&mut std::io::Cursor::new(&output), ()).unwrap(),
+            empty
+        );
+
+        let longest = BZString::new(vec![b'x'; BZString::MAX_LEN]);
+        let mut output = Vec::new();
+        longest.write_to(&mut output, ()).unwrap();
+        assert_eq!(output[0], 0xFF);
+        assert_eq!(output.len() as u64, longest.data_size(()));
+        assert_eq!(
+            BZString::parse(&mut std::io::Cursor::new(&output), ()).unwrap(),
+            longest
+        );
+        let mut output = Vec::new();
+        match BZString::new(vec![b'x'; 255]).write_to(&mut output, ()) {
+            Err(WriteError::TooManyBits) => {}
+            x => panic!("expected too many bits, got {:?}", x),
+        }
+        assert!(output.is_empty());
+
+        let zstring = ZString::from(BZString::from("name"));
+        assert_eq!(zstring, "name");
+        assert_eq!(BZString::from(zstring).as_slice(), b"name");
+
+        // Missing terminator
+        for data in [&b"\x05Armor"[..], b"\x00", b"\x03abc"].iter() {
+            match BZString::parse(&mut std::io::Cursor::new(data), ()) {
+                Err(ParseError::InvalidByte) => {}
+                x => panic!("expected invalid byte, got {:?}", x),
+            }
+        }
+        match BZString::parse(&mut std::io::Cursor::new(b"\x08Armor\0"), ()) {
+            Err(ParseError::ExpectedBytesFound(8, 6)) => {}
+            x => panic!("expected a short read, got {:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_other_terminators() {
+        let data = b"name\xFFnext";
+        let mut cursor = std::io::Cursor::new(&data);
+        let parsed = ZString::parse_with_terminator(&mut cursor, 0xFF).unwrap();
+        assert_eq!(parsed, "name");
+        assert_eq!(cursor.position(), 5);
+        let mut output = Vec::new();
+        parsed.write_with_terminator(&mut output, 0xFF).unwrap();
+        assert_eq!(output, b"name\xFF");
+
+        let data = b"first line\nsecond line\n";
+        let mut cursor = std::io::Cursor::new(&data);
+        let first = TermString::parse(&mut cursor, b'\n').unwrap();
+        let second = TermString::parse(&mut cursor, b'\n').unwrap();
+        assert_eq!(first.as_slice(), b"first line");
+        assert_eq!(second, TermString::new(b"second line".to_vec(), b'\n'));
+        assert_eq!(first.data_size(()), 11);
+        // Nulls are just content when they aren't the terminator
+        let with_null = TermString::new(b"a\0b".to_vec(), b'\n');
+        let mut output = Vec::new();
+        first.write_to(&mut output, ()).unwrap();
+        second.write_to(&mut output, ()).unwrap();
+        with_null.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, b"first line\nsecond line\na\0b\n");
+
+        // Nulls don't end a newline-terminated string
+        let parsed = ZString::parse_with_terminator(&mut std::io::Cursor::new(b"a\0b\n"), b'\n');
+        assert_eq!(parsed.unwrap(), &b"a\0b"[..]);
+
+        match ZString::parse_with_terminator(&mut std::io::Cursor::new(b"abc\0"), 0xFF) {
+            Err(ParseError::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof),
+            x => panic!("expected eof, got {:?}", x),
+        }
+    }
+
+    type Names = ZStringArray<3, 16, 5>;
+
+    fn names(values: [&[u8]; 3]) -> Names {
+        ZStringArray::new([
+            ZString::new(values[0].to_vec()),
+            ZString::new(values[1].to_vec()),
+            ZString::new(values[2].to_vec()),
+        ])
+    }
+
+    #[test]
+    fn test_array_round_trip() {
+        let data = b"abc\x00\x00hello\x00\x00\x00\x00\x00\x00";
+        let parsed = Names::parse(&mut std::io::Cursor::new(&data[..]), ()).unwrap();
+        assert_eq!(parsed, names([b"abc", b"", b"hello"]));
+        assert_eq!(parsed[0].as_slice(), b"abc");
+        assert!(parsed[1].is_empty());
+        assert_eq!(parsed.get(2).unwrap().as_slice(), b"hello");
+        assert!(parsed.get(3).is_none());
+        assert_eq!(parsed.content_size(), 11);
+        assert_eq!(parsed.data_size(()), 16);
+
+        let mut output = Vec::new();
+        parsed.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, data);
+
+        // Completely full region
+        let full = names([b"abcd", b"efgh", b"ijklm"]);
+        let mut output = Vec::new();
+        full.write_to(&mut output, ()).unwrap();
+        assert_eq!(output, b"abcd\x00efgh\x00ijklm\x00");
+        let parsed = Names::parse(&mut std::io::Cursor::new(output), ()).unwrap();
+        assert_eq!(parsed, full);
+    }
+
+    #[test]
+    fn test_array_errors() {
+        let parse = |data: &[u8]| Names::parse(&mut std::io::Cursor::new(data), ());
+
+        match parse(b"abc\x00def\x00ghijklmn") {
+            Err(ParseError::MissingTerminators(3, 2)) => {}
+            x => panic!("expected missing terminators, got {:?}", x),
+        }
+        match parse(b"abc\x00\x00\x00\x00\x00\x00x\x00\x00\x00\x00\x00\x00") {
+            Err(ParseError::NonZeroPadding(9)) => {}
+            x => panic!("expected nonzero padding, got {:?}", x),
+        }
+        // Region is shorter than REGION
+        match parse(b"a\x00b\x00c\x00") {
+            Err(ParseError::ExpectedBytesFound(16, 6)) => {}
+            x => panic!("expected a short read, got {:?}", x),
+        }
+
+        let mut output = Vec::new();
+        match names([b"abcde", b"fghij", b"klmn"]).write_to(&mut output, ()) {
+            Err(WriteError::ExcessiveData) => {}
+            x => panic!("expected excessive data, got {:?}", x),
+        }
+        match names([b"a\x00b", b"", b""]).write_to(&mut output, ()) {
+            Err(WriteError::InvalidByte) => {}
+            x => panic!("expected invalid byte, got {:?}", x),
+        }
+        assert!(output.is_empty());
+    }
+
+    #[test]
+    fn test_array_max_len() {
+        // Fits in the region, but the second string is over the maximum
+        let data = b"ab\x00cdefgh\x00\x00\x00\x00\x00\x00\x00";
+        match Names::parse(&mut std::io::Cursor::new(&data[..]), ()) {
+            Err(ParseError::StringTooLong(1, 6)) => {}
+            x => panic!("expected too long string, got {:?}", x),
+        }
+
+        let mut output = Vec::new();
+        match names([b"ab", b"cdefgh", b""]).write_to(&mut output, ()) {
+            Err(WriteError::StringTooLong(1, 6)) => {}
+            x => panic!("expected too long string, got {:?}", x),
+        }
+        assert!(output.is_empty());
+
+        // With no limit beyond the region
+        let parsed = ZStringArray::<3, 16, 16>::parse(&mut std::io::Cursor::new(&data[..]), ());
+        assert_eq!(parsed.unwrap()[1].as_slice(), b"cdefgh");
+    }
+
+    #[test]
+    fn test_str_is_unframed() {
+        let text = "h\u{E9}llo \u{1F600}";
+        let mut raw = Vec::new();
+        text.write_to(&mut raw, ()).unwrap();
+        assert_eq!(raw, text.as_bytes());
+        assert_eq!(raw.len() as u64, text.data_size(()));
+        let mut owned = Vec::new();
+        text.to_string().write_to(&mut owned, ()).unwrap();
+        assert_eq!(owned, raw);
+        assert_eq!(text.to_string().data_size(()), text.data_size(()));
+
+        // The same text as a ZString gets a terminator
+        let mut terminated = Vec::new();
+        let zstring: ZString = text.parse().unwrap();
+        zstring.write_to(&mut terminated, ()).unwrap();
+        assert_eq!(&terminated[..raw.len()], raw.as_slice());
+        assert_eq!(&terminated[raw.len()..], b"\x00");
+        assert_eq!(zstring.data_size(()), text.data_size(()) + 1);
+
+        // Interior nulls are removed, as with From<&str>
+        assert_eq!("a\0b".parse::<ZString>().unwrap(), ZString::from("ab"));
+    }
+
+    #[test]
+    fn test_empty_str() {
+        let mut output = Vec::new();
+        "".write_to(&mut output, ()).unwrap();
+        String::new().write_to(&mut output, ()).unwrap();
+        assert!(output.is_empty());
+        assert_eq!("".data_size(()), 0);
+        assert_eq!(String::new().data_size(()), 0);
+
+        "".parse::<ZString>()
+            .unwrap()
+            .write_to(&mut output, ())
+            .unwrap();
+        assert_eq!(output, b"\x00");
+    }
 }